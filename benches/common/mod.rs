@@ -0,0 +1,153 @@
+//! Documents for the benchmarks, generated at a chosen size so that no
+//! captures need to be checked in.
+
+#![allow(dead_code)]
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The JSON `pw-dump` prints for a graph of `nodes` ALSA nodes, each with
+/// an input and an output port linked to the next node, and the clients
+/// and devices they belong to.
+pub fn pw_dump(nodes: usize) -> String {
+    let mut out = String::from("[\n");
+    let mut id = 0;
+    let mut object = |out: &mut String, kind: &str, info: String| {
+        if id > 0 {
+            out.push_str(",\n");
+        }
+        write!(
+            out,
+            "  {{\n    \"id\": {id},\n    \"type\": \"PipeWire:Interface:{kind}\",\n    \
+             \"version\": 3,\n    \"permissions\": [ \"r\", \"w\", \"x\", \"m\" ],\n    \
+             \"info\": {info}\n  }}"
+        )
+        .unwrap();
+        id += 1;
+    };
+    for n in 0..nodes {
+        object(
+            &mut out,
+            "Client",
+            props(n, &[("application.name", "pipewire-media-session")]),
+        );
+        object(
+            &mut out,
+            "Device",
+            props(
+                n,
+                &[("device.api", "alsa"), ("media.class", "Audio/Device")],
+            ),
+        );
+        object(&mut out, "Node", node_info(n));
+        for direction in ["input", "output"] {
+            let info = format!(
+                "{{\n      \"direction\": \"{direction}\",\n      \"props\": {{\n        \
+                 \"port.name\": \"playback_FL\",\n        \"port.id\": 0,\n        \
+                 \"audio.channel\": \"FL\",\n        \"node.id\": {n}\n      }},\n      \
+                 \"params\": {{ }}\n    }}"
+            );
+            object(&mut out, "Port", info);
+        }
+        let link = format!(
+            "{{\n      \"output-node-id\": {n},\n      \"output-port-id\": {},\n      \
+             \"input-node-id\": {},\n      \"input-port-id\": {},\n      \
+             \"state\": \"active\",\n      \"error\": null,\n      \"format\": null,\n      \
+             \"props\": {{ \"link.passive\": false }}\n    }}",
+            n * 2 + 1,
+            n + 1,
+            n * 2 + 2
+        );
+        object(&mut out, "Link", link);
+    }
+    out.push_str("\n]\n");
+    out
+}
+
+fn props(n: usize, extra: &[(&str, &str)]) -> String {
+    let mut info = format!(
+        "{{\n      \"props\": {{\n        \"object.serial\": {n},\n        \
+         \"object.path\": \"alsa:pcm:{n}\""
+    );
+    for (key, value) in extra {
+        write!(info, ",\n        \"{key}\": \"{value}\"").unwrap();
+    }
+    info.push_str("\n      },\n      \"params\": { }\n    }");
+    info
+}
+
+fn node_info(n: usize) -> String {
+    format!(
+        "{{\n      \"max-input-ports\": 0,\n      \"max-output-ports\": 65,\n      \
+         \"n-input-ports\": 1,\n      \"n-output-ports\": 1,\n      \
+         \"state\": \"running\",\n      \"error\": null,\n      \"props\": {{\n        \
+         \"node.name\": \"alsa_output.pci-0000_00_1f.3.analog-stereo.{n}\",\n        \
+         \"node.description\": \"Built-in Audio Analog Stereo\",\n        \
+         \"media.class\": \"Audio/Sink\",\n        \"api.alsa.path\": \"front:0\",\n        \
+         \"api.alsa.card.longname\": \"HDA Intel PCH at 0xf7f10000 irq 32\",\n        \
+         \"audio.rate\": 48000,\n        \"audio.channels\": 2,\n        \
+         \"node.latency\": \"1024/48000\",\n        \"priority.session\": 1009\n      }},\n      \
+         \"params\": {{\n        \"EnumFormat\": [\n          {{\n            \
+         \"mediaType\": \"audio\",\n            \"mediaSubtype\": \"raw\",\n            \
+         \"format\": {{ \"default\": \"S32LE\", \"alt1\": \"S24_32LE\", \"alt2\": \"S16LE\" }},\n            \
+         \"rate\": {{ \"default\": 48000, \"min\": 44100, \"max\": 192000 }},\n            \
+         \"channels\": 2,\n            \"position\": [ \"FL\", \"FR\" ]\n          }}\n        ],\n        \
+         \"Props\": [\n          {{\n            \"volume\": 1.0,\n            \"mute\": false,\n            \
+         \"channelVolumes\": [ 0.399985, 0.399985 ],\n            \"softVolumes\": [ 1.0, 1.0 ]\n          }}\n        ]\n      }}\n    }}"
+    )
+}
+
+/// A daemon configuration in the shape of `pipewire.conf`, with `modules`
+/// modules and rules.
+#[derive(Clone, Debug, Serialize)]
+pub struct Config {
+    #[serde(rename = "context.properties")]
+    pub properties: BTreeMap<String, u32>,
+    #[serde(rename = "context.modules")]
+    pub modules: Vec<Module>,
+    #[serde(rename = "node.rules")]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Module {
+    pub name: String,
+    pub args: BTreeMap<String, String>,
+    pub flags: Vec<&'static str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Rule {
+    pub matches: Vec<BTreeMap<String, String>>,
+    pub actions: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+pub fn config(modules: usize) -> Config {
+    Config {
+        properties: (0..64)
+            .map(|i| (format!("default.clock.property-{i}"), 48000 + i))
+            .collect(),
+        modules: (0..modules)
+            .map(|i| Module {
+                name: format!("libpipewire-module-{i}"),
+                args: (0..8)
+                    .map(|j| (format!("arg.{j}"), format!("value with spaces {i}.{j}")))
+                    .collect(),
+                flags: vec!["ifexists", "nofail"],
+            })
+            .collect(),
+        rules: (0..modules)
+            .map(|i| Rule {
+                matches: vec![BTreeMap::from([(
+                    "node.name".to_owned(),
+                    format!("~alsa_output.{i}.*"),
+                )])],
+                actions: BTreeMap::from([(
+                    "update-props".to_owned(),
+                    BTreeMap::from([("node.pause-on-idle".to_owned(), 0.5)]),
+                )]),
+            })
+            .collect(),
+    }
+}
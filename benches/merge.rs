@@ -0,0 +1,53 @@
+//! Merging configuration fragments, and comparing configurations with
+//! `diff` and `minimal_fragment`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use spa_json::ser::to_string;
+use spa_json::value::Value;
+use spa_json::{diff, merge, minimal_fragment};
+use std::hint::black_box;
+
+mod common;
+
+/// A configuration of `modules` modules, and a fragment changing some of
+/// its properties, adding a module and overriding the rules.
+fn documents(modules: usize) -> (Value, Value) {
+    let base = to_string(&common::config(modules))
+        .unwrap()
+        .parse()
+        .unwrap();
+    let fragment = "context.properties = { default.clock.property-7 = 44100 } \
+                    context.modules = [ { name = libpipewire-module-extra args = { } } ] \
+                    override.node.rules = [ ]"
+        .parse()
+        .unwrap();
+    (base, fragment)
+}
+
+fn merge_and_diff(c: &mut Criterion) {
+    let mut group = c.benchmark_group("merge");
+    for modules in [10, 1000] {
+        let (base, fragment) = documents(modules);
+        group.bench_with_input(BenchmarkId::new("merge", modules), &modules, |b, _| {
+            b.iter_batched(
+                || (base.clone(), fragment.clone()),
+                |(mut base, fragment)| merge(&mut base, black_box(fragment)),
+                BatchSize::SmallInput,
+            )
+        });
+        let mut merged = base.clone();
+        merge(&mut merged, fragment.clone());
+        group.bench_with_input(BenchmarkId::new("diff", modules), &modules, |b, _| {
+            b.iter(|| diff(black_box(&base), black_box(&merged)))
+        });
+        group.bench_with_input(
+            BenchmarkId::new("minimal_fragment", modules),
+            &modules,
+            |b, _| b.iter(|| minimal_fragment(black_box(&base), black_box(&merged)).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, merge_and_diff);
+criterion_main!(benches);
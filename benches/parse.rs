@@ -0,0 +1,34 @@
+//! Parsing `pw-dump` captures into the typed model, into `Value`s with the
+//! recursive and the iterative parser, and into tokens.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spa_json::de::{from_str, from_str_value, ParseOptions};
+use spa_json::lexer::Lexer;
+use spa_json::value::Value;
+use std::hint::black_box;
+
+mod common;
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for nodes in [10, 1000] {
+        let input = common::pw_dump(nodes);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("dump", nodes), &input, |b, input| {
+            b.iter(|| spa_json::dump::from_str(black_box(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("value", nodes), &input, |b, input| {
+            b.iter(|| from_str::<Value>(black_box(input)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("iterative", nodes), &input, |b, input| {
+            b.iter(|| from_str_value(black_box(input), ParseOptions::default()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("lexer", nodes), &input, |b, input| {
+            b.iter(|| Lexer::new(black_box(input)).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
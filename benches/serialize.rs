@@ -0,0 +1,44 @@
+//! Serializing large configuration structs and parsed documents, with the
+//! default layout and with the options that reformat open containers.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use spa_json::ser::{to_string, to_string_value, to_string_with_options, SerializerOptions};
+use spa_json::value::Value;
+use std::hint::black_box;
+
+mod common;
+
+fn serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    for modules in [10, 1000] {
+        let config = common::config(modules);
+        let len = to_string(&config).unwrap().len();
+        group.throughput(Throughput::Bytes(len as u64));
+        group.bench_with_input(BenchmarkId::new("struct", modules), &config, |b, config| {
+            b.iter(|| to_string(black_box(config)).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("reformat", modules),
+            &config,
+            |b, config| {
+                let options = SerializerOptions::new()
+                    .braceless(true)
+                    .inline_width(80)
+                    .align_equals(true);
+                b.iter(|| to_string_with_options(black_box(config), options.clone()).unwrap())
+            },
+        );
+    }
+    let dump: Value = common::pw_dump(1000).parse().unwrap();
+    let len = to_string_value(&dump, SerializerOptions::new())
+        .unwrap()
+        .len();
+    group.throughput(Throughput::Bytes(len as u64));
+    group.bench_function("value", |b| {
+        b.iter(|| to_string_value(black_box(&dump), SerializerOptions::new()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialize);
+criterion_main!(benches);
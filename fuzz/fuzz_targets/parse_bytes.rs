@@ -0,0 +1,20 @@
+//! Parses arbitrary input, which may fail but must not panic, with the
+//! strict and the recovering parser, and checks that the lexer gives back
+//! the text it was given.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spa_json::lexer::Lexer;
+use spa_json::de::{from_str, parse_lossy};
+use spa_json::value::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+    let _ = from_str::<Value>(input);
+    let _ = parse_lossy(input);
+    let text: String = Lexer::new(input).map(|token| token.text).collect();
+    assert_eq!(text, input);
+});
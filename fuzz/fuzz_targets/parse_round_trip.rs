@@ -0,0 +1,36 @@
+//! Writes any document that parses and parses the output again, which must
+//! give back the same document. Floats with an integral value are written
+//! as integers, so values are compared canonicalized.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spa_json::ser::{to_string_value, SerializerOptions};
+use spa_json::value::Value;
+
+fuzz_target!(|input: &str| {
+    let Ok(mut value) = input.parse::<Value>() else {
+        return;
+    };
+    if !finite(&value) {
+        return;
+    }
+    let written = to_string_value(&value, SerializerOptions::new()).unwrap();
+    let mut read_back: Value = written
+        .parse()
+        .unwrap_or_else(|e| panic!("{e} in {written:?}"));
+    value.canonicalize();
+    read_back.canonicalize();
+    assert_eq!(read_back, value, "{written}");
+});
+
+/// Whether all floats in `value` are finite. Others are written as `inf`
+/// or `NaN`, which are read back as strings.
+fn finite(value: &Value) -> bool {
+    match value {
+        Value::Number(n) => n.as_f64().is_finite(),
+        Value::Array(items) => items.iter().all(finite),
+        Value::Object(map) => map.iter().all(|(_, v)| finite(v)),
+        _ => true,
+    }
+}
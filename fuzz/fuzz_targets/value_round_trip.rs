@@ -0,0 +1,17 @@
+//! Writes generated values, with the iterative writer for `Value` and
+//! through serde, and checks that both parsers read them back unchanged.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spa_json::de::{from_str_value, ParseOptions};
+use spa_json::ser::{to_string, to_string_value, SerializerOptions};
+use spa_json::value::Value;
+
+fuzz_target!(|value: Value| {
+    let written = to_string_value(&value, SerializerOptions::new()).unwrap();
+    assert_eq!(written, to_string(&value).unwrap());
+    assert_eq!(written.parse::<Value>().unwrap(), value, "{written}");
+    let iterative = from_str_value(&written, ParseOptions::default()).unwrap();
+    assert_eq!(iterative, value, "{written}");
+});
@@ -0,0 +1,148 @@
+//! `#[derive(SpaJsonComments)]`, re-exported by `spa-json` with its
+//! `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, Fields, Lit, LitStr, Token};
+
+/// Implements `SpaJsonComments` for a struct with named fields, taking the
+/// comment of each field from its doc comment, or from
+/// `#[spa_json(comment = "...")]`.
+///
+/// Keys are the field names, or the name given with `#[serde(rename =
+/// "...")]`. Fields marked `#[spa_json(nested)]` look up the comments
+/// below them in their own type's implementation.
+#[proc_macro_derive(SpaJsonComments, attributes(spa_json))]
+pub fn derive_spa_json_comments(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// What the attributes of a field say.
+#[derive(Default)]
+struct FieldAttrs {
+    key: Option<String>,
+    comment: Option<String>,
+    nested: bool,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(unsupported(input)),
+        },
+        _ => return Err(unsupported(input)),
+    };
+
+    let mut arms = Vec::new();
+    for field in fields {
+        let attrs = field_attrs(&field.attrs)?;
+        let key = match attrs.key {
+            Some(key) => key,
+            None => field.ident.as_ref().unwrap().to_string(),
+        };
+        let comment = match &attrs.comment {
+            Some(comment) => quote!(::std::option::Option::Some(#comment.to_owned())),
+            None => quote!(::std::option::Option::None),
+        };
+        let ty = &field.ty;
+        if attrs.nested {
+            arms.push(quote! {
+                [::spa_json::path::PathSegment::Key(key), rest @ ..] if key == #key => {
+                    if rest.is_empty() {
+                        #comment
+                    } else {
+                        <#ty as ::spa_json::comments::SpaJsonComments>::comment(rest)
+                    }
+                }
+            });
+        } else if attrs.comment.is_some() {
+            arms.push(quote! {
+                [::spa_json::path::PathSegment::Key(key)] if key == #key => #comment,
+            });
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::spa_json::comments::SpaJsonComments for #name #ty_generics #where_clause {
+            fn comment(
+                path: &[::spa_json::path::PathSegment],
+            ) -> ::std::option::Option<::std::string::String> {
+                match path {
+                    #(#arms)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+fn unsupported(input: &DeriveInput) -> syn::Error {
+    syn::Error::new_spanned(
+        &input.ident,
+        "SpaJsonComments can only be derived for structs with named fields",
+    )
+}
+
+fn field_attrs(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+    let mut result = FieldAttrs::default();
+    let mut doc = Vec::new();
+    for attr in attrs {
+        if attr.path().is_ident("doc") {
+            if let syn::Meta::NameValue(meta) = &attr.meta {
+                if let Expr::Lit(expr) = &meta.value {
+                    if let Lit::Str(line) = &expr.lit {
+                        let line = line.value();
+                        doc.push(line.strip_prefix(' ').unwrap_or(&line).to_owned());
+                    }
+                }
+            }
+        } else if attr.path().is_ident("serde") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") && meta.input.peek(Token![=]) {
+                    result.key = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    skip(meta)
+                }
+            })?;
+        } else if attr.path().is_ident("spa_json") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("comment") {
+                    result.comment = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else if meta.path.is_ident("nested") {
+                    result.nested = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `comment` or `nested`"))
+                }
+            })?;
+        }
+    }
+    while doc.last().is_some_and(|line| line.trim().is_empty()) {
+        doc.pop();
+    }
+    if result.comment.is_none() && !doc.is_empty() {
+        result.comment = Some(doc.join("\n"));
+    }
+    Ok(result)
+}
+
+/// Skips a serde attribute this derive does not care about.
+fn skip(meta: ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(skip)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,282 @@
+//! A document model allocated in a [`bumpalo`] arena, for workloads that
+//! parse a document, look at it and drop it.
+//!
+//! Nodes, keys and strings all live in the arena, so parsing makes no heap
+//! allocation per node, and dropping the arena frees the whole document at
+//! once. Values are `Copy` references into the arena.
+//!
+//! ```
+//! use bumpalo::Bump;
+//! use spa_json::arena;
+//!
+//! let bump = Bump::new();
+//! let value = arena::from_str(&bump, "node.name = alsa_output, channels = [ FL FR ]").unwrap();
+//! assert_eq!(value.get("node.name").and_then(|v| v.as_str()), Some("alsa_output"));
+//! assert_eq!(value.get("channels").and_then(|v| v.as_array()).map(|a| a.len()), Some(2));
+//! ```
+
+use crate::de::{Deserializer, Error};
+use crate::value::{self, Number};
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Value<'b> {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(&'b str),
+    Array(&'b [Value<'b>]),
+    /// Entries in the order they are written, with the values of
+    /// duplicate keys replaced in place as in [`value::Map`].
+    Object(&'b [(&'b str, Value<'b>)]),
+}
+
+/// Parses a document into `bump`.
+pub fn from_str<'b>(bump: &'b Bump, input: &str) -> Result<Value<'b>, Error> {
+    let mut deserializer = Deserializer::from_str(input);
+    let value = deserializer.value_seed(Seed { bump })?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+impl<'b> Value<'b> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&'b str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&'b [Value<'b>]> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&'b [(&'b str, Value<'b>)]> {
+        match self {
+            Value::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is an object.
+    pub fn get(&self, key: &str) -> Option<&'b Value<'b>> {
+        self.as_object()?
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Copies the value out of the arena into an owned [`value::Value`].
+    pub fn into_owned(self) -> value::Value {
+        match self {
+            Value::Null => value::Value::Null,
+            Value::Bool(b) => value::Value::Bool(b),
+            Value::Number(n) => value::Value::Number(n),
+            Value::String(s) => value::Value::from(s),
+            Value::Array(a) => value::Value::Array(a.iter().map(|v| v.into_owned()).collect()),
+            Value::Object(m) => {
+                value::Value::Object(m.iter().map(|(k, v)| (*k, v.into_owned())).collect())
+            }
+        }
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => n.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => {
+                let mut seq = serializer.serialize_seq(Some(a.len()))?;
+                for v in a.iter() {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Object(m) => {
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m.iter() {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Deserializes values into the arena.
+#[derive(Clone, Copy)]
+struct Seed<'b> {
+    bump: &'b Bump,
+}
+
+impl<'de, 'b> DeserializeSeed<'de> for Seed<'b> {
+    type Value = Value<'b>;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value<'b>, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de, 'b> Visitor<'de> for Seed<'b> {
+    type Value = Value<'b>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any SPA-JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value<'b>, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value<'b>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value<'b>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value<'b>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value<'b>, E> {
+        Ok(Value::String(self.bump.alloc_str(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value<'b>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value<'b>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Value<'b>, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value<'b>, A::Error> {
+        let mut array = BumpVec::new_in(self.bump);
+        while let Some(v) = seq.next_element_seed(self)? {
+            array.push(v);
+        }
+        Ok(Value::Array(array.into_bump_slice()))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value<'b>, A::Error> {
+        let mut entries: BumpVec<(&str, Value)> = BumpVec::new_in(self.bump);
+        while let Some(k) = access.next_key_seed(KeySeed { bump: self.bump })? {
+            let v = access.next_value_seed(self)?;
+            match entries.iter_mut().find(|(key, _)| *key == k) {
+                Some((_, slot)) => *slot = v,
+                None => entries.push((k, v)),
+            }
+        }
+        Ok(Value::Object(entries.into_bump_slice()))
+    }
+}
+
+/// Deserializes a key into the arena.
+struct KeySeed<'b> {
+    bump: &'b Bump,
+}
+
+impl<'de, 'b> DeserializeSeed<'de> for KeySeed<'b> {
+    type Value = &'b str;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<&'b str, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de, 'b> Visitor<'de> for KeySeed<'b> {
+    type Value = &'b str;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<&'b str, E> {
+        Ok(self.bump.alloc_str(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+
+    #[test]
+    fn test_arena() {
+        let input = r#"
+            node.name = alsa_output
+            "node.nick" = "Speakers \"L\""
+            channels = [ FL FR ]
+            rate = 48000
+            props = { a = null, b = [ 1.5, true ] }
+            node.name = alsa_input
+        "#;
+        let bump = Bump::new();
+        let value = from_str(&bump, input).unwrap();
+        assert_eq!(value.as_object().unwrap().len(), 5);
+        assert_eq!(value.get("node.name").unwrap().as_str(), Some("alsa_input"));
+        assert_eq!(
+            value.get("node.nick").unwrap().as_str(),
+            Some("Speakers \"L\"")
+        );
+        assert_eq!(value.get("rate").and_then(Value::as_u64), Some(48000));
+        assert!(bump.allocated_bytes() > 0);
+
+        let owned: value::Value = input.parse().unwrap();
+        assert_eq!(value.into_owned(), owned);
+        assert_eq!(to_string(&value).unwrap(), to_string(&owned).unwrap());
+        assert_eq!(
+            from_str(&bump, "a = [ 1").unwrap_err().to_string(),
+            "expected array close bracket at line 1 column 8"
+        );
+    }
+}
@@ -0,0 +1,499 @@
+//! A language server for SPA-JSON configuration files, speaking the
+//! Language Server Protocol over standard input and output.
+//!
+//! It publishes syntax errors and lints as diagnostics, shows what is known
+//! about PipeWire keys on hover, formats documents without comments and
+//! jumps from a module name to the module in `context.modules`. JSON-RPC
+//! messages are JSON, which is SPA-JSON as well, so they are read and
+//! written with this crate.
+
+use spa_json::de::{parse_with_recovery, Error};
+use spa_json::highlight::{classify, TokenClass};
+use spa_json::lexer::{Lexer, TokenKind};
+use spa_json::lint::{self, Kind};
+use spa_json::path::{DisplayPath, PathSegment};
+use spa_json::schema::{Range, Schema};
+use spa_json::schemas;
+use spa_json::ser::{self, SerializerOptions};
+use spa_json::source_map::{parse_with_source_map, Span};
+use spa_json::value::{Map, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// LSP diagnostic severities.
+const ERROR: u64 = 1;
+const WARNING: u64 = 2;
+
+fn main() -> ExitCode {
+    let mut server = Server {
+        documents: HashMap::new(),
+        shutdown: false,
+        output: io::stdout(),
+    };
+    let mut input = io::stdin().lock();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("spa-json-lsp: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        let message = match message.parse::<Value>() {
+            Ok(message) => message,
+            Err(e) => {
+                server.send_error(Value::Null, PARSE_ERROR, e.to_string());
+                continue;
+            }
+        };
+        let method = message.get("method").and_then(Value::as_str);
+        let null = Value::Null;
+        let params = message.get("params").unwrap_or(&null);
+        match (method, message.get("id")) {
+            (Some("exit"), _) if server.shutdown => return ExitCode::SUCCESS,
+            (Some("exit"), _) => return ExitCode::FAILURE,
+            (Some(method), Some(id)) => match server.request(method, params) {
+                Some(result) => server.send_result(id.clone(), result),
+                None => server.send_error(
+                    id.clone(),
+                    METHOD_NOT_FOUND,
+                    format!("unknown method {:?}", method),
+                ),
+            },
+            (Some(method), None) => server.notification(method, params),
+            (None, id) => server.send_error(
+                id.cloned().unwrap_or_default(),
+                INVALID_REQUEST,
+                "missing method".to_owned(),
+            ),
+        }
+    }
+}
+
+/// Reads the body of the next message, or `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header")
+    })?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+struct Server {
+    /// The text of the open documents by URI.
+    documents: HashMap<String, String>,
+    shutdown: bool,
+    output: io::Stdout,
+}
+
+impl Server {
+    fn send(&mut self, message: Value) {
+        let body = message.to_canonical_string();
+        let mut output = self.output.lock();
+        // A client that went away is noticed when reading.
+        let _ = write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = output.flush();
+    }
+
+    fn send_result(&mut self, id: Value, result: Value) {
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("result", result),
+        ]));
+    }
+
+    fn send_error(&mut self, id: Value, code: i64, message: String) {
+        let error = object([
+            ("code", Value::from(code)),
+            ("message", Value::from(message)),
+        ]);
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("id", id),
+            ("error", error),
+        ]));
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(object([
+            ("jsonrpc", Value::from("2.0")),
+            ("method", Value::from(method)),
+            ("params", params),
+        ]));
+    }
+
+    /// Answers a request, or returns `None` if the method is not supported.
+    fn request(&mut self, method: &str, params: &Value) -> Option<Value> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|document| document.get("uri"))
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let offset = || {
+            let text = self.documents.get(uri)?;
+            let position = params.get("position")?;
+            Some(offset(
+                text,
+                position.get("line")?.as_u64()? as usize,
+                position.get("character")?.as_u64()? as usize,
+            ))
+        };
+        Some(match method {
+            "initialize" => initialize(),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/hover" => offset()
+                .and_then(|offset| hover(uri, &self.documents[uri], offset))
+                .unwrap_or_default(),
+            "textDocument/definition" => offset()
+                .and_then(|offset| definition(uri, &self.documents[uri], offset))
+                .unwrap_or_default(),
+            "textDocument/formatting" => self
+                .documents
+                .get(uri)
+                .and_then(|text| format(text))
+                .unwrap_or_default(),
+            _ => return None,
+        })
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let Some(document) = params.get("textDocument") else {
+            return;
+        };
+        let Some(uri) = document.get("uri").and_then(Value::as_str) else {
+            return;
+        };
+        let text = match method {
+            "textDocument/didOpen" => document.get("text").and_then(Value::as_str),
+            // With full synchronization, the last change holds the text.
+            "textDocument/didChange" => params
+                .get("contentChanges")
+                .and_then(Value::as_array)
+                .and_then(|changes| changes.last())
+                .and_then(|change| change.get("text"))
+                .and_then(Value::as_str),
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                self.publish(uri, Vec::new());
+                return;
+            }
+            _ => return,
+        };
+        if let Some(text) = text {
+            let diagnostics = diagnostics(uri, text);
+            self.documents.insert(uri.to_owned(), text.to_owned());
+            self.publish(uri, diagnostics);
+        }
+    }
+
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) {
+        self.notify(
+            "textDocument/publishDiagnostics",
+            object([
+                ("uri", Value::from(uri)),
+                ("diagnostics", Value::from(diagnostics)),
+            ]),
+        );
+    }
+}
+
+fn initialize() -> Value {
+    let capabilities = object([
+        // Full text on every change.
+        ("textDocumentSync", Value::from(1u64)),
+        ("hoverProvider", Value::from(true)),
+        ("definitionProvider", Value::from(true)),
+        ("documentFormattingProvider", Value::from(true)),
+    ]);
+    let info = object([
+        ("name", Value::from("spa-json-lsp")),
+        ("version", Value::from(env!("CARGO_PKG_VERSION"))),
+    ]);
+    object([("capabilities", capabilities), ("serverInfo", info)])
+}
+
+/// The schema for a document, picked by its file name as in `spa-json
+/// lint`.
+fn schema_for(uri: &str) -> Schema {
+    uri.rsplit('/')
+        .next()
+        .and_then(schemas::by_name)
+        .unwrap_or(Schema::Any)
+}
+
+/// Syntax errors if the text does not parse, otherwise lints.
+fn diagnostics(uri: &str, text: &str) -> Vec<Value> {
+    let (value, map) = match parse_with_source_map(text) {
+        Ok(parsed) => parsed,
+        Err(_) => {
+            let errors = parse_with_recovery(text).err().unwrap_or_default();
+            return errors
+                .into_iter()
+                .filter_map(|error| match error {
+                    Error::Parse {
+                        message,
+                        line,
+                        column,
+                        ..
+                    } => {
+                        let offset = error_offset(text, line, column);
+                        let range = range_value(text, offset..offset);
+                        Some(diagnostic(range, ERROR, "syntax", message))
+                    }
+                    _ => None,
+                })
+                .collect();
+        }
+    };
+    lint::lint(&schema_for(uri), &value)
+        .into_iter()
+        .map(|lint| {
+            let (severity, code) = match lint.kind {
+                Kind::UnknownKey => (ERROR, "unknown-key"),
+                Kind::Invalid => (ERROR, "invalid-value"),
+                Kind::Deprecated => (WARNING, "deprecated-key"),
+            };
+            let range = map.nearest(&lint.path).map_or(0..0, |span| {
+                span.key.clone().unwrap_or_else(|| span.value.clone())
+            });
+            let message = match lint.path.is_empty() {
+                true => lint.message,
+                false => format!("{}: {}", DisplayPath(&lint.path), lint.message),
+            };
+            diagnostic(range_value(text, range), severity, code, message)
+        })
+        .collect()
+}
+
+fn diagnostic(range: Value, severity: u64, code: &str, message: String) -> Value {
+    object([
+        ("range", range),
+        ("severity", Value::from(severity)),
+        ("code", Value::from(code)),
+        ("source", Value::from("spa-json")),
+        ("message", Value::from(message)),
+    ])
+}
+
+/// Describes the key under the cursor: its type from the document's schema,
+/// or from the well-known properties.
+fn hover(uri: &str, text: &str, offset: usize) -> Option<Value> {
+    let (_, map) = parse_with_source_map(text).ok()?;
+    let (path, span) = map.iter().find(|(_, span)| {
+        span.key
+            .as_ref()
+            .is_some_and(|key| key.start <= offset && offset <= key.end)
+    })?;
+    let Some(PathSegment::Key(key)) = path.last() else {
+        return None;
+    };
+    let schema = schema_for(uri);
+    let properties = schemas::properties();
+    let schema = match schema_at(&schema, path) {
+        Some(schema) if *schema != Schema::Any => schema,
+        _ => &properties.get(key)?.schema,
+    };
+    let contents = object([
+        ("kind", Value::from("markdown")),
+        (
+            "value",
+            Value::from(format!("`{}`: {}", key, describe(schema))),
+        ),
+    ]);
+    Some(object([
+        ("contents", contents),
+        ("range", range_value(text, span.key.clone()?)),
+    ]))
+}
+
+/// The schema of the value at `path`, if `schema` describes it.
+fn schema_at<'a>(schema: &'a Schema, path: &[PathSegment]) -> Option<&'a Schema> {
+    let Some((first, rest)) = path.split_first() else {
+        return Some(schema);
+    };
+    let next = match (schema, first) {
+        (Schema::Object(object), PathSegment::Key(key)) => object
+            .get(key)
+            .map(|property| &property.schema)
+            .or(object.additional.as_deref())?,
+        (Schema::Array(items), PathSegment::Index(_)) => items,
+        (Schema::OneOf(alternatives), _) => {
+            return alternatives.iter().find_map(|s| schema_at(s, path))
+        }
+        _ => return None,
+    };
+    schema_at(next, rest)
+}
+
+fn describe(schema: &Schema) -> String {
+    match schema {
+        Schema::Integer(range) | Schema::Float(range) => {
+            format!("{}{}", schema, describe_range(range))
+        }
+        Schema::Enum(values) => {
+            let values: Vec<String> = values.iter().map(Value::to_canonical_string).collect();
+            format!("one of {}", values.join(", "))
+        }
+        Schema::Array(items) => format!("array of {}", describe(items)),
+        Schema::Object(object) if !object.properties.is_empty() => {
+            let keys: Vec<String> = object
+                .properties
+                .iter()
+                .map(|property| format!("`{}`", property.name))
+                .collect();
+            format!("object with {}", keys.join(", "))
+        }
+        _ => schema.to_string(),
+    }
+}
+
+fn describe_range(range: &Range) -> String {
+    // Bounds at the end of the integer range only mean there is none.
+    let bound = |b: Option<f64>| b.filter(|b| b.abs() < 1e15);
+    match (bound(range.min), bound(range.max)) {
+        (Some(min), Some(max)) => format!(" from {} to {}", min, max),
+        (Some(min), None) => format!(" of at least {}", min),
+        (None, Some(max)) => format!(" of at most {}", max),
+        (None, None) => String::new(),
+    }
+}
+
+/// Finds the module in `context.modules` named by the string under the
+/// cursor.
+fn definition(uri: &str, text: &str, offset: usize) -> Option<Value> {
+    let (value, map) = parse_with_source_map(text).ok()?;
+    let (span, _) = classify(text).into_iter().find(|(span, class)| {
+        *class == TokenClass::String && span.start <= offset && offset <= span.end
+    })?;
+    let token: Value = text[span].parse().ok()?;
+    let name = token.as_str()?;
+    let modules = PathSegment::Key("context.modules".to_owned());
+    let index = value
+        .get("context.modules")?
+        .as_array()?
+        .iter()
+        .position(|module| module.get("name").and_then(Value::as_str) == Some(name))?;
+    let path = [
+        modules,
+        PathSegment::Index(index),
+        PathSegment::Key("name".to_owned()),
+    ];
+    let Span { value: target, .. } = map.get(&path)?;
+    Some(object([
+        ("uri", Value::from(uri)),
+        ("range", range_value(text, target.clone())),
+    ]))
+}
+
+/// Reformats the whole document like `spa-json fmt`. Documents with
+/// comments are left alone, since formatting would drop them.
+fn format(text: &str) -> Option<Value> {
+    if Lexer::new(text).any(|token| token.kind == TokenKind::Comment) {
+        return None;
+    }
+    let value: Value = text.parse().ok()?;
+    let options = SerializerOptions::new()
+        .braceless(is_braceless(text))
+        .trailing_newline(true);
+    let formatted = ser::to_string_with_options(&value, options).ok()?;
+    let edit = object([
+        ("range", range_value(text, 0..text.len())),
+        ("newText", Value::from(formatted)),
+    ]);
+    Some(Value::from(vec![edit]))
+}
+
+fn is_braceless(text: &str) -> bool {
+    !text.trim_start().starts_with(['{', '['])
+}
+
+fn object<const N: usize>(entries: [(&str, Value); N]) -> Value {
+    Value::Object(entries.into_iter().collect::<Map>())
+}
+
+/// The LSP range of the bytes `range` of `text`.
+fn range_value(text: &str, range: std::ops::Range<usize>) -> Value {
+    object([
+        ("start", position(text, range.start)),
+        ("end", position(text, range.end)),
+    ])
+}
+
+/// The LSP position of byte `offset`: a line counted from 0 and a column
+/// in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    object([
+        ("line", Value::from(before.matches('\n').count() as u64)),
+        (
+            "character",
+            Value::from(before[line_start..].encode_utf16().count() as u64),
+        ),
+    ])
+}
+
+/// The byte offset of an LSP position, clamped to the end of its line.
+fn offset(text: &str, line: usize, character: usize) -> usize {
+    let line_start = match line {
+        0 => 0,
+        _ => match text.match_indices('\n').nth(line - 1) {
+            Some((i, _)) => i + 1,
+            None => return text.len(),
+        },
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+/// The byte offset of a parse error at `line` and `column`, which count
+/// characters from 1.
+fn error_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start = match line {
+        0 | 1 => 0,
+        _ => text
+            .match_indices('\n')
+            .nth(line - 2)
+            .map_or(text.len(), |(i, _)| i + 1),
+    };
+    text[line_start..]
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(text.len(), |(i, _)| line_start + i)
+}
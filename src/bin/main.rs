@@ -1,22 +1,832 @@
-use serde::Serialize;
+use spa_json::de::parse_with_recovery;
+use spa_json::dump::Object;
+use spa_json::filter::Filter;
+use spa_json::highlight::{to_ansi, to_html, HTML_STYLE};
+use spa_json::lua;
+use spa_json::matches::Match;
+use spa_json::path::{DisplayPath, PathSegment, SpaPath};
+use spa_json::properties::Properties;
+use spa_json::schema::{Diagnostic, Schema};
+use spa_json::ser::{self, SerializerOptions};
+use spa_json::source_map::{line_column, parse_with_source_map, SourceMap};
+use spa_json::value::{Map, Value};
+use std::io::{IsTerminal, Read};
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-#[derive(Serialize)]
-struct Person {
-    age: f64,
-    name: String,
-    friends: Vec<Person>,
+const USAGE: &str = "usage: spa-json validate [--schema NAME] [FILE...]
+       spa-json lint [--schema NAME] [FILE...]
+       spa-json convert [--from FORMAT] [--to FORMAT] [FILE]
+       spa-json fmt [--minimal] [-i[SUFFIX]] [FILE...]
+       spa-json get PATH [FILE]
+       spa-json query FILTER [FILE]
+       spa-json set [-i[SUFFIX]] PATH VALUE [FILE]
+       spa-json merge [-i[SUFFIX]] FILE FRAGMENT...
+       spa-json dump [--filter KEY=VALUE]... [FILE]
+       spa-json render [--html] [FILE]
+
+Arguments after `--` are never taken for options. Input is read from
+standard input when FILE is missing or `-`. lint picks the schema from the
+file name unless --schema is given, and checks what can be read of files
+with syntax errors. With -i or --in-place[=SUFFIX], FILE is rewritten
+instead, keeping a backup with SUFFIX if one is given.
+fmt --minimal keeps comments and leaves lines that are already formatted
+as they are.
+dump runs pw-dump if there is no FILE and standard input is a terminal; its
+filters match object properties as the matches of WirePlumber rules do, with
+VALUE as a regex if it starts with ~ and a glob otherwise.
+FORMAT is one of spa-json, json, yaml, toml and lua, which can only be
+written. JSON is written on one line with the keys of objects sorted.
+PATH is a dotted path such as context.modules[0].args; keys containing dots
+can be quoted.
+query runs a jq-like FILTER such as '.context.modules | map(.name)' and
+prints each of its outputs.
+render prints FILE as it is written, comments included, highlighted with
+ANSI colors even when standard output is not a terminal, or with --html as
+an HTML page.
+Every command accepts --color WHEN to highlight SPA-JSON output, where WHEN
+is auto (the default), always or never, and --format json to print
+diagnostics and errors as one JSON object per line, with the fields file,
+span, path, severity, code and message.";
+
+/// Name used for standard input in messages.
+const STDIN: &str = "-";
+
+/// Whether SPA-JSON printed to standard output is highlighted.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether diagnostics and errors are printed as JSON.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_format(&mut args)
+        .and_then(|()| parse_color(&mut args))
+        .map_err(Problem::from)
+        .and_then(|()| match args.split_first() {
+            Some((command, rest)) if command == "validate" => validate(rest),
+            Some((command, rest)) if command == "lint" => lint(rest),
+            Some((command, rest)) if command == "convert" => convert(rest),
+            Some((command, rest)) if command == "fmt" => fmt(rest),
+            Some((command, rest)) if command == "get" => get(rest),
+            Some((command, rest)) if command == "query" => query(rest),
+            Some((command, rest)) if command == "set" => set(rest),
+            Some((command, rest)) if command == "merge" => merge(rest),
+            Some((command, rest)) if command == "dump" => dump(rest),
+            Some((command, rest)) if command == "render" => render_highlighted(rest),
+            _ => Err(USAGE.into()),
+        });
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(problem) if JSON.load(Ordering::Relaxed) => {
+            eprintln!("{}", problem.to_json());
+            ExitCode::from(2)
+        }
+        Err(problem) => {
+            eprintln!("spa-json: {}", problem);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Something to report: a diagnostic in a file, or the error that stopped
+/// a command.
+struct Problem {
+    file: Option<String>,
+    /// Line and column, both counted from 1.
+    position: Option<(usize, usize)>,
+    /// Path of the offending value, for schema and lint diagnostics.
+    path: Option<String>,
+    warning: bool,
+    code: &'static str,
+    message: String,
+}
+
+impl Problem {
+    fn error(file: &str, code: &'static str, message: String) -> Problem {
+        Problem {
+            file: Some(file.to_owned()),
+            position: None,
+            path: None,
+            warning: false,
+            code,
+            message,
+        }
+    }
+
+    fn parse(file: &str, error: spa_json::de::Error) -> Problem {
+        match error {
+            spa_json::de::Error::Parse {
+                message,
+                line,
+                column,
+                path,
+            } => Problem {
+                position: Some((line, column)),
+                path: (!path.is_empty()).then(|| DisplayPath(&path).to_string()),
+                ..Problem::error(file, "syntax", message)
+            },
+            error => Problem::error(file, "syntax", error.to_string()),
+        }
+    }
+
+    fn schema(file: &str, diagnostic: &Diagnostic, position: Option<(usize, usize)>) -> Problem {
+        Problem {
+            position,
+            path: Some(DisplayPath(&diagnostic.path).to_string()),
+            ..Problem::error(file, "schema", diagnostic.message.clone())
+        }
+    }
+
+    #[cfg(feature = "schemas")]
+    fn lint(file: &str, lint: &spa_json::lint::Lint, position: Option<(usize, usize)>) -> Problem {
+        use spa_json::lint::Kind;
+        let code = match lint.kind {
+            Kind::UnknownKey => "unknown-key",
+            Kind::Invalid => "invalid-value",
+            Kind::Deprecated => "deprecated-key",
+        };
+        Problem {
+            position,
+            path: Some(DisplayPath(&lint.path).to_string()),
+            warning: lint.kind == Kind::Deprecated,
+            ..Problem::error(file, code, lint.message.clone())
+        }
+    }
+
+    /// One line of JSON with the fields `file`, `span`, `path`,
+    /// `severity`, `code` and `message`. Fields that do not apply are
+    /// `null`, as is `span` when the location is not known.
+    fn to_json(&self) -> String {
+        let mut map = Map::new();
+        map.insert(
+            "file",
+            self.file.as_deref().map_or(Value::Null, Value::from),
+        );
+        let span = self.position.map_or(Value::Null, |(line, column)| {
+            let mut span = Map::new();
+            span.insert("line", Value::from(line as u64));
+            span.insert("column", Value::from(column as u64));
+            Value::Object(span)
+        });
+        map.insert("span", span);
+        map.insert(
+            "path",
+            self.path.as_deref().map_or(Value::Null, Value::from),
+        );
+        let severity = if self.warning { "warning" } else { "error" };
+        map.insert("severity", Value::from(severity));
+        map.insert("code", Value::from(self.code));
+        map.insert("message", Value::from(self.message.as_str()));
+        Value::Object(map).to_canonical_string()
+    }
+}
+
+/// The text form, as `FILE: PATH: MESSAGE at line L column C`.
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file)?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "{}: ", path)?;
+        }
+        f.write_str(&self.message)?;
+        if let Some((line, column)) = self.position {
+            write!(f, " at line {} column {}", line, column)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for Problem {
+    fn from(message: String) -> Problem {
+        Problem {
+            file: None,
+            position: None,
+            path: None,
+            warning: false,
+            code: "usage",
+            message,
+        }
+    }
+}
+
+impl From<&str> for Problem {
+    fn from(message: &str) -> Problem {
+        Problem::from(message.to_owned())
+    }
+}
+
+/// Prints a diagnostic to standard output.
+fn report(problem: &Problem) {
+    if JSON.load(Ordering::Relaxed) {
+        println!("{}", problem.to_json());
+    } else {
+        println!("{}", problem);
+    }
+}
+
+/// Parses every file and checks it against the selected schema, printing one
+/// line per problem. Returns whether all files were valid.
+fn validate(args: &[String]) -> Result<bool, Problem> {
+    let (schema, files) = parse_schema(args)?;
+
+    let mut valid = true;
+    for file in files {
+        let input = read_input(file)?;
+        let Ok((value, map)) = parse_with_source_map(&input) else {
+            // Parse again to report every error rather than the first.
+            for e in parse_with_recovery(&input).err().unwrap_or_default() {
+                report(&Problem::parse(file, e));
+            }
+            valid = false;
+            continue;
+        };
+        if let Some(schema) = &schema {
+            for diagnostic in schema.validate(&value) {
+                let position = position(&input, &map, &diagnostic.path);
+                report(&Problem::schema(file, &diagnostic, position));
+                valid = false;
+            }
+        }
+    }
+    Ok(valid)
+}
+
+/// The line and column of the value at `path`, or of its key, or of the
+/// closest enclosing value if it is missing.
+fn position(input: &str, map: &SourceMap, path: &[PathSegment]) -> Option<(usize, usize)> {
+    let span = map.nearest(path)?;
+    let offset = span.key.as_ref().unwrap_or(&span.value).start;
+    Some(line_column(input, offset))
 }
 
-fn main() {
-    let alex = Person {
-        age: 30.,
-        name: "sander".to_owned(),
-        friends: vec![Person {
-            age: 55.33333,
-            name: "Horst Schlämmer".to_owned(),
-            friends: Vec::new()
-        }]
+/// Splits the arguments of validate and lint into the schema given with
+/// --schema and the files.
+fn parse_schema(args: &[String]) -> Result<(Option<Schema>, Vec<&str>), String> {
+    let mut schema = None;
+    let mut files = Vec::new();
+    let mut options = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" if options => options = false,
+            "--schema" if options => {
+                let name = args.next().ok_or("--schema requires a name")?;
+                schema = Some(load_schema(name)?);
+            }
+            _ if options && is_option(arg) => return Err(unknown_option(arg)),
+            _ => files.push(arg.as_str()),
+        }
+    }
+    if files.is_empty() {
+        files.push(STDIN);
+    }
+    Ok((schema, files))
+}
+
+/// Like [`validate`], but also flags misspelled, deprecated and mistyped
+/// properties, with suggestions.
+#[cfg(feature = "schemas")]
+fn lint(args: &[String]) -> Result<bool, Problem> {
+    let (schema, files) = parse_schema(args)?;
+
+    let mut clean = true;
+    for file in files {
+        let input = read_input(file)?;
+        // Lint what could be read of a broken file as well, without
+        // positions.
+        let (value, map) = match parse_with_source_map(&input) {
+            Ok((value, map)) => (value, map),
+            Err(_) => {
+                let (value, errors) = spa_json::de::parse_lossy(&input);
+                for e in errors {
+                    report(&Problem::parse(file, e));
+                    clean = false;
+                }
+                (value, SourceMap::default())
+            }
+        };
+        let detected = Path::new(file)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(spa_json::schemas::by_name);
+        let schema = schema
+            .as_ref()
+            .or(detected.as_ref())
+            .unwrap_or(&Schema::Any);
+        for lint in spa_json::lint::lint(schema, &value) {
+            let position = position(&input, &map, &lint.path);
+            report(&Problem::lint(file, &lint, position));
+            clean = false;
+        }
+    }
+    Ok(clean)
+}
+
+#[cfg(not(feature = "schemas"))]
+fn lint(_args: &[String]) -> Result<bool, Problem> {
+    Err("built without the `schemas` feature".into())
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    SpaJson,
+    /// Strict JSON, which is read as the SPA-JSON it also is.
+    Json,
+    Yaml,
+    Toml,
+    /// WirePlumber 0.4 Lua configuration, which can only be written.
+    Lua,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Result<Format, String> {
+        match name {
+            "spa-json" => Ok(Format::SpaJson),
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "lua" => Ok(Format::Lua),
+            _ => Err(format!("unknown format {:?}\n{}", name, USAGE)),
+        }
+    }
+
+    /// Guesses the format of a file from its extension.
+    fn detect(path: &Path) -> Format {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Json,
+            Some("yaml" | "yml") => Format::Yaml,
+            Some("toml") => Format::Toml,
+            Some("lua") => Format::Lua,
+            _ => Format::SpaJson,
+        }
+    }
+
+    fn parse(self, input: &str) -> Result<Value, spa_json::de::Error> {
+        match self {
+            Format::SpaJson | Format::Json => input.parse(),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => spa_json::yaml::from_yaml(input),
+            #[cfg(feature = "toml")]
+            Format::Toml => spa_json::toml::from_toml(input),
+            #[cfg(not(feature = "yaml"))]
+            Format::Yaml => Err(missing_feature("yaml")),
+            #[cfg(not(feature = "toml"))]
+            Format::Toml => Err(missing_feature("toml")),
+            Format::Lua => Err(spa_json::de::Error::Message(
+                "reading Lua is not supported".to_owned(),
+            )),
+        }
+    }
+
+    fn render(self, value: &Value) -> Result<String, spa_json::ser::Error> {
+        match self {
+            Format::SpaJson => {
+                let options = SerializerOptions::new()
+                    .braceless(true)
+                    .trailing_newline(true);
+                ser::to_string_value(value, options)
+            }
+            Format::Json => Ok(value.to_canonical_string() + "\n"),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => spa_json::yaml::to_yaml(value),
+            #[cfg(feature = "toml")]
+            Format::Toml => spa_json::toml::to_toml(value),
+            #[cfg(not(feature = "yaml"))]
+            Format::Yaml => Err(missing_feature("yaml")),
+            #[cfg(not(feature = "toml"))]
+            Format::Toml => Err(missing_feature("toml")),
+            Format::Lua => lua::to_lua_chunk(value),
+        }
+    }
+}
+
+#[cfg(not(all(feature = "yaml", feature = "toml")))]
+fn missing_feature(feature: &str) -> spa_json::Error {
+    spa_json::Error::Message(format!("built without the `{}` feature", feature))
+}
+
+/// Converts a file between SPA-JSON, YAML and TOML, printing the result.
+/// The input format is guessed from the file extension unless given.
+fn convert(args: &[String]) -> Result<bool, Problem> {
+    let mut from = None;
+    let mut to = Format::SpaJson;
+    let mut file = None;
+    let mut options = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" if options => options = false,
+            "--from" if options => {
+                from = Some(Format::from_name(
+                    args.next().ok_or("--from requires a format")?,
+                )?)
+            }
+            "--to" if options => {
+                to = Format::from_name(args.next().ok_or("--to requires a format")?)?
+            }
+            _ if options && is_option(arg) => return Err(unknown_option(arg).into()),
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let file = file.unwrap_or(STDIN);
+
+    let input = read_input(file)?;
+    let from = from.unwrap_or_else(|| Format::detect(Path::new(file)));
+    let value = from.parse(&input).map_err(|e| Problem::parse(file, e))?;
+    let output = to.render(&value).map_err(|e| format!("{}: {}", file, e))?;
+    match to {
+        Format::SpaJson => print_spa_json(&output),
+        _ => print!("{}", output),
+    }
+    Ok(true)
+}
+
+/// Reformats SPA-JSON files. Comments are not preserved, except with
+/// --minimal, which only changes the lines that are not formatted.
+fn fmt(args: &[String]) -> Result<bool, Problem> {
+    let minimal = args.first().is_some_and(|arg| arg == "--minimal");
+    let (in_place, mut files) = parse_in_place(&args[usize::from(minimal)..])?;
+    if files.is_empty() {
+        files.push(STDIN);
+    }
+    for file in files {
+        let input = read_input(file)?;
+        let output = if minimal {
+            spa_json::reformat::reformat(&input).map_err(|e| Problem::parse(file, e))?
+        } else {
+            let value = parse_spa_json(file, &input)?;
+            render(file, &value, is_braceless(&input))?
+        };
+        write_output(file, in_place, &output)?;
+    }
+    Ok(true)
+}
+
+/// Prints the value at a path: strings as they are, anything else as
+/// SPA-JSON. Fails if there is no value at the path.
+fn get(args: &[String]) -> Result<bool, Problem> {
+    let (path, file) = match positional(args) {
+        [path] => (path, STDIN),
+        [path, file] => (path, file.as_str()),
+        _ => return Err(USAGE.into()),
+    };
+    let path: SpaPath = path.parse().map_err(|e: spa_json::Error| e.to_string())?;
+    let input = read_input(file)?;
+    let value = parse_spa_json(file, &input)?;
+    match value.lookup(&path) {
+        Some(Value::String(s)) => println!("{}", s),
+        Some(value) => print_spa_json(&render(file, value, false)?),
+        None => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Prints the outputs of a filter: strings as they are, anything else as
+/// SPA-JSON.
+fn query(args: &[String]) -> Result<bool, Problem> {
+    let (filter, file) = match positional(args) {
+        [filter] => (filter, STDIN),
+        [filter, file] => (filter, file.as_str()),
+        _ => return Err(USAGE.into()),
     };
+    let filter: Filter = filter
+        .parse()
+        .map_err(|e: spa_json::de::Error| e.to_string())?;
+    let input = read_input(file)?;
+    let value = parse_spa_json(file, &input)?;
+    let outputs = filter.run(&value).map_err(|e| format!("{}: {}", file, e))?;
+    for output in outputs {
+        match &output {
+            Value::String(s) => println!("{}", s),
+            value => print_spa_json(&render(file, value, false)?),
+        }
+    }
+    Ok(true)
+}
+
+/// Sets the value at a path, creating missing objects on the way. The
+/// value is parsed as SPA-JSON, so `48000` is a number and `[ FL FR ]` an
+/// array.
+fn set(args: &[String]) -> Result<bool, Problem> {
+    let (in_place, args) = parse_in_place(args)?;
+    let (path, new, file) = match args[..] {
+        [path, new] => (path, new, STDIN),
+        [path, new, file] => (path, new, file),
+        _ => return Err(USAGE.into()),
+    };
+    let path: SpaPath = path.parse().map_err(|e: spa_json::Error| e.to_string())?;
+    let new: Value = new
+        .parse()
+        .map_err(|e| format!("invalid value {:?}: {}", new, e))?;
+    let input = read_input(file)?;
+    let mut value = parse_spa_json(file, &input)?;
+    value.assign(&path, new).map_err(|e| e.to_string())?;
+    let output = render(file, &value, is_braceless(&input))?;
+    write_output(file, in_place, &output)?;
+    Ok(true)
+}
+
+/// Merges fragments into a file the way PipeWire merges drop-in fragments.
+fn merge(args: &[String]) -> Result<bool, Problem> {
+    let (in_place, args) = parse_in_place(args)?;
+    let Some((file, fragments)) = args.split_first() else {
+        return Err(USAGE.into());
+    };
+    if fragments.is_empty() {
+        return Err(USAGE.into());
+    }
+    let input = read_input(file)?;
+    let mut value = parse_spa_json(file, &input)?;
+    for fragment in fragments {
+        let fragment = parse_spa_json(fragment, &read_input(fragment)?)?;
+        spa_json::merge(&mut value, fragment);
+    }
+    let output = render(file, &value, is_braceless(&input))?;
+    write_output(file, in_place, &output)?;
+    Ok(true)
+}
+
+/// Takes an option such as `--color WHEN` or `--color=WHEN` out of
+/// `args`, wherever it is before a `--`, returning its value.
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    let Some(i) = args
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .position(|arg| arg == name || arg.strip_prefix(name).is_some_and(|v| v.starts_with('=')))
+    else {
+        return Ok(None);
+    };
+    let arg = args.remove(i);
+    match arg.strip_prefix(name) {
+        Some("") if i < args.len() => Ok(Some(args.remove(i))),
+        Some(value) if value.starts_with('=') => Ok(Some(value[1..].to_owned())),
+        _ => Err(unknown_option(&arg)),
+    }
+}
+
+/// Takes the `--format text|json` option out of `args`.
+fn parse_format(args: &mut Vec<String>) -> Result<(), String> {
+    match take_option(args, "--format")?.as_deref() {
+        None | Some("text") => {}
+        Some("json") => JSON.store(true, Ordering::Relaxed),
+        Some(format) => return Err(format!("invalid --format value {:?}\n{}", format, USAGE)),
+    }
+    Ok(())
+}
+
+/// Takes the `--color WHEN` option out of `args`.
+fn parse_color(args: &mut Vec<String>) -> Result<(), String> {
+    let when = take_option(args, "--color")?.unwrap_or_else(|| "auto".to_owned());
+    let color = match when.as_str() {
+        "always" => true,
+        "never" => false,
+        "auto" => std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        _ => return Err(format!("invalid --color value {:?}\n{}", when, USAGE)),
+    };
+    COLOR.store(color, Ordering::Relaxed);
+    Ok(())
+}
+
+fn print_spa_json(output: &str) {
+    if COLOR.load(Ordering::Relaxed) {
+        print!("{}", to_ansi(output));
+    } else {
+        print!("{}", output);
+    }
+}
+
+/// Prints a file highlighted for a terminal or, with --html, as a web
+/// page. The file does not have to parse.
+fn render_highlighted(args: &[String]) -> Result<bool, Problem> {
+    let html = args.first().is_some_and(|arg| arg == "--html");
+    let file = match &args[usize::from(html)..] {
+        [] => STDIN,
+        [separator, file] if separator == "--" => file.as_str(),
+        [file] if !is_option(file) => file.as_str(),
+        [arg] => return Err(unknown_option(arg).into()),
+        _ => return Err(USAGE.into()),
+    };
+    let input = read_input(file)?;
+    if html {
+        let title = if file == STDIN {
+            "standard input"
+        } else {
+            file
+        };
+        print!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            title
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            HTML_STYLE,
+            to_html(&input)
+        );
+    } else {
+        print!("{}", to_ansi(&input));
+    }
+    Ok(true)
+}
+
+/// Prints the objects of a `pw-dump` whose properties match all filters.
+fn dump(args: &[String]) -> Result<bool, Problem> {
+    let mut filters = Vec::new();
+    let mut file = None;
+    let mut options = true;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--" if options => options = false,
+            "--filter" if options => {
+                let filter = args.next().ok_or("--filter requires KEY=VALUE")?;
+                let (key, value) = filter
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid filter {:?}, expected KEY=VALUE", filter))?;
+                let condition = Map::from_iter([(key, Value::from(value))]);
+                let filter = Match::new(&[condition])
+                    .map_err(|e| format!("invalid filter {:?}: {}", filter, e))?;
+                filters.push(filter);
+            }
+            _ if options && is_option(arg) => return Err(unknown_option(arg).into()),
+            _ if file.is_none() => file = Some(arg.as_str()),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let (name, input) = match file {
+        Some(file) => (file, read_input(file)?),
+        None if std::io::stdin().is_terminal() => ("pw-dump", run_pw_dump()?),
+        None => (STDIN, read_input(STDIN)?),
+    };
+    let objects = spa_json::dump::from_str(&input).map_err(|e| Problem::parse(name, e))?;
+    let matching: Vec<Value> = objects
+        .iter()
+        .filter(|object| {
+            let props = object.props().map(Properties::from).unwrap_or_default();
+            filters.iter().all(|filter| filter.matches(&props))
+        })
+        .filter_map(summarize)
+        .collect();
+    print_spa_json(&render(name, &Value::Array(matching), false)?);
+    Ok(true)
+}
+
+fn run_pw_dump() -> Result<String, String> {
+    let output = std::process::Command::new("pw-dump")
+        .output()
+        .map_err(|e| format!("failed to run pw-dump: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pw-dump failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| "pw-dump output is not UTF-8".to_owned())
+}
+
+/// The fields of a dumped object worth printing, or `None` for object
+/// types without a model.
+fn summarize(object: &Object) -> Option<Value> {
+    let mut map = Map::new();
+    map.insert("id", Value::from(object.id()?));
+    let kind = match object {
+        Object::Node(_) => "Node",
+        Object::Port(_) => "Port",
+        Object::Link(_) => "Link",
+        Object::Device(_) => "Device",
+        Object::Client(_) => "Client",
+        Object::Other => return None,
+    };
+    map.insert("type", Value::from(kind));
+    match object {
+        Object::Node(node) => {
+            if let Some(state) = &node.info.state {
+                map.insert("state", Value::from(state.as_str()));
+            }
+        }
+        Object::Port(port) => {
+            if let Some(direction) = &port.info.direction {
+                map.insert("direction", Value::from(direction.as_str()));
+            }
+        }
+        Object::Link(link) => {
+            let info = &link.info;
+            let output = format!("{}:{}", info.output_node_id, info.output_port_id);
+            let input = format!("{}:{}", info.input_node_id, info.input_port_id);
+            map.insert("output", Value::from(output));
+            map.insert("input", Value::from(input));
+        }
+        _ => {}
+    }
+    map.insert("props", Value::Object(object.props()?.clone()));
+    Some(Value::Object(map))
+}
+
+fn is_option(arg: &str) -> bool {
+    arg.starts_with('-') && arg != STDIN
+}
+
+/// The arguments of a command without options, after a leading `--`.
+fn positional(args: &[String]) -> &[String] {
+    match args {
+        [separator, rest @ ..] if separator == "--" => rest,
+        _ => args,
+    }
+}
+
+fn unknown_option(arg: &str) -> String {
+    format!("unknown option {}\n{}", arg, USAGE)
+}
+
+/// Splits off the `-i`/`--in-place` option, returning the backup suffix
+/// (empty for no backup) if it was given, and the remaining arguments.
+/// Options must come first, so that values such as `-1` are not taken
+/// for options, and end at a `--`.
+fn parse_in_place(args: &[String]) -> Result<(Option<&str>, Vec<&str>), String> {
+    let mut in_place = None;
+    let mut args = args.iter().map(String::as_str).peekable();
+    while let Some(arg) = args.next_if(|arg| is_option(arg)) {
+        if arg == "--" {
+            break;
+        }
+        let suffix = match arg.strip_prefix("--in-place") {
+            Some(suffix) if suffix.is_empty() => suffix,
+            Some(suffix) if suffix.starts_with('=') => &suffix[1..],
+            _ => arg.strip_prefix("-i").ok_or_else(|| unknown_option(arg))?,
+        };
+        in_place = Some(suffix);
+    }
+    Ok((in_place, args.collect()))
+}
+
+fn read_input(file: &str) -> Result<String, Problem> {
+    let mut input = String::new();
+    let result = if file == STDIN {
+        std::io::stdin().read_to_string(&mut input).map(|_| ())
+    } else {
+        std::fs::read_to_string(file).map(|s| input = s)
+    };
+    result.map_err(|e| Problem::error(file, "io", e.to_string()))?;
+    Ok(input)
+}
+
+/// Prints `output`, or replaces `file` with it if `in_place` is set,
+/// first copying the file to its name with the suffix appended.
+fn write_output(file: &str, in_place: Option<&str>, output: &str) -> Result<(), String> {
+    let Some(suffix) = in_place else {
+        print_spa_json(output);
+        return Ok(());
+    };
+    if file == STDIN {
+        return Err("--in-place requires a file".to_owned());
+    }
+    if !suffix.is_empty() {
+        std::fs::copy(file, format!("{}{}", file, suffix))
+            .map_err(|e| format!("{}{}: {}", file, suffix, e))?;
+    }
+    std::fs::write(file, output).map_err(|e| format!("{}: {}", file, e))
+}
+
+fn parse_spa_json(file: &str, input: &str) -> Result<Value, Problem> {
+    input.parse().map_err(|e| Problem::parse(file, e))
+}
+
+fn render(file: &str, value: &Value, braceless: bool) -> Result<String, String> {
+    let options = SerializerOptions::new()
+        .braceless(braceless)
+        .trailing_newline(true);
+    ser::to_string_value(value, options).map_err(|e| format!("{}: {}", file, e))
+}
+
+/// Whether a document is written without braces around its top-level
+/// object, as configuration files are.
+fn is_braceless(input: &str) -> bool {
+    let mut rest = input.trim_start();
+    while let Some(comment) = rest.strip_prefix('#') {
+        rest = comment.split_once('\n').map_or("", |(_, r)| r).trim_start();
+    }
+    !rest.starts_with(['{', '['])
+}
+
+#[cfg(feature = "schemas")]
+fn load_schema(name: &str) -> Result<Schema, String> {
+    spa_json::schemas::by_name(name).ok_or_else(|| {
+        format!(
+            "unknown schema {:?}, expected one of {}",
+            name,
+            spa_json::schemas::NAMES.join(", ")
+        )
+    })
+}
 
-    std::fs::write("a.spa-json", spa_json::spa_json_serializer::to_string(&alex).unwrap()).unwrap()
+#[cfg(not(feature = "schemas"))]
+fn load_schema(_name: &str) -> Result<Schema, String> {
+    Err("built without the `schemas` feature".to_owned())
 }
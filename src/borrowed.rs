@@ -0,0 +1,361 @@
+//! A document model whose strings borrow from the input, for read-only
+//! analysis of large documents such as `pw-dump` output.
+//!
+//! Keys and strings are only copied when they contain escapes, or when
+//! environment variables are expanded in them.
+//!
+//! ```
+//! use spa_json::borrowed;
+//! use std::borrow::Cow;
+//!
+//! let input = r#"node.name = alsa_output, node.nick = "Speakers \"L\"""#;
+//! let value = borrowed::from_str(input).unwrap();
+//! assert!(matches!(value.get("node.name"), Some(borrowed::Value::String(Cow::Borrowed(_)))));
+//! assert_eq!(value.get("node.nick").unwrap().as_str(), Some("Speakers \"L\""));
+//! ```
+
+use crate::de::Error;
+use crate::value::{self, Number};
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value<'a> {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+    Array(Vec<Value<'a>>),
+    Object(Map<'a>),
+}
+
+/// Insertion-ordered object map, like [`value::Map`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Map<'a> {
+    entries: Vec<(Cow<'a, str>, Value<'a>)>,
+}
+
+/// Parses a document, borrowing its strings from `input`.
+pub fn from_str(input: &str) -> Result<Value<'_>, Error> {
+    crate::de::from_str(input)
+}
+
+impl<'a> Value<'a> {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Number(n) => n.as_i64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) => n.as_u64(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(n.as_f64()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value<'a>>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map<'a>> {
+        match self {
+            Value::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is an object.
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        self.as_object().and_then(|m| m.get(key))
+    }
+
+    /// Copies the borrowed strings into an owned [`value::Value`].
+    pub fn into_owned(self) -> value::Value {
+        match self {
+            Value::Null => value::Value::Null,
+            Value::Bool(b) => value::Value::Bool(b),
+            Value::Number(n) => value::Value::Number(n),
+            Value::String(s) => value::Value::from(s.into_owned()),
+            Value::Array(a) => value::Value::Array(a.into_iter().map(Value::into_owned).collect()),
+            Value::Object(m) => value::Value::Object(
+                m.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl<'a> From<&'a value::Value> for Value<'a> {
+    fn from(value: &'a value::Value) -> Self {
+        match value {
+            value::Value::Null => Value::Null,
+            value::Value::Bool(b) => Value::Bool(*b),
+            value::Value::Number(n) => Value::Number(*n),
+            value::Value::String(s) => Value::String(Cow::Borrowed(s)),
+            value::Value::Array(a) => Value::Array(a.iter().map(Value::from).collect()),
+            value::Value::Object(m) => Value::Object(Map {
+                entries: m
+                    .iter()
+                    .map(|(k, v)| (Cow::Borrowed(k.as_str()), Value::from(v)))
+                    .collect(),
+            }),
+        }
+    }
+}
+
+impl<'a> Map<'a> {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value<'a>> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Inserts `value`, replacing the value of an existing `key` in place.
+    pub fn insert(&mut self, key: impl Into<Cow<'a, str>>, value: Value<'a>) -> Option<Value<'a>> {
+        let key = key.into();
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, slot)) => Some(std::mem::replace(slot, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value<'a>)> {
+        self.entries.iter().map(|(k, v)| (&**k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| &**k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value<'a>> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+impl<'a> IntoIterator for Map<'a> {
+    type Item = (Cow<'a, str>, Value<'a>);
+    type IntoIter = std::vec::IntoIter<(Cow<'a, str>, Value<'a>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl Serialize for Value<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => n.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => {
+                let mut seq = serializer.serialize_seq(Some(a.len()))?;
+                for v in a {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Object(m) => {
+                let mut map = serializer.serialize_map(Some(m.len()))?;
+                for (k, v) in m.iter() {
+                    map.serialize_entry(k, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value<'de>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any SPA-JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value<'de>, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value<'de>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value<'de>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value<'de>, E> {
+        Ok(Value::Number(Number::from(v)))
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Borrowed(v)))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value<'de>, E> {
+        Ok(Value::String(Cow::Owned(v)))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value<'de>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value<'de>, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value<'de>, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value<'de>, A::Error> {
+        let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element()? {
+            array.push(v);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value<'de>, A::Error> {
+        let mut map = Map {
+            entries: Vec::with_capacity(access.size_hint().unwrap_or(0)),
+        };
+        while let Some(k) = access.next_key_seed(KeySeed)? {
+            map.insert(k, access.next_value()?);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// Deserializes a key, borrowing it if the deserializer allows.
+struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = Cow<'de, str>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Cow<'de, str>, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object key")
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Borrowed(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Owned(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Cow<'de, str>, E> {
+        Ok(Cow::Owned(v))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value<'de>, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+
+    #[test]
+    fn test_borrowed() {
+        let input = r#"
+            node.name = alsa_output
+            "node.nick" = "Speakers \"L\""
+            "media\tclass" = Audio/Sink
+            channels = [ FL FR ]
+            rate = 48000
+            node.name = alsa_input
+        "#;
+        let value = from_str(input).unwrap();
+        let map = value.as_object().unwrap();
+        assert_eq!(map.len(), 5);
+        assert!(matches!(
+            value.get("node.name"),
+            Some(Value::String(Cow::Borrowed("alsa_input")))
+        ));
+        assert!(matches!(
+            value.get("node.nick"),
+            Some(Value::String(Cow::Owned(_)))
+        ));
+        assert!(matches!(map.entries[0].0, Cow::Borrowed(_)));
+        assert!(matches!(map.entries[2].0, Cow::Owned(_)));
+        assert_eq!(value.get("rate").and_then(Value::as_u64), Some(48000));
+        assert_eq!(value.get("channels").unwrap().as_array().unwrap().len(), 2);
+
+        let owned: value::Value = input.parse().unwrap();
+        assert_eq!(Value::from(&owned), value);
+        assert_eq!(to_string(&value).unwrap(), to_string(&owned).unwrap());
+        assert_eq!(value.into_owned(), owned);
+    }
+}
@@ -0,0 +1,211 @@
+//! Comments for the fields of typed configuration structs, so that
+//! serializing one writes a documented template file.
+//!
+//! [`SpaJsonComments::comment`] has the signature of a
+//! [`CommentFn`](crate::ser::CommentFn), so it can be passed
+//! to [`SerializerOptions::comments`] directly. With the `derive` feature,
+//! `#[derive(SpaJsonComments)]` takes the comments from the doc comments of
+//! the fields.
+//!
+//! ```
+//! use serde::Serialize;
+//! use spa_json::comments::SpaJsonComments;
+//! use spa_json::path::PathSegment;
+//! use spa_json::ser::{to_string_with_options, SerializerOptions};
+//!
+//! #[derive(Serialize)]
+//! struct Props {
+//!     rate: u32,
+//! }
+//!
+//! impl SpaJsonComments for Props {
+//!     fn comment(path: &[PathSegment]) -> Option<String> {
+//!         match path {
+//!             [PathSegment::Key(key)] if key == "rate" => Some("Sample rate in Hz".to_owned()),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! let options = SerializerOptions::new().braceless(true).comments(Props::comment);
+//! let output = to_string_with_options(&Props { rate: 48000 }, options).unwrap();
+//! assert_eq!(output, "# Sample rate in Hz\nrate = 48000\n");
+//! ```
+//!
+//! [`SerializerOptions::comments`]: crate::ser::SerializerOptions::comments
+
+use crate::error::Error;
+use crate::path::PathSegment;
+use crate::ser::{to_string_with_options, SerializerOptions};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::Serialize;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+#[cfg(feature = "derive")]
+pub use spa_json_derive::SpaJsonComments;
+
+/// Types that know the comments to write above their keys.
+pub trait SpaJsonComments {
+    /// The comment for the key at `path`, relative to a value of this type.
+    fn comment(path: &[PathSegment]) -> Option<String>;
+}
+
+/// Writes a configuration file listing every field of `T` under its
+/// comment, with its default value commented out, the way shipped files
+/// such as `pipewire.conf` present their options. Objects stay open, so
+/// that their fields are listed one by one.
+///
+/// ```
+/// use serde::Serialize;
+/// use spa_json::comments::{generate_template, SpaJsonComments};
+/// use spa_json::path::PathSegment;
+///
+/// #[derive(Default, Serialize)]
+/// struct Props {
+///     rate: u32,
+/// }
+///
+/// impl SpaJsonComments for Props {
+///     fn comment(_: &[PathSegment]) -> Option<String> {
+///         Some("Sample rate in Hz".to_owned())
+///     }
+/// }
+///
+/// assert_eq!(generate_template::<Props>().unwrap(), "# Sample rate in Hz\n#rate = 0\n");
+/// ```
+pub fn generate_template<T>() -> Result<String, Error>
+where
+    T: Default + Serialize + SpaJsonComments,
+{
+    let options = SerializerOptions::new()
+        .braceless(true)
+        .inline_width(80)
+        .comments(T::comment)
+        .comment_out_values(true);
+    to_string_with_options(&T::default(), options)
+}
+
+/// The items of an array share the comments of their type.
+impl<T: SpaJsonComments> SpaJsonComments for Vec<T> {
+    fn comment(path: &[PathSegment]) -> Option<String> {
+        match path {
+            [PathSegment::Index(_), rest @ ..] => T::comment(rest),
+            _ => None,
+        }
+    }
+}
+
+impl<T: SpaJsonComments> SpaJsonComments for Option<T> {
+    fn comment(path: &[PathSegment]) -> Option<String> {
+        T::comment(path)
+    }
+}
+
+impl<T: SpaJsonComments> SpaJsonComments for Box<T> {
+    fn comment(path: &[PathSegment]) -> Option<String> {
+        T::comment(path)
+    }
+}
+
+/// The values of a map share the comments of their type.
+impl<K, V: SpaJsonComments> SpaJsonComments for BTreeMap<K, V> {
+    fn comment(path: &[PathSegment]) -> Option<String> {
+        match path {
+            [PathSegment::Key(_), rest @ ..] => V::comment(rest),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V: SpaJsonComments, S> SpaJsonComments for HashMap<K, V, S> {
+    fn comment(path: &[PathSegment]) -> Option<String> {
+        BTreeMap::<K, V>::comment(path)
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use super::*;
+
+    #[derive(Default, Serialize, SpaJsonComments)]
+    struct Config {
+        /// Properties of the daemon.
+        ///
+        /// See pipewire(1).
+        #[serde(rename = "context.properties")]
+        #[spa_json(nested)]
+        properties: Properties,
+        /// Modules to load, in order.
+        #[serde(rename = "context.modules")]
+        #[spa_json(nested)]
+        modules: Vec<Module>,
+    }
+
+    #[derive(Default, Serialize, SpaJsonComments)]
+    struct Properties {
+        #[serde(rename = "default.clock.rate", skip_serializing_if = "Option::is_none")]
+        #[spa_json(comment = "Sample rate in Hz")]
+        rate: Option<u32>,
+        quantum: u32,
+    }
+
+    #[derive(Default, Serialize, SpaJsonComments)]
+    struct Module {
+        /// Library name.
+        name: String,
+    }
+
+    #[test]
+    fn test_derive() {
+        let config = Config {
+            properties: Properties {
+                rate: Some(48000),
+                quantum: 1024,
+            },
+            modules: vec![Module {
+                name: "libpipewire-module-rt".to_owned(),
+            }],
+        };
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .comments(Config::comment);
+        assert_eq!(
+            to_string_with_options(&config, options).unwrap(),
+            "# Properties of the daemon.\n\
+             #\n\
+             # See pipewire(1).\n\
+             context.properties = {\n  \
+               # Sample rate in Hz\n  \
+               default.clock.rate = 48000\n  \
+               quantum = 1024\n\
+             }\n\
+             # Modules to load, in order.\n\
+             context.modules = [\n  \
+               {\n    \
+                 # Library name.\n    \
+                 name = libpipewire-module-rt\n  \
+               }\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn test_generate_template() {
+        assert_eq!(
+            generate_template::<Config>().unwrap(),
+            "# Properties of the daemon.\n\
+             #\n\
+             # See pipewire(1).\n\
+             context.properties = {\n  \
+               #quantum = 0\n\
+             }\n\
+             # Modules to load, in order.\n\
+             #context.modules = [ ]\n"
+        );
+    }
+}
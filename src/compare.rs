@@ -0,0 +1,183 @@
+//! Comparing documents by what they mean rather than how they are written.
+//!
+//! Two values are semantically equal when their objects have the same
+//! entries in any order, their arrays the same items in the same order, and
+//! their numbers the same value, so that `48000`, `48000.0` and `4.8e4` are
+//! all equal. Floats can be allowed to differ by a tolerance, for values
+//! that went through arithmetic or a lossy format.
+//!
+//! [`assert_spa_json_eq!`](crate::assert_spa_json_eq) asserts this in tests
+//! and lists the differences when it fails:
+//!
+//! ```
+//! use spa_json::value::Value;
+//!
+//! let written: Value = "{ rate = 48000, channels = [ FL FR ] }".parse()?;
+//! let expected: Value = "channels = [ FL, FR ]\nrate = 48000.0".parse()?;
+//! spa_json::assert_spa_json_eq!(written, expected);
+//! # Ok::<(), spa_json::Error>(())
+//! ```
+
+use crate::diff::{diff_by, Change};
+use crate::path::DisplayPath;
+use crate::ser::{to_string_value, SerializerOptions};
+use crate::value::{Number, Value};
+use std::fmt::Write;
+
+/// How [`semantic_eq`] and [`semantic_diff`] compare values.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompareOptions {
+    float_tolerance: f64,
+}
+
+impl CompareOptions {
+    pub fn new() -> Self {
+        CompareOptions::default()
+    }
+
+    /// Treats numbers as equal when at least one of them is a float and
+    /// they differ by at most `tolerance`. Integers are always compared
+    /// exactly.
+    pub fn float_tolerance(mut self, tolerance: f64) -> Self {
+        self.float_tolerance = tolerance;
+        self
+    }
+}
+
+/// Whether `a` and `b` are the same document, ignoring the order of object
+/// entries and how numbers are written.
+pub fn semantic_eq(a: &Value, b: &Value, options: CompareOptions) -> bool {
+    semantic_diff(a, b, options).is_empty()
+}
+
+/// The differences from `old` to `new`, as [`diff`](crate::diff()) reports
+/// them, leaving out numbers that are semantically equal.
+pub fn semantic_diff(old: &Value, new: &Value, options: CompareOptions) -> Vec<Change> {
+    diff_by(old, new, &|a, b| match (a, b) {
+        (Value::Number(a), Value::Number(b)) => numbers_eq(a, b, options.float_tolerance),
+        _ => a == b,
+    })
+}
+
+fn numbers_eq(a: &Number, b: &Number, tolerance: f64) -> bool {
+    let integer = |n: &Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => {
+            let (a, b) = (a.as_f64(), b.as_f64());
+            a == b || (a - b).abs() <= tolerance || a.is_nan() && b.is_nan()
+        }
+    }
+}
+
+/// Checks `left` and `right` for [`assert_spa_json_eq!`], returning the
+/// failure message when they differ.
+///
+/// [`assert_spa_json_eq!`]: crate::assert_spa_json_eq
+#[doc(hidden)]
+pub fn check_eq(left: &Value, right: &Value, options: CompareOptions) -> Result<(), String> {
+    let changes = semantic_diff(left, right, options);
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let mut message = String::from("assertion `left == right` failed: the documents differ\n");
+    for change in &changes {
+        let path = DisplayPath(change.path());
+        match change {
+            Change::Added { value, .. } => writeln!(message, "+ {path} = {}", inline(value)),
+            Change::Removed { value, .. } => writeln!(message, "- {path} = {}", inline(value)),
+            Change::Modified { old, new, .. } => {
+                writeln!(message, "~ {path}: {} -> {}", inline(old), inline(new))
+            }
+        }
+        .unwrap();
+    }
+    message.pop();
+    Err(message)
+}
+
+fn inline(value: &Value) -> String {
+    let options = SerializerOptions::new().inline_width(usize::MAX);
+    to_string_value(value, options).unwrap_or_else(|_| format!("{value:?}"))
+}
+
+/// Asserts that two [`Value`]s are semantically equal, as
+/// [`semantic_eq`](crate::compare::semantic_eq) tells, and otherwise panics
+/// listing what was added (`+`), removed (`-`) and modified (`~`) going
+/// from the left value to the right one. Comparison options may follow
+/// the values:
+///
+/// ```
+/// use spa_json::compare::CompareOptions;
+/// use spa_json::value::Value;
+///
+/// let volume: Value = "volume = 0.30000000000000004".parse()?;
+/// let expected: Value = "volume = 0.3".parse()?;
+/// spa_json::assert_spa_json_eq!(volume, expected, CompareOptions::new().float_tolerance(1e-9));
+/// # Ok::<(), spa_json::Error>(())
+/// ```
+///
+/// [`Value`]: crate::value::Value
+#[macro_export]
+macro_rules! assert_spa_json_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_spa_json_eq!($left, $right, $crate::compare::CompareOptions::new())
+    };
+    ($left:expr, $right:expr, $options:expr $(,)?) => {
+        if let Err(message) = $crate::compare::check_eq(&$left, &$right, $options) {
+            panic!("{}", message);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(text: &str) -> Value {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_semantic_eq() {
+        let options = CompareOptions::new();
+        let a = value("{ rate = 48000, props = { a = 1 b = [ 1.5 2 ] } }");
+        let b = value("props = { b = [ 1.5, 2.0 ] a = 1 } rate = 4.8e4");
+        assert!(semantic_eq(&a, &b, options));
+        assert_ne!(a, b);
+        assert!(!semantic_eq(&value("[ 1 2 ]"), &value("[ 2 1 ]"), options));
+        assert!(!semantic_eq(&value("0.1"), &value("0.1000001"), options));
+        let tolerant = options.float_tolerance(1e-3);
+        assert!(semantic_eq(&value("0.1"), &value("0.1000001"), tolerant));
+        assert!(!semantic_eq(
+            &value("1"),
+            &value("2"),
+            tolerant.float_tolerance(2.0)
+        ));
+        assert!(semantic_eq(
+            &Value::from(u64::MAX),
+            &value("18446744073709551615"),
+            options
+        ));
+    }
+
+    #[test]
+    fn test_assert_message() {
+        let left = value("rate = 44100 props = { a = 1 } quantum = 1024");
+        let right = value("props = { a = 1 b = [ x ] } rate = 48000.0");
+        assert_eq!(
+            check_eq(&left, &right, CompareOptions::new()).unwrap_err(),
+            "assertion `left == right` failed: the documents differ\n\
+             ~ rate: 44100 -> 48000\n\
+             + props.b = [ x ]\n\
+             - quantum = 1024"
+        );
+        let result = std::panic::catch_unwind(|| assert_spa_json_eq!(left, right));
+        assert!(result.is_err());
+        assert_spa_json_eq!(left, left.clone());
+    }
+}
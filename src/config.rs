@@ -0,0 +1,9 @@
+//! Typed models of the configuration files shipped with PipeWire.
+//!
+//! Sections that are not modelled are kept as written in each model's
+//! `other` map, so a file survives a round trip through its model.
+
+pub mod client;
+pub mod jack;
+pub mod pipewire;
+pub mod pipewire_pulse;
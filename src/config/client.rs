@@ -0,0 +1,139 @@
+//! Typed model of `client.conf`, read by native PipeWire clients.
+//!
+//! [`StreamProperties`] is shared with `pipewire-pulse.conf`.
+
+use super::pipewire::Module;
+use crate::de::{self, Error};
+use crate::fraction::Fraction;
+use crate::properties::Properties;
+use crate::rules::Rule;
+use crate::value::Map;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(rename = "context.properties", skip_serializing_if = "Map::is_empty")]
+    pub properties: Map,
+    #[serde(
+        rename = "context.spa-libs",
+        skip_serializing_if = "Properties::is_empty"
+    )]
+    pub spa_libs: Properties,
+    #[serde(rename = "context.modules", skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<Module>,
+    /// Defaults for the properties of filters the client creates.
+    #[serde(rename = "filter.properties", skip_serializing_if = "Map::is_empty")]
+    pub filter_properties: Map,
+    #[serde(
+        rename = "stream.properties",
+        skip_serializing_if = "StreamProperties::is_empty"
+    )]
+    pub stream_properties: StreamProperties,
+    /// Per-stream overrides, with `update-props` applied to matching
+    /// streams.
+    #[serde(rename = "stream.rules", skip_serializing_if = "Vec::is_empty")]
+    pub stream_rules: Vec<Rule>,
+    /// Sections not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+/// Defaults for the properties of streams, `stream.properties`.
+///
+/// Values must have the type of their field; a quoted `"true"` does not
+/// deserialize into a `bool`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamProperties {
+    /// Requested latency as `frames/rate`, e.g. `1024/48000`.
+    #[serde(rename = "node.latency", skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Fraction>,
+    #[serde(rename = "node.autoconnect", skip_serializing_if = "Option::is_none")]
+    pub autoconnect: Option<bool>,
+    /// Resampler quality from 0 to 14.
+    #[serde(rename = "resample.quality", skip_serializing_if = "Option::is_none")]
+    pub resample_quality: Option<u32>,
+    #[serde(rename = "resample.disable", skip_serializing_if = "Option::is_none")]
+    pub resample_disable: Option<bool>,
+    #[serde(rename = "channelmix.disable", skip_serializing_if = "Option::is_none")]
+    pub channelmix_disable: Option<bool>,
+    #[serde(
+        rename = "channelmix.normalize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub channelmix_normalize: Option<bool>,
+    #[serde(rename = "channelmix.mix-lfe", skip_serializing_if = "Option::is_none")]
+    pub channelmix_mix_lfe: Option<bool>,
+    #[serde(rename = "channelmix.upmix", skip_serializing_if = "Option::is_none")]
+    pub channelmix_upmix: Option<bool>,
+    /// `none`, `simple` or `psd`.
+    #[serde(
+        rename = "channelmix.upmix-method",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub channelmix_upmix_method: Option<String>,
+    #[serde(rename = "dither.method", skip_serializing_if = "Option::is_none")]
+    pub dither_method: Option<String>,
+    /// Properties not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+impl StreamProperties {
+    pub fn is_empty(&self) -> bool {
+        *self == StreamProperties::default()
+    }
+}
+
+/// Parses the contents of a `client.conf` file.
+pub fn from_str(input: &str) -> Result<Config, Error> {
+    de::from_str(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+    use crate::value::Value;
+
+    #[test]
+    fn test_config() {
+        let input = r#"
+            context.modules = [ { name = libpipewire-module-protocol-native } ]
+            filter.properties = { node.latency = 1024/48000 }
+            stream.properties = {
+                node.latency = 1024/48000
+                node.autoconnect = true
+                resample.quality = 4
+                channelmix.upmix-method = psd
+                channelmix.lfe-cutoff = 150.5
+            }
+            stream.rules = [
+                {
+                    matches = [ { application.name = "~Chromium.*" } ]
+                    actions = { update-props = { node.latency = 2048/48000 } }
+                }
+            ]
+        "#;
+        let config = from_str(input).unwrap();
+        assert_eq!(config.modules.len(), 1);
+        assert!(config.filter_properties.contains_key("node.latency"));
+        let stream = &config.stream_properties;
+        assert_eq!(stream.latency, Some(Fraction::new(1024, 48000)));
+        assert_eq!(stream.autoconnect, Some(true));
+        assert_eq!(stream.resample_quality, Some(4));
+        assert_eq!(stream.channelmix_upmix_method.as_deref(), Some("psd"));
+        assert_eq!(
+            stream.other.get("channelmix.lfe-cutoff"),
+            Some(&Value::from(150.5))
+        );
+        assert_eq!(config.stream_rules.len(), 1);
+
+        let options = SerializerOptions::new().braceless(true);
+        let output = to_string_with_options(&config, options).unwrap();
+        assert_eq!(from_str(&output).unwrap(), config);
+
+        assert!(from_str("stream.properties = { resample.quality = high }").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Typed model of `jack.conf`, read by JACK applications running on
+//! PipeWire.
+
+use super::pipewire::Module;
+use crate::de::{self, Error};
+use crate::fraction::Fraction;
+use crate::properties::Properties;
+use crate::rules::Rule;
+use crate::value::Map;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(rename = "context.properties", skip_serializing_if = "Map::is_empty")]
+    pub properties: Map,
+    #[serde(
+        rename = "context.spa-libs",
+        skip_serializing_if = "Properties::is_empty"
+    )]
+    pub spa_libs: Properties,
+    #[serde(rename = "context.modules", skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<Module>,
+    #[serde(
+        rename = "jack.properties",
+        skip_serializing_if = "JackProperties::is_empty"
+    )]
+    pub jack_properties: JackProperties,
+    /// Per-client overrides, with `update-props` applied to matching
+    /// clients.
+    #[serde(rename = "jack.rules", skip_serializing_if = "Vec::is_empty")]
+    pub jack_rules: Vec<Rule>,
+    /// Sections not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+/// Settings of the JACK client library, `jack.properties`.
+///
+/// Values must have the type of their field; a quoted `"true"` does not
+/// deserialize into a `bool`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JackProperties {
+    /// Requested latency as `frames/rate`, e.g. `1024/48000`.
+    #[serde(rename = "node.latency", skip_serializing_if = "Option::is_none")]
+    pub latency: Option<Fraction>,
+    /// Requested rate as `1/rate`, e.g. `1/48000`.
+    #[serde(rename = "node.rate", skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Fraction>,
+    #[serde(rename = "node.quantum", skip_serializing_if = "Option::is_none")]
+    pub quantum: Option<String>,
+    #[serde(rename = "node.lock-quantum", skip_serializing_if = "Option::is_none")]
+    pub lock_quantum: Option<bool>,
+    #[serde(rename = "node.force-quantum", skip_serializing_if = "Option::is_none")]
+    pub force_quantum: Option<u32>,
+    #[serde(rename = "jack.show-monitor", skip_serializing_if = "Option::is_none")]
+    pub show_monitor: Option<bool>,
+    #[serde(rename = "jack.merge-monitor", skip_serializing_if = "Option::is_none")]
+    pub merge_monitor: Option<bool>,
+    #[serde(rename = "jack.show-midi", skip_serializing_if = "Option::is_none")]
+    pub show_midi: Option<bool>,
+    #[serde(rename = "jack.short-name", skip_serializing_if = "Option::is_none")]
+    pub short_name: Option<bool>,
+    #[serde(rename = "jack.filter-name", skip_serializing_if = "Option::is_none")]
+    pub filter_name: Option<bool>,
+    #[serde(rename = "jack.filter-char", skip_serializing_if = "Option::is_none")]
+    pub filter_char: Option<String>,
+    /// `allow`, `fail-external`, `ignore-external`, `fail-all` or
+    /// `ignore-all`.
+    #[serde(
+        rename = "jack.self-connect-mode",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub self_connect_mode: Option<String>,
+    #[serde(
+        rename = "jack.locked-process",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub locked_process: Option<bool>,
+    #[serde(
+        rename = "jack.default-as-system",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_as_system: Option<bool>,
+    #[serde(
+        rename = "jack.fix-midi-events",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub fix_midi_events: Option<bool>,
+    #[serde(
+        rename = "jack.global-buffer-size",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub global_buffer_size: Option<bool>,
+    #[serde(
+        rename = "jack.max-client-ports",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_client_ports: Option<u32>,
+    /// Properties not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+impl JackProperties {
+    pub fn is_empty(&self) -> bool {
+        *self == JackProperties::default()
+    }
+}
+
+/// Parses the contents of a `jack.conf` file.
+pub fn from_str(input: &str) -> Result<Config, Error> {
+    de::from_str(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+
+    #[test]
+    fn test_config() {
+        let input = r#"
+            jack.properties = {
+                node.latency = 256/48000
+                node.lock-quantum = true
+                jack.short-name = true
+                jack.self-connect-mode = fail-external
+                jack.max-client-ports = 768
+                jack.passive-links = false
+            }
+            jack.rules = [
+                {
+                    matches = [ { client.name = Ardour } ]
+                    actions = { update-props = { jack.merge-monitor = false } }
+                }
+            ]
+        "#;
+        let config = from_str(input).unwrap();
+        let jack = &config.jack_properties;
+        assert_eq!(jack.latency, Some(Fraction::new(256, 48000)));
+        assert_eq!(jack.lock_quantum, Some(true));
+        assert_eq!(jack.short_name, Some(true));
+        assert_eq!(jack.self_connect_mode.as_deref(), Some("fail-external"));
+        assert_eq!(jack.max_client_ports, Some(768));
+        assert!(jack.other.contains_key("jack.passive-links"));
+        let update = config.jack_rules[0].actions.update_props.as_ref().unwrap();
+        assert!(update.contains_key("jack.merge-monitor"));
+
+        let options = SerializerOptions::new().braceless(true);
+        let output = to_string_with_options(&config, options).unwrap();
+        assert_eq!(from_str(&output).unwrap(), config);
+        assert_eq!(from_str("").unwrap(), Config::default());
+    }
+}
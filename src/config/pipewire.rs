@@ -0,0 +1,171 @@
+//! Typed model of `pipewire.conf`, the daemon configuration.
+//!
+//! The module, object and exec entries are shared with the client-side
+//! configuration files.
+
+use crate::de::{self, Error};
+use crate::properties::Properties;
+use crate::value::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(rename = "context.properties", skip_serializing_if = "Map::is_empty")]
+    pub properties: Map,
+    /// Factory name patterns mapped to the SPA plugin that provides them,
+    /// e.g. `audio.convert.* = audioconvert/libspa-audioconvert`.
+    #[serde(
+        rename = "context.spa-libs",
+        skip_serializing_if = "Properties::is_empty"
+    )]
+    pub spa_libs: Properties,
+    #[serde(rename = "context.modules", skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<Module>,
+    #[serde(rename = "context.objects", skip_serializing_if = "Vec::is_empty")]
+    pub objects: Vec<Object>,
+    #[serde(rename = "context.exec", skip_serializing_if = "Vec::is_empty")]
+    pub exec: Vec<Exec>,
+    /// Sections not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+/// An entry of `context.modules`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    pub name: String,
+    /// Module arguments, usually an object of properties.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Flag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Vec<Map>>,
+}
+
+/// An entry of `context.objects`, created with a factory.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Object {
+    pub factory: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub args: Map,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Flag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Vec<Map>>,
+}
+
+/// An entry of `context.exec`, a program started with the daemon.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Exec {
+    pub path: String,
+    /// A string of arguments, or an array of them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Vec<Map>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Flag {
+    /// Skip the entry if its module or factory does not exist.
+    IfExists,
+    /// Carry on if the entry fails to load.
+    NoFail,
+}
+
+impl Module {
+    pub fn new(name: impl Into<String>) -> Self {
+        Module {
+            name: name.into(),
+            ..Module::default()
+        }
+    }
+
+    /// The arguments if they are an object.
+    pub fn args_map(&self) -> Option<&Map> {
+        self.args.as_ref().and_then(Value::as_object)
+    }
+}
+
+/// Parses the contents of a `pipewire.conf` file.
+pub fn from_str(input: &str) -> Result<Config, Error> {
+    de::from_str(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+
+    #[test]
+    fn test_config() {
+        let input = r#"
+            context.properties = {
+                default.clock.rate = 48000
+                default.clock.allowed-rates = [ 44100 48000 ]
+            }
+            context.spa-libs = {
+                audio.convert.* = audioconvert/libspa-audioconvert
+                support.*       = support/libspa-support
+            }
+            context.modules = [
+                { name = libpipewire-module-rt
+                    args = { nice.level = -11 }
+                    flags = [ ifexists nofail ]
+                }
+                { name = libpipewire-module-protocol-native }
+                { name = libpipewire-module-x11-bell
+                    condition = [ { module.x11.bell = true } ]
+                }
+            ]
+            context.objects = [
+                { factory = spa-node-factory
+                    args = { factory.name = support.node.driver, node.name = Dummy-Driver }
+                }
+            ]
+            context.exec = [
+                { path = /usr/bin/pipewire args = "-c pipewire-pulse.conf" }
+            ]
+            custom.section = { enabled = true }
+        "#;
+        let config = from_str(input).unwrap();
+        assert_eq!(
+            config.properties.get("default.clock.rate"),
+            Some(&Value::from(48000))
+        );
+        assert_eq!(
+            config.spa_libs.get("support.*"),
+            Some("support/libspa-support")
+        );
+        assert_eq!(config.modules.len(), 3);
+        let rt = &config.modules[0];
+        assert_eq!(rt.name, "libpipewire-module-rt");
+        assert_eq!(rt.flags, [Flag::IfExists, Flag::NoFail]);
+        assert_eq!(
+            rt.args_map().unwrap().get("nice.level"),
+            Some(&Value::from(-11))
+        );
+        assert_eq!(
+            config.modules[1],
+            Module::new("libpipewire-module-protocol-native")
+        );
+        assert_eq!(config.modules[2].condition.as_ref().unwrap().len(), 1);
+        assert_eq!(config.objects[0].factory, "spa-node-factory");
+        assert_eq!(
+            config.objects[0].args.get("node.name"),
+            Some(&Value::from("Dummy-Driver"))
+        );
+        assert_eq!(config.exec[0].path, "/usr/bin/pipewire");
+        assert!(config.other.contains_key("custom.section"));
+
+        let options = SerializerOptions::new().braceless(true);
+        let output = to_string_with_options(&config, options).unwrap();
+        assert_eq!(from_str(&output).unwrap(), config);
+
+        assert!(from_str("context.modules = [ { args = {} } ]").is_err());
+        assert!(from_str("context.modules = [ { name = a, flags = [ sometimes ] } ]").is_err());
+    }
+}
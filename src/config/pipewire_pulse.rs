@@ -0,0 +1,173 @@
+//! Typed model of `pipewire-pulse.conf`, the PulseAudio compatibility
+//! server.
+
+use super::client::StreamProperties;
+use super::pipewire::{Exec, Flag, Module};
+use crate::de::{self, Error};
+use crate::properties::Properties;
+use crate::rules::Rule;
+use crate::value::{Map, Value};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(rename = "context.properties", skip_serializing_if = "Map::is_empty")]
+    pub properties: Map,
+    #[serde(
+        rename = "context.spa-libs",
+        skip_serializing_if = "Properties::is_empty"
+    )]
+    pub spa_libs: Properties,
+    #[serde(rename = "context.modules", skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<Module>,
+    #[serde(rename = "context.exec", skip_serializing_if = "Vec::is_empty")]
+    pub exec: Vec<Exec>,
+    /// Commands run at startup, such as `load-module`.
+    #[serde(rename = "pulse.cmd", skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<Command>,
+    #[serde(
+        rename = "stream.properties",
+        skip_serializing_if = "StreamProperties::is_empty"
+    )]
+    pub stream_properties: StreamProperties,
+    #[serde(
+        rename = "pulse.properties",
+        skip_serializing_if = "PulseProperties::is_empty"
+    )]
+    pub pulse_properties: PulseProperties,
+    /// Per-client overrides, with `update-props` applied to matching
+    /// clients.
+    #[serde(rename = "pulse.rules", skip_serializing_if = "Vec::is_empty")]
+    pub pulse_rules: Vec<Rule>,
+    /// Sections not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+/// An entry of `pulse.cmd`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Command {
+    pub cmd: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<Flag>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Vec<Map>>,
+}
+
+/// Settings of the server, `pulse.properties`.
+///
+/// Values must have the type of their field; a quoted `"5"` does not
+/// deserialize into a number.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PulseProperties {
+    /// Addresses to listen on: strings such as `unix:native` or
+    /// `tcp:4713`, or objects with an `address` and more settings.
+    #[serde(rename = "server.address", skip_serializing_if = "Vec::is_empty")]
+    pub server_address: Vec<Value>,
+    #[serde(rename = "pulse.min.req", skip_serializing_if = "Option::is_none")]
+    pub min_req: Option<String>,
+    #[serde(rename = "pulse.default.req", skip_serializing_if = "Option::is_none")]
+    pub default_req: Option<String>,
+    #[serde(rename = "pulse.min.frag", skip_serializing_if = "Option::is_none")]
+    pub min_frag: Option<String>,
+    #[serde(rename = "pulse.default.frag", skip_serializing_if = "Option::is_none")]
+    pub default_frag: Option<String>,
+    #[serde(
+        rename = "pulse.default.tlength",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_tlength: Option<String>,
+    #[serde(rename = "pulse.min.quantum", skip_serializing_if = "Option::is_none")]
+    pub min_quantum: Option<String>,
+    #[serde(
+        rename = "pulse.default.format",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_format: Option<String>,
+    #[serde(
+        rename = "pulse.default.position",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub default_position: Option<String>,
+    /// Seconds after which idle streams are corked, 0 to never.
+    #[serde(rename = "pulse.idle.timeout", skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u32>,
+    /// Settings used when running in a virtual machine.
+    #[serde(rename = "vm.overrides", skip_serializing_if = "Option::is_none")]
+    pub vm_overrides: Option<Map>,
+    /// Properties not listed above, kept as written.
+    #[serde(flatten)]
+    pub other: Map,
+}
+
+impl PulseProperties {
+    pub fn is_empty(&self) -> bool {
+        *self == PulseProperties::default()
+    }
+}
+
+/// Parses the contents of a `pipewire-pulse.conf` file.
+pub fn from_str(input: &str) -> Result<Config, Error> {
+    de::from_str(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fraction::Fraction;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+
+    #[test]
+    fn test_config() {
+        let input = r#"
+            context.modules = [ { name = libpipewire-module-protocol-pulse } ]
+            pulse.cmd = [
+                { cmd = load-module args = module-always-sink flags = [ nofail ] }
+            ]
+            stream.properties = { node.latency = 1024/48000 }
+            pulse.properties = {
+                server.address = [
+                    "unix:native"
+                    { address = "tcp:4713", max-clients = 64 }
+                ]
+                pulse.min.req = 128/48000
+                pulse.idle.timeout = 5
+                vm.overrides = { pulse.min.quantum = 1024/48000 }
+                pulse.fix.format = S16LE
+            }
+            pulse.rules = [
+                {
+                    matches = [ { application.process.binary = teams } ]
+                    actions = { quirks = [ force-s16-info ] }
+                }
+            ]
+        "#;
+        let config = from_str(input).unwrap();
+        assert_eq!(config.commands[0].cmd, "load-module");
+        assert_eq!(
+            config.commands[0].args.as_deref(),
+            Some("module-always-sink")
+        );
+        assert_eq!(config.commands[0].flags, [Flag::NoFail]);
+        assert_eq!(
+            config.stream_properties.latency,
+            Some(Fraction::new(1024, 48000))
+        );
+        let pulse = &config.pulse_properties;
+        assert_eq!(pulse.server_address.len(), 2);
+        assert_eq!(pulse.server_address[0], Value::from("unix:native"));
+        assert_eq!(pulse.min_req.as_deref(), Some("128/48000"));
+        assert_eq!(pulse.idle_timeout, Some(5));
+        assert!(pulse.vm_overrides.is_some());
+        assert!(pulse.other.contains_key("pulse.fix.format"));
+        assert!(config.pulse_rules[0].actions.other.contains_key("quirks"));
+
+        let options = SerializerOptions::new().braceless(true);
+        let output = to_string_with_options(&config, options).unwrap();
+        assert_eq!(from_str(&output).unwrap(), config);
+    }
+}
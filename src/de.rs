@@ -0,0 +1,1998 @@
+use alloc::borrow::{Cow, ToOwned};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+#[cfg(feature = "value")]
+use core::ops::Range;
+#[cfg(feature = "value")]
+use serde::de::DeserializeOwned;
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::Deserialize;
+
+pub use crate::error::Error;
+use crate::number::{parse_non_finite, parse_number, Number};
+use crate::path::{PathSegment, SpaPath};
+use crate::scan;
+#[cfg(feature = "value")]
+use crate::source_map::parse_with_source_map;
+use crate::trace;
+#[cfg(feature = "value")]
+use crate::value::{from_value, PathError, Value};
+#[cfg(feature = "value")]
+pub use iterative::from_str_value;
+#[cfg(feature = "value")]
+pub use recovery::{parse_lossy, parse_with_recovery};
+
+#[cfg(feature = "value")]
+mod iterative;
+#[cfg(feature = "value")]
+mod recovery;
+
+pub(crate) const MAX_DEPTH: usize = 128;
+
+pub struct Deserializer<'de> {
+    input: &'de str,
+    pos: usize,
+    depth: usize,
+    /// Set while the document is a top-level object written without braces.
+    braceless: bool,
+    /// Set while an object key is being deserialized.
+    in_key: bool,
+    /// The keys and indices leading to the value being deserialized.
+    /// Segments are only removed once their value was read successfully,
+    /// so after an error this is where it happened.
+    path: Vec<Segment>,
+    options: ParseOptions,
+    /// The number of values read so far, for [`ParseLimits::max_nodes`].
+    nodes: usize,
+}
+
+enum Segment {
+    /// The offset of the key in the input, so it is only unquoted when
+    /// an error is reported.
+    Key(usize),
+    Index(usize),
+}
+
+/// Opt-in extensions to the syntax accepted by the parser.
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    expand_env: Option<UndefinedVar>,
+    lenient_bools: bool,
+    non_finite: NonFinite,
+    line_continuations: bool,
+    max_depth: Option<usize>,
+    limits: ParseLimits,
+}
+
+/// Bounds on what a document may contain, for services parsing input
+/// they do not trust. Every limit is off by default.
+#[derive(Clone, Debug, Default)]
+pub struct ParseLimits {
+    max_input_bytes: Option<usize>,
+    max_string_len: Option<usize>,
+    max_nodes: Option<usize>,
+    max_object_entries: Option<usize>,
+}
+
+impl ParseLimits {
+    pub fn new() -> Self {
+        ParseLimits::default()
+    }
+
+    /// Rejects input longer than `bytes`.
+    pub fn max_input_bytes(mut self, bytes: usize) -> Self {
+        self.max_input_bytes = Some(bytes);
+        self
+    }
+
+    /// Rejects strings, including keys, longer than `bytes` once unescaped.
+    pub fn max_string_len(mut self, bytes: usize) -> Self {
+        self.max_string_len = Some(bytes);
+        self
+    }
+
+    /// Rejects documents with more than `nodes` values, counting every
+    /// array, object and scalar but not keys.
+    pub fn max_nodes(mut self, nodes: usize) -> Self {
+        self.max_nodes = Some(nodes);
+        self
+    }
+
+    /// Rejects objects with more than `entries` entries, counting
+    /// duplicate keys.
+    pub fn max_object_entries(mut self, entries: usize) -> Self {
+        self.max_object_entries = Some(entries);
+        self
+    }
+}
+
+/// How bare `nan`, `inf` and `-inf` are read, in any case and also spelled
+/// `infinity`. They are written like this by `pw-dump`, for one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinite {
+    /// As strings, like other bare words.
+    #[default]
+    String,
+    /// As floats.
+    Float,
+    /// Fail with a parse error.
+    Error,
+}
+
+/// What to do with a `$VAR` reference to a variable that is not set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UndefinedVar {
+    /// Fail with a parse error.
+    Error,
+    /// Leave the reference in the string as written.
+    Keep,
+}
+
+/// A value split one level deep by [`Deserializer::split`].
+#[cfg(feature = "value")]
+#[derive(Clone, Debug)]
+pub(crate) enum Split<'de> {
+    Object(Vec<(Cow<'de, str>, Range<usize>)>),
+    Array(Vec<Range<usize>>),
+    Scalar,
+}
+
+enum Scalar<'de> {
+    Bare(Cow<'de, str>),
+    Quoted(Cow<'de, str>),
+}
+
+pub fn from_str<'a, T>(s: &'a str) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    from_str_with_options(s, ParseOptions::default())
+}
+
+/// Line and column of the byte `offset` of `text`, both counted from 1,
+/// as in parse errors. Columns count characters.
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    (line, column)
+}
+
+/// Unescapes the contents of a quoted string, without the quotes, the way
+/// the parser does. Only copies when there is an escape.
+///
+/// ```
+/// use spa_json::de::unescape_str;
+///
+/// assert_eq!(unescape_str(r#"a \"b\"\n\u00e4"#).unwrap(), "a \"b\"\nä");
+/// assert!(unescape_str(r"\x").is_err());
+/// ```
+pub fn unescape_str(s: &str) -> Result<Cow<'_, str>, Error> {
+    Deserializer::from_str(s).parse_escaped(None)
+}
+
+pub fn from_str_with_options<'a, T>(s: &'a str, options: ParseOptions) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    let phase = trace::phase!("parse", bytes = s.len());
+    let mut deserializer = Deserializer::from_str_with_options(s, options);
+    phase.result(
+        T::deserialize(&mut deserializer)
+            .map_err(|e| deserializer.locate(e))
+            .and_then(|t| deserializer.end().map(|()| t)),
+    )
+}
+
+/// Deserializes only the value at `path`, such as `context.properties` or
+/// `context.modules[0].args`, skipping over the rest of the document
+/// without building it. Names in the path also match keys made of several
+/// of them joined with dots, and the first matching key is used. The input
+/// after the value is not checked.
+///
+/// ```
+/// use spa_json::de::from_str_at;
+///
+/// let input = "context.properties = { default.clock.rate = 48000 }\ncontext.modules = []\n";
+/// let rate: u32 = from_str_at(input, "context.properties.default.clock.rate").unwrap();
+/// assert_eq!(rate, 48000);
+/// ```
+pub fn from_str_at<'a, T>(s: &'a str, path: &str) -> Result<T, Error>
+where
+    T: Deserialize<'a>,
+{
+    let segments: SpaPath = path.parse()?;
+    let mut deserializer = Deserializer::from_str(s);
+    if !deserializer.seek(&segments)? {
+        return Err(Error::Message(format!("no value at {}", path)));
+    }
+    deserializer.value()
+}
+
+/// Deserializes `input` laid over `defaults` with [`merge`], so that a
+/// fragment setting only some keys, such as a drop-in file, can be read
+/// into a complete type. Values that do not match their type are reported
+/// at their place in `input`, or at the closest value there for those
+/// coming from `defaults`.
+///
+/// ```
+/// use serde::Deserialize;
+/// use spa_json::de::from_str_with_defaults;
+/// use spa_json::value::Value;
+///
+/// #[derive(Deserialize)]
+/// struct Props {
+///     rate: u32,
+///     quantum: u32,
+/// }
+///
+/// let defaults: Value = "rate = 48000, quantum = 1024".parse().unwrap();
+/// let props: Props = from_str_with_defaults("quantum = 256", &defaults).unwrap();
+/// assert_eq!((props.rate, props.quantum), (48000, 256));
+/// ```
+///
+/// [`merge`]: crate::merge
+#[cfg(feature = "value")]
+pub fn from_str_with_defaults<T>(input: &str, defaults: &Value) -> Result<T, Error>
+where
+    T: DeserializeOwned,
+{
+    let (fragment, source_map) = parse_with_source_map(input)?;
+    let mut value = defaults.clone();
+    crate::merge(&mut value, fragment);
+    from_value(&value).map_err(|PathError { message, path }| {
+        let at = source_map.nearest(&path).map_or(0, |span| span.value.start);
+        let (line, column) = line_column(input, at);
+        Error::Parse {
+            message,
+            line,
+            column,
+            path,
+        }
+    })
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        ParseOptions::default()
+    }
+
+    /// Expands `$VAR` and `${VAR}` in string values (not keys) from the
+    /// environment; `$$` stands for a literal `$`. Bare values are typed
+    /// after expansion, so `rate = $RATE` or `rate = ${RATE}` can yield a
+    /// number; the `}` of `${VAR}` does not close an object there.
+    pub fn expand_env(mut self, undefined: UndefinedVar) -> Self {
+        self.expand_env = Some(undefined);
+        self
+    }
+
+    /// Lets `bool` fields also be read from `yes`/`no`, `on`/`off` and
+    /// `1`/`0`, in any case and quoted or not, as found in hand-edited
+    /// files. Otherwise only bare `true` and `false` are booleans.
+    pub fn lenient_bools(mut self, lenient: bool) -> Self {
+        self.lenient_bools = lenient;
+        self
+    }
+
+    /// Sets how non-finite numbers are read, as strings by default.
+    pub fn non_finite(mut self, non_finite: NonFinite) -> Self {
+        self.non_finite = non_finite;
+        self
+    }
+
+    /// Sets how deeply arrays and objects may be nested, 128 levels by
+    /// default. Deserializing recurses once per level, so deeper limits
+    /// risk overflowing the stack, except with [`from_str_value`], which
+    /// keeps a stack of its own. `usize::MAX` disables the limit.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    /// Enforces `limits` while parsing.
+    pub fn limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Reads a `\` at the end of a line in a quoted string as a line
+    /// continuation: the line break and the indentation after it are left
+    /// out, as written by [`SerializerOptions::wrap_strings`].
+    ///
+    /// [`SerializerOptions::wrap_strings`]: crate::ser::SerializerOptions::wrap_strings
+    pub fn line_continuations(mut self, continuations: bool) -> Self {
+        self.line_continuations = continuations;
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Deserializer::from_str_with_options(input, ParseOptions::default())
+    }
+
+    pub fn from_str_with_options(input: &'de str, options: ParseOptions) -> Self {
+        let mut deserializer = Deserializer {
+            input,
+            pos: 0,
+            depth: 0,
+            nodes: 0,
+            braceless: false,
+            in_key: false,
+            path: Vec::new(),
+            options,
+        };
+        deserializer.braceless = deserializer.starts_braceless();
+        deserializer
+    }
+
+    /// A deserializer for the value starting at `pos`, which cannot be a
+    /// braceless object.
+    #[cfg(feature = "value")]
+    pub(crate) fn at(input: &'de str, pos: usize) -> Self {
+        Deserializer {
+            input,
+            pos,
+            depth: 0,
+            nodes: 0,
+            braceless: false,
+            in_key: false,
+            path: Vec::new(),
+            options: ParseOptions::default(),
+        }
+    }
+
+    /// Deserializes the value at the current position, attaching the
+    /// position and path to errors.
+    pub(crate) fn value<T: Deserialize<'de>>(&mut self) -> Result<T, Error> {
+        self.value_seed(PhantomData)
+    }
+
+    /// Like [`value`](Self::value), with a seed.
+    pub(crate) fn value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, Error> {
+        seed.deserialize(&mut *self).map_err(|e| self.locate(e))
+    }
+
+    /// Checks that nothing but whitespace and comments is left in the input.
+    pub fn end(&mut self) -> Result<(), Error> {
+        self.check_input_len()?;
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("trailing characters")),
+        }
+    }
+
+    /// A document is a braceless object unless it is a single value or
+    /// starts with an explicit container.
+    fn starts_braceless(&mut self) -> bool {
+        self.skip_whitespace();
+        let braceless = match self.peek() {
+            None => true,
+            Some(b'{') | Some(b'[') => false,
+            Some(_) => {
+                self.parse_scalar().is_ok() && {
+                    self.skip_whitespace();
+                    self.peek().is_some()
+                }
+            }
+        };
+        self.pos = 0;
+        braceless
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b' ' | b'\t' | b'\r' | b'\n' | b'\0' | b',' => self.pos += 1,
+                b'#' => {
+                    self.pos += scan::line_len(&self.input.as_bytes()[self.pos..]);
+                    self.pos = self.input.len().min(self.pos + 1);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_key_separator(&mut self) {
+        self.skip_whitespace();
+        while let Some(b':') | Some(b'=') = self.peek() {
+            self.pos += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        self.error_at(self.pos, message.to_owned())
+    }
+
+    fn error_at(&self, pos: usize, message: String) -> Error {
+        let (line, column) = line_column(self.input, pos);
+        Error::Parse {
+            message,
+            line,
+            column,
+            path: SpaPath::new(),
+        }
+    }
+
+    /// Attaches the current position and path to errors raised by
+    /// `Deserialize` impls.
+    fn locate(&self, err: Error) -> Error {
+        match err {
+            Error::Message(message) => match self.error_at(self.pos, message) {
+                Error::Parse {
+                    message,
+                    line,
+                    column,
+                    ..
+                } => Error::Parse {
+                    message,
+                    line,
+                    column,
+                    path: self.path.iter().map(|s| self.path_segment(s)).collect(),
+                },
+                err => err,
+            },
+            err => err,
+        }
+    }
+
+    fn path_segment(&self, segment: &Segment) -> PathSegment {
+        match *segment {
+            Segment::Index(index) => PathSegment::Index(index),
+            Segment::Key(pos) => {
+                let mut key = Deserializer {
+                    input: self.input,
+                    pos,
+                    depth: 0,
+                    nodes: 0,
+                    braceless: false,
+                    in_key: true,
+                    path: Vec::new(),
+                    options: ParseOptions::default(),
+                };
+                let key = match key.parse_scalar() {
+                    Ok(Scalar::Bare(s) | Scalar::Quoted(s)) => s.into_owned(),
+                    Err(_) => String::new(),
+                };
+                PathSegment::Key(key)
+            }
+        }
+    }
+
+    fn take_braceless(&mut self) -> bool {
+        core::mem::replace(&mut self.braceless, false)
+    }
+
+    fn enter(&mut self) -> Result<(), Error> {
+        if self.depth >= self.options.max_depth.unwrap_or(MAX_DEPTH) {
+            return Err(self.error("too deep nesting"));
+        }
+        self.count_node()?;
+        self.depth += 1;
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn leave(&mut self, close: u8) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.depth -= 1;
+            self.pos += 1;
+            Ok(())
+        } else if close == b'}' {
+            Err(self.error("expected object close brace"))
+        } else {
+            Err(self.error("expected array close bracket"))
+        }
+    }
+
+    /// Counts a value about to be read against the limits, checking the
+    /// length of the input before the first one.
+    fn count_node(&mut self) -> Result<(), Error> {
+        if self.nodes == 0 {
+            self.check_input_len()?;
+        }
+        self.nodes += 1;
+        match self.options.limits.max_nodes {
+            Some(max) if self.nodes > max => {
+                Err(self.error(&format!("document has more than {} values", max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_input_len(&self) -> Result<(), Error> {
+        match self.options.limits.max_input_bytes {
+            Some(max) if self.input.len() > max => {
+                Err(self.error_at(0, format!("input is longer than {} bytes", max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the number of entries read from an object against the
+    /// limits.
+    fn check_entries(&self, entries: usize) -> Result<(), Error> {
+        match self.options.limits.max_object_entries {
+            Some(max) if entries > max => {
+                Err(self.error(&format!("object has more than {} entries", max)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_scalar(&mut self) -> Result<Scalar<'de>, Error> {
+        let start = self.pos;
+        let scalar = self.parse_token()?;
+        let (Scalar::Bare(s) | Scalar::Quoted(s)) = &scalar;
+        match self.options.limits.max_string_len {
+            Some(max) if s.len() > max => {
+                Err(self.error_at(start, format!("string is longer than {} bytes", max)))
+            }
+            _ => Ok(scalar),
+        }
+    }
+
+    fn parse_token(&mut self) -> Result<Scalar<'de>, Error> {
+        if let Some(quote @ (b'"' | b'\'')) = self.peek() {
+            return self.parse_quoted(quote).map(Scalar::Quoted);
+        }
+        let start = self.pos;
+        loop {
+            self.pos += scan::bare_len(&self.input.as_bytes()[self.pos..]);
+            while let Some(c) = self.peek() {
+                match c {
+                    _ if is_delimiter(c) => break,
+                    b'\\' => return Err(self.error("escape not allowed")),
+                    32..=126 => self.pos += 1,
+                    _ => return Err(self.error("character not allowed")),
+                }
+            }
+            // The `}` of a `${VAR}` that is expanded does not end the value.
+            let expands = self.options.expand_env.is_some() && !self.in_key;
+            if expands && self.peek() == Some(b'}') && in_braced_var(&self.input[start..self.pos]) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        if self.pos == start {
+            return Err(match self.peek() {
+                None => self.error("unexpected end of input"),
+                Some(c) => self.error(&format!("unexpected character '{}'", c as char)),
+            });
+        }
+        Ok(Scalar::Bare(Cow::Borrowed(&self.input[start..self.pos])))
+    }
+
+    /// Parses a scalar and applies the value transformations enabled in the options.
+    fn scalar(&mut self) -> Result<Scalar<'de>, Error> {
+        if !self.in_key {
+            self.count_node()?;
+        }
+        let start = self.pos;
+        let scalar = self.parse_scalar()?;
+        match self.options.expand_env {
+            Some(undefined) if !self.in_key => match scalar {
+                Scalar::Bare(s) => self.expand_env(start, s, undefined).map(Scalar::Bare),
+                Scalar::Quoted(s) => self.expand_env(start, s, undefined).map(Scalar::Quoted),
+            },
+            _ => Ok(scalar),
+        }
+    }
+
+    fn expand_env(
+        &self,
+        start: usize,
+        s: Cow<'de, str>,
+        undefined: UndefinedVar,
+    ) -> Result<Cow<'de, str>, Error> {
+        if !s.contains('$') {
+            return Ok(s);
+        }
+        let mut out = String::with_capacity(s.len());
+        let mut rest = &*s;
+        while let Some(i) = rest.find('$') {
+            out.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            if let Some(tail) = rest.strip_prefix('$') {
+                out.push('$');
+                rest = tail;
+                continue;
+            }
+            let (name, len) = match rest.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(end) => (&braced[..end], end + 2),
+                    None => return Err(self.error_at(start, "unterminated ${".to_owned())),
+                },
+                None => {
+                    let end = rest
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                        .unwrap_or(rest.len());
+                    (&rest[..end], end)
+                }
+            };
+            #[cfg(feature = "std")]
+            let value = std::env::var(name).ok();
+            // Without the standard library there is no environment.
+            #[cfg(not(feature = "std"))]
+            let value: Option<String> = None;
+            match value {
+                Some(value) if !name.is_empty() => out.push_str(&value),
+                _ if undefined == UndefinedVar::Keep || name.is_empty() => {
+                    out.push('$');
+                    out.push_str(&rest[..len]);
+                }
+                _ => {
+                    let message = format!("undefined environment variable {}", name);
+                    return Err(self.error_at(start, message));
+                }
+            }
+            rest = &rest[len..];
+        }
+        out.push_str(rest);
+        Ok(Cow::Owned(out))
+    }
+
+    /// Parses a string enclosed in `quote`, which is `"` or `'`.
+    fn parse_quoted(&mut self, quote: u8) -> Result<Cow<'de, str>, Error> {
+        self.pos += 1;
+        self.parse_escaped(Some(quote))
+    }
+
+    /// Unescapes the contents of a string up to the closing `quote`, which
+    /// is consumed, or up to the end of the input if there is none.
+    fn parse_escaped(&mut self, quote: Option<u8>) -> Result<Cow<'de, str>, Error> {
+        let mut start = self.pos;
+        let mut owned: Option<String> = None;
+        loop {
+            self.pos += scan::string_len(&self.input.as_bytes()[self.pos..], quote);
+            let c = self.peek();
+            if c == quote {
+                let tail = &self.input[start..self.pos];
+                if c.is_some() {
+                    self.pos += 1;
+                }
+                return Ok(match owned {
+                    Some(mut s) => {
+                        s.push_str(tail);
+                        Cow::Owned(s)
+                    }
+                    None => Cow::Borrowed(tail),
+                });
+            }
+            match c {
+                None => return Err(self.error("unfinished string")),
+                Some(b'\\') => {
+                    let s = owned.get_or_insert_with(String::new);
+                    s.push_str(&self.input[start..self.pos]);
+                    let escape = self.pos;
+                    self.pos += 1;
+                    if self.options.line_continuations && self.skip_line_break() {
+                        start = self.pos;
+                        continue;
+                    }
+                    let c = match self.peek() {
+                        Some(b'"') => '"',
+                        Some(b'\'') => '\'',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{0008}',
+                        Some(b'f') => '\u{000C}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.parse_unicode_escape()?,
+                        _ => return Err(self.error_at(escape, "invalid escape".to_owned())),
+                    };
+                    s.push(c);
+                    self.pos += 1;
+                    start = self.pos;
+                }
+                Some(c) if c < 0x20 => return Err(self.error("character not allowed")),
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// Skips a line break at `pos` and the spaces and tabs after it.
+    /// Returns false if there is none.
+    fn skip_line_break(&mut self) -> bool {
+        let rest = &self.input[self.pos..];
+        let Some(rest) = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+        else {
+            return false;
+        };
+        let next = rest.trim_start_matches([' ', '\t']);
+        self.pos = self.input.len() - next.len();
+        true
+    }
+
+    /// Decodes the escape starting with the `u` at `pos`, including a
+    /// following low surrogate escape, leaving `pos` on the last hex digit.
+    fn parse_unicode_escape(&mut self) -> Result<char, Error> {
+        let escape = self.pos - 1;
+        let high = self.parse_hex4(escape)?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                if self.input.get(self.pos + 1..self.pos + 3) != Some("\\u") {
+                    return Err(
+                        self.error_at(escape, "lone surrogate in unicode escape".to_owned())
+                    );
+                }
+                self.pos += 2;
+                let low = self.parse_hex4(self.pos - 1)?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(
+                        self.error_at(escape, "lone surrogate in unicode escape".to_owned())
+                    );
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            0xDC00..=0xDFFF => {
+                return Err(self.error_at(escape, "lone surrogate in unicode escape".to_owned()))
+            }
+            code => code,
+        };
+        Ok(char::from_u32(code).unwrap())
+    }
+
+    /// Reads the four hex digits after the `u` at `pos`, leaving `pos` on the last one.
+    fn parse_hex4(&mut self, escape: usize) -> Result<u32, Error> {
+        let hex = self
+            .input
+            .get(self.pos + 1..self.pos + 5)
+            .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error_at(escape, "invalid unicode escape".to_owned()))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(hex, 16).unwrap())
+    }
+
+    /// Moves to the value at `path`, skipping the values before it.
+    /// Returns false if there is none. A key can match several names of
+    /// the path joined with dots, so a key that leads nowhere is backed
+    /// out of and the search goes on.
+    fn seek(&mut self, path: &[PathSegment]) -> Result<bool, Error> {
+        let Some(first) = path.first() else {
+            return Ok(true);
+        };
+        self.skip_whitespace();
+        let braceless = self.take_braceless();
+        match first {
+            PathSegment::Key(_) => {
+                if !braceless {
+                    if self.peek() != Some(b'{') {
+                        return Ok(false);
+                    }
+                    self.enter()?;
+                }
+                while let Some((key_pos, key)) = self.next_entry(braceless)? {
+                    let (value, depth, len) = (self.pos, self.depth, self.path.len());
+                    for n in matching_names(&key, path) {
+                        self.path.push(Segment::Key(key_pos));
+                        if self.seek(&path[n..])? {
+                            return Ok(true);
+                        }
+                        self.pos = value;
+                        self.depth = depth;
+                        self.path.truncate(len);
+                    }
+                    self.skip_value()?;
+                }
+                Ok(false)
+            }
+            PathSegment::Index(index) => {
+                if braceless || self.peek() != Some(b'[') {
+                    return Ok(false);
+                }
+                self.enter()?;
+                let mut i = 0;
+                while self.next_item()? {
+                    if i == *index {
+                        self.path.push(Segment::Index(i));
+                        return self.seek(&path[1..]);
+                    }
+                    self.skip_value()?;
+                    i += 1;
+                }
+                Ok(false)
+            }
+        }
+    }
+
+    /// Splits the value at the current position one level deep: into the
+    /// keys and the spans of the values of an object, or the spans of the
+    /// items of an array. Everything in it is checked but not built.
+    #[cfg(feature = "value")]
+    pub(crate) fn split(&mut self) -> Result<Split<'de>, Error> {
+        self.skip_whitespace();
+        let braceless = self.take_braceless();
+        if braceless || self.peek() == Some(b'{') {
+            if !braceless {
+                self.enter()?;
+            }
+            let mut entries = Vec::new();
+            while let Some((_, key)) = self.next_entry(braceless)? {
+                self.check_entries(entries.len() + 1)?;
+                let start = self.pos;
+                self.skip_value()?;
+                entries.push((key, start..self.pos));
+            }
+            if !braceless {
+                self.leave(b'}')?;
+            }
+            Ok(Split::Object(entries))
+        } else if self.peek() == Some(b'[') {
+            self.enter()?;
+            let mut items = Vec::new();
+            while self.next_item()? {
+                let start = self.pos;
+                self.skip_value()?;
+                items.push(start..self.pos);
+            }
+            self.leave(b']')?;
+            Ok(Split::Array(items))
+        } else {
+            self.skip_value()?;
+            Ok(Split::Scalar)
+        }
+    }
+
+    /// Moves to the next value of an object, returning where its key
+    /// starts and the key, or `None` at the end of the object.
+    fn next_entry(&mut self, braceless: bool) -> Result<Option<(usize, Cow<'de, str>)>, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            None if braceless => return Ok(None),
+            Some(b'}') if !braceless => return Ok(None),
+            None | Some(b'}' | b']' | b'{' | b'[' | b':' | b'=') => {
+                return Err(self.error("expected object key"))
+            }
+            Some(_) => {}
+        }
+        let key_pos = self.pos;
+        let (Scalar::Bare(key) | Scalar::Quoted(key)) = self.parse_scalar()?;
+        self.skip_key_separator();
+        if let None | Some(b'}' | b']') = self.peek() {
+            return Err(self.error("expected object value"));
+        }
+        Ok(Some((key_pos, key)))
+    }
+
+    /// Moves to the next item of an array, returning false at its end.
+    fn next_item(&mut self) -> Result<bool, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b']') => Ok(false),
+            None | Some(b'}') => Err(self.error("expected array close bracket")),
+            Some(b':' | b'=') => Err(self.error("invalid array separator")),
+            Some(_) => Ok(true),
+        }
+    }
+
+    /// Skips the value at the current position, checking its syntax.
+    fn skip_value(&mut self) -> Result<(), Error> {
+        de::IgnoredAny::deserialize(&mut *self).map_err(|e| self.locate(e))?;
+        Ok(())
+    }
+
+    fn visit_scalar<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        let scalar = self.scalar()?;
+        visit_typed(visitor, scalar, self.options.non_finite)
+    }
+
+    /// Reads a key typed as a bare scalar, for maps with number or
+    /// boolean keys. Keys are otherwise read as strings.
+    fn deserialize_typed_key<V: Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        self.visit_scalar(visitor)
+    }
+
+    fn peek_null(&self) -> bool {
+        let rest = &self.input.as_bytes()[self.pos..];
+        rest.starts_with(b"null") && rest.get(4).is_none_or(|&c| is_delimiter(c))
+    }
+}
+
+/// The numbers of leading names of `path` that joined with dots give
+/// `key`, shortest first.
+fn matching_names(key: &str, path: &[PathSegment]) -> Vec<usize> {
+    let mut matching = Vec::new();
+    let mut joined = String::new();
+    for (n, segment) in path.iter().enumerate() {
+        let PathSegment::Key(name) = segment else {
+            break;
+        };
+        if n > 0 {
+            joined.push('.');
+        }
+        joined.push_str(name);
+        if joined.len() > key.len() {
+            break;
+        }
+        if joined == key {
+            matching.push(n + 1);
+        }
+    }
+    matching
+}
+
+/// Visits a bare scalar as the type it looks like, and a quoted one as a
+/// string.
+fn visit_typed<'de, V: Visitor<'de>>(
+    visitor: V,
+    scalar: Scalar<'de>,
+    non_finite: NonFinite,
+) -> Result<V::Value, Error> {
+    match scalar {
+        Scalar::Quoted(s) => visit_cow(visitor, s),
+        Scalar::Bare(s) => match &*s {
+            "true" => visitor.visit_bool(true),
+            "false" => visitor.visit_bool(false),
+            "null" => visitor.visit_unit(),
+            t => match parse_number(t) {
+                Some(Number::Int(v)) => visitor.visit_i64(v),
+                Some(Number::UInt(v)) => visitor.visit_u64(v),
+                Some(Number::Float(v)) => visitor.visit_f64(v),
+                None => match parse_non_finite(t) {
+                    Some(v) if non_finite == NonFinite::Float => visitor.visit_f64(v),
+                    Some(_) if non_finite == NonFinite::Error => {
+                        Err(Error::Message(format!("non-finite number `{}`", t)))
+                    }
+                    _ => visit_cow(visitor, s),
+                },
+            },
+        },
+    }
+}
+
+/// The boolean spelled `s` in [`ParseOptions::lenient_bools`] mode.
+pub(crate) fn lenient_bool(s: &str) -> Option<bool> {
+    const SPELLINGS: [(&str, bool); 8] = [
+        ("true", true),
+        ("false", false),
+        ("yes", true),
+        ("no", false),
+        ("on", true),
+        ("off", false),
+        ("1", true),
+        ("0", false),
+    ];
+    SPELLINGS
+        .iter()
+        .find(|(spelling, _)| s.eq_ignore_ascii_case(spelling))
+        .map(|&(_, v)| v)
+}
+
+/// Whether `token` ends inside the name of a `${VAR}`.
+fn in_braced_var(token: &str) -> bool {
+    token.rfind("${").is_some_and(|i| !token[i..].contains('}'))
+}
+
+fn visit_cow<'de, V: Visitor<'de>>(visitor: V, s: Cow<'de, str>) -> Result<V::Value, Error> {
+    match s {
+        Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+        Cow::Owned(s) => visitor.visit_string(s),
+    }
+}
+
+pub(crate) fn is_delimiter(c: u8) -> bool {
+    matches!(
+        c,
+        b'\0' | b'\t' | b' ' | b'\r' | b'\n' | b'"' | b'#' | b':' | b',' | b'=' | b']' | b'}'
+    )
+}
+
+/// Number methods of the deserializer, which read keys by their text and
+/// any other value as [`deserialize_any`] does.
+///
+/// [`deserialize_any`]: de::Deserializer::deserialize_any
+macro_rules! deserialize_numbers {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                if self.in_key {
+                    self.deserialize_typed_key(visitor)
+                } else {
+                    self.deserialize_any(visitor)
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = Error;
+
+    /// Bare scalars are typed here as they would be for [`Value`], and
+    /// object keys are always strings. This is what `#[serde(untagged)]`
+    /// enums are matched against. Values
+    /// that serde buffers before their type is known (fields of a
+    /// `#[serde(flatten)]`ed struct) are therefore typed too, so a bare
+    /// `48000` there only deserializes into a number, not a `String`.
+    ///
+    /// [`Value`]: crate::value::Value
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.braceless {
+            return self.deserialize_map(visitor);
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            // Keys are always strings, however they look.
+            _ if self.in_key => self.deserialize_str(visitor),
+            Some(b'{') => self.deserialize_map(visitor),
+            Some(b'[') => self.deserialize_seq(visitor),
+            _ => self.visit_scalar(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf ignored_any
+    }
+
+    deserialize_numbers! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.in_key {
+            return self.deserialize_typed_key(visitor);
+        }
+        if !self.options.lenient_bools || self.braceless {
+            return self.deserialize_any(visitor);
+        }
+        self.skip_whitespace();
+        if let Some(b'{') | Some(b'[') = self.peek() {
+            return self.deserialize_any(visitor);
+        }
+        let scalar = self.scalar()?;
+        let (Scalar::Bare(s) | Scalar::Quoted(s)) = &scalar;
+        match lenient_bool(s) {
+            Some(v) => visitor.visit_bool(v),
+            None => visit_typed(visitor, scalar, self.options.non_finite),
+        }
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    /// Scalars are untyped in SPA-JSON, so any bare token is accepted as a string.
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') | Some(b'[') => self.deserialize_any(visitor),
+            _ => match self.scalar()? {
+                Scalar::Bare(s) | Scalar::Quoted(s) => visit_cow(visitor, s),
+            },
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        if !self.braceless && self.peek_null() {
+            self.count_node()?;
+            self.pos += 4;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        if self.peek_null() {
+            self.count_node()?;
+            self.pos += 4;
+            visitor.visit_unit()
+        } else {
+            Err(self.error("expected null"))
+        }
+    }
+
+    /// Unit structs are read from `null`, or from their name as written
+    /// with [`SerializerOptions::unit_struct_names`].
+    ///
+    /// [`SerializerOptions::unit_struct_names`]: crate::ser::SerializerOptions::unit_struct_names
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        if self.peek_null() || matches!(self.peek(), Some(b'{') | Some(b'[')) {
+            return self.deserialize_unit(visitor);
+        }
+        let start = self.pos;
+        match self.scalar()? {
+            Scalar::Bare(s) | Scalar::Quoted(s) if s == name => visitor.visit_unit(),
+            _ => Err(self.error_at(start, format!("expected null or `{}`", name))),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        if self.braceless || self.peek() != Some(b'[') {
+            return Err(self.error("expected array"));
+        }
+        self.enter()?;
+        let value = visitor.visit_seq(Seq {
+            de: &mut *self,
+            index: 0,
+        })?;
+        self.leave(b']')?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.take_braceless() {
+            self.count_node()?;
+            return visitor.visit_map(Map {
+                de: &mut *self,
+                braceless: true,
+                key: 0,
+                entries: 0,
+            });
+        }
+        self.skip_whitespace();
+        if self.peek() != Some(b'{') {
+            return Err(self.error("expected object"));
+        }
+        self.enter()?;
+        let value = visitor.visit_map(Map {
+            de: &mut *self,
+            braceless: false,
+            key: 0,
+            entries: 0,
+        })?;
+        self.leave(b'}')?;
+        Ok(value)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.skip_whitespace();
+        if self.peek() == Some(b'{') {
+            self.enter()?;
+            let value = visitor.visit_enum(Enum { de: &mut *self })?;
+            self.leave(b'}')?;
+            return Ok(value);
+        }
+        match self.scalar()? {
+            Scalar::Bare(Cow::Borrowed(s)) | Scalar::Quoted(Cow::Borrowed(s)) => {
+                visitor.visit_enum(s.into_deserializer())
+            }
+            Scalar::Bare(Cow::Owned(s)) | Scalar::Quoted(Cow::Owned(s)) => {
+                visitor.visit_enum(s.into_deserializer())
+            }
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_str(visitor)
+    }
+}
+
+struct Seq<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.de.skip_whitespace();
+        match self.de.peek() {
+            Some(b']') => Ok(None),
+            None | Some(b'}') => Err(self.de.error("expected array close bracket")),
+            Some(b':') | Some(b'=') => Err(self.de.error("invalid array separator")),
+            Some(_) => {
+                self.de.path.push(Segment::Index(self.index));
+                let value = seed.deserialize(&mut *self.de)?;
+                self.de.path.pop();
+                self.index += 1;
+                Ok(Some(value))
+            }
+        }
+    }
+}
+
+struct Map<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+    braceless: bool,
+    /// Where the last key starts.
+    key: usize,
+    /// The number of keys read.
+    entries: usize,
+}
+
+impl<'de> MapAccess<'de> for Map<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        self.de.skip_whitespace();
+        match self.de.peek() {
+            None if self.braceless => Ok(None),
+            Some(b'}') if !self.braceless => Ok(None),
+            None => Err(self.de.error("expected object close brace")),
+            Some(b'}') => Err(self.de.error("mismatched bracket")),
+            Some(b']') => Err(self.de.error("expected object close brace")),
+            Some(b'{') | Some(b'[') | Some(b':') | Some(b'=') => {
+                Err(self.de.error("expected object key"))
+            }
+            Some(_) => {
+                self.entries += 1;
+                self.de.check_entries(self.entries)?;
+                self.key = self.de.pos;
+                self.de.in_key = true;
+                let key = seed.deserialize(&mut *self.de);
+                self.de.in_key = false;
+                key.map(Some)
+            }
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.de.skip_key_separator();
+        match self.de.peek() {
+            None | Some(b'}') | Some(b']') => Err(self.de.error("expected object value")),
+            Some(_) => {
+                self.de.path.push(Segment::Key(self.key));
+                let value = seed.deserialize(&mut *self.de)?;
+                self.de.path.pop();
+                Ok(value)
+            }
+        }
+    }
+}
+
+struct Enum<'a, 'de: 'a> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        self.de.skip_whitespace();
+        let variant = seed.deserialize(&mut *self.de)?;
+        self.de.skip_key_separator();
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Enum<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+#[cfg(all(test, feature = "value"))]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+    use crate::value::Value;
+    use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_struct() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Test {
+            int: u32,
+            seq: Vec<String>,
+            str: String,
+            opt: Option<f64>,
+        }
+
+        let j = r#"{ "int": 1, "seq": ["a", "b"], "str": "string", "opt": null }"#;
+        let expected = Test {
+            int: 1,
+            seq: vec!["a".to_owned(), "b".to_owned()],
+            str: "string".to_owned(),
+            opt: None,
+        };
+        assert_eq!(expected, from_str(j).unwrap());
+
+        let j = "# comment\nint = 1\nseq = [ a b ]\nstr: string, opt 2.5\n";
+        let expected = Test {
+            opt: Some(2.5),
+            ..expected
+        };
+        assert_eq!(expected, from_str(j).unwrap());
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        enum E {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, u32),
+            Struct { a: u32 },
+        }
+
+        assert_eq!(E::Unit, from_str("Unit").unwrap());
+        assert_eq!(E::Unit, from_str(r#""Unit""#).unwrap());
+        assert_eq!(E::Newtype(1), from_str("{ Newtype = 1 }").unwrap());
+        assert_eq!(E::Tuple(1, 2), from_str("{ Tuple = [ 1 2 ] }").unwrap());
+        assert_eq!(
+            E::Struct { a: 1 },
+            from_str("{ Struct = { a = 1 } }").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Audio {
+            #[serde(rename = "audio.rate")]
+            rate: u32,
+            #[serde(rename = "audio.channels")]
+            channels: Option<u32>,
+        }
+
+        #[derive(Deserialize, PartialEq, Debug)]
+        struct Node {
+            name: String,
+            #[serde(flatten)]
+            audio: Audio,
+            #[serde(flatten)]
+            extra: HashMap<String, Value>,
+        }
+
+        let input = r#"
+            name = 1234
+            audio.rate = 48000
+            priority.session = 1000
+            audio.position = [ FL FR ]
+            node.description = "Speakers"
+        "#;
+        let node: Node = from_str(input).unwrap();
+        assert_eq!(node.name, "1234");
+        assert_eq!(
+            node.audio,
+            Audio {
+                rate: 48000,
+                channels: None
+            }
+        );
+        assert_eq!(node.extra.len(), 3);
+        assert_eq!(node.extra["priority.session"], Value::from(1000));
+        assert_eq!(
+            node.extra["audio.position"],
+            Value::Array(vec![Value::from("FL"), Value::from("FR")])
+        );
+
+        let node: Node =
+            from_str(r#"{ name = sink, audio.rate = 44100, audio.channels = 2 }"#).unwrap();
+        assert_eq!(node.audio.channels, Some(2));
+        assert!(node.extra.is_empty());
+
+        assert!(from_str::<Node>("name = sink").is_err());
+    }
+
+    #[test]
+    fn test_untagged() {
+        #[derive(Deserialize, PartialEq, Debug)]
+        #[serde(untagged)]
+        enum Latency {
+            Fraction { num: u32, denom: u32 },
+            Samples(u32),
+            Text(String),
+            List(Vec<Latency>),
+            PerRate(HashMap<String, u32>),
+            Unset(Option<()>),
+        }
+
+        let latencies: Vec<Latency> = from_str(
+            r#"[ 256 "512" 256/48000 { num = 1, denom = 48000 } [ 1 x ] { 44100 = 256 } null ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            latencies,
+            [
+                Latency::Samples(256),
+                Latency::Text("512".to_owned()),
+                Latency::Text("256/48000".to_owned()),
+                Latency::Fraction {
+                    num: 1,
+                    denom: 48000
+                },
+                Latency::List(vec![Latency::Samples(1), Latency::Text("x".to_owned())]),
+                Latency::PerRate([("44100".to_owned(), 256)].into_iter().collect()),
+                Latency::Unset(None),
+            ]
+        );
+        assert!(from_str::<Latency>("{ a = b }").is_err());
+
+        let rates: BTreeMap<u32, String> = [(44100, "cd".to_owned()), (48000, "dvd".to_owned())]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            from_str::<BTreeMap<u32, String>>(&to_string(&rates).unwrap()).unwrap(),
+            rates
+        );
+        let flags: BTreeMap<bool, i8> = from_str("{ true = 1, false = -1 }").unwrap();
+        assert_eq!(flags, BTreeMap::from([(false, -1), (true, 1)]));
+        assert!(from_str::<BTreeMap<u32, u32>>("{ x = 1 }").is_err());
+    }
+
+    #[test]
+    fn test_tagged_enum() {
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        #[serde(tag = "type", rename_all = "lowercase")]
+        enum FilterNode {
+            Builtin {
+                name: String,
+                label: String,
+            },
+            Ladspa {
+                name: String,
+                plugin: String,
+                label: String,
+                control: Option<HashMap<String, f64>>,
+            },
+            Sofa,
+        }
+
+        let nodes: Vec<FilterNode> = from_str(
+            r#"[
+                { type = builtin name = copy label = copy }
+                { name = eq, label = "bq_lowshelf", type = ladspa, plugin = "/usr/lib/eq.so"
+                  control = { "Freq" = 100 } }
+                { type = sofa }
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            nodes[0],
+            FilterNode::Builtin {
+                name: "copy".to_owned(),
+                label: "copy".to_owned()
+            }
+        );
+        let FilterNode::Ladspa {
+            plugin, control, ..
+        } = &nodes[1]
+        else {
+            panic!("expected ladspa node, got {:?}", nodes[1]);
+        };
+        assert_eq!(plugin, "/usr/lib/eq.so");
+        assert_eq!(control.as_ref().unwrap()["Freq"], 100.0);
+        assert_eq!(nodes[2], FilterNode::Sofa);
+        assert!(from_str::<FilterNode>("{ name = x }").is_err());
+        assert!(from_str::<FilterNode>("{ type = lv2 }").is_err());
+        assert_eq!(
+            from_str::<Vec<FilterNode>>(&to_string(&nodes).unwrap()).unwrap(),
+            nodes
+        );
+
+        #[derive(Serialize, Deserialize, PartialEq, Debug)]
+        #[serde(tag = "factory", content = "args")]
+        enum ContextObject {
+            #[serde(rename = "spa-node-factory")]
+            SpaNode(HashMap<String, Value>),
+            #[serde(rename = "adapter")]
+            Adapter {
+                #[serde(rename = "node.name")]
+                name: String,
+            },
+            #[serde(rename = "metadata")]
+            Metadata,
+        }
+
+        let objects: Vec<ContextObject> = from_str(
+            r#"[
+                { factory = spa-node-factory, args = { factory.name = support.node.driver } }
+                { args = { node.name = Dummy-Driver } factory = adapter }
+                { factory = metadata }
+            ]"#,
+        )
+        .unwrap();
+        let ContextObject::SpaNode(args) = &objects[0] else {
+            panic!("expected spa node, got {:?}", objects[0]);
+        };
+        assert_eq!(args["factory.name"], Value::from("support.node.driver"));
+        assert_eq!(
+            objects[1],
+            ContextObject::Adapter {
+                name: "Dummy-Driver".to_owned()
+            }
+        );
+        assert_eq!(objects[2], ContextObject::Metadata);
+        assert_eq!(
+            from_str::<Vec<ContextObject>>(&to_string(&objects).unwrap()).unwrap(),
+            objects
+        );
+    }
+
+    #[test]
+    fn test_value() {
+        let value: Value = from_str(
+            r#"
+            context.properties = {
+                default.clock.rate = 48000
+                node.name = "Horst \"Schlämmer\""
+                volume = -0.5
+                flag = true
+            }
+            context.modules = [ { name = libpipewire-module-rt } ]
+            "#,
+        )
+        .unwrap();
+        let props = value.get("context.properties").unwrap();
+        assert_eq!(props.get("default.clock.rate"), Some(&Value::from(48000)));
+        assert_eq!(
+            props.get("node.name"),
+            Some(&Value::from("Horst \"Schlämmer\""))
+        );
+        assert_eq!(props.get("volume"), Some(&Value::from(-0.5)));
+        assert_eq!(props.get("flag"), Some(&Value::from(true)));
+        let modules = value.get("context.modules").unwrap().as_array().unwrap();
+        assert_eq!(
+            modules[0].get("name").unwrap().as_str(),
+            Some("libpipewire-module-rt")
+        );
+
+        assert_eq!(
+            from_str::<Value>("").unwrap(),
+            Value::Object(Default::default())
+        );
+        assert_eq!(from_str::<Value>(" 42 ").unwrap(), Value::from(42));
+        assert_eq!(from_str::<Value>("\"true\"").unwrap(), Value::from("true"));
+        assert_eq!(from_str::<String>("48000").unwrap(), "48000");
+        assert_eq!(
+            from_str::<Vec<Value>>("[ 0x1F -0x10 0XffffFFFFffffFFFF 0x 0xg -0x8000000000000001 ]")
+                .unwrap(),
+            [
+                Value::from(31),
+                Value::from(-16),
+                Value::from(u64::MAX),
+                Value::from("0x"),
+                Value::from("0xg"),
+                Value::from("-0x8000000000000001"),
+            ]
+        );
+        assert_eq!(
+            from_str::<String>(r#""Schl\u00E4mmer \ud83c\udfa7 \u0041""#).unwrap(),
+            "Schlämmer 🎧 A"
+        );
+    }
+
+    #[test]
+    fn test_exponent() {
+        assert_eq!(
+            from_str::<Vec<Value>>("[ 1e6 2.5E-3 -1e+2 .5e1 1e 1e+ e5 1.5e3x ]").unwrap(),
+            [
+                Value::from(1e6),
+                Value::from(2.5e-3),
+                Value::from(-100.0),
+                Value::from(5.0),
+                Value::from("1e"),
+                Value::from("1e+"),
+                Value::from("e5"),
+                Value::from("1.5e3x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let value: Value =
+            from_str(r#"a = 'it\'s "quoted"' b = it's c = '' d = '\u00e4\n'"#).unwrap();
+        assert_eq!(value.get("a"), Some(&Value::from("it's \"quoted\"")));
+        assert_eq!(value.get("b"), Some(&Value::from("it's")));
+        assert_eq!(value.get("c"), Some(&Value::from("")));
+        assert_eq!(value.get("d"), Some(&Value::from("ä\n")));
+        assert_eq!(
+            from_str::<Value>("a = 'x").unwrap_err().to_string(),
+            "unfinished string at line 1 column 7"
+        );
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("SPA_JSON_TEST_RATE", "48000");
+        std::env::set_var("SPA_JSON_TEST_DIR", "/run/user/1000");
+        std::env::remove_var("SPA_JSON_TEST_UNSET");
+
+        let input = r#"
+            $SPA_JSON_TEST_RATE = 1
+            rate = $SPA_JSON_TEST_RATE
+            socket = "${SPA_JSON_TEST_DIR}/pipewire-0"
+            price = "$$5"
+            unset = "x$SPA_JSON_TEST_UNSET"
+        "#;
+        let keep = ParseOptions::new().expand_env(UndefinedVar::Keep);
+        let value: Value = from_str_with_options(input, keep.clone()).unwrap();
+        assert_eq!(value.get("$SPA_JSON_TEST_RATE"), Some(&Value::from(1)));
+        assert_eq!(value.get("rate"), Some(&Value::from(48000)));
+        assert_eq!(
+            value.get("socket"),
+            Some(&Value::from("/run/user/1000/pipewire-0"))
+        );
+        assert_eq!(value.get("price"), Some(&Value::from("$5")));
+        assert_eq!(
+            value.get("unset"),
+            Some(&Value::from("x$SPA_JSON_TEST_UNSET"))
+        );
+
+        let strict = ParseOptions::new().expand_env(UndefinedVar::Error);
+        assert_eq!(
+            from_str_with_options::<Value>(input, strict)
+                .unwrap_err()
+                .to_string(),
+            "undefined environment variable SPA_JSON_TEST_UNSET at line 6 column 21"
+        );
+        assert_eq!(
+            from_str::<Value>(input).unwrap().get("rate"),
+            Some(&Value::from("$SPA_JSON_TEST_RATE"))
+        );
+
+        let braced = "a = { rate = ${SPA_JSON_TEST_RATE}, dir = ${SPA_JSON_TEST_DIR}/x}";
+        let value: Value = from_str_with_options(braced, keep).unwrap();
+        assert_eq!(
+            value.to_canonical_string(),
+            r#"{"a":{"dir":"/run/user/1000/x","rate":48000}}"#
+        );
+        let error = from_str::<Value>(braced).unwrap_err().to_string();
+        assert_eq!(error, "mismatched bracket at line 1 column 62");
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let input = "a = [ 1 2 3 ], \"long key\" = { x = 1, y = 2 }, c = \"abcdef\"";
+        let errors = [
+            (
+                ParseLimits::new().max_input_bytes(16),
+                "input is longer than 16 bytes at line 1 column 1",
+            ),
+            (
+                ParseLimits::new().max_string_len(6),
+                "string is longer than 6 bytes at line 1 column 16",
+            ),
+            (
+                ParseLimits::new().max_nodes(5),
+                "document has more than 5 values at line 1 column 29",
+            ),
+            (
+                ParseLimits::new().max_object_entries(2),
+                "object has more than 2 entries at line 1 column 47",
+            ),
+        ];
+        for (limits, expected) in errors {
+            let options = ParseOptions::new().limits(limits);
+            let error = from_str_with_options::<Value>(input, options.clone()).unwrap_err();
+            assert_eq!(error.to_string(), expected);
+            let error = from_str_value(input, options).unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        }
+
+        let limits = ParseLimits::new()
+            .max_input_bytes(input.len())
+            .max_string_len(8)
+            .max_nodes(10)
+            .max_object_entries(3);
+        let options = ParseOptions::new().limits(limits);
+        let value: Value = from_str_with_options(input, options.clone()).unwrap();
+        assert_eq!(from_str_value(input, options).unwrap(), value);
+
+        #[derive(Deserialize)]
+        struct Sizes {
+            #[allow(dead_code)]
+            sizes: Vec<Option<u32>>,
+        }
+        let options = ParseOptions::new().limits(ParseLimits::new().max_nodes(4));
+        assert!(from_str_with_options::<Sizes>("sizes = [ null 1 ]", options.clone()).is_ok());
+        assert!(from_str_with_options::<Sizes>("sizes = [ null 1 null ]", options).is_err());
+    }
+
+    #[test]
+    fn test_lenient_bools() {
+        #[derive(Debug, Deserialize)]
+        struct Props {
+            a: bool,
+            b: bool,
+            c: bool,
+            d: Vec<bool>,
+        }
+
+        let input = "a = yes, b = \"Off\", c = true, d = [ 1 0 ON no ]";
+        let lenient = ParseOptions::new().lenient_bools(true);
+        let props: Props = from_str_with_options(input, lenient.clone()).unwrap();
+        assert!(props.a && !props.b && props.c);
+        assert_eq!(props.d, [true, false, true, false]);
+        assert_eq!(
+            from_str_with_options::<Props>("a = maybe", lenient)
+                .unwrap_err()
+                .to_string(),
+            "a: invalid type: string \"maybe\", expected a boolean at line 1 column 10"
+        );
+        assert!(from_str::<Props>(input).is_err());
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let input = "[ nan -inf +Infinity NaN \"inf\" info ]";
+        let values: Vec<Value> = from_str(input).unwrap();
+        assert_eq!(values[1], Value::from("-inf"));
+
+        let float = ParseOptions::new().non_finite(NonFinite::Float);
+        let values: Vec<Value> = from_str_with_options(input, float).unwrap();
+        assert!(values[0].as_f64().unwrap().is_nan());
+        assert_eq!(values[1], Value::from(f64::NEG_INFINITY));
+        assert_eq!(values[2], Value::from(f64::INFINITY));
+        assert!(values[3].as_f64().unwrap().is_nan());
+        assert_eq!(values[4..], [Value::from("inf"), Value::from("info")]);
+
+        let error = ParseOptions::new().non_finite(NonFinite::Error);
+        assert_eq!(
+            from_str_with_options::<Value>("rate = -inf", error)
+                .unwrap_err()
+                .to_string(),
+            "rate: non-finite number `-inf` at line 1 column 12"
+        );
+    }
+
+    #[test]
+    fn test_from_str_at() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Args {
+            #[serde(rename = "nice.level")]
+            nice: i32,
+        }
+
+        let input = r#"
+            context = { x = 1 }
+            context.properties = { default.clock.rate = 48000 }
+            context.modules = [
+                { name = libpipewire-module-protocol-native }
+                { name = libpipewire-module-rt, args = { nice.level = -11 } }
+            ]
+            trailing = [ "not checked"
+        "#;
+        let rate: u32 = from_str_at(input, "context.properties.default.clock.rate").unwrap();
+        assert_eq!(rate, 48000);
+        let args: Args = from_str_at(input, "context.modules[1].args").unwrap();
+        assert_eq!(args, Args { nice: -11 });
+        let x: u32 = from_str_at(input, "\"context\".x").unwrap();
+        assert_eq!(x, 1);
+
+        assert_eq!(
+            from_str_at::<u32>("a = { b = [ 1 ] }", "a.b[1]")
+                .unwrap_err()
+                .to_string(),
+            "no value at a.b[1]"
+        );
+        assert_eq!(
+            from_str_at::<String>(input, "context.modules[1].args")
+                .unwrap_err()
+                .to_string(),
+            "\"context.modules\"[1].args: invalid type: map, expected a string at line 6 column 57"
+        );
+    }
+
+    #[test]
+    fn test_from_str_with_defaults() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            #[serde(rename = "context.properties")]
+            properties: HashMap<String, u32>,
+            #[serde(rename = "context.modules")]
+            modules: Vec<String>,
+        }
+
+        let defaults: Value = "context.properties = { rate = 48000, quantum = 1024 }\n\
+                               context.modules = [ a ]"
+            .parse()
+            .unwrap();
+        let config: Config = from_str_with_defaults(
+            "context.properties = { quantum = 256 }\ncontext.modules = [ b ]",
+            &defaults,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                properties: HashMap::from([
+                    ("rate".to_owned(), 48000),
+                    ("quantum".to_owned(), 256)
+                ]),
+                modules: vec!["a".to_owned(), "b".to_owned()],
+            }
+        );
+
+        let error = |input: &str| {
+            from_str_with_defaults::<Config>(input, &defaults)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("context.modules = [ ]\ncontext.properties = { quantum = -1 }"),
+            "\"context.properties\".quantum: invalid value: integer `-1`, expected u32 \
+             at line 2 column 34"
+        );
+        assert_eq!(
+            error("context.properties = { quantum = 256 }, x = ["),
+            "expected array close bracket at line 1 column 46"
+        );
+        let defaults: Value = "context.properties = { rate = fast }".parse().unwrap();
+        assert_eq!(
+            from_str_with_defaults::<Config>("context.properties = { }", &defaults)
+                .unwrap_err()
+                .to_string(),
+            "\"context.properties\".rate: invalid type: string \"fast\", expected u32 \
+             at line 1 column 22"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| from_str::<Value>(s).unwrap_err().to_string();
+        assert_eq!(
+            error("a = [ 1 = 2 ]"),
+            "invalid array separator at line 1 column 9"
+        );
+        assert_eq!(
+            error("a = {\n  b = 1\n"),
+            "expected object close brace at line 3 column 1"
+        );
+        assert_eq!(error("a = \"x"), "unfinished string at line 1 column 7");
+        assert_eq!(
+            error("a = { b }"),
+            "expected object value at line 1 column 9"
+        );
+        assert_eq!(error("a = b\\c"), "escape not allowed at line 1 column 6");
+        assert_eq!(
+            error(r#"a = "\u12""#),
+            "invalid unicode escape at line 1 column 6"
+        );
+        assert_eq!(
+            error(r#"a = "x\ud83c""#),
+            "lone surrogate in unicode escape at line 1 column 7"
+        );
+        assert_eq!(
+            error(r#"a = "\ud83c\u0041""#),
+            "lone surrogate in unicode escape at line 1 column 6"
+        );
+        assert_eq!(
+            error(r#"a = "\udfa7""#),
+            "lone surrogate in unicode escape at line 1 column 6"
+        );
+        assert_eq!(error("[ 1 ] 2"), "trailing characters at line 1 column 7");
+        assert_eq!(
+            from_str::<u32>("hello").unwrap_err().to_string(),
+            "invalid type: string \"hello\", expected u32 at line 1 column 6"
+        );
+    }
+
+    #[test]
+    fn test_error_path() {
+        #[derive(Debug, Deserialize)]
+        struct Module {
+            #[allow(dead_code)]
+            args: HashMap<String, u32>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct Config {
+            #[allow(dead_code)]
+            #[serde(rename = "context.modules")]
+            modules: Vec<Module>,
+        }
+
+        let input = "context.modules = [\n  { args = { rate = 48000 } }\n  { args = { \"the rate\" = fast } }\n]";
+        let err = from_str::<Config>(input).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"context.modules\"[1].args.\"the rate\": invalid type: string \"fast\", \
+             expected u32 at line 3 column 31"
+        );
+        match err {
+            Error::Parse { path, .. } => assert_eq!(
+                path,
+                [
+                    PathSegment::Key("context.modules".to_owned()),
+                    PathSegment::Index(1),
+                    PathSegment::Key("args".to_owned()),
+                    PathSegment::Key("the rate".to_owned()),
+                ]
+            ),
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        let err = from_str::<Config>("context.modules = [ { args = {} }, {} ]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "\"context.modules\"[1]: missing field `args` at line 1 column 37"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! A parser for [`Value`]s that keeps the arrays and objects being read on
+//! a stack of its own instead of recursing, so that the nesting depth of
+//! untrusted input cannot overflow the stack, even with
+//! [`ParseOptions::max_depth`] raised or disabled.
+//!
+//! It reads the same syntax and reports the same errors as deserializing a
+//! [`Value`] with [`from_str_with_options`](super::from_str_with_options).
+
+use super::{Deserializer, Error, ParseOptions, Scalar, Segment};
+use crate::trace;
+use crate::value::{Interner, Key, Map, Value};
+use serde::Deserialize;
+
+/// Parses a document into a [`Value`] without recursing.
+///
+/// ```
+/// use spa_json::de::{from_str_value, ParseOptions};
+///
+/// let input = "[".repeat(100_000) + &"]".repeat(100_000);
+/// assert!(from_str_value(&input, ParseOptions::new()).is_err());
+/// let options = ParseOptions::new().max_depth(usize::MAX);
+/// assert!(from_str_value(&input, options).is_ok());
+/// ```
+pub fn from_str_value(input: &str, options: ParseOptions) -> Result<Value, Error> {
+    let phase = trace::phase!("parse", bytes = input.len());
+    let mut deserializer = Deserializer::from_str_with_options(input, options);
+    phase.result(
+        parse(&mut deserializer)
+            .map_err(|e| deserializer.locate(e))
+            .and_then(|value| deserializer.end().map(|()| value)),
+    )
+}
+
+/// An array or object being read.
+enum Open {
+    Array(Vec<Value>),
+    Object {
+        map: Map,
+        braceless: bool,
+        /// The key of the value being read.
+        key: Option<Key>,
+        /// The number of keys read, counting duplicates.
+        entries: usize,
+    },
+}
+
+fn parse(de: &mut Deserializer) -> Result<Value, Error> {
+    let mut keys = Interner::default();
+    let mut open = Vec::new();
+    // Whether a value starts at the current position, rather than the
+    // next item or entry of the innermost container or its end.
+    let mut at_value = true;
+    if de.take_braceless() {
+        de.count_node()?;
+        open.push(Open::Object {
+            map: Map::new(),
+            braceless: true,
+            key: None,
+            entries: 0,
+        });
+        at_value = false;
+    }
+    loop {
+        de.skip_whitespace();
+        let value = if at_value {
+            match de.peek() {
+                Some(b'{') => {
+                    de.enter()?;
+                    open.push(Open::Object {
+                        map: Map::new(),
+                        braceless: false,
+                        key: None,
+                        entries: 0,
+                    });
+                    at_value = false;
+                    continue;
+                }
+                Some(b'[') => {
+                    de.enter()?;
+                    open.push(Open::Array(Vec::new()));
+                    at_value = false;
+                    continue;
+                }
+                _ => Value::deserialize(&mut *de)?,
+            }
+        } else {
+            match open.last_mut() {
+                Some(Open::Array(items)) => match de.peek() {
+                    Some(b']') => {
+                        de.leave(b']')?;
+                        close(&mut open)
+                    }
+                    None | Some(b'}') => return Err(de.error("expected array close bracket")),
+                    Some(b':') | Some(b'=') => return Err(de.error("invalid array separator")),
+                    Some(_) => {
+                        de.path.push(Segment::Index(items.len()));
+                        at_value = true;
+                        continue;
+                    }
+                },
+                Some(Open::Object {
+                    braceless,
+                    key,
+                    entries,
+                    ..
+                }) => match de.peek() {
+                    None if *braceless => close(&mut open),
+                    Some(b'}') if !*braceless => {
+                        de.leave(b'}')?;
+                        close(&mut open)
+                    }
+                    None => return Err(de.error("expected object close brace")),
+                    Some(b'}') => return Err(de.error("mismatched bracket")),
+                    Some(b']') => return Err(de.error("expected object close brace")),
+                    Some(b'{') | Some(b'[') | Some(b':') | Some(b'=') => {
+                        return Err(de.error("expected object key"))
+                    }
+                    Some(_) => {
+                        *entries += 1;
+                        de.check_entries(*entries)?;
+                        let key_pos = de.pos;
+                        de.in_key = true;
+                        let scalar = de.scalar();
+                        de.in_key = false;
+                        let (Scalar::Bare(name) | Scalar::Quoted(name)) = scalar?;
+                        *key = Some(keys.intern(&name));
+                        de.skip_key_separator();
+                        if let None | Some(b'}' | b']') = de.peek() {
+                            return Err(de.error("expected object value"));
+                        }
+                        de.path.push(Segment::Key(key_pos));
+                        at_value = true;
+                        continue;
+                    }
+                },
+                None => unreachable!("no open container to continue"),
+            }
+        };
+        match open.last_mut() {
+            None => return Ok(value),
+            Some(Open::Array(items)) => items.push(value),
+            Some(Open::Object { map, key, .. }) => {
+                map.insert(key.take().unwrap(), value);
+            }
+        }
+        de.path.pop();
+        at_value = false;
+    }
+}
+
+/// Ends the innermost container, returning it as a value.
+fn close(open: &mut Vec<Open>) -> Value {
+    match open.pop() {
+        Some(Open::Array(items)) => Value::Array(items),
+        Some(Open::Object { map, .. }) => Value::Object(map),
+        None => unreachable!("no open container to close"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::{from_str_with_options, NonFinite};
+
+    #[test]
+    fn test_same_as_deserialize() {
+        let options = ParseOptions::new().non_finite(NonFinite::Error);
+        for input in [
+            "",
+            "null",
+            "a = 1, b = [ x 2.5 \"y z\" { c = true } ], a = { d = [] }",
+            "{ \"a b\": [ [ ], { } ], 'c' = \"\\u00e9\" } # comment",
+            "[ 1 2 3",
+            "{ a = 1 ]",
+            "a = 1 }",
+            "[ a = 1 ]",
+            "{ a }",
+            "{ = 1 }",
+            "[ ] [ ]",
+            "a = { b = [ 1 { c = nan } ] }",
+            "a = [ \"unterminated ]",
+        ] {
+            let expected = from_str_with_options::<Value>(input, options.clone());
+            let value = from_str_value(input, options.clone());
+            match (value, expected) {
+                (Ok(value), Ok(expected)) => assert_eq!(value, expected, "{}", input),
+                (Err(error), Err(expected)) => {
+                    assert_eq!(error.to_string(), expected.to_string(), "{}", input)
+                }
+                (value, expected) => panic!("{}: {:?} != {:?}", input, value, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep() {
+        let depth = 200_000;
+        let input = "{ a = [ ".repeat(depth) + &" ] }".repeat(depth);
+        assert_eq!(
+            from_str_value(&input, ParseOptions::new())
+                .unwrap_err()
+                .to_string(),
+            "too deep nesting at line 1 column 513"
+        );
+        let options = ParseOptions::new().max_depth(usize::MAX);
+        let parsed = from_str_value(&input, options).unwrap();
+        let mut value = &parsed;
+        for _ in 1..depth {
+            value = &value.get("a").unwrap().as_array().unwrap()[0];
+        }
+        assert_eq!(value.get("a"), Some(&Value::Array(Vec::new())));
+    }
+}
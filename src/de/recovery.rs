@@ -0,0 +1,380 @@
+//! A parser that carries on after syntax errors, so that every problem in a
+//! document can be reported at once.
+//!
+//! It reads the same syntax as [`Deserializer`] and reports the same
+//! errors. After an error it skips the offending token: a stray bracket or
+//! separator, a malformed scalar or a key without a value. A closing
+//! bracket of the wrong kind ends the container it belongs to if that is
+//! open, and unterminated strings are cut at the end of their line.
+
+use super::{is_delimiter, Deserializer, Error, Scalar, MAX_DEPTH};
+use crate::number::{parse_number, Number};
+use crate::value::{Map, Value};
+
+/// Parses a document like [`Value::from_str`](std::str::FromStr), but
+/// instead of stopping at the first syntax error, returns all of them in
+/// the order they appear.
+pub fn parse_with_recovery(input: &str) -> Result<Value, Vec<Error>> {
+    let (value, errors) = parse_lossy(input);
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses as much of a document as possible, returning it along with the
+/// syntax errors found, for tools that show or inspect broken files.
+///
+/// Entries and items whose value is malformed are left out, as are keys
+/// without a value. An unclosed container holds what was read up to the
+/// end of the input. If the document itself is a malformed scalar, the
+/// value is null.
+pub fn parse_lossy(input: &str) -> (Value, Vec<Error>) {
+    let mut parser = Parser {
+        de: Deserializer::from_str(input),
+        open: Vec::new(),
+        errors: Vec::new(),
+    };
+    let value = if parser.de.take_braceless() {
+        Value::Object(parser.members(true))
+    } else {
+        parser.value().unwrap_or_default()
+    };
+    parser.de.skip_whitespace();
+    if parser.de.peek().is_some() {
+        let error = parser.de.error("trailing characters");
+        parser.errors.push(error);
+    }
+    (value, parser.errors)
+}
+
+struct Parser<'de> {
+    de: Deserializer<'de>,
+    /// The opening brackets of the containers being parsed.
+    open: Vec<u8>,
+    errors: Vec<Error>,
+}
+
+impl<'de> Parser<'de> {
+    fn report(&mut self, message: &str) {
+        let error = self.de.error(message);
+        self.errors.push(error);
+    }
+
+    /// Parses a value, or reports its errors and returns `None` if it is
+    /// malformed. Malformed parts of a container are dropped, but the
+    /// container itself is kept.
+    fn value(&mut self) -> Option<Value> {
+        self.de.skip_whitespace();
+        match self.de.peek() {
+            Some(open @ (b'{' | b'[')) => {
+                if self.de.depth >= MAX_DEPTH {
+                    self.report("too deep nesting");
+                    self.skip_container();
+                    return None;
+                }
+                self.de.depth += 1;
+                self.de.pos += 1;
+                self.open.push(open);
+                let value = if open == b'{' {
+                    Value::Object(self.members(false))
+                } else {
+                    Value::Array(self.elements())
+                };
+                self.open.pop();
+                self.de.depth -= 1;
+                Some(value)
+            }
+            _ => Some(match self.scalar()? {
+                Scalar::Quoted(s) => Value::from(s.into_owned()),
+                Scalar::Bare(s) => match &*s {
+                    "true" => Value::Bool(true),
+                    "false" => Value::Bool(false),
+                    "null" => Value::Null,
+                    t => match parse_number(t) {
+                        Some(Number::Int(v)) => Value::from(v),
+                        Some(Number::UInt(v)) => Value::from(v),
+                        Some(Number::Float(v)) => Value::from(v),
+                        None => Value::from(s.into_owned()),
+                    },
+                },
+            }),
+        }
+    }
+
+    /// Parses the entries of an object up to and including its closing
+    /// brace, or to the end of the input if it is `braceless`.
+    fn members(&mut self, braceless: bool) -> Map {
+        let mut map = Map::new();
+        loop {
+            self.de.skip_whitespace();
+            match self.de.peek() {
+                None if braceless => break,
+                Some(b'}') if !braceless => {
+                    self.de.pos += 1;
+                    break;
+                }
+                None => {
+                    self.report("expected object close brace");
+                    break;
+                }
+                Some(b'}') => {
+                    self.report("mismatched bracket");
+                    self.de.pos += 1;
+                }
+                Some(b']') => {
+                    self.report("expected object close brace");
+                    if self.open.contains(&b'[') {
+                        break;
+                    }
+                    self.de.pos += 1;
+                }
+                Some(b'{') | Some(b'[') => {
+                    self.report("expected object key");
+                    self.value();
+                }
+                Some(b':') | Some(b'=') => {
+                    self.report("expected object key");
+                    self.de.pos += 1;
+                }
+                Some(_) => {
+                    self.de.in_key = true;
+                    let key = self.scalar();
+                    self.de.in_key = false;
+                    self.de.skip_key_separator();
+                    match self.de.peek() {
+                        None | Some(b'}') | Some(b']') => {
+                            self.report("expected object value");
+                            // Drop the bracket here if it closes nothing.
+                            match self.de.peek() {
+                                Some(b'}') if braceless => self.de.pos += 1,
+                                Some(b']') if !self.open.contains(&b'[') => self.de.pos += 1,
+                                _ => {}
+                            }
+                            continue;
+                        }
+                        _ => {}
+                    }
+                    let value = self.value();
+                    if let (Some(Scalar::Bare(key) | Scalar::Quoted(key)), Some(value)) =
+                        (key, value)
+                    {
+                        map.insert(key.into_owned(), value);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    /// Parses the items of an array up to and including its closing bracket.
+    fn elements(&mut self) -> Vec<Value> {
+        let mut array = Vec::new();
+        loop {
+            self.de.skip_whitespace();
+            match self.de.peek() {
+                Some(b']') => {
+                    self.de.pos += 1;
+                    break;
+                }
+                None => {
+                    self.report("expected array close bracket");
+                    break;
+                }
+                Some(b'}') => {
+                    self.report("expected array close bracket");
+                    if self.open.contains(&b'{') {
+                        break;
+                    }
+                    self.de.pos += 1;
+                }
+                Some(b':') | Some(b'=') => {
+                    self.report("invalid array separator");
+                    self.de.pos += 1;
+                }
+                Some(_) => array.extend(self.value()),
+            }
+        }
+        array
+    }
+
+    /// Reads a scalar, or reports its error and skips it.
+    fn scalar(&mut self) -> Option<Scalar<'de>> {
+        let start = self.de.pos;
+        match self.de.scalar() {
+            Ok(scalar) => Some(scalar),
+            Err(error) => {
+                self.errors.push(error);
+                self.skip_token(start);
+                None
+            }
+        }
+    }
+
+    /// Moves past the malformed token at `start`: to the end of a quoted
+    /// string, at the latest at the end of its line, or to the next
+    /// delimiter.
+    fn skip_token(&mut self, start: usize) {
+        let bytes = self.de.input.as_bytes();
+        let mut pos = start + 1;
+        match bytes[start] {
+            quote @ (b'"' | b'\'') => {
+                while pos < bytes.len() && bytes[pos] != b'\n' {
+                    match bytes[pos] {
+                        b'\\' => pos += 2,
+                        c if c == quote => {
+                            pos += 1;
+                            break;
+                        }
+                        _ => pos += 1,
+                    }
+                }
+            }
+            _ => {
+                while pos < bytes.len() && !is_delimiter(bytes[pos]) {
+                    pos += 1;
+                }
+            }
+        }
+        self.de.pos = pos.min(bytes.len());
+    }
+
+    /// Skips a container that is nested too deeply, up to its matching
+    /// bracket.
+    fn skip_container(&mut self) {
+        let bytes = self.de.input.as_bytes();
+        let mut depth = 0;
+        while let Some(&c) = bytes.get(self.de.pos) {
+            match c {
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.de.pos += 1;
+                        return;
+                    }
+                }
+                b'"' | b'\'' => {
+                    self.skip_token(self.de.pos);
+                    continue;
+                }
+                _ => {}
+            }
+            self.de.pos += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::to_string;
+
+    fn errors(input: &str) -> Vec<String> {
+        match parse_with_recovery(input) {
+            Ok(_) => Vec::new(),
+            Err(errors) => errors.iter().map(Error::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_with_recovery() {
+        let input = "a = 1\nb = [ 1 = 2 ]\nc = \"x\\q\"\nd = { e }\nf = b\\c\ng = 2";
+        assert_eq!(
+            errors(input),
+            [
+                "invalid array separator at line 2 column 9",
+                "invalid escape at line 3 column 7",
+                "expected object value at line 4 column 9",
+                "escape not allowed at line 5 column 6",
+            ]
+        );
+        let value = parse_with_recovery("a = 1\nb = { c = 2 }").unwrap();
+        assert_eq!(value, "a = 1\nb = { c = 2 }".parse().unwrap());
+
+        // Unterminated strings end at the end of their line.
+        assert_eq!(
+            errors("a = \"x\nb = ]\n"),
+            [
+                "character not allowed at line 1 column 7",
+                "expected object value at line 2 column 5",
+            ]
+        );
+        assert_eq!(
+            errors("{ a = [ 1 }\nb = 2"),
+            [
+                "expected array close bracket at line 1 column 11",
+                "trailing characters at line 2 column 1",
+            ]
+        );
+        assert_eq!(
+            errors("[ 1 ] 2"),
+            ["trailing characters at line 1 column 7"]
+        );
+
+        // The first error is the one the strict parser reports.
+        for input in ["a = [ 1 = 2 ]", "a = {\n  b = 1\n", "a = { b }", "[ 1 ] 2"] {
+            let strict = input.parse::<Value>().unwrap_err().to_string();
+            assert_eq!(errors(input)[0], strict, "{}", input);
+        }
+
+        let deep = format!("a = {}{}\nb = c\\d", "[".repeat(200), "]".repeat(200));
+        assert_eq!(
+            errors(&deep),
+            [
+                "too deep nesting at line 1 column 133",
+                "escape not allowed at line 2 column 6",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lossy() {
+        let input = r#"
+            context.properties = {
+                default.clock.rate = 48000
+                bad = "x\q"
+                default.clock.quantum
+            }
+            context.modules = [
+                { name = libpipewire-module-rt }
+                { name = b\c }
+                broken\ }
+            ]
+            context.exec = [ { path = /usr/bin/true
+        "#;
+        let (value, errors) = parse_lossy(input);
+        let messages: Vec<String> = errors.iter().map(Error::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "invalid escape at line 4 column 25",
+                "expected object value at line 6 column 13",
+                "escape not allowed at line 9 column 27",
+                "escape not allowed at line 10 column 23",
+                "expected array close bracket at line 10 column 25",
+                "expected object close brace at line 13 column 9",
+                "expected array close bracket at line 13 column 9",
+            ]
+        );
+        assert_eq!(
+            to_string(&value).unwrap(),
+            to_string(
+                &r#"{
+                    context.properties = { default.clock.rate = 48000 }
+                    context.modules = [ { name = libpipewire-module-rt } {} ]
+                    context.exec = [ { path = /usr/bin/true } ]
+                }"#
+                .parse::<Value>()
+                .unwrap()
+            )
+            .unwrap()
+        );
+
+        assert_eq!(parse_lossy(r"a\b").0, Value::Null);
+        let (value, errors) = parse_lossy("a = 1");
+        assert_eq!(value, "a = 1".parse().unwrap());
+        assert!(errors.is_empty());
+    }
+}
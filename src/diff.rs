@@ -0,0 +1,192 @@
+use crate::path::{DisplayPath, PathSegment, SpaPath};
+use crate::value::Value;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    Added {
+        path: SpaPath,
+        value: Value,
+    },
+    Removed {
+        path: SpaPath,
+        value: Value,
+    },
+    Modified {
+        path: SpaPath,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl Change {
+    pub fn path(&self) -> &SpaPath {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } => path,
+            Change::Modified { path, .. } => path,
+        }
+    }
+}
+
+/// Structural difference from `old` to `new`.
+///
+/// Objects are compared key by key and arrays element by element, so an
+/// element inserted in the middle of an array shows up as modifications of
+/// every later element plus an addition at the end.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    diff_by(old, new, &|a, b| a == b)
+}
+
+/// [`diff`] with `same` telling whether two values that are not both
+/// arrays or both objects are unchanged.
+pub(crate) fn diff_by(
+    old: &Value,
+    new: &Value,
+    same: &dyn Fn(&Value, &Value) -> bool,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_into(&mut SpaPath::new(), old, new, same, &mut changes);
+    changes
+}
+
+fn diff_into(
+    path: &mut SpaPath,
+    old: &Value,
+    new: &Value,
+    same: &dyn Fn(&Value, &Value) -> bool,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, old_value) in a.iter() {
+                path.push(PathSegment::Key(key.to_string()));
+                match b.get(key) {
+                    Some(new_value) => diff_into(path, old_value, new_value, same, changes),
+                    None => changes.push(Change::Removed {
+                        path: path.clone(),
+                        value: old_value.clone(),
+                    }),
+                }
+                path.pop();
+            }
+            for (key, new_value) in b.iter().filter(|(k, _)| !a.contains_key(k)) {
+                path.push(PathSegment::Key(key.to_string()));
+                changes.push(Change::Added {
+                    path: path.clone(),
+                    value: new_value.clone(),
+                });
+                path.pop();
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for index in 0..a.len().max(b.len()) {
+                path.push(PathSegment::Index(index));
+                match (a.get(index), b.get(index)) {
+                    (Some(old_value), Some(new_value)) => {
+                        diff_into(path, old_value, new_value, same, changes)
+                    }
+                    (Some(old_value), None) => changes.push(Change::Removed {
+                        path: path.clone(),
+                        value: old_value.clone(),
+                    }),
+                    (None, Some(new_value)) => changes.push(Change::Added {
+                        path: path.clone(),
+                        value: new_value.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+                path.pop();
+            }
+        }
+        _ if !same(old, new) => changes.push(Change::Modified {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (sign, path) = match self {
+            Change::Added { path, .. } => ('+', path),
+            Change::Removed { path, .. } => ('-', path),
+            Change::Modified { path, .. } => ('~', path),
+        };
+        write!(f, "{} {}", sign, DisplayPath(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Map;
+
+    #[test]
+    fn test_object_changes() {
+        let old = Value::from(Map::from_iter([
+            ("default.clock.rate", Value::from(44100)),
+            ("mem.warn-mlock", Value::from(false)),
+            (
+                "props",
+                Value::from(Map::from_iter([("a", Value::from(1))])),
+            ),
+        ]));
+        let new = Value::from(Map::from_iter([
+            ("default.clock.rate", Value::from(48000)),
+            (
+                "props",
+                Value::from(Map::from_iter([
+                    ("a", Value::from(1)),
+                    ("b", Value::from("x")),
+                ])),
+            ),
+        ]));
+
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                Change::Modified {
+                    path: SpaPath::new().key("default.clock.rate"),
+                    old: Value::from(44100),
+                    new: Value::from(48000),
+                },
+                Change::Removed {
+                    path: SpaPath::new().key("mem.warn-mlock"),
+                    value: Value::from(false),
+                },
+                Change::Added {
+                    path: SpaPath::new().key("props").key("b"),
+                    value: Value::from("x"),
+                },
+            ]
+        );
+        let rendered: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            rendered,
+            [
+                "~ \"default.clock.rate\"",
+                "- \"mem.warn-mlock\"",
+                "+ props.b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_changes() {
+        let old = Value::Array(vec![Value::from(1), Value::from(2)]);
+        let new = Value::Array(vec![Value::from(1), Value::from(3), Value::from(4)]);
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(*changes[0].path(), [PathSegment::Index(1)]);
+        assert_eq!(changes[1].to_string(), "+ [2]");
+        assert!(diff(&new, &new).is_empty());
+        assert_eq!(
+            diff(&Value::Object(Map::new()), &Value::Array(vec![])).len(),
+            1
+        );
+    }
+}
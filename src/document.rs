@@ -0,0 +1,242 @@
+//! Documents that are kept parsed while they are edited, for editor
+//! integration.
+//!
+//! An edit inside an array or object is handled by parsing only that
+//! container again, as long as its brackets are not touched. Since the
+//! parser reads a container the same way wherever it appears, this gives
+//! the same result as parsing the whole text. Edits elsewhere, or ones that
+//! break the container, fall back to a full parse.
+//!
+//! ```
+//! use spa_json::document::Document;
+//! use spa_json::path::PathSegment;
+//! use spa_json::value::Value;
+//!
+//! let mut doc = Document::parse("rate = 48000\nrates = [ 44100 48000 ]".to_owned()).unwrap();
+//! let range = doc.text().find("48000 ]").unwrap();
+//! let path = doc.edit(range..range + 5, "96000").unwrap();
+//! assert_eq!(path, [PathSegment::Key("rates".to_owned())]);
+//! assert_eq!(doc.value().get("rates").unwrap().as_array().unwrap()[1], Value::from(96000));
+//! ```
+
+use crate::de::{self, MAX_DEPTH};
+use crate::error::Error;
+use crate::path::{PathSegment, SpaPath};
+use crate::source_map::{Node, SourceMap};
+use crate::value::Value;
+use std::ops::Range;
+
+#[derive(Clone, Debug)]
+pub struct Document {
+    text: String,
+    value: Value,
+    /// The syntax tree of `value`, or `None` if the text no longer parses.
+    tree: Option<Node>,
+}
+
+impl Document {
+    pub fn parse(text: String) -> Result<Document, Error> {
+        let value: Value = text.parse()?;
+        let tree = Node::build(&text, 0);
+        Ok(Document {
+            text,
+            value,
+            tree: Some(tree),
+        })
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The value of the text as of the last edit that left it valid.
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The positions of the values in the text, or `None` while it does
+    /// not parse.
+    pub fn source_map(&self) -> Option<SourceMap> {
+        let mut map = SourceMap::default();
+        map.add(self.tree.as_ref()?, &mut Vec::new());
+        Some(map)
+    }
+
+    /// Replaces `range` of the text with `replacement` and updates the
+    /// value, returning the path of the container that was parsed again,
+    /// which is empty when the whole text was.
+    ///
+    /// The text is changed even if it no longer parses; the error is then
+    /// returned and [`value`](Self::value) is left as it was until an edit
+    /// makes the text valid again.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Result<SpaPath, Error> {
+        if range.start > range.end
+            || !self.text.is_char_boundary(range.start)
+            || !self.text.is_char_boundary(range.end)
+        {
+            return Err(Error::Message(format!(
+                "invalid edit range {}..{}",
+                range.start, range.end
+            )));
+        }
+        self.text.replace_range(range.clone(), replacement);
+        if let Some(path) = self.reparse_container(&range, replacement.len()) {
+            return Ok(path);
+        }
+        let value = self.text.parse();
+        self.tree = None;
+        self.value = value?;
+        self.tree = Some(Node::build(&self.text, 0));
+        Ok(SpaPath::new())
+    }
+
+    /// Parses the innermost container around the edit again, if there is
+    /// one below the root and the edit leaves it valid.
+    fn reparse_container(&mut self, range: &Range<usize>, inserted: usize) -> Option<SpaPath> {
+        let tree = self.tree.as_mut()?;
+        // The indices of the children leading to the container, their
+        // path, and the number of brackets around the container.
+        let mut indices = Vec::new();
+        let mut path = SpaPath::new();
+        let mut depth = usize::from(tree.container);
+        let mut node = &*tree;
+        while let Some(i) = node.children.iter().position(|child| {
+            child.container && child.span.start < range.start && range.end < child.span.end
+        }) {
+            node = &node.children[i];
+            indices.push(i);
+            path.push(match &node.key {
+                Some((key, _)) => PathSegment::Key(key.clone()),
+                None => PathSegment::Index(i),
+            });
+            depth += 1;
+        }
+        if indices.is_empty() || has_duplicate_keys(node) {
+            return None;
+        }
+
+        let end = node.span.end + inserted - (range.end - range.start);
+        let span = node.span.start..end;
+        let value: Value = de::from_str(&self.text[span.clone()]).ok()?;
+        if depth - 1 + nesting(&value) > MAX_DEPTH {
+            return None;
+        }
+        let mut new = Node::build(&self.text[span.clone()], span.start);
+        new.key = node.key.clone();
+        let delta = end as isize - node.span.end as isize;
+
+        *self.value.get_path_mut(&path)? = value;
+        // Replace the node, and move everything after it.
+        let mut node = tree;
+        for &i in &indices {
+            node.span.end = node.span.end.wrapping_add_signed(delta);
+            for later in &mut node.children[i + 1..] {
+                later.shift(delta);
+            }
+            node = &mut node.children[i];
+        }
+        *node = new;
+        Some(path)
+    }
+}
+
+fn has_duplicate_keys(node: &Node) -> bool {
+    let key = |node: &Node| node.key.as_ref().map(|(key, _)| key.clone());
+    node.children.iter().enumerate().any(|(i, child)| {
+        child.key.is_some() && node.children[..i].iter().any(|c| key(c) == key(child))
+    })
+}
+
+/// Number of nested containers in `value`, counting itself.
+fn nesting(value: &Value) -> usize {
+    match value {
+        Value::Array(a) => 1 + a.iter().map(nesting).max().unwrap_or(0),
+        Value::Object(m) => 1 + m.values().map(nesting).max().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = r#"
+context.properties = { default.clock.rate = 48000 }
+context.modules = [
+    { name = libpipewire-module-rt, args = { nice.level = -11 } }
+    { name = libpipewire-module-protocol-native }
+]
+"#;
+
+    fn edit(doc: &mut Document, find: &str, replacement: &str) -> SpaPath {
+        let start = doc.text().find(find).unwrap();
+        let path = doc.edit(start..start + find.len(), replacement).unwrap();
+        assert_eq!(doc.value(), &doc.text().parse::<Value>().unwrap());
+        path
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut doc = Document::parse(INPUT.to_owned()).unwrap();
+        let modules = PathSegment::Key("context.modules".to_owned());
+        let args = vec![
+            modules.clone(),
+            PathSegment::Index(0),
+            PathSegment::Key("args".to_owned()),
+        ];
+
+        assert_eq!(edit(&mut doc, "-11", "-15"), args);
+        assert_eq!(edit(&mut doc, "-15", "-5 rt.prio = 88"), args);
+        assert_eq!(
+            doc.value()
+                .get("context.modules")
+                .unwrap()
+                .as_array()
+                .unwrap()[0]
+                .get("args")
+                .unwrap()
+                .get("rt.prio"),
+            Some(&Value::from(88))
+        );
+        // Spans after the edit moved along.
+        assert_eq!(
+            edit(&mut doc, "protocol-native", "protocol-pulse"),
+            [modules.clone(), PathSegment::Index(1)]
+        );
+        assert_eq!(
+            edit(
+                &mut doc,
+                "\n    { name = libpipewire-module-protocol-pulse }",
+                ""
+            ),
+            [modules]
+        );
+        assert_eq!(
+            edit(&mut doc, "48000", "44100"),
+            [PathSegment::Key("context.properties".to_owned())]
+        );
+        // Outside of any container, or touching brackets.
+        assert_eq!(edit(&mut doc, "context.properties", "props"), []);
+        assert_eq!(edit(&mut doc, "{ default", "{ x = 1, default"), []);
+
+        let map = doc.source_map().unwrap();
+        let span = map.get(&[PathSegment::Key("props".to_owned())]).unwrap();
+        assert_eq!(
+            &doc.text()[span.value.clone()],
+            "{ x = 1, default.clock.rate = 44100 }"
+        );
+
+        // An edit that breaks the text keeps the last value.
+        let before = doc.value().clone();
+        let start = doc.text().find("nice.level").unwrap();
+        assert!(doc.edit(start..start, "}").is_err());
+        assert_eq!(doc.value(), &before);
+        assert!(doc.source_map().is_none());
+        assert!(doc.text().contains("}nice.level"));
+        assert_eq!(edit(&mut doc, "}nice.level", "nice.level"), []);
+
+        let mut doc = Document::parse("a = { b = 1, b = 2 }".to_owned()).unwrap();
+        assert_eq!(edit(&mut doc, "2", "3"), []);
+        assert!(doc.edit(3..doc.text().len() + 1, "").is_err());
+    }
+}
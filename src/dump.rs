@@ -0,0 +1,251 @@
+//! Typed model of the JSON printed by `pw-dump`.
+//!
+//! Only the object types most tools care about are modelled; everything else
+//! (modules, factories, metadata, ...) is kept as [`Object::Other`].
+
+use crate::de::{self, Error};
+use crate::value::{Map, Value};
+use serde::Deserialize;
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+pub enum Object {
+    #[serde(rename = "PipeWire:Interface:Node")]
+    Node(Node),
+    #[serde(rename = "PipeWire:Interface:Port")]
+    Port(Port),
+    #[serde(rename = "PipeWire:Interface:Link")]
+    Link(Link),
+    #[serde(rename = "PipeWire:Interface:Device")]
+    Device(Device),
+    #[serde(rename = "PipeWire:Interface:Client")]
+    Client(Client),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Node {
+    pub id: u32,
+    #[serde(default)]
+    pub info: NodeInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NodeInfo {
+    pub max_input_ports: u32,
+    pub max_output_ports: u32,
+    pub n_input_ports: u32,
+    pub n_output_ports: u32,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub props: Map,
+    pub params: Map,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Port {
+    pub id: u32,
+    #[serde(default)]
+    pub info: PortInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PortInfo {
+    /// `"input"` or `"output"`.
+    pub direction: Option<String>,
+    pub props: Map,
+    pub params: Map,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Link {
+    pub id: u32,
+    #[serde(default)]
+    pub info: LinkInfo,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LinkInfo {
+    pub output_node_id: u32,
+    pub output_port_id: u32,
+    pub input_node_id: u32,
+    pub input_port_id: u32,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub format: Option<Value>,
+    pub props: Map,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Device {
+    pub id: u32,
+    #[serde(default)]
+    pub info: Info,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Client {
+    pub id: u32,
+    #[serde(default)]
+    pub info: Info,
+}
+
+/// The `info` section shared by object types without extra fields.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Info {
+    pub props: Map,
+    pub params: Map,
+}
+
+/// Parses the array printed by `pw-dump`.
+pub fn from_str(input: &str) -> Result<Vec<Object>, Error> {
+    de::from_str(input)
+}
+
+impl Object {
+    pub fn id(&self) -> Option<u32> {
+        match self {
+            Object::Node(o) => Some(o.id),
+            Object::Port(o) => Some(o.id),
+            Object::Link(o) => Some(o.id),
+            Object::Device(o) => Some(o.id),
+            Object::Client(o) => Some(o.id),
+            Object::Other => None,
+        }
+    }
+
+    pub fn props(&self) -> Option<&Map> {
+        match self {
+            Object::Node(o) => Some(&o.info.props),
+            Object::Port(o) => Some(&o.info.props),
+            Object::Link(o) => Some(&o.info.props),
+            Object::Device(o) => Some(&o.info.props),
+            Object::Client(o) => Some(&o.info.props),
+            Object::Other => None,
+        }
+    }
+}
+
+/// Values of the param `id` (e.g. `"EnumFormat"`), empty if it was not enumerated.
+fn params<'a>(params: &'a Map, id: &str) -> &'a [Value] {
+    params
+        .get(id)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+impl NodeInfo {
+    pub fn params(&self, id: &str) -> &[Value] {
+        params(&self.params, id)
+    }
+}
+
+impl PortInfo {
+    pub fn params(&self, id: &str) -> &[Value] {
+        params(&self.params, id)
+    }
+}
+
+impl Info {
+    pub fn params(&self, id: &str) -> &[Value] {
+        params(&self.params, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"[
+      {
+        "id": 0,
+        "type": "PipeWire:Interface:Core",
+        "info": { "name": "pipewire-0" }
+      },
+      {
+        "id": 42,
+        "type": "PipeWire:Interface:Node",
+        "version": 3,
+        "permissions": [ "r", "w", "x", "m" ],
+        "info": {
+          "max-input-ports": 0,
+          "max-output-ports": 2,
+          "change-mask": [ "input-ports", "props" ],
+          "n-input-ports": 0,
+          "n-output-ports": 2,
+          "state": "suspended",
+          "error": null,
+          "props": { "node.name": "alsa_input.pci", "media.class": "Audio/Source" },
+          "params": { "EnumFormat": [ { "mediaType": "audio" } ], "Props": [ ] }
+        }
+      },
+      {
+        "id": 43,
+        "type": "PipeWire:Interface:Port",
+        "info": { "direction": "output", "props": { "port.name": "capture_FL" } }
+      },
+      {
+        "id": 50,
+        "type": "PipeWire:Interface:Link",
+        "info": {
+          "output-node-id": 42, "output-port-id": 43,
+          "input-node-id": 44, "input-port-id": 45,
+          "state": "active", "error": null, "format": null, "props": {}
+        }
+      },
+      { "id": 51, "type": "PipeWire:Interface:Device", "info": { "props": { "device.api": "alsa" } } },
+      { "id": 52, "type": "PipeWire:Interface:Client" }
+    ]"#;
+
+    #[test]
+    fn test_dump() {
+        let objects = from_str(DUMP).unwrap();
+        assert_eq!(objects.len(), 6);
+        assert_eq!(objects[0], Object::Other);
+
+        let Object::Node(node) = &objects[1] else {
+            panic!("expected node, got {:?}", objects[1]);
+        };
+        assert_eq!(node.id, 42);
+        assert_eq!(node.info.n_output_ports, 2);
+        assert_eq!(node.info.state.as_deref(), Some("suspended"));
+        assert_eq!(node.info.error, None);
+        assert_eq!(
+            node.info.props.get("media.class"),
+            Some(&Value::from("Audio/Source"))
+        );
+        assert_eq!(node.info.params("EnumFormat").len(), 1);
+        assert!(node.info.params("Format").is_empty());
+
+        let Object::Port(port) = &objects[2] else {
+            panic!("expected port, got {:?}", objects[2]);
+        };
+        assert_eq!(port.info.direction.as_deref(), Some("output"));
+
+        let Object::Link(link) = &objects[3] else {
+            panic!("expected link, got {:?}", objects[3]);
+        };
+        assert_eq!(
+            (link.info.output_port_id, link.info.input_node_id),
+            (43, 44)
+        );
+
+        assert_eq!(objects[4].id(), Some(51));
+        assert_eq!(
+            objects[4].props().unwrap().get("device.api"),
+            Some(&Value::from("alsa"))
+        );
+        assert_eq!(
+            objects[5],
+            Object::Client(Client {
+                id: 52,
+                info: Info::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Overrides of configuration values from environment variables, so that
+//! deployments such as containers can change settings without editing
+//! files.
+//!
+//! After a prefix such as `SPAJSON_`, the name of a variable is the path of
+//! the value to set, with `__` between keys. Keys are matched against the
+//! document ignoring case, with every character other than a letter or digit
+//! written as `_`, and a key may stand for several joined with `__`, so
+//! that `SPAJSON_context__properties__default_clock_rate` sets
+//! `default.clock.rate` in the `context.properties` object. Keys that are
+//! not found are added, with `_` read as `.`. A number selects an item of
+//! an array.
+//!
+//! The value of the variable is parsed as SPA-JSON, so that it can be a
+//! number, a boolean or an array, and taken as a string when it is not a
+//! single value.
+//!
+//! ```
+//! use spa_json::env::EnvOverride;
+//! use spa_json::value::Value;
+//!
+//! let mut config: Value = "context.properties = { default.clock.rate = 44100 }".parse().unwrap();
+//! let rate = EnvOverride::parse("SPAJSON_", "SPAJSON_context__properties__default_clock_rate", "48000");
+//! rate.unwrap().apply(&mut config).unwrap();
+//! assert_eq!(config, "context.properties = { default.clock.rate = 48000 }".parse().unwrap());
+//! ```
+
+use crate::error::Error;
+use crate::path::PathSegment;
+use crate::value::Value;
+
+/// A value to set, read from an environment variable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvOverride {
+    /// The name of the variable.
+    pub var: String,
+    pub value: Value,
+    /// The parts of the name after the prefix, split at `__`.
+    words: Vec<String>,
+}
+
+impl EnvOverride {
+    /// Reads the variable `var` set to `value`, if its name starts with
+    /// `prefix` and has a path after it.
+    pub fn parse(prefix: &str, var: &str, value: &str) -> Option<EnvOverride> {
+        let path = var.strip_prefix(prefix).filter(|path| !path.is_empty())?;
+        let value = match crate::de::from_str_value(value, Default::default()) {
+            // Text with several values parses as an object without braces.
+            Ok(Value::Object(_)) if !value.trim_start().starts_with('{') => Value::from(value),
+            Ok(parsed) => parsed,
+            Err(_) => Value::from(value),
+        };
+        Some(EnvOverride {
+            var: var.to_owned(),
+            value,
+            words: path.split("__").map(str::to_owned).collect(),
+        })
+    }
+
+    /// The path the override sets in `document`, matching the keys there.
+    pub fn path(&self, document: &Value) -> Vec<PathSegment> {
+        let mut path = Vec::new();
+        let mut current = Some(document);
+        let mut words = &self.words[..];
+        while let [word, ..] = words {
+            if let (Some(Value::Array(items)), Ok(index)) = (current, word.parse::<usize>()) {
+                path.push(PathSegment::Index(index));
+                current = items.get(index);
+                words = &words[1..];
+                continue;
+            }
+            let found = current.and_then(Value::as_object).and_then(|map| {
+                // The key standing for the most words wins.
+                (1..=words.len()).rev().find_map(|n| {
+                    let name = words[..n].join("_");
+                    map.iter()
+                        .find(|(key, _)| matches_key(key, &name))
+                        .map(|(key, value)| (n, key, value))
+                })
+            });
+            match found {
+                Some((n, key, value)) => {
+                    path.push(PathSegment::Key(key.to_string()));
+                    current = Some(value);
+                    words = &words[n..];
+                }
+                None => {
+                    path.push(PathSegment::Key(word.replace('_', ".")));
+                    current = None;
+                    words = &words[1..];
+                }
+            }
+        }
+        path
+    }
+
+    /// Sets the value in `document`, returning the path it was set at.
+    pub fn apply(&self, document: &mut Value) -> Result<Vec<PathSegment>, Error> {
+        let path = self.path(document);
+        document
+            .set_path(&path, self.value.clone())
+            .map_err(|e| Error::Message(format!("{}: {}", self.var, e)))?;
+        Ok(path)
+    }
+}
+
+/// The overrides in the environment variables whose names start with
+/// `prefix`, such as `SPAJSON_`, sorted by name. Variables that are not
+/// valid UTF-8 are skipped.
+pub fn env_overrides(prefix: &str) -> Vec<EnvOverride> {
+    let mut overrides: Vec<EnvOverride> = std::env::vars_os()
+        .filter_map(|(var, value)| EnvOverride::parse(prefix, var.to_str()?, value.to_str()?))
+        .collect();
+    overrides.sort_by(|a, b| a.var.cmp(&b.var));
+    overrides
+}
+
+/// Whether `key` is written `name` in a variable name.
+fn matches_key(key: &str, name: &str) -> bool {
+    key.len() == name.len()
+        && key.bytes().zip(name.bytes()).all(|(k, n)| match k {
+            k if k.is_ascii_alphanumeric() => k.eq_ignore_ascii_case(&n),
+            _ => n == b'_',
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(document: &str, var: &str, value: &str) -> Result<String, Error> {
+        let mut document: Value = document.parse().unwrap();
+        EnvOverride::parse("SPAJSON_", var, value)
+            .unwrap()
+            .apply(&mut document)?;
+        Ok(document.to_canonical_string())
+    }
+
+    #[test]
+    fn test_apply() {
+        let config = "context.properties = { default.clock.rate = 44100, link.max-buffers = 16 } \
+                      context.modules = [ { name = a } { name = b } ]";
+        let expected = |s: &str| s.parse::<Value>().unwrap().to_canonical_string();
+        assert_eq!(
+            apply(config, "SPAJSON_CONTEXT_PROPERTIES__LINK_MAX_BUFFERS", "64").unwrap(),
+            expected(
+                "context.properties = { default.clock.rate = 44100, link.max-buffers = 64 } \
+                 context.modules = [ { name = a } { name = b } ]"
+            )
+        );
+        assert_eq!(
+            apply(config, "SPAJSON_context__modules__1__name", "hello world").unwrap(),
+            expected(
+                "context.properties = { default.clock.rate = 44100, link.max-buffers = 16 } \
+                 context.modules = [ { name = a } { name = \"hello world\" } ]"
+            )
+        );
+        assert_eq!(
+            apply(config, "SPAJSON_context__properties__log_level", "[ 1 2 ]").unwrap(),
+            expected(
+                "context.properties = { default.clock.rate = 44100, link.max-buffers = 16, \
+                 log.level = [ 1 2 ] } \
+                 context.modules = [ { name = a } { name = b } ]"
+            )
+        );
+        assert_eq!(
+            apply(config, "SPAJSON_context__modules__5", "c")
+                .unwrap_err()
+                .to_string(),
+            "SPAJSON_context__modules__5: \"context.modules\": \
+             index 5 out of range for array of length 2"
+        );
+        assert_eq!(EnvOverride::parse("SPAJSON_", "SPAJSON_", "1"), None);
+        assert_eq!(EnvOverride::parse("SPAJSON_", "HOME", "/root"), None);
+        let object = EnvOverride::parse("SPAJSON_", "SPAJSON_a", "{ d = 1 }").unwrap();
+        assert_eq!(object.value, "d = 1".parse().unwrap());
+    }
+}
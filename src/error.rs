@@ -0,0 +1,89 @@
+use crate::path::SpaPath;
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::{de, ser};
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    Message(String),
+    #[cfg(feature = "std")]
+    Io(io::Error),
+    /// Malformed input, or input that does not match the type being deserialized.
+    Parse {
+        message: String,
+        line: usize,
+        column: usize,
+        /// Where in the document a value did not match its type, empty
+        /// for syntax errors.
+        path: SpaPath,
+    },
+    /// An error while reading or parsing the file at `path`.
+    #[cfg(feature = "std")]
+    File {
+        path: PathBuf,
+        error: Box<Error>,
+    },
+}
+
+/// The result of parsing or serializing SPA-JSON.
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Message(msg.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Message(msg) => write!(f, "{}", msg),
+            #[cfg(feature = "std")]
+            Error::Io(err) => write!(f, "IO error: {}", err),
+            Error::Parse {
+                message,
+                line,
+                column,
+                path,
+            } => {
+                if !path.is_empty() {
+                    write!(f, "{}: ", path)?;
+                }
+                write!(f, "{} at line {} column {}", message, line, column)
+            }
+            #[cfg(feature = "std")]
+            Error::File { path, error } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(err) => Some(err),
+            #[cfg(feature = "std")]
+            Error::File { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,44 @@
+//! Escaping of strings, shared by the serializer and paths, which are
+//! written even when only the deserializer is built.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Escapes `s` to be written between double quotes, the way the
+/// serializer does.
+///
+/// ```
+/// use spa_json::ser::escape_str;
+///
+/// let arg = format!("\"{}\"", escape_str("say \"hi\"\n"));
+/// assert_eq!(arg, r#""say \"hi\"\n""#);
+/// ```
+pub fn escape_str(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    escape_into(&mut escaped, s, '"', false);
+    escaped
+}
+
+/// Escapes `s` for a string enclosed in `quote`.
+pub(crate) fn escape_into(escaped: &mut String, s: &str, quote: char, ascii_only: bool) {
+    for c in s.chars() {
+        match c {
+            '"' if quote == '"' => escaped.push_str("\\\""),
+            '\'' if quote == '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{0008}' => escaped.push_str("\\b"),
+            '\u{000C}' => escaped.push_str("\\f"),
+            '\u{0000}'..='\u{001F}' => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            _ if ascii_only && !c.is_ascii() => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    write!(escaped, "\\u{:04x}", unit).unwrap();
+                }
+            }
+            _ => escaped.push(c),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Methods for reading and writing SPA-JSON on the types themselves, for
+//! call sites that read better as `config.to_spa_json()?` and
+//! `Config::from_spa_json(text)?` than with the free functions.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use spa_json::ext::{FromSpaJson, ToSpaJson};
+//!
+//! #[derive(Debug, PartialEq, Deserialize, Serialize)]
+//! struct Props {
+//!     rate: u32,
+//!     channels: Vec<String>,
+//! }
+//!
+//! let props = Props::from_spa_json("rate = 48000 channels = [ FL FR ]")?;
+//! assert_eq!(props.to_spa_json()?, "{ rate = 48000 channels = [ FL FR ] }");
+//! assert_eq!(
+//!     props.to_spa_json_pretty()?,
+//!     "{\n  rate = 48000\n  channels = [\n    FL\n    FR\n  ]\n}"
+//! );
+//! # Ok::<(), spa_json::Error>(())
+//! ```
+
+use crate::error::Result;
+#[cfg(feature = "ser")]
+use crate::ser::{to_string, to_string_with_options, SerializerOptions};
+#[cfg(feature = "ser")]
+use alloc::string::String;
+#[cfg(feature = "de")]
+use serde::Deserialize;
+#[cfg(feature = "ser")]
+use serde::Serialize;
+
+/// Writes any [`Serialize`] type as SPA-JSON.
+#[cfg(feature = "ser")]
+pub trait ToSpaJson: Serialize {
+    /// Writes `self` on a single line, as `{ rate = 48000 }`.
+    fn to_spa_json(&self) -> Result<String> {
+        to_string_with_options(self, SerializerOptions::new().inline_width(usize::MAX))
+    }
+
+    /// Writes `self` one entry and item per line, as [`to_string`] does.
+    fn to_spa_json_pretty(&self) -> Result<String> {
+        to_string(self)
+    }
+}
+
+#[cfg(feature = "ser")]
+impl<T: ?Sized + Serialize> ToSpaJson for T {}
+
+/// Reads any [`Deserialize`] type from SPA-JSON.
+#[cfg(feature = "de")]
+pub trait FromSpaJson<'de>: Deserialize<'de> {
+    /// Reads a value from `s`, as [`from_str`](crate::de::from_str) does.
+    fn from_spa_json(s: &'de str) -> Result<Self> {
+        crate::de::from_str(s)
+    }
+}
+
+#[cfg(feature = "de")]
+impl<'de, T: Deserialize<'de>> FromSpaJson<'de> for T {}
+
+#[cfg(all(test, feature = "value"))]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_extension_traits() {
+        let props = BTreeMap::<String, Vec<u32>>::from_spa_json("rates = [ 44100 48000 ]").unwrap();
+        assert_eq!(props.to_spa_json().unwrap(), "{ rates = [ 44100 48000 ] }");
+        let value = Value::from_spa_json(&props.to_spa_json_pretty().unwrap()).unwrap();
+        assert_eq!(value.to_spa_json().unwrap(), "{ rates = [ 44100 48000 ] }");
+        assert!(u32::from_spa_json("rate").is_err());
+    }
+}
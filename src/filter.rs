@@ -0,0 +1,1000 @@
+//! A small jq-like language for picking values out of documents and
+//! reshaping them.
+//!
+//! A filter takes a value and produces any number of values:
+//!
+//! - `.` produces its input, `.name` or `."name"` the value of a key and
+//!   `.[2]` an item, counting from the end if negative, with `null` for
+//!   missing ones. `.[]` produces all items or values, and `..` the input
+//!   and everything below it.
+//! - `a | b` runs `b` on each output of `a`, and `a, b` produces the
+//!   outputs of both.
+//! - `[f]` collects the outputs of `f` into an array, and
+//!   `{a: f, "b": g, c}` builds objects, with `c` short for `c: .c`.
+//! - `==`, `!=`, `<`, `<=`, `>` and `>=` compare values, `and` and `or`
+//!   combine conditions, and `a // b` produces the outputs of `a` other
+//!   than `null` and `false`, or those of `b` if there are none.
+//! - `f?` ignores errors of `f`.
+//! - The functions are `add`, `all`, `any`, `empty`, `endswith(s)`,
+//!   `first`, `from_entries`, `has(k)`, `keys`, `last`, `length`, `map(f)`,
+//!   `not`, `recurse`, `select(f)`, `sort`, `startswith(s)`, `test(re)`,
+//!   `to_entries`, `tonumber`, `tostring` and `type`.
+//!
+//! As in [queries](crate::query), names in dot notation also match keys
+//! made of several of them joined with dots, so `.context.properties`
+//! finds the `context.properties` key.
+//!
+//! ```
+//! use spa_json::filter::filter;
+//! use spa_json::value::Value;
+//!
+//! let config: Value = r#"
+//!     context.properties = { default.clock.rate = 48000, log.level = 2 }
+//!     context.modules = [
+//!         { name = libpipewire-module-rt, args = { nice.level = -11 } }
+//!         { name = libpipewire-module-protocol-native }
+//!     ]
+//! "#
+//! .parse()
+//! .unwrap();
+//! assert_eq!(
+//!     filter(&config, ".context.properties | keys").unwrap(),
+//!     ["[ \"default.clock.rate\", \"log.level\" ]".parse::<Value>().unwrap()]
+//! );
+//! assert_eq!(
+//!     filter(&config, ".context.modules | map(select(.args) | .name)").unwrap(),
+//!     [Value::from(vec![Value::from("libpipewire-module-rt")])]
+//! );
+//! ```
+
+use crate::de::unescape_str;
+use crate::error::Error;
+use crate::path::SpaPath;
+use crate::query::select_names;
+use crate::value::{Map, Value};
+use regex::Regex;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// A parsed filter, which can be run on any number of values.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    expr: Expr,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Identity,
+    Recurse,
+    Literal(Value),
+    /// Names in dot notation looked up in the outputs of a filter.
+    Names(Box<Expr>, Vec<String>),
+    /// Indexes the outputs of the first filter with those of the second,
+    /// which runs on the input.
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Optional(Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Alternative(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, Op, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Function {
+    Add,
+    All,
+    Any,
+    Empty,
+    EndsWith,
+    First,
+    FromEntries,
+    Has,
+    Keys,
+    Last,
+    Length,
+    Map,
+    Not,
+    Recurse,
+    Select,
+    Sort,
+    StartsWith,
+    Test,
+    ToEntries,
+    ToNumber,
+    ToString,
+    Type,
+}
+
+/// Names of the functions with the number of arguments they take.
+const FUNCTIONS: [(&str, usize, Function); 22] = [
+    ("add", 0, Function::Add),
+    ("all", 0, Function::All),
+    ("any", 0, Function::Any),
+    ("empty", 0, Function::Empty),
+    ("endswith", 1, Function::EndsWith),
+    ("first", 0, Function::First),
+    ("from_entries", 0, Function::FromEntries),
+    ("has", 1, Function::Has),
+    ("keys", 0, Function::Keys),
+    ("last", 0, Function::Last),
+    ("length", 0, Function::Length),
+    ("map", 1, Function::Map),
+    ("not", 0, Function::Not),
+    ("recurse", 0, Function::Recurse),
+    ("select", 1, Function::Select),
+    ("sort", 0, Function::Sort),
+    ("startswith", 1, Function::StartsWith),
+    ("test", 1, Function::Test),
+    ("to_entries", 0, Function::ToEntries),
+    ("tonumber", 0, Function::ToNumber),
+    ("tostring", 0, Function::ToString),
+    ("type", 0, Function::Type),
+];
+
+/// Runs `filter` on `value`, returning its outputs in order.
+pub fn filter(value: &Value, filter: &str) -> Result<Vec<Value>, Error> {
+    filter.parse::<Filter>()?.run(value)
+}
+
+impl Filter {
+    /// Runs the filter on `value`, returning its outputs in order.
+    pub fn run(&self, value: &Value) -> Result<Vec<Value>, Error> {
+        self.expr.eval(value)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut parser = Parser { filter: s, pos: 0 };
+        let expr = parser.pipe()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Filter { expr })
+    }
+}
+
+fn message(message: String) -> Error {
+    Error::Message(message)
+}
+
+impl Expr {
+    fn eval(&self, input: &Value) -> Result<Vec<Value>, Error> {
+        let mut out = Vec::new();
+        match self {
+            Expr::Identity => out.push(input.clone()),
+            Expr::Recurse => recurse(input, &mut out),
+            Expr::Literal(value) => out.push(value.clone()),
+            Expr::Names(target, names) => {
+                for value in target.eval(input)? {
+                    match value {
+                        Value::Object(_) => {
+                            let mut found = Vec::new();
+                            select_names(names, SpaPath::new(), &value, &mut found);
+                            if found.is_empty() {
+                                out.push(Value::Null);
+                            }
+                            out.extend(found.into_iter().map(|(_, v)| v.clone()));
+                        }
+                        Value::Null => out.push(Value::Null),
+                        value => {
+                            return Err(message(format!(
+                                "cannot index {} with {:?}",
+                                value.type_name(),
+                                names.join(".")
+                            )))
+                        }
+                    }
+                }
+            }
+            Expr::Index(target, index) => {
+                let indices = index.eval(input)?;
+                for value in target.eval(input)? {
+                    for index in &indices {
+                        out.push(index_value(&value, index)?);
+                    }
+                }
+            }
+            Expr::Iterate(target) => {
+                for value in target.eval(input)? {
+                    out.extend(iterate(&value)?);
+                }
+            }
+            Expr::Optional(expr) => out = expr.eval(input).unwrap_or_default(),
+            Expr::Array(expr) => {
+                let items = match expr {
+                    Some(expr) => expr.eval(input)?,
+                    None => Vec::new(),
+                };
+                out.push(Value::Array(items));
+            }
+            Expr::Object(entries) => {
+                let mut maps = vec![Map::new()];
+                for (key, value) in entries {
+                    let keys = key.eval(input)?;
+                    let values = value.eval(input)?;
+                    let mut next = Vec::new();
+                    for map in &maps {
+                        for key in &keys {
+                            let Value::String(key) = key else {
+                                return Err(message(format!(
+                                    "object keys must be strings, not {}",
+                                    key.type_name()
+                                )));
+                            };
+                            for value in &values {
+                                let mut map = map.clone();
+                                map.insert(key, value.clone());
+                                next.push(map);
+                            }
+                        }
+                    }
+                    maps = next;
+                }
+                out.extend(maps.into_iter().map(Value::Object));
+            }
+            Expr::Pipe(a, b) => {
+                for value in a.eval(input)? {
+                    out.extend(b.eval(&value)?);
+                }
+            }
+            Expr::Comma(a, b) => {
+                out = a.eval(input)?;
+                out.extend(b.eval(input)?);
+            }
+            Expr::Alternative(a, b) => {
+                out = a.eval(input).unwrap_or_default();
+                out.retain(truthy);
+                if out.is_empty() {
+                    out = b.eval(input)?;
+                }
+            }
+            Expr::And(a, b) | Expr::Or(a, b) => {
+                // `or` is decided by a true left side, `and` by a false one.
+                let or = matches!(self, Expr::Or(..));
+                for left in a.eval(input)? {
+                    if truthy(&left) == or {
+                        out.push(Value::Bool(or));
+                    } else {
+                        out.extend(b.eval(input)?.iter().map(|v| Value::Bool(truthy(v))));
+                    }
+                }
+            }
+            Expr::Compare(a, op, b) => {
+                let right = b.eval(input)?;
+                for left in a.eval(input)? {
+                    for right in &right {
+                        out.push(Value::Bool(op.holds(order(&left, right))));
+                    }
+                }
+            }
+            Expr::Call(function, args) => out = function.call(args, input)?,
+        }
+        Ok(out)
+    }
+}
+
+impl Op {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Op::Eq => ordering.is_eq(),
+            Op::Ne => ordering.is_ne(),
+            Op::Lt => ordering.is_lt(),
+            Op::Le => ordering.is_le(),
+            Op::Gt => ordering.is_gt(),
+            Op::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+impl Function {
+    fn call(self, args: &[Expr], input: &Value) -> Result<Vec<Value>, Error> {
+        let value = match self {
+            Function::Add => iterate(input)?.into_iter().try_fold(Value::Null, add)?,
+            Function::All => Value::Bool(iterate(input)?.iter().all(truthy)),
+            Function::Any => Value::Bool(iterate(input)?.iter().any(truthy)),
+            Function::Empty => return Ok(Vec::new()),
+            Function::First => index_value(input, &Value::from(0))?,
+            Function::FromEntries => {
+                let mut map = Map::new();
+                for entry in iterate(input)? {
+                    let key = entry.get("key").or_else(|| entry.get("name"));
+                    let Some(Value::String(key)) = key else {
+                        return Err(message(
+                            "entries must be objects with a string key".to_owned(),
+                        ));
+                    };
+                    map.insert(key, entry.get("value").cloned().unwrap_or(Value::Null));
+                }
+                Value::Object(map)
+            }
+            Function::Has => {
+                let mut out = Vec::new();
+                for key in args[0].eval(input)? {
+                    let has = match (input, &key) {
+                        (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                        (Value::Array(items), Value::Number(n)) => {
+                            (0.0..items.len() as f64).contains(&n.as_f64())
+                        }
+                        _ => {
+                            return Err(message(format!(
+                                "cannot check whether {} has a {} key",
+                                input.type_name(),
+                                key.type_name()
+                            )))
+                        }
+                    };
+                    out.push(Value::Bool(has));
+                }
+                return Ok(out);
+            }
+            Function::Keys => match input {
+                Value::Object(map) => {
+                    let mut keys: Vec<&str> = map.keys().map(|key| key.as_str()).collect();
+                    keys.sort_unstable();
+                    Value::Array(keys.into_iter().map(Value::from).collect())
+                }
+                Value::Array(items) => {
+                    Value::Array((0..items.len() as u64).map(Value::from).collect())
+                }
+                value => return Err(message(format!("{} has no keys", value.type_name()))),
+            },
+            Function::Last => index_value(input, &Value::from(-1))?,
+            Function::Length => match input {
+                Value::Null => Value::from(0),
+                Value::Number(n) => Value::from(n.as_f64().abs()),
+                Value::String(s) => Value::from(s.chars().count() as u64),
+                Value::Array(items) => Value::from(items.len() as u64),
+                Value::Object(map) => Value::from(map.len() as u64),
+                Value::Bool(_) => return Err(message("boolean has no length".to_owned())),
+            },
+            Function::Map => {
+                let mut items = Vec::new();
+                for item in iterate(input)? {
+                    items.extend(args[0].eval(&item)?);
+                }
+                Value::Array(items)
+            }
+            Function::Not => Value::Bool(!truthy(input)),
+            Function::Recurse => {
+                let mut out = Vec::new();
+                recurse(input, &mut out);
+                return Ok(out);
+            }
+            Function::Select => {
+                let selected = args[0].eval(input)?.iter().filter(|v| truthy(v)).count();
+                return Ok(vec![input.clone(); selected]);
+            }
+            Function::Sort => match input {
+                Value::Array(items) => {
+                    let mut items = items.clone();
+                    items.sort_by(order);
+                    Value::Array(items)
+                }
+                value => return Err(message(format!("cannot sort {}", value.type_name()))),
+            },
+            Function::EndsWith | Function::StartsWith | Function::Test => {
+                let Value::String(s) = input else {
+                    return Err(message(format!(
+                        "cannot match {} against strings",
+                        input.type_name()
+                    )));
+                };
+                let mut out = Vec::new();
+                for arg in args[0].eval(input)? {
+                    let Value::String(arg) = &arg else {
+                        return Err(message(format!(
+                            "expected a string argument, not {}",
+                            arg.type_name()
+                        )));
+                    };
+                    out.push(Value::Bool(match self {
+                        Function::EndsWith => s.ends_with(&**arg),
+                        Function::StartsWith => s.starts_with(&**arg),
+                        _ => Regex::new(arg)
+                            .map_err(|e| message(e.to_string()))?
+                            .is_match(s),
+                    }));
+                }
+                return Ok(out);
+            }
+            Function::ToEntries => match input {
+                Value::Object(map) => Value::Array(
+                    map.iter()
+                        .map(|(key, value)| {
+                            let mut entry = Map::new();
+                            entry.insert("key", Value::from(key.as_str()));
+                            entry.insert("value", value.clone());
+                            Value::Object(entry)
+                        })
+                        .collect(),
+                ),
+                value => return Err(message(format!("{} has no entries", value.type_name()))),
+            },
+            Function::ToNumber => match input {
+                Value::Number(_) => input.clone(),
+                Value::String(s) => match s.trim().parse() {
+                    Ok(value @ Value::Number(_)) => value,
+                    _ => return Err(message(format!("cannot parse {:?} as a number", s))),
+                },
+                value => {
+                    return Err(message(format!(
+                        "cannot parse {} as a number",
+                        value.type_name()
+                    )))
+                }
+            },
+            Function::ToString => match input {
+                Value::String(_) => input.clone(),
+                value => Value::from(value.to_canonical_string()),
+            },
+            Function::Type => Value::from(match input {
+                Value::Number(_) => "number",
+                value => value.type_name(),
+            }),
+        };
+        Ok(vec![value])
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+/// Collects `value` and everything below it, parents first.
+fn recurse(value: &Value, out: &mut Vec<Value>) {
+    out.push(value.clone());
+    match value {
+        Value::Array(items) => items.iter().for_each(|item| recurse(item, out)),
+        Value::Object(map) => map.values().for_each(|value| recurse(value, out)),
+        _ => {}
+    }
+}
+
+fn iterate(value: &Value) -> Result<Vec<Value>, Error> {
+    match value {
+        Value::Array(items) => Ok(items.clone()),
+        Value::Object(map) => Ok(map.values().cloned().collect()),
+        value => Err(message(format!(
+            "cannot iterate over {}",
+            value.type_name()
+        ))),
+    }
+}
+
+fn index_value(value: &Value, index: &Value) -> Result<Value, Error> {
+    let found = match (value, index) {
+        (Value::Object(map), Value::String(key)) => map.get(key),
+        (Value::Array(items), Value::Number(n)) => {
+            let i = n.as_f64().floor() as i64;
+            let i = if i < 0 { i + items.len() as i64 } else { i };
+            usize::try_from(i).ok().and_then(|i| items.get(i))
+        }
+        (Value::Null, Value::String(_) | Value::Number(_)) => None,
+        _ => {
+            return Err(message(format!(
+                "cannot index {} with {}",
+                value.type_name(),
+                index.type_name()
+            )))
+        }
+    };
+    Ok(found.cloned().unwrap_or(Value::Null))
+}
+
+fn add(mut a: Value, mut b: Value) -> Result<Value, Error> {
+    Ok(match (&mut a, &mut b) {
+        (Value::Null, _) => b,
+        (_, Value::Null) => a,
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) if x.checked_add(y).is_some() => Value::from(x + y),
+            _ => Value::from(x.as_f64() + y.as_f64()),
+        },
+        (Value::String(x), Value::String(y)) => Value::from(format!("{}{}", x, y)),
+        (Value::Array(x), Value::Array(y)) => {
+            x.append(y);
+            a
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.extend(std::mem::take(y));
+            a
+        }
+        _ => {
+            return Err(message(format!(
+                "cannot add {} and {}",
+                a.type_name(),
+                b.type_name()
+            )))
+        }
+    })
+}
+
+/// Orders values as jq does: `null`, `false`, `true`, numbers, strings,
+/// arrays and objects, each compared by their contents.
+fn order(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    fn sorted_keys(map: &Map) -> Vec<&str> {
+        let mut keys: Vec<&str> = map.keys().map(|key| key.as_str()).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| order(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => {
+            let keys = sorted_keys(a);
+            keys.cmp(&sorted_keys(b)).then_with(|| {
+                keys.iter()
+                    .map(|key| order(a.get(key).unwrap(), b.get(key).unwrap()))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+struct Parser<'f> {
+    filter: &'f str,
+    pos: usize,
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_name(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Message(format!(
+            "invalid filter `{}`: {} at offset {}",
+            self.filter, message, self.pos
+        ))
+    }
+
+    fn rest(&self) -> &str {
+        &self.filter[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    /// Eats `word` if it is not the start of a longer name.
+    fn eat_keyword(&mut self, word: &str) -> bool {
+        let found = self
+            .rest()
+            .strip_prefix(word)
+            .is_some_and(|rest| !rest.starts_with(is_name));
+        if found {
+            self.pos += word.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.filter.len() - self.rest().trim_start().len();
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), Error> {
+        self.skip_whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", token)))
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.comma()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("|") {
+                return Ok(expr);
+            }
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+    }
+
+    fn comma(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.alternative()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat(",") {
+                return Ok(expr);
+            }
+            expr = Expr::Comma(Box::new(expr), Box::new(self.alternative()?));
+        }
+    }
+
+    fn alternative(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.or()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("//") {
+                return Ok(expr);
+            }
+            expr = Expr::Alternative(Box::new(expr), Box::new(self.or()?));
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_keyword("or") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.compare()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_keyword("and") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.compare()?));
+        }
+    }
+
+    fn compare(&mut self) -> Result<Expr, Error> {
+        const OPS: [(&str, Op); 6] = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        let left = self.postfix()?;
+        self.skip_whitespace();
+        match OPS.iter().find(|(token, _)| self.rest().starts_with(token)) {
+            Some(&(token, op)) => {
+                self.pos += token.len();
+                Ok(Expr::Compare(Box::new(left), op, Box::new(self.postfix()?)))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, Error> {
+        self.skip_whitespace();
+        let mut expr = self.primary()?;
+        loop {
+            if self.rest().starts_with('.') && !self.rest().starts_with("..") {
+                self.pos += 1;
+                expr = self.after_dot(expr)?;
+            } else if self.eat("[") {
+                expr = self.bracket(expr)?;
+            } else if self.eat("?") {
+                expr = Expr::Optional(Box::new(expr));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Parses what follows a `.` applied to `target`.
+    fn after_dot(&mut self, target: Expr) -> Result<Expr, Error> {
+        match self.peek() {
+            Some('"') => {
+                let key = Expr::Literal(Value::from(self.string()?));
+                Ok(Expr::Index(Box::new(target), Box::new(key)))
+            }
+            Some('[') => {
+                self.pos += 1;
+                self.bracket(target)
+            }
+            Some(c) if is_name_start(c) => {
+                let mut names = vec![self.name()];
+                while self.rest().starts_with('.') && self.rest()[1..].starts_with(is_name_start) {
+                    self.pos += 1;
+                    names.push(self.name());
+                }
+                Ok(Expr::Names(Box::new(target), names))
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    /// Parses what follows a `[` applied to `target`.
+    fn bracket(&mut self, target: Expr) -> Result<Expr, Error> {
+        self.skip_whitespace();
+        if self.eat("]") {
+            return Ok(Expr::Iterate(Box::new(target)));
+        }
+        let index = self.pipe()?;
+        self.expect("]")?;
+        Ok(Expr::Index(Box::new(target), Box::new(index)))
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        if self.eat("..") {
+            return Ok(Expr::Recurse);
+        }
+        if self.eat(".") {
+            return match self.peek() {
+                Some(c) if c == '"' || c == '[' || is_name_start(c) => {
+                    self.after_dot(Expr::Identity)
+                }
+                _ => Ok(Expr::Identity),
+            };
+        }
+        if self.eat("(") {
+            let expr = self.pipe()?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+        if self.eat("[") {
+            self.skip_whitespace();
+            if self.eat("]") {
+                return Ok(Expr::Array(None));
+            }
+            let expr = self.pipe()?;
+            self.expect("]")?;
+            return Ok(Expr::Array(Some(Box::new(expr))));
+        }
+        if self.eat("{") {
+            return self.object();
+        }
+        match self.peek() {
+            Some('"') => Ok(Expr::Literal(Value::from(self.string()?))),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.number(),
+            Some(c) if is_name_start(c) => self.call(),
+            _ => Err(self.error("expected a filter")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Expr, Error> {
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.eat("}") {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let (key, name) = match self.peek() {
+                Some('"') => {
+                    let name = self.string()?;
+                    (Expr::Literal(Value::from(name.as_str())), Some(name))
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    (key, None)
+                }
+                Some(c) if is_name_start(c) => {
+                    let name = self.name();
+                    (Expr::Literal(Value::from(name.as_str())), Some(name))
+                }
+                _ => return Err(self.error("expected a key")),
+            };
+            self.skip_whitespace();
+            let value = if self.eat(":") {
+                self.alternative()?
+            } else if let Some(name) = name {
+                Expr::Names(Box::new(Expr::Identity), vec![name])
+            } else {
+                return Err(self.error("expected `:`"));
+            };
+            entries.push((key, value));
+            self.skip_whitespace();
+            if self.eat("}") {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let len = self
+            .rest()
+            .find(|c| !is_name(c))
+            .unwrap_or(self.rest().len());
+        let name = self.rest()[..len].to_owned();
+        self.pos += len;
+        name
+    }
+
+    fn call(&mut self) -> Result<Expr, Error> {
+        let start = self.pos;
+        let name = self.name();
+        match name.as_str() {
+            "true" => return Ok(Expr::Literal(Value::Bool(true))),
+            "false" => return Ok(Expr::Literal(Value::Bool(false))),
+            "null" => return Ok(Expr::Literal(Value::Null)),
+            _ => {}
+        }
+        let mut args = Vec::new();
+        if self.eat("(") {
+            loop {
+                args.push(self.pipe()?);
+                self.skip_whitespace();
+                if !self.eat(";") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        }
+        match FUNCTIONS.iter().find(|(n, _, _)| *n == name) {
+            Some(&(_, arity, function)) if arity == args.len() => Ok(Expr::Call(function, args)),
+            Some(&(_, arity, _)) => Err(self.error(&format!(
+                "{} takes {} argument{}",
+                name,
+                arity,
+                if arity == 1 { "" } else { "s" }
+            ))),
+            None => {
+                self.pos = start;
+                Err(self.error(&format!("unknown function {}", name)))
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, Error> {
+        let len = self
+            .rest()
+            .char_indices()
+            .find(|&(i, c)| {
+                !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || i == 0 && c == '-')
+            })
+            .map_or(self.rest().len(), |(i, _)| i);
+        match self.rest()[..len].parse() {
+            Ok(value @ Value::Number(_)) => {
+                self.pos += len;
+                Ok(Expr::Literal(value))
+            }
+            _ => Err(self.error("invalid number")),
+        }
+    }
+
+    /// Parses a string in double quotes, with escapes as in SPA-JSON
+    /// strings.
+    fn string(&mut self) -> Result<String, Error> {
+        let mut escaped = false;
+        let end = self.rest()[1..].char_indices().find_map(|(i, c)| {
+            if c == '"' && !escaped {
+                return Some(i + 1);
+            }
+            escaped = c == '\\' && !escaped;
+            None
+        });
+        let end = end.ok_or_else(|| self.error("unfinished string"))?;
+        let string = unescape_str(&self.rest()[1..end])
+            .map_err(|_| self.error("invalid escape"))?
+            .into_owned();
+        self.pos += end + 1;
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        context.properties = { default.clock.rate = 48000, log.level = 2 }
+        context.modules = [
+            { name = libpipewire-module-rt, args = { nice.level = -11, rt.prio = 88 } }
+            { name = libpipewire-module-protocol-native }
+            { name = libpipewire-module-loopback, args = { capture.props = { node.name = cap } } }
+        ]
+    "#;
+
+    fn run(filter: &str) -> Result<String, Error> {
+        let config: Value = CONFIG.parse().unwrap();
+        let outputs = filter.parse::<Filter>()?.run(&config)?;
+        let outputs: Vec<String> = outputs.iter().map(Value::to_canonical_string).collect();
+        Ok(outputs.join(" "))
+    }
+
+    #[test]
+    fn test_filter() {
+        let cases = [
+            (".context.properties | keys", r#"["default.clock.rate","log.level"]"#),
+            (".context.properties.default.clock.rate", "48000"),
+            (r#".context.properties."log.level""#, "2"),
+            (".context.modules[-1].name", r#""libpipewire-module-loopback""#),
+            (".context.modules[].args.rt.prio", "88 null null"),
+            (
+                ".context.modules | map(select(.args) | .name)",
+                r#"["libpipewire-module-rt","libpipewire-module-loopback"]"#,
+            ),
+            (
+                r#".context.modules[] | select(.name | test("native$")) | {name, args: (.args // "none")}"#,
+                r#"{"args":"none","name":"libpipewire-module-protocol-native"}"#,
+            ),
+            (
+                ".context.modules | map(has(\"args\")) | any, all",
+                "true false",
+            ),
+            (".context.modules | length, (first | type)", "3 \"object\""),
+            (
+                "[.. | .node.name? | select(. != null)]",
+                r#"["cap"]"#,
+            ),
+            (
+                ".context.properties | to_entries | map(.value) | add",
+                "48002",
+            ),
+            (
+                ".context.properties | to_entries | map({key, value: (.value | tostring)}) | from_entries",
+                r#"{"default.clock.rate":"48000","log.level":"2"}"#,
+            ),
+            ("[3, 1, null, \"a\", 2] | sort", r#"[null,1,2,3,"a"]"#),
+            ("1 < 2 and (2 <= 1 or true), empty, (\"7\" | tonumber)", "true 7"),
+        ];
+        for (filter, expected) in cases {
+            assert_eq!(run(filter).unwrap(), expected, "{filter}");
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        for invalid in ["", ".[", "map", "nope", "{a b}", ". |", "\"a", ".a ]"] {
+            assert!(invalid.parse::<Filter>().is_err(), "{invalid}");
+        }
+        assert_eq!(
+            run(".context.properties.log.level[]")
+                .unwrap_err()
+                .to_string(),
+            "cannot iterate over integer"
+        );
+        assert_eq!(run(".context.modules | keys | .[0] | .a?").unwrap(), "");
+    }
+}
@@ -0,0 +1,133 @@
+//! Fractions written as `num/denom`, the notation of properties such as
+//! `node.latency = 256/48000` or `node.rate = 1/48000`.
+
+use crate::error::Error;
+use alloc::format;
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// A fraction with a nonzero denominator, such as a latency of `num`
+/// samples at a rate of `denom` Hz.
+///
+/// Comparison is by numerator and denominator, so `1/2` and `2/4` are not
+/// equal; [`Fraction::reduce`] them first to compare their values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fraction {
+    pub num: u32,
+    pub denom: u32,
+}
+
+impl Fraction {
+    /// # Panics
+    ///
+    /// If `denom` is zero.
+    pub const fn new(num: u32, denom: u32) -> Self {
+        assert!(denom != 0, "fraction with a zero denominator");
+        Fraction { num, denom }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        f64::from(self.num) / f64::from(self.denom)
+    }
+
+    /// The fraction as a number of seconds, e.g. the time `256/48000` takes
+    /// to play, rounded down to whole nanoseconds.
+    pub fn to_duration(self) -> Duration {
+        let nanos = u64::from(self.num) * 1_000_000_000 / u64::from(self.denom);
+        Duration::from_nanos(nanos)
+    }
+
+    /// The equal fraction with the smallest denominator.
+    pub fn reduce(self) -> Self {
+        let (mut a, mut b) = (self.num, self.denom);
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        Fraction::new(self.num / a, self.denom / a)
+    }
+
+    /// The nearest fraction with denominator `denom`, e.g. the same latency
+    /// at another rate.
+    ///
+    /// # Panics
+    ///
+    /// If `denom` is zero, or the numerator does not fit in a `u32`.
+    pub fn with_denom(self, denom: u32) -> Self {
+        let scaled = u64::from(self.num) * u64::from(denom);
+        let num = (scaled + u64::from(self.denom) / 2) / u64::from(self.denom);
+        Fraction::new(u32::try_from(num).expect("numerator out of range"), denom)
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.num, self.denom)
+    }
+}
+
+impl FromStr for Fraction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::Message(format!("invalid fraction `{}`", s));
+        let (num, denom) = s.split_once('/').ok_or_else(invalid)?;
+        let num = num.parse().map_err(|_| invalid())?;
+        let denom = denom.parse().map_err(|_| invalid())?;
+        if denom == 0 {
+            return Err(invalid());
+        }
+        Ok(Fraction { num, denom })
+    }
+}
+
+impl Serialize for Fraction {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Fraction {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(FractionVisitor)
+    }
+}
+
+struct FractionVisitor;
+
+impl Visitor<'_> for FractionVisitor {
+    type Value = Fraction;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a fraction such as 256/48000")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Fraction, E> {
+        v.parse()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+    use crate::ser::to_string;
+
+    #[test]
+    fn test_fraction() {
+        let latency: Fraction = from_str("256/48000").unwrap();
+        assert_eq!(latency, Fraction::new(256, 48000));
+        assert_eq!(to_string(&latency).unwrap(), "256/48000");
+        assert_eq!(latency.to_duration(), Duration::from_nanos(5_333_333));
+        assert_eq!(latency.reduce(), Fraction::new(2, 375));
+        assert_eq!(latency.with_denom(44100), Fraction::new(235, 44100));
+        assert_eq!(Fraction::new(1, 4).to_f64(), 0.25);
+
+        for invalid in ["256", "256/0", "-1/48000", "1/2/3", "\"a/b\""] {
+            assert!(from_str::<Fraction>(invalid).is_err(), "{invalid}");
+        }
+    }
+}
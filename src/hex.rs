@@ -0,0 +1,133 @@
+use core::fmt;
+use core::marker::PhantomData;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+/// Integer that is serialized in hexadecimal notation, e.g. `0x1f`.
+///
+/// The parser reads `0x` literals as plain integers, so the value can be
+/// deserialized into the wrapped type directly. Deserializing a `Hex`
+/// accepts integers written either way, and strings holding them as well,
+/// for formats without hexadecimal literals.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hex<T>(pub T);
+
+/// Negative values are written with a sign, e.g. `-0x5`, rather than in
+/// two's complement, so that they read back as the same number.
+impl<T: fmt::LowerHex + Copy + TryInto<i128>> fmt::Display for Hex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0.try_into() {
+            Ok(v) if v < 0 => write!(f, "-{:#x}", v.unsigned_abs()),
+            _ => write!(f, "{:#x}", self.0),
+        }
+    }
+}
+
+/// Name of the newtype struct a `Hex` is serialized as, which tells the
+/// SPA-JSON serializer to write the literal without the quotes other
+/// strings that read back as numbers get. Other formats write a string.
+pub(crate) const TOKEN: &str = "$spa_json::private::Hex";
+
+impl<T: fmt::LowerHex + Copy + TryInto<i128>> Serialize for Hex<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(TOKEN, &format_args!("{}", self))
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Hex<T>
+where
+    T: TryFrom<u64> + TryFrom<i64>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(HexVisitor(PhantomData))
+    }
+}
+
+struct HexVisitor<T>(PhantomData<T>);
+
+impl<T> Visitor<'_> for HexVisitor<T>
+where
+    T: TryFrom<u64> + TryFrom<i64>,
+{
+    type Value = Hex<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a hexadecimal or decimal integer")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        T::try_from(v)
+            .map(Hex)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(v), &self))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        T::try_from(v)
+            .map(Hex)
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let (negative, digits) = match v.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, v),
+        };
+        let magnitude = match digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => digits.parse(),
+        }
+        .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))?;
+        if !negative {
+            self.visit_u64(magnitude)
+        } else if magnitude <= i64::MIN.unsigned_abs() {
+            self.visit_i64(0i64.wrapping_sub_unsigned(magnitude))
+        } else {
+            Err(E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ser", feature = "de"))]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+    use crate::ser::to_string;
+    use serde::{Deserialize, Serialize};
+
+    #[test]
+    fn test_hex() {
+        #[derive(Serialize, Deserialize)]
+        struct Test {
+            mask: u32,
+        }
+
+        #[derive(Serialize, Deserialize)]
+        struct HexTest {
+            mask: Hex<u32>,
+        }
+
+        let output = to_string(&HexTest { mask: Hex(0x3f) }).unwrap();
+        assert_eq!(output, "{\n  mask = 0x3f\n}");
+        assert_eq!(from_str::<Test>(&output).unwrap().mask, 0x3f);
+        assert_eq!(from_str::<HexTest>(&output).unwrap().mask, Hex(0x3f));
+        assert_eq!(from_str::<Hex<u32>>("63").unwrap(), Hex(63));
+        assert_eq!(from_str::<Hex<u8>>("\"0xff\"").unwrap(), Hex(0xff));
+        assert_eq!(from_str::<Hex<i16>>("\"-0x10\"").unwrap(), Hex(-16));
+        assert_eq!(to_string(&Hex(-5i32)).unwrap(), "-0x5");
+        assert_eq!(from_str::<Hex<i32>>("-0x5").unwrap(), Hex(-5));
+        for v in [i16::MIN, -1, 0, i16::MAX] {
+            let output = to_string(&Hex(v)).unwrap();
+            assert_eq!(from_str::<Hex<i16>>(&output).unwrap(), Hex(v));
+            assert_eq!(from_str::<i16>(&output).unwrap(), v);
+        }
+        assert_eq!(
+            to_string(&Hex(u128::MAX)).unwrap(),
+            format!("{:#x}", u128::MAX)
+        );
+        assert!(from_str::<Hex<u8>>("0x100").is_err());
+        assert!(from_str::<Hex<u32>>("\"0xzz\"").is_err());
+    }
+}
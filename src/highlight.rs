@@ -0,0 +1,309 @@
+//! Classification of the tokens of SPA-JSON text for syntax highlighting
+//! in editors and terminal user interfaces.
+//!
+//! Unlike the bare [`lexer`](crate::lexer), this tells keys from values,
+//! following the nesting of objects and arrays. It works on text that does
+//! not parse as well, so that files can be highlighted while they are
+//! edited.
+//!
+//! [`to_ansi`] and [`to_html`] render highlighted text for terminals and
+//! web pages.
+//!
+//! ```
+//! use spa_json::highlight::{classify, TokenClass};
+//!
+//! let input = "node.rate = 48000";
+//! let classes: Vec<(&str, TokenClass)> = classify(input)
+//!     .into_iter()
+//!     .map(|(span, class)| (&input[span], class))
+//!     .collect();
+//! assert_eq!(
+//!     classes,
+//!     [
+//!         ("node.rate", TokenClass::Key),
+//!         ("=", TokenClass::Punctuation),
+//!         ("48000", TokenClass::Number),
+//!     ]
+//! );
+//! ```
+
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::value::Value;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    /// The key of an object member, quoted or not.
+    Key,
+    /// A quoted or bare string value.
+    String,
+    Number,
+    /// `true` or `false`.
+    Boolean,
+    /// `null`.
+    Null,
+    /// Brackets, separators and commas.
+    Punctuation,
+    Comment,
+    /// An unterminated string or a bare scalar with characters that need
+    /// quoting.
+    Invalid,
+}
+
+/// A container being read: whether it is an object, and if so whether a
+/// key comes next.
+struct Frame {
+    object: bool,
+    expect_key: bool,
+}
+
+/// Returns the byte range and class of every token of `input` except
+/// whitespace, in order.
+pub fn classify(input: &str) -> Vec<(Range<usize>, TokenClass)> {
+    let tokens: Vec<Token> = Lexer::new(input)
+        .filter(|token| token.kind != TokenKind::Whitespace)
+        .collect();
+    // Like the parser, take anything but a container or a single scalar as
+    // an object without braces.
+    let mut meaningful = tokens.iter().filter(|token| !token.kind.is_trivia());
+    let braceless = match meaningful.next() {
+        None => false,
+        Some(token) => {
+            !matches!(token.kind, TokenKind::ObjectStart | TokenKind::ArrayStart)
+                && meaningful.next().is_some()
+        }
+    };
+    let mut stack = Vec::new();
+    if braceless {
+        stack.push(Frame {
+            object: true,
+            expect_key: true,
+        });
+    }
+    let root = stack.len();
+
+    let mut classes = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let class = match token.kind {
+            TokenKind::Comment => TokenClass::Comment,
+            TokenKind::Separator | TokenKind::Comma => TokenClass::Punctuation,
+            TokenKind::ObjectStart | TokenKind::ArrayStart => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.expect_key = parent.object;
+                }
+                stack.push(Frame {
+                    object: token.kind == TokenKind::ObjectStart,
+                    expect_key: true,
+                });
+                TokenClass::Punctuation
+            }
+            TokenKind::ObjectEnd | TokenKind::ArrayEnd => {
+                if stack.len() > root {
+                    stack.pop();
+                }
+                TokenClass::Punctuation
+            }
+            TokenKind::Quoted | TokenKind::Bare | TokenKind::Invalid => match stack.last_mut() {
+                Some(frame) if frame.object && frame.expect_key => {
+                    frame.expect_key = false;
+                    TokenClass::Key
+                }
+                frame => {
+                    if let Some(frame) = frame {
+                        frame.expect_key = frame.object;
+                    }
+                    scalar_class(&token)
+                }
+            },
+            TokenKind::Whitespace => continue,
+        };
+        classes.push((token.span, class));
+    }
+    classes
+}
+
+fn scalar_class(token: &Token) -> TokenClass {
+    match token.kind {
+        TokenKind::Quoted => TokenClass::String,
+        TokenKind::Invalid => TokenClass::Invalid,
+        _ => match token.text.parse::<Value>() {
+            Ok(Value::Number(_)) => TokenClass::Number,
+            Ok(Value::Bool(_)) => TokenClass::Boolean,
+            Ok(Value::Null) => TokenClass::Null,
+            _ => TokenClass::String,
+        },
+    }
+}
+
+const ANSI_KEY: &str = "\x1b[34m";
+const ANSI_STRING: &str = "\x1b[32m";
+const ANSI_NUMBER: &str = "\x1b[36m";
+const ANSI_LITERAL: &str = "\x1b[35m";
+const ANSI_COMMENT: &str = "\x1b[90m";
+const ANSI_INVALID: &str = "\x1b[4;31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Adds ANSI colors to SPA-JSON text: keys, strings, numbers, `true`,
+/// `false` and `null`, and comments each get their own color, and invalid
+/// tokens are underlined in red.
+pub fn to_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 2);
+    let mut pos = 0;
+    for (span, class) in classify(text) {
+        let color = match class {
+            TokenClass::Key => ANSI_KEY,
+            TokenClass::String => ANSI_STRING,
+            TokenClass::Number => ANSI_NUMBER,
+            TokenClass::Boolean | TokenClass::Null => ANSI_LITERAL,
+            TokenClass::Comment => ANSI_COMMENT,
+            TokenClass::Invalid => ANSI_INVALID,
+            TokenClass::Punctuation => continue,
+        };
+        out.push_str(&text[pos..span.start]);
+        out.push_str(color);
+        out.push_str(&text[span.clone()]);
+        out.push_str(ANSI_RESET);
+        pos = span.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// Style sheet for the output of [`to_html`], with the colors of
+/// [`to_ansi`].
+pub const HTML_STYLE: &str = "\
+pre.spa-json { background: #fafafa; color: #24292e; padding: 1em; }
+pre.spa-json .key { color: #005cc5; }
+pre.spa-json .string { color: #22863a; }
+pre.spa-json .number { color: #1b7c83; }
+pre.spa-json .boolean, pre.spa-json .null { color: #6f42c1; }
+pre.spa-json .comment { color: #6a737d; font-style: italic; }
+pre.spa-json .invalid { color: #cb2431; text-decoration: underline wavy; }
+";
+
+/// Renders SPA-JSON text as an HTML `<pre class="spa-json">` element, with
+/// every token but punctuation in a `<span>` whose class names its
+/// [`TokenClass`], such as `key` or `comment`. [`HTML_STYLE`] colors them.
+///
+/// ```
+/// use spa_json::highlight::to_html;
+///
+/// assert_eq!(
+///     to_html("rate = 48000"),
+///     "<pre class=\"spa-json\"><code><span class=\"key\">rate</span> = \
+///      <span class=\"number\">48000</span></code></pre>\n"
+/// );
+/// ```
+pub fn to_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() * 3);
+    out.push_str("<pre class=\"spa-json\"><code>");
+    let mut pos = 0;
+    for (span, class) in classify(text) {
+        let name = match class {
+            TokenClass::Key => "key",
+            TokenClass::String => "string",
+            TokenClass::Number => "number",
+            TokenClass::Boolean => "boolean",
+            TokenClass::Null => "null",
+            TokenClass::Comment => "comment",
+            TokenClass::Invalid => "invalid",
+            TokenClass::Punctuation => continue,
+        };
+        escape_html(&text[pos..span.start], &mut out);
+        out.push_str("<span class=\"");
+        out.push_str(name);
+        out.push_str("\">");
+        escape_html(&text[span.clone()], &mut out);
+        out.push_str("</span>");
+        pos = span.end;
+    }
+    escape_html(&text[pos..], &mut out);
+    out.push_str("</code></pre>\n");
+    out
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenClass::*;
+
+    fn classes(input: &str) -> Vec<(&str, TokenClass)> {
+        classify(input)
+            .into_iter()
+            .map(|(span, class)| (&input[span], class))
+            .collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classes("# rt\nmodules = [ { name \"rt\" args = { on: true, x = null } } 1.5 ]"),
+            [
+                ("# rt", Comment),
+                ("modules", Key),
+                ("=", Punctuation),
+                ("[", Punctuation),
+                ("{", Punctuation),
+                ("name", Key),
+                ("\"rt\"", String),
+                ("args", Key),
+                ("=", Punctuation),
+                ("{", Punctuation),
+                ("on", Key),
+                (":", Punctuation),
+                ("true", Boolean),
+                (",", Punctuation),
+                ("x", Key),
+                ("=", Punctuation),
+                ("null", Null),
+                ("}", Punctuation),
+                ("}", Punctuation),
+                ("1.5", Number),
+                ("]", Punctuation),
+            ]
+        );
+        assert_eq!(classes("[ a b ]")[1..3], [("a", String), ("b", String)]);
+        assert_eq!(classes("true"), [("true", Boolean)]);
+        // Broken text is still classified, and a stray bracket does not
+        // end an object without braces.
+        assert_eq!(
+            classes("a = \"open\n} b = c\\d"),
+            [
+                ("a", Key),
+                ("=", Punctuation),
+                ("\"open", Invalid),
+                ("}", Punctuation),
+                ("b", Key),
+                ("=", Punctuation),
+                ("c\\d", Invalid),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "# <a & b>\nname = \"x\" x = [ 1 ]";
+        assert_eq!(
+            to_html(input),
+            "<pre class=\"spa-json\"><code><span class=\"comment\"># &lt;a &amp; b&gt;</span>\n\
+             <span class=\"key\">name</span> = <span class=\"string\">&quot;x&quot;</span> \
+             <span class=\"key\">x</span> = [ <span class=\"number\">1</span> ]</code></pre>\n"
+        );
+        assert_eq!(
+            to_ansi("a = null b = \"open"),
+            "\x1b[34ma\x1b[0m = \x1b[35mnull\x1b[0m \x1b[34mb\x1b[0m = \x1b[4;31m\"open\x1b[0m"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Values read from their source only as far as they are looked at, for
+//! tools that need a few keys out of large documents such as `pw-dump`
+//! output.
+//!
+//! A [`LazyValue`] is a part of a document. Looking up a key or an item
+//! splits it one level deep, checking the syntax of everything in it and
+//! remembering where each entry is without building any of them. Only
+//! [`parse`](LazyValue::parse) and [`deserialize`](LazyValue::deserialize)
+//! build values, of the part they are called on.
+//!
+//! ```
+//! use spa_json::lazy::LazyValue;
+//!
+//! let input = r#"[
+//!     { "id": 30, "info": { "props": { "node.name": "alsa_output", "node.nick": "Speakers" } } }
+//!     { "id": 31, "info": { "props": { "node.name": "alsa_input" } } }
+//! ]"#;
+//! let dump = LazyValue::new(input);
+//! let props = dump.index(1)?.unwrap().get("info")?.unwrap().get("props")?.unwrap();
+//! let name: &str = props.get("node.name")?.unwrap().deserialize()?;
+//! assert_eq!(name, "alsa_input");
+//! # Ok::<(), spa_json::de::Error>(())
+//! ```
+
+use crate::de::{from_str, Deserializer, Split};
+use crate::error::Error;
+use crate::path::{PathSegment, SpaPath};
+use crate::value::Value;
+use serde::Deserialize;
+use std::cell::OnceCell;
+use std::ops::Range;
+
+/// A value in a document that is only parsed when needed.
+#[derive(Clone, Debug)]
+pub struct LazyValue<'a> {
+    input: &'a str,
+    /// Where the value is in `input`, or `None` for the whole document.
+    span: Option<Range<usize>>,
+    path: SpaPath,
+    /// The entries or items, once they were looked at.
+    split: OnceCell<Split<'a>>,
+}
+
+impl<'a> LazyValue<'a> {
+    /// The document in `input`, which is not read yet.
+    pub fn new(input: &'a str) -> Self {
+        LazyValue {
+            input,
+            span: None,
+            path: SpaPath::new(),
+            split: OnceCell::new(),
+        }
+    }
+
+    /// Where the value is in its document.
+    pub fn path(&self) -> &SpaPath {
+        &self.path
+    }
+
+    /// The source of the value, or all of the input for the document.
+    pub fn source(&self) -> &'a str {
+        match &self.span {
+            Some(span) => &self.input[span.clone()],
+            None => self.input,
+        }
+    }
+
+    /// The value of `key`, if this is an object that has it. Of duplicate
+    /// keys, the last one counts, as when parsing.
+    pub fn get(&self, key: &str) -> Result<Option<LazyValue<'a>>, Error> {
+        let Split::Object(entries) = self.split()? else {
+            return Ok(None);
+        };
+        Ok(entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(k, span)| self.child(PathSegment::Key(k.to_string()), span)))
+    }
+
+    /// The item at `index`, if this is an array that long.
+    pub fn index(&self, index: usize) -> Result<Option<LazyValue<'a>>, Error> {
+        let Split::Array(items) = self.split()? else {
+            return Ok(None);
+        };
+        Ok(items
+            .get(index)
+            .map(|span| self.child(PathSegment::Index(index), span)))
+    }
+
+    /// The value at `path` below this one, splitting only the values on
+    /// the way.
+    pub fn get_path(&self, path: &[PathSegment]) -> Result<Option<LazyValue<'a>>, Error> {
+        let mut value = self.clone();
+        for segment in path {
+            let next = match segment {
+                PathSegment::Key(key) => value.get(key)?,
+                PathSegment::Index(index) => value.index(*index)?,
+            };
+            match next {
+                Some(next) => value = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(value))
+    }
+
+    /// The entries of an object in the order they are written, including
+    /// duplicate keys, or nothing for other values.
+    pub fn entries(&self) -> Result<Vec<(&str, LazyValue<'a>)>, Error> {
+        Ok(match self.split()? {
+            Split::Object(entries) => entries
+                .iter()
+                .map(|(key, span)| (&**key, self.child(PathSegment::Key(key.to_string()), span)))
+                .collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    /// The items of an array, or nothing for other values.
+    pub fn items(&self) -> Result<Vec<LazyValue<'a>>, Error> {
+        Ok(match self.split()? {
+            Split::Array(items) => items
+                .iter()
+                .enumerate()
+                .map(|(i, span)| self.child(PathSegment::Index(i), span))
+                .collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    /// Parses the whole value.
+    pub fn parse(&self) -> Result<Value, Error> {
+        self.deserialize()
+    }
+
+    /// Deserializes the value. Errors have the position in the document
+    /// and the path from its root.
+    pub fn deserialize<T: Deserialize<'a>>(&self) -> Result<T, Error> {
+        match &self.span {
+            Some(span) => Deserializer::at(self.input, span.start).value(),
+            None => from_str(self.input),
+        }
+        .map_err(|e| self.locate(e))
+    }
+
+    fn split(&self) -> Result<&Split<'a>, Error> {
+        if let Some(split) = self.split.get() {
+            return Ok(split);
+        }
+        let split = match &self.span {
+            Some(span) => Deserializer::at(self.input, span.start).split(),
+            None => {
+                let mut deserializer = Deserializer::from_str(self.input);
+                deserializer
+                    .split()
+                    .and_then(|split| deserializer.end().map(|()| split))
+            }
+        };
+        let split = split.map_err(|e| self.locate(e))?;
+        Ok(self.split.get_or_init(|| split))
+    }
+
+    fn child(&self, segment: PathSegment, span: &Range<usize>) -> LazyValue<'a> {
+        let mut path = self.path.clone();
+        path.push(segment);
+        LazyValue {
+            input: self.input,
+            span: Some(span.clone()),
+            path,
+            split: OnceCell::new(),
+        }
+    }
+
+    /// Puts the path of the value in front of the path of a parse error.
+    fn locate(&self, error: Error) -> Error {
+        match error {
+            Error::Parse {
+                message,
+                line,
+                column,
+                path,
+            } => Error::Parse {
+                message,
+                line,
+                column,
+                path: self
+                    .path
+                    .iter()
+                    .cloned()
+                    .chain(path.into_segments())
+                    .collect(),
+            },
+            error => error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lazy() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Module<'a> {
+            name: &'a str,
+            #[serde(default)]
+            flags: Vec<String>,
+        }
+
+        let input = "\
+            context.properties = { default.clock.rate = 48000 }\n\
+            context.modules = [\n\
+            \x20 { name = libpipewire-module-rt, flags = [ ifexists nofail ] }\n\
+            \x20 { name = libpipewire-module-protocol-native }\n\
+            \x20 { name = [ oops ] }\n\
+            ]\n\
+            context.properties = { log.level = 2 }\n";
+        let document = LazyValue::new(input);
+        let modules = document.get("context.modules").unwrap().unwrap();
+        assert_eq!(modules.items().unwrap().len(), 3);
+        let rt: Module = modules.index(0).unwrap().unwrap().deserialize().unwrap();
+        assert_eq!(rt.name, "libpipewire-module-rt");
+        assert_eq!(rt.flags, ["ifexists", "nofail"]);
+        assert!(modules.index(3).unwrap().is_none());
+
+        let properties = document.get("context.properties").unwrap().unwrap();
+        assert_eq!(properties.source(), "{ log.level = 2 }");
+        assert_eq!(
+            properties.parse().unwrap(),
+            "{ log.level = 2 }".parse::<Value>().unwrap()
+        );
+        let keys: Vec<&str> = document.entries().unwrap().iter().map(|e| e.0).collect();
+        assert_eq!(
+            keys,
+            [
+                "context.properties",
+                "context.modules",
+                "context.properties"
+            ]
+        );
+
+        let path: SpaPath = "\"context.modules\"[2].name".parse().unwrap();
+        let name = document.get_path(&path).unwrap().unwrap();
+        assert_eq!(name.path(), &path);
+        assert_eq!(
+            modules.index(2).unwrap().unwrap().deserialize::<Module>().unwrap_err().to_string(),
+            "\"context.modules\"[2].name: invalid type: sequence, expected a borrowed string at line 5 column 13"
+        );
+        assert!(LazyValue::new("a = [ 1").get("a").is_err());
+        assert!(LazyValue::new("{} x").items().is_err());
+    }
+}
@@ -0,0 +1,215 @@
+//! The tokens of SPA-JSON text, for tools such as formatters and
+//! highlighters that work on the text rather than on parsed values.
+//!
+//! The lexer is lossless: every byte of the input belongs to exactly one
+//! token, whitespace and comments included, so concatenating the text of
+//! all tokens gives back the input. It never fails; text the parser would
+//! reject becomes an [`Invalid`](TokenKind::Invalid) token.
+//!
+//! ```
+//! use spa_json::lexer::{Lexer, TokenKind};
+//!
+//! let kinds: Vec<TokenKind> = Lexer::new("rate = 48000 # Hz")
+//!     .filter(|token| token.kind != TokenKind::Whitespace)
+//!     .map(|token| token.kind)
+//!     .collect();
+//! assert_eq!(
+//!     kinds,
+//!     [TokenKind::Bare, TokenKind::Separator, TokenKind::Bare, TokenKind::Comment]
+//! );
+//! ```
+
+use crate::de::is_delimiter;
+use crate::scan;
+use core::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// `{`
+    ObjectStart,
+    /// `}`
+    ObjectEnd,
+    /// `[`
+    ArrayStart,
+    /// `]`
+    ArrayEnd,
+    /// `=` or `:` between a key and its value.
+    Separator,
+    /// `,`, which separates nothing in SPA-JSON and is skipped like
+    /// whitespace.
+    Comma,
+    /// A run of spaces, tabs, line breaks and NUL characters.
+    Whitespace,
+    /// A `#` comment, up to but not including the end of the line.
+    Comment,
+    /// A string in double or single quotes, quotes included. Escapes are
+    /// only checked when the string is parsed.
+    Quoted,
+    /// An unquoted scalar: a key, a number, `true`, `false`, `null` or a
+    /// string.
+    Bare,
+    /// A string without its closing quote, which ends at the end of the
+    /// line, or a bare scalar with characters that need quoting.
+    Invalid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// Byte range of the token in the input.
+    pub span: Range<usize>,
+    pub text: &'a str,
+}
+
+impl TokenKind {
+    /// Whether tokens of this kind carry no meaning: whitespace, commas
+    /// and comments.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace | TokenKind::Comma | TokenKind::Comment
+        )
+    }
+}
+
+/// Iterator over the tokens of a string.
+#[derive(Clone, Debug)]
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer { input, pos: 0 }
+    }
+
+    /// Length and kind of the token at the current position.
+    fn measure(&self) -> (usize, TokenKind) {
+        let rest = &self.input.as_bytes()[self.pos..];
+        let run = |pred: fn(u8) -> bool| rest.iter().position(|&c| !pred(c)).unwrap_or(rest.len());
+        match rest[0] {
+            b'{' => (1, TokenKind::ObjectStart),
+            b'}' => (1, TokenKind::ObjectEnd),
+            b'[' => (1, TokenKind::ArrayStart),
+            b']' => (1, TokenKind::ArrayEnd),
+            b'=' | b':' => (1, TokenKind::Separator),
+            b',' => (1, TokenKind::Comma),
+            b' ' | b'\t' | b'\r' | b'\n' | b'\0' => (
+                run(|c| matches!(c, b' ' | b'\t' | b'\r' | b'\n' | b'\0')),
+                TokenKind::Whitespace,
+            ),
+            b'#' => (scan::line_len(rest), TokenKind::Comment),
+            quote @ (b'"' | b'\'') => {
+                let mut i = 1;
+                loop {
+                    i += scan::string_len(&rest[i..], Some(quote));
+                    match rest.get(i) {
+                        Some(&c) if c == quote => return (i + 1, TokenKind::Quoted),
+                        Some(b'\\') if rest.get(i + 1).is_some_and(|&c| c >= 0x20) => i += 2,
+                        Some(b'\\') => i += 1,
+                        // Up to the line break or other control character
+                        // the parser stops at.
+                        _ => return (i, TokenKind::Invalid),
+                    }
+                }
+            }
+            _ => {
+                let len = scan::bare_len(rest);
+                match rest.get(len) {
+                    Some(&c) if !is_delimiter(c) => {
+                        let invalid = rest[len..].iter().position(|&c| is_delimiter(c));
+                        (invalid.map_or(rest.len(), |n| len + n), TokenKind::Invalid)
+                    }
+                    _ => (len, TokenKind::Bare),
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.pos >= self.input.len() {
+            return None;
+        }
+        let (len, kind) = self.measure();
+        let span = self.pos..self.pos + len;
+        self.pos = span.end;
+        Some(Token {
+            kind,
+            text: &self.input[span.clone()],
+            span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use TokenKind::*;
+
+    fn tokens(input: &str) -> Vec<(TokenKind, &str)> {
+        Lexer::new(input).map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn test_lexer() {
+        assert_eq!(
+            tokens("a.b = { c: [ 1, \"x\\\"y\" ] } # done\n'q'"),
+            [
+                (Bare, "a.b"),
+                (Whitespace, " "),
+                (Separator, "="),
+                (Whitespace, " "),
+                (ObjectStart, "{"),
+                (Whitespace, " "),
+                (Bare, "c"),
+                (Separator, ":"),
+                (Whitespace, " "),
+                (ArrayStart, "["),
+                (Whitespace, " "),
+                (Bare, "1"),
+                (Comma, ","),
+                (Whitespace, " "),
+                (Quoted, "\"x\\\"y\""),
+                (Whitespace, " "),
+                (ArrayEnd, "]"),
+                (Whitespace, " "),
+                (ObjectEnd, "}"),
+                (Whitespace, " "),
+                (Comment, "# done"),
+                (Whitespace, "\n"),
+                (Quoted, "'q'"),
+            ]
+        );
+        // Brackets inside a bare scalar do not end it, like in the parser.
+        assert_eq!(tokens("a{b"), [(Bare, "a{b")]);
+        assert_eq!(
+            tokens("a = \"open\nb = c\\d é"),
+            [
+                (Bare, "a"),
+                (Whitespace, " "),
+                (Separator, "="),
+                (Whitespace, " "),
+                (Invalid, "\"open"),
+                (Whitespace, "\n"),
+                (Bare, "b"),
+                (Whitespace, " "),
+                (Separator, "="),
+                (Whitespace, " "),
+                (Invalid, "c\\d"),
+                (Whitespace, " "),
+                (Invalid, "é"),
+            ]
+        );
+        assert_eq!(tokens("\"\\"), [(Invalid, "\"\\")]);
+
+        let input = "context.modules = [ { name = x, args = { a = \"b\" } } ] # c";
+        let tokens: Vec<Token> = Lexer::new(input).collect();
+        assert_eq!(tokens.iter().map(|t| t.text).collect::<String>(), input);
+        assert!(tokens.iter().all(|t| &input[t.span.clone()] == t.text));
+    }
+}
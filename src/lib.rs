@@ -1 +1,125 @@
-pub mod spa_json_serializer;
\ No newline at end of file
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+// Lets `#[derive(SpaJsonComments)]` refer to `::spa_json` inside the crate.
+extern crate self as spa_json;
+
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "value")]
+pub mod borrowed;
+#[cfg(feature = "ser")]
+pub mod comments;
+#[cfg(feature = "value")]
+pub mod compare;
+#[cfg(feature = "value")]
+pub mod config;
+#[cfg(feature = "de")]
+pub mod de;
+#[cfg(feature = "value")]
+pub mod diff;
+#[cfg(feature = "value")]
+pub mod document;
+#[cfg(feature = "value")]
+pub mod dump;
+#[cfg(feature = "value")]
+pub mod env;
+mod error;
+mod escape;
+#[cfg(any(feature = "ser", feature = "de"))]
+pub mod ext;
+#[cfg(feature = "value")]
+pub mod filter;
+pub mod fraction;
+pub mod hex;
+#[cfg(feature = "value")]
+pub mod highlight;
+#[cfg(feature = "value")]
+pub mod lazy;
+#[cfg(feature = "de")]
+pub mod lexer;
+#[cfg(feature = "schemas")]
+pub mod lint;
+#[cfg(feature = "value")]
+pub mod loader;
+#[cfg(feature = "value")]
+pub mod lua;
+#[cfg(feature = "value")]
+pub mod matches;
+#[cfg(feature = "value")]
+pub mod merge;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(any(feature = "ser", feature = "de"))]
+mod number;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+pub mod path;
+#[cfg(feature = "value")]
+pub mod pod;
+pub mod prelude;
+#[cfg(feature = "value")]
+pub mod properties;
+#[cfg(feature = "value")]
+pub mod query;
+#[cfg(feature = "value")]
+pub mod reformat;
+#[cfg(feature = "value")]
+pub mod rules;
+#[cfg(feature = "de")]
+mod scan;
+#[cfg(feature = "value")]
+pub mod schema;
+#[cfg(feature = "schemas")]
+pub mod schemas;
+#[cfg(feature = "ser")]
+pub mod ser;
+#[cfg(feature = "value")]
+pub mod source_map;
+#[cfg(feature = "value")]
+pub mod stats;
+#[cfg(feature = "value")]
+pub mod testing;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "de")]
+mod trace;
+#[cfg(feature = "value")]
+pub mod value;
+pub mod with;
+#[cfg(feature = "value")]
+pub mod writer;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+#[cfg(feature = "de")]
+pub use de::from_str;
+#[cfg(feature = "value")]
+pub use diff::diff;
+pub use error::{Error, Result};
+#[cfg(feature = "value")]
+pub use loader::load_config_with_dropins;
+#[cfg(feature = "value")]
+pub use merge::{merge, minimal_fragment};
+#[cfg(feature = "value")]
+pub use rules::apply_rules;
+#[cfg(all(feature = "ser", feature = "std"))]
+pub use ser::to_writer;
+#[cfg(feature = "ser")]
+pub use ser::{to_string, to_string_with_options};
+#[cfg(feature = "value")]
+pub use value::Value;
+
+/// The serializer under its old name.
+#[cfg(feature = "ser")]
+#[deprecated(note = "renamed to `spa_json::ser`")]
+pub mod spa_json_serializer {
+    pub use crate::ser::*;
+}
+
+/// The deserializer under its old name.
+#[cfg(feature = "de")]
+#[deprecated(note = "renamed to `spa_json::de`")]
+pub mod spa_json_deserializer {
+    pub use crate::de::*;
+}
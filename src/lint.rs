@@ -0,0 +1,270 @@
+//! Lints for PipeWire and WirePlumber configuration files.
+//!
+//! On top of what [`Schema::validate`] reports, keys are checked against
+//! the bundled property database: misspelled keys get a suggestion,
+//! renamed keys are flagged as deprecated and well-known properties are
+//! checked for their type wherever they appear.
+
+use crate::path::{DisplayPath, PathSegment};
+use crate::schema::{coerce, ObjectSchema, Schema};
+use crate::schemas;
+use crate::value::Value;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// A key that is not known where it appears.
+    UnknownKey,
+    /// A value of the wrong type or out of range, or a missing key.
+    Invalid,
+    /// A key that was renamed.
+    Deprecated,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lint {
+    pub path: Vec<PathSegment>,
+    pub kind: Kind,
+    pub message: String,
+    /// Key to use instead, for unknown and deprecated keys. It is also
+    /// mentioned in the message.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", DisplayPath(&self.path), self.message)
+    }
+}
+
+/// Lints `value` against `schema`; pass [`Schema::Any`] to only check the
+/// property database.
+pub fn lint(schema: &Schema, value: &Value) -> Vec<Lint> {
+    // Unknown keys are reported by the walk below, with suggestions.
+    let mut lints: Vec<Lint> = schema
+        .validate(value)
+        .into_iter()
+        .filter(|d| d.message != "unknown key")
+        .map(|d| Lint {
+            path: d.path,
+            kind: Kind::Invalid,
+            message: d.message,
+            suggestion: None,
+        })
+        .collect();
+    let linter = Linter {
+        properties: schemas::properties(),
+    };
+    linter.walk(Some(schema), &mut Vec::new(), value, &mut lints);
+    lints
+}
+
+struct Linter {
+    properties: ObjectSchema,
+}
+
+impl Linter {
+    fn walk(
+        &self,
+        schema: Option<&Schema>,
+        path: &mut Vec<PathSegment>,
+        value: &Value,
+        lints: &mut Vec<Lint>,
+    ) {
+        match value {
+            Value::Array(array) => {
+                let items = schema.and_then(array_items);
+                for (index, item) in array.iter().enumerate() {
+                    path.push(PathSegment::Index(index));
+                    self.walk(items, path, item, lints);
+                    path.pop();
+                }
+            }
+            Value::Object(map) => {
+                let object = schema.and_then(object_schema);
+                for (key, item) in map.iter() {
+                    path.push(PathSegment::Key(key.to_string()));
+                    let property = object.and_then(|o| o.get(key));
+                    let additional = match object {
+                        Some(o) => o.additional.as_deref(),
+                        None => Some(&Schema::Any),
+                    };
+                    if property.is_none() {
+                        self.check_key(object, additional, path, key, item, lints);
+                    }
+                    self.walk(
+                        property.map(|p| &p.schema).or(additional),
+                        path,
+                        item,
+                        lints,
+                    );
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks a key that the schema does not describe.
+    fn check_key(
+        &self,
+        object: Option<&ObjectSchema>,
+        additional: Option<&Schema>,
+        path: &[PathSegment],
+        key: &str,
+        value: &Value,
+        lints: &mut Vec<Lint>,
+    ) {
+        let lint = |kind, mut message: String, suggestion: Option<&str>| {
+            match (suggestion, kind) {
+                (Some(key), Kind::Deprecated) => message += &format!(", use {:?} instead", key),
+                (Some(key), _) => message += &format!(", did you mean {:?}?", key),
+                (None, _) => {}
+            }
+            Lint {
+                path: path.to_vec(),
+                kind,
+                message,
+                suggestion: suggestion.map(str::to_owned),
+            }
+        };
+        if let Some((_, new)) = schemas::DEPRECATED.iter().find(|(old, _)| *old == key) {
+            lints.push(lint(
+                Kind::Deprecated,
+                "deprecated key".to_owned(),
+                Some(new),
+            ));
+            return;
+        }
+        match (additional, self.properties.get(key)) {
+            (Some(Schema::Any), Some(property)) => {
+                for diagnostic in property.schema.validate(&coerce(&property.schema, value)) {
+                    lints.push(lint(Kind::Invalid, diagnostic.message, None));
+                }
+            }
+            (Some(_), Some(_)) => {}
+            (additional, None) => {
+                let suggestion = self.closest(object, key);
+                if additional.is_none() || suggestion.is_some() {
+                    lints.push(lint(Kind::UnknownKey, "unknown key".to_owned(), suggestion));
+                }
+            }
+            (None, Some(_)) => {
+                lints.push(lint(Kind::UnknownKey, "unknown key".to_owned(), None));
+            }
+        }
+    }
+
+    /// Finds a known key that `key` is likely a misspelling of.
+    fn closest<'a>(&'a self, object: Option<&'a ObjectSchema>, key: &str) -> Option<&'a str> {
+        let limit = if key.chars().count() >= 12 { 2 } else { 1 };
+        object
+            .into_iter()
+            .chain(Some(&self.properties))
+            .flat_map(|o| o.properties.iter())
+            .map(|p| (distance(key, &p.name), p.name.as_str()))
+            .filter(|&(d, _)| d > 0 && d <= limit)
+            .min_by_key(|&(d, _)| d)
+            .map(|(_, name)| name)
+    }
+}
+
+fn object_schema(schema: &Schema) -> Option<&ObjectSchema> {
+    match schema {
+        Schema::Object(object) => Some(object),
+        Schema::OneOf(alternatives) => alternatives.iter().find_map(object_schema),
+        _ => None,
+    }
+}
+
+fn array_items(schema: &Schema) -> Option<&Schema> {
+    match schema {
+        Schema::Array(items) => Some(items),
+        Schema::OneOf(alternatives) => alternatives.iter().find_map(array_items),
+        _ => None,
+    }
+}
+
+/// Edit distance between `a` and `b`, counting a swap of two adjacent
+/// characters as one edit.
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Three rows of the distance matrix: two rows back, the previous and
+    // the current one.
+    let mut before: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("node.name", "node.name"), 0);
+        assert_eq!(distance("node.nmae", "node.name"), 1);
+        assert_eq!(distance("node.nam", "node.name"), 1);
+        assert_eq!(distance("node.nick", "node.name"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_lint() {
+        let config: Value = r#"
+            context.properties = {
+                default.clock.rtae = 48000
+                log.level = "2"
+            }
+            monitor.bluez.properties = {
+                bluez5.msbc-support = true
+            }
+            monitor.alsa.rules = [
+                {
+                    matches = [ { device.nmae = "~alsa_card.*" } ]
+                    actions = {
+                        update-props = {
+                            api.alsa.use-acp = "yes"
+                            api.alsa.period-size = "1024"
+                            node.pause-on-idle = false
+                            my.custom.property = 1
+                        }
+                    }
+                }
+            ]
+        "#
+        .parse()
+        .unwrap();
+        let rendered: Vec<String> = lint(&schemas::wireplumber(), &config)
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "\"context.properties\".\"default.clock.rtae\": unknown key, did you mean \"default.clock.rate\"?",
+                "\"monitor.bluez.properties\".\"bluez5.msbc-support\": deprecated key, use \"bluez5.enable-msbc\" instead",
+                "\"monitor.alsa.rules\"[0].matches[0].\"device.nmae\": unknown key, did you mean \"device.name\"?",
+                "\"monitor.alsa.rules\"[0].actions.update-props.\"api.alsa.use-acp\": expected boolean, found string",
+            ]
+        );
+
+        let lints = lint(&Schema::Any, &"node.target = 42".parse().unwrap());
+        assert_eq!(lints[0].kind, Kind::Deprecated);
+        assert_eq!(lints[0].suggestion.as_deref(), Some("target.object"));
+    }
+}
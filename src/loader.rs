@@ -0,0 +1,395 @@
+use crate::env::env_overrides;
+use crate::error::Error;
+use crate::merge::merge;
+use crate::path::PathSegment;
+use crate::value::Value;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadedConfig {
+    /// The effective configuration after merging all files.
+    pub value: Value,
+    /// Every file that contributed, in the order they were merged.
+    pub files: Vec<PathBuf>,
+    /// `value` with every scalar replaced by the index in `files` of the
+    /// file it came from, built by merging alongside it.
+    origins: Value,
+}
+
+impl LoadedConfig {
+    /// The file the value at `path` came from. For an array or object,
+    /// this is the last file that set something in it, and `None` if it
+    /// is empty. Values set from the environment have no file.
+    pub fn origin(&self, path: &[PathSegment]) -> Option<&Path> {
+        let file = last_file(self.origins.get_path(path)?)?;
+        Some(&self.files[file])
+    }
+
+    /// Merges the file at `path` in. The first file is taken as it is.
+    fn add(&mut self, path: &Path) -> Result<(), Error> {
+        let fragment = load_file(path)?;
+        let origins = origins(&fragment, self.files.len());
+        if self.files.is_empty() {
+            self.value = fragment;
+            self.origins = origins;
+        } else {
+            merge(&mut self.value, fragment);
+            merge(&mut self.origins, origins);
+        }
+        self.files.push(path.to_owned());
+        Ok(())
+    }
+
+    /// Merges every `*.conf` file of the directory `dir` in, in lexical
+    /// order. A missing directory is skipped.
+    fn add_dropins(&mut self, dir: &Path) -> Result<(), Error> {
+        let mut fragments = match std::fs::read_dir(dir) {
+            Ok(entries) => entries
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| Error::File {
+                    path: dir.to_owned(),
+                    error: Box::new(e.into()),
+                })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(Error::File {
+                    path: dir.to_owned(),
+                    error: Box::new(e.into()),
+                })
+            }
+        };
+        fragments.retain(|p| p.extension().is_some_and(|ext| ext == "conf") && p.is_file());
+        fragments.sort();
+        for fragment in fragments {
+            self.add(&fragment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Loads `path` and then merges every `*.conf` file of the `<path>.d`
+/// directory on top of it, in lexical order. A missing drop-in directory is
+/// not an error.
+pub fn load_config_with_dropins(path: impl AsRef<Path>) -> Result<LoadedConfig, Error> {
+    let path = path.as_ref();
+    let mut config = LoadedConfig {
+        value: Value::Null,
+        files: Vec::new(),
+        origins: Value::Null,
+    };
+    config.add(path)?;
+    config.add_dropins(&dropin_dir(path))?;
+    Ok(config)
+}
+
+/// Loads a configuration file from the directories PipeWire looks in,
+/// layering the copies found in each.
+///
+/// ```no_run
+/// use spa_json::loader::Loader;
+/// use spa_json::path::SpaPath;
+///
+/// let config = Loader::new("pipewire.conf").load()?;
+/// let rate = SpaPath::new().key("context.properties").key("default.clock.rate");
+/// println!("{:?} from {:?}", config.value.get_path(&rate), config.origin(&rate));
+/// # Ok::<(), spa_json::de::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Loader {
+    name: String,
+    dirs: Vec<PathBuf>,
+    env_prefix: Option<String>,
+}
+
+impl Loader {
+    /// A loader for `name`, such as `pipewire.conf`, searching
+    /// `/usr/share/pipewire`, `/etc/pipewire` and
+    /// `$XDG_CONFIG_HOME/pipewire` (`~/.config/pipewire` by default). As for
+    /// PipeWire, setting `PIPEWIRE_CONFIG_DIR` searches that directory
+    /// alone instead.
+    pub fn new(name: impl Into<String>) -> Self {
+        Loader {
+            name: name.into(),
+            dirs: standard_dirs(|var| std::env::var_os(var)),
+            env_prefix: None,
+        }
+    }
+
+    /// Searches `dirs` instead, from the lowest priority to the highest.
+    pub fn dirs<I, P>(mut self, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Also searches `dir`, with a higher priority than the others.
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    /// Applies the [overrides](crate::env) of the environment variables
+    /// starting with `prefix`, such as `SPAJSON_`, after merging the files.
+    pub fn env_overrides(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// The directories searched, from the lowest priority to the highest.
+    pub fn search_path(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    /// Merges the file from every directory that has it, from the lowest
+    /// priority to the highest, and then the `*.conf` files of the
+    /// `<name>.d` directories in the same order, and last the overrides of
+    /// the environment. It is an error if no directory has the file.
+    pub fn load(&self) -> Result<LoadedConfig, Error> {
+        let mut config = LoadedConfig {
+            value: Value::Null,
+            files: Vec::new(),
+            origins: Value::Null,
+        };
+        for dir in &self.dirs {
+            let path = dir.join(&self.name);
+            if path.is_file() {
+                config.add(&path)?;
+            }
+        }
+        if config.files.is_empty() {
+            let dirs: Vec<String> = self.dirs.iter().map(|d| d.display().to_string()).collect();
+            return Err(Error::Message(format!(
+                "{} not found in {}",
+                self.name,
+                dirs.join(", ")
+            )));
+        }
+        for dir in &self.dirs {
+            config.add_dropins(&dropin_dir(&dir.join(&self.name)))?;
+        }
+        for env in self
+            .env_prefix
+            .iter()
+            .flat_map(|prefix| env_overrides(prefix))
+        {
+            let path = env.apply(&mut config.value)?;
+            // The value may replace a scalar with a container, which the
+            // origins cannot follow; it then has the origin of the scalar.
+            let _ = config.origins.set_path(&path, Value::Null);
+        }
+        Ok(config)
+    }
+}
+
+/// The standard search path, reading the environment through `var`.
+fn standard_dirs(var: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    if let Some(dir) = var("PIPEWIRE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        return vec![dir.into()];
+    }
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/pipewire"),
+        PathBuf::from("/etc/pipewire"),
+    ];
+    let config_home = match var("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => var("HOME").map(|home| PathBuf::from(home).join(".config")),
+    };
+    dirs.extend(config_home.map(|dir| dir.join("pipewire")));
+    dirs
+}
+
+/// The drop-in directory of the file at `path`, `<path>.d`.
+fn dropin_dir(path: &Path) -> PathBuf {
+    let mut dir = path.as_os_str().to_owned();
+    dir.push(".d");
+    PathBuf::from(dir)
+}
+
+/// `value` with every scalar replaced by `file`.
+fn origins(value: &Value, file: usize) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(|v| origins(v, file)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, v)| (key.clone(), origins(v, file)))
+                .collect(),
+        ),
+        _ => Value::from(file as u64),
+    }
+}
+
+/// The highest file index in a tree built by [`origins`].
+fn last_file(origins: &Value) -> Option<usize> {
+    match origins {
+        Value::Array(items) => items.iter().filter_map(last_file).max(),
+        Value::Object(map) => map.iter().filter_map(|(_, v)| last_file(v)).max(),
+        v => v.as_u64().map(|file| file as usize),
+    }
+}
+
+fn load_file(path: &Path) -> Result<Value, Error> {
+    let in_file = |error: Error| Error::File {
+        path: path.to_owned(),
+        error: Box::new(error),
+    };
+    let input = std::fs::read_to_string(path).map_err(|e| in_file(e.into()))?;
+    let value: Value = input.parse().map_err(in_file)?;
+    if !matches!(value, Value::Object(_)) {
+        return Err(in_file(Error::Message(format!(
+            "expected an object, found {}",
+            value.type_name()
+        ))));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::SpaPath;
+
+    #[test]
+    fn test_load_config_with_dropins() {
+        let dir = std::env::temp_dir().join(format!("spa-json-loader-{}", std::process::id()));
+        let dropins = dir.join("pipewire.conf.d");
+        std::fs::create_dir_all(&dropins).unwrap();
+        let base = dir.join("pipewire.conf");
+        std::fs::write(&base, "context.properties = { log.level = 2 }").unwrap();
+        std::fs::write(
+            dropins.join("20-rate.conf"),
+            "context.properties = { log.level = 4 }",
+        )
+        .unwrap();
+        std::fs::write(
+            dropins.join("10-rate.conf"),
+            "context.properties = { log.level = 3 }",
+        )
+        .unwrap();
+        std::fs::write(dropins.join("README"), "not a fragment").unwrap();
+
+        let config = load_config_with_dropins(&base).unwrap();
+        assert_eq!(
+            config
+                .value
+                .get("context.properties")
+                .unwrap()
+                .get("log.level"),
+            Some(&Value::from(4))
+        );
+        assert_eq!(
+            config.files,
+            [
+                base.clone(),
+                dropins.join("10-rate.conf"),
+                dropins.join("20-rate.conf")
+            ]
+        );
+
+        std::fs::write(dropins.join("30-bad.conf"), "a = [").unwrap();
+        let error = load_config_with_dropins(&base).unwrap_err().to_string();
+        assert!(error.ends_with("30-bad.conf: expected array close bracket at line 1 column 6"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(
+            load_config_with_dropins(&base),
+            Err(Error::File { .. })
+        ));
+    }
+
+    #[test]
+    fn test_loader() {
+        let dir = std::env::temp_dir().join(format!("spa-json-layers-{}", std::process::id()));
+        let (system, user) = (dir.join("share"), dir.join("user"));
+        std::fs::create_dir_all(system.join("client.conf.d")).unwrap();
+        std::fs::create_dir_all(user.join("client.conf.d")).unwrap();
+        std::fs::write(
+            system.join("client.conf"),
+            "props = { rate = 48000, quantum = 1024 }\nmodules = [ a ]",
+        )
+        .unwrap();
+        std::fs::write(user.join("client.conf"), "props = { quantum = 256 }").unwrap();
+        std::fs::write(system.join("client.conf.d/10-b.conf"), "modules = [ b ]").unwrap();
+        std::fs::write(
+            user.join("client.conf.d/10-rate.conf"),
+            "override.props = { rate = 44100 }",
+        )
+        .unwrap();
+
+        let loader = Loader::new("client.conf").dirs([&system, &dir.join("missing"), &user]);
+        let config = loader.load().unwrap();
+        assert_eq!(
+            config.value,
+            "props = { rate = 44100 }, modules = [ a b ]"
+                .parse()
+                .unwrap()
+        );
+        assert_eq!(
+            config.files,
+            [
+                system.join("client.conf"),
+                user.join("client.conf"),
+                system.join("client.conf.d/10-b.conf"),
+                user.join("client.conf.d/10-rate.conf"),
+            ]
+        );
+        let origin = |path: &str| config.origin(&path.parse::<SpaPath>().unwrap());
+        assert_eq!(origin("props.rate"), Some(&*config.files[3]));
+        assert_eq!(origin("props.quantum"), None);
+        assert_eq!(origin("modules[0]"), Some(&*config.files[0]));
+        assert_eq!(origin("modules"), Some(&*config.files[2]));
+
+        let var = format!("SPAJSON_TEST_{}_props__quantum", std::process::id());
+        std::env::set_var(&var, "512");
+        let loader = loader.env_overrides(format!("SPAJSON_TEST_{}_", std::process::id()));
+        let config = loader.load().unwrap();
+        std::env::remove_var(&var);
+        let quantum = "props.quantum".parse::<SpaPath>().unwrap();
+        assert_eq!(config.value.get_path(&quantum), Some(&Value::from(512)));
+        assert_eq!(config.origin(&quantum), None);
+
+        let error = Loader::new("other.conf")
+            .dirs([&system])
+            .load()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("other.conf not found in {}", system.display())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_standard_dirs() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+        assert_eq!(
+            standard_dirs(env(&[("HOME", "/home/me")])),
+            [
+                Path::new("/usr/share/pipewire"),
+                Path::new("/etc/pipewire"),
+                Path::new("/home/me/.config/pipewire"),
+            ]
+        );
+        assert_eq!(
+            standard_dirs(env(&[("HOME", "/home/me"), ("XDG_CONFIG_HOME", "/cfg")]))[2],
+            Path::new("/cfg/pipewire")
+        );
+        assert_eq!(
+            standard_dirs(env(&[
+                ("PIPEWIRE_CONFIG_DIR", "/opt/pw"),
+                ("HOME", "/home/me")
+            ])),
+            [Path::new("/opt/pw")]
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! Rendering of [`Value`]s as Lua table constructors, the configuration
+//! format of WirePlumber 0.4, so the same data can be used with the Lua
+//! and the SPA-JSON based versions.
+//!
+//! Objects and arrays both become tables, `null` becomes `nil`. Keys that
+//! are Lua names are written bare, all others as `["key"]`.
+
+use crate::error::Error;
+use crate::value::Value;
+use std::fmt::Write;
+
+/// Arrays of scalars up to this length are written on one line, like the
+/// `{ "node.name", "matches", "alsa_*" }` constraints of WirePlumber rules.
+const INLINE_WIDTH: usize = 60;
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Writes `value` as a Lua expression.
+pub fn to_lua(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0);
+    out
+}
+
+/// Writes an object as a Lua chunk with one assignment per key, the way
+/// WirePlumber 0.4 configuration files are written:
+///
+/// ```text
+/// alsa_monitor.properties = {
+///   ["alsa.reserve"] = true,
+/// }
+/// ```
+///
+/// Keys must be Lua names or dotted paths of names.
+pub fn to_lua_chunk(value: &Value) -> Result<String, Error> {
+    let map = value
+        .as_object()
+        .ok_or_else(|| Error::Message("only objects can be written as a Lua chunk".to_owned()))?;
+    let mut out = String::new();
+    for (key, value) in map.iter() {
+        if !key.split('.').all(is_name) {
+            return Err(Error::Message(format!(
+                "key {:?} cannot be assigned in Lua",
+                key
+            )));
+        }
+        out.push_str(key);
+        out.push_str(" = ");
+        write_value(&mut out, value, 0);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+fn is_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&s)
+}
+
+fn write_value(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Null => out.push_str("nil"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) if n.is_f64() => {
+            let v = n.as_f64();
+            if v.is_nan() {
+                out.push_str("(0/0)");
+            } else if v.is_infinite() {
+                out.push_str(if v < 0.0 { "-math.huge" } else { "math.huge" });
+            } else {
+                // Debug keeps the `.0` of integral floats.
+                write!(out, "{:?}", v).unwrap();
+            }
+        }
+        Value::Number(n) => write!(out, "{}", n).unwrap(),
+        Value::String(s) => write_string(out, s),
+        Value::Array(a) if a.is_empty() => out.push_str("{}"),
+        Value::Object(m) if m.is_empty() => out.push_str("{}"),
+        Value::Array(a) if a.iter().all(is_scalar) && inline_len(a) <= INLINE_WIDTH => {
+            out.push_str("{ ");
+            for (i, v) in a.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_value(out, v, indent);
+            }
+            out.push_str(" }");
+        }
+        Value::Array(a) => {
+            out.push_str("{\n");
+            for v in a {
+                write_indent(out, indent + 2);
+                write_value(out, v, indent + 2);
+                out.push_str(",\n");
+            }
+            write_indent(out, indent);
+            out.push('}');
+        }
+        Value::Object(m) => {
+            out.push_str("{\n");
+            for (k, v) in m.iter() {
+                write_indent(out, indent + 2);
+                if is_name(k) {
+                    out.push_str(k);
+                } else {
+                    out.push('[');
+                    write_string(out, k);
+                    out.push(']');
+                }
+                out.push_str(" = ");
+                write_value(out, v, indent + 2);
+                out.push_str(",\n");
+            }
+            write_indent(out, indent);
+            out.push('}');
+        }
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+/// Length of an array of scalars written on one line.
+fn inline_len(array: &[Value]) -> usize {
+    let items: usize = array.iter().map(|v| to_lua(v).len()).sum();
+    items + 2 * array.len() + 2
+}
+
+fn write_indent(out: &mut String, indent: usize) {
+    out.extend(std::iter::repeat_n(' ', indent));
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            // Decimal escapes take up to three digits, so always write
+            // three in case a digit follows.
+            c if c.is_control() && c.is_ascii() => write!(out, "\\{:03}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_lua() {
+        let value: Value = r#"{
+            alsa.reserve = true
+            rules = [
+                { matches = [ [ [ node.name matches "alsa_output.*" ] ] ]
+                  apply_properties = { node.nick = "Speakers \"L\"", priority.session = 1000 } }
+            ]
+            end = null
+            long = [ 1234567890 1234567890 1234567890 1234567890 1234567890 1234567890 ]
+            empty = {}
+            ratio = 1.0
+            tab = "a\tb\u0001"
+        }"#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            to_lua(&value),
+            r#"{
+  ["alsa.reserve"] = true,
+  rules = {
+    {
+      matches = {
+        {
+          { "node.name", "matches", "alsa_output.*" },
+        },
+      },
+      apply_properties = {
+        ["node.nick"] = "Speakers \"L\"",
+        ["priority.session"] = 1000,
+      },
+    },
+  },
+  ["end"] = nil,
+  long = {
+    1234567890,
+    1234567890,
+    1234567890,
+    1234567890,
+    1234567890,
+    1234567890,
+  },
+  empty = {},
+  ratio = 1.0,
+  tab = "a\tb\001",
+}"#
+        );
+        assert_eq!(to_lua(&Value::from(f64::NEG_INFINITY)), "-math.huge");
+    }
+
+    #[test]
+    fn test_to_lua_chunk() {
+        let value: Value = r#"
+            alsa_monitor.enabled = true
+            alsa_monitor.properties = { alsa.jack-device = false }
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(
+            to_lua_chunk(&value).unwrap(),
+            "alsa_monitor.enabled = true\n\
+             alsa_monitor.properties = {\n  [\"alsa.jack-device\"] = false,\n}\n"
+        );
+        assert!(to_lua_chunk(&"node-name = 1".parse().unwrap()).is_err());
+        assert!(to_lua_chunk(&Value::from(1)).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Evaluation of the `matches` list of WirePlumber rules:
+//!
+//! ```text
+//! matches = [
+//!   { node.name = "~alsa_output.*", media.class = "Audio/Sink" }
+//!   { device.api = bluez5, node.nick = "!Headset*" }
+//! ]
+//! ```
+//!
+//! The list matches if any of its objects does, and an object matches if
+//! all of its properties do. Each value is compared with the property of
+//! the same name:
+//!
+//! * `~regex` matches if the regular expression matches anywhere in the
+//!   property. Expressions are read by the [regex] crate, whose syntax
+//!   covers the POSIX extended expressions WirePlumber uses, and take
+//!   linear time to match;
+//! * `*` and `?` in any other string are glob wildcards, matching any run
+//!   of characters and any single character;
+//! * `null` matches if the property is not set;
+//! * a leading `!` negates the rest of the value, so `"!~^alsa"` matches
+//!   properties that do not start with `alsa`, and ones that are not set.
+//!
+//! Values other than strings are compared in their SPA-JSON form, so
+//! `true` matches the property `"true"`.
+//!
+//! [regex]: https://docs.rs/regex
+
+use crate::error::Error;
+use crate::properties::Properties;
+use crate::value::{Map, Value};
+use regex::Regex;
+
+#[derive(Clone, Debug)]
+pub struct Match {
+    alternatives: Vec<Vec<(String, Pattern)>>,
+}
+
+#[derive(Clone, Debug)]
+enum Pattern {
+    Unset,
+    Glob(String),
+    Regex(Regex),
+    Not(Box<Pattern>),
+}
+
+impl Match {
+    /// Compiles the objects of a `matches` list, as found in
+    /// [`Rule::matches`](crate::rules::Rule::matches).
+    pub fn new(matches: &[Map]) -> Result<Match, Error> {
+        let alternatives = matches
+            .iter()
+            .map(|object| {
+                object
+                    .iter()
+                    .map(|(key, value)| Ok((key.to_string(), Pattern::new(value)?)))
+                    .collect()
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Match { alternatives })
+    }
+
+    /// Whether the rule applies to an object with the properties `props`.
+    /// Empty match objects never match.
+    pub fn matches(&self, props: &Properties) -> bool {
+        self.alternatives.iter().any(|conditions| {
+            !conditions.is_empty()
+                && conditions
+                    .iter()
+                    .all(|(key, pattern)| pattern.matches(props.get(key)))
+        })
+    }
+}
+
+/// Accepts the `matches` array, or a single match object.
+impl TryFrom<&Value> for Match {
+    type Error = Error;
+
+    fn try_from(value: &Value) -> Result<Match, Error> {
+        let invalid = || Error::Message("matches must be an array of objects".to_owned());
+        match value {
+            Value::Object(object) => Match::new(std::slice::from_ref(object)),
+            Value::Array(array) => {
+                let objects = array
+                    .iter()
+                    .map(|v| v.as_object().cloned().ok_or_else(invalid))
+                    .collect::<Result<Vec<Map>, Error>>()?;
+                Match::new(&objects)
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Pattern {
+    fn new(value: &Value) -> Result<Pattern, Error> {
+        let text = match value {
+            Value::Null => return Ok(Pattern::Unset),
+            Value::String(s) => s.to_string(),
+            v => v.to_canonical_string(),
+        };
+        Pattern::parse(&text)
+    }
+
+    fn parse(text: &str) -> Result<Pattern, Error> {
+        if let Some(rest) = text.strip_prefix('!') {
+            return Ok(Pattern::Not(Box::new(Pattern::parse(rest)?)));
+        }
+        match text.strip_prefix('~') {
+            Some(regex) => Regex::new(regex)
+                .map(Pattern::Regex)
+                .map_err(|e| Error::Message(format!("invalid regex {:?}: {}", regex, e))),
+            None => Ok(Pattern::Glob(text.to_owned())),
+        }
+    }
+
+    fn matches(&self, value: Option<&str>) -> bool {
+        match (self, value) {
+            (Pattern::Unset, value) => value.is_none(),
+            (Pattern::Not(pattern), value) => !pattern.matches(value),
+            (_, None) => false,
+            (Pattern::Glob(glob), Some(value)) => glob_match(glob, value),
+            (Pattern::Regex(regex), Some(value)) => regex.is_match(value),
+        }
+    }
+}
+
+/// Matches `s` against a glob where `*` stands for any run of characters
+/// and `?` for a single character.
+pub fn glob_match(glob: &str, s: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let s: Vec<char> = s.chars().collect();
+    let (mut g, mut i) = (0, 0);
+    // Where to resume after the last `*`: its position and the next
+    // character of `s` it would swallow.
+    let mut star = None;
+    while i < s.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, i));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == s[i] => {
+                g += 1;
+                i += 1;
+            }
+            _ => match star {
+                Some((star_g, star_i)) => {
+                    g = star_g + 1;
+                    i = star_i + 1;
+                    star = Some((star_g, star_i + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn props(entries: &[(&str, &str)]) -> Properties {
+        entries.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob_match("alsa_*", "alsa_output.pci"));
+        assert!(glob_match("*.pci*", "alsa_output.pci-0000"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("alsa", "alsa_output"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let rule: Match = (&r#"[
+            { node.name = "~^alsa_output\\.", media.class = "Audio/Sink" }
+            { device.api = bluez5, node.nick = "!Headset*" }
+            { node.virtual = true, node.description = null }
+            {}
+        ]"#
+        .parse::<Value>()
+        .unwrap())
+            .try_into()
+            .unwrap();
+
+        let sink = props(&[
+            ("node.name", "alsa_output.pci-0000_00_1f.3.analog-stereo"),
+            ("media.class", "Audio/Sink"),
+        ]);
+        assert!(rule.matches(&sink));
+        let source = props(&[
+            ("node.name", "alsa_input.pci-0000_00_1f.3.analog-stereo"),
+            ("media.class", "Audio/Source"),
+        ]);
+        assert!(!rule.matches(&source));
+
+        let speaker = props(&[("device.api", "bluez5"), ("node.nick", "Speaker")]);
+        assert!(rule.matches(&speaker));
+        assert!(rule.matches(&props(&[("device.api", "bluez5")])));
+        let headset = props(&[("device.api", "bluez5"), ("node.nick", "Headset 2")]);
+        assert!(!rule.matches(&headset));
+
+        assert!(rule.matches(&props(&[("node.virtual", "true")])));
+        let described = props(&[("node.virtual", "true"), ("node.description", "x")]);
+        assert!(!rule.matches(&described));
+        assert!(!rule.matches(&props(&[])));
+
+        let single: Match = (&"{ node.name = \"!~^alsa\" }".parse::<Value>().unwrap())
+            .try_into()
+            .unwrap();
+        assert!(single.matches(&props(&[("node.name", "v4l2_input")])));
+        assert!(!single.matches(&props(&[("node.name", "alsa_input")])));
+
+        let err = Match::try_from(&"[ { node.name = \"~(\" } ]".parse::<Value>().unwrap());
+        assert!(err
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid regex \"(\""));
+        assert!(Match::try_from(&Value::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_regex_linear_time() {
+        // Both take exponential time or stack space with backtracking.
+        let rule = |text: &str| -> Match { (&text.parse::<Value>().unwrap()).try_into().unwrap() };
+        let nested = rule("{ node.name = \"~(a+)+$\" }");
+        let name = format!("{}!", "a".repeat(64));
+        assert!(!nested.matches(&props(&[("node.name", &name)])));
+        assert!(nested.matches(&props(&[("node.name", "aa")])));
+        let long = rule("{ node.nick = \"~.*x\" }");
+        let mut nick = "y".repeat(200_000);
+        assert!(!long.matches(&props(&[("node.nick", &nick)])));
+        nick.push('x');
+        assert!(long.matches(&props(&[("node.nick", &nick)])));
+    }
+}
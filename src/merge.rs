@@ -0,0 +1,215 @@
+use crate::error::Error;
+use crate::trace;
+use crate::value::{Key, Map, Value};
+
+const OVERRIDE_PREFIX: &str = "override.";
+
+/// Merges `fragment` into `base` with the rules used for configuration
+/// fragments: objects are merged key by key, arrays are concatenated and any
+/// other value replaces the previous one.
+///
+/// A key written as `override.name` replaces the value of `name` instead of
+/// merging into it.
+pub fn merge(base: &mut Value, fragment: Value) {
+    let _phase = trace::phase!(
+        "merge",
+        entries = fragment.as_object().map_or(0, |entries| entries.len())
+    );
+    merge_into(base, fragment);
+}
+
+fn merge_into(base: &mut Value, mut fragment: Value) {
+    match (base, &mut fragment) {
+        (Value::Object(base), Value::Object(entries)) => {
+            for (key, value) in std::mem::take(entries) {
+                match key.strip_prefix(OVERRIDE_PREFIX) {
+                    Some(name) => {
+                        let mut replacement = Value::Null;
+                        merge_into(&mut replacement, value);
+                        base.insert(name, replacement);
+                    }
+                    None => match base.get_mut(&key) {
+                        Some(slot) => merge_into(slot, value),
+                        None => {
+                            let mut fresh = Value::Null;
+                            merge_into(&mut fresh, value);
+                            base.insert(key, fresh);
+                        }
+                    },
+                }
+            }
+        }
+        (Value::Array(base), Value::Array(items)) => base.append(items),
+        (base, Value::Object(_)) => {
+            *base = Value::Object(Default::default());
+            merge_into(base, fragment);
+        }
+        (base, _) => *base = fragment,
+    }
+}
+
+/// How a value has to change to get from a base to a desired value.
+enum Delta {
+    Unchanged,
+    /// Merging this value produces the desired one.
+    Merge(Value),
+    /// Merging cannot remove entries, the whole value has to be overridden.
+    Replace,
+}
+
+/// The smallest fragment that turns `base` into `desired` when merged with
+/// [`merge`]: only changed keys are listed, arrays that grew only list the
+/// appended elements and values that lost entries are replaced with an
+/// `override.` key.
+///
+/// Fails if `desired` lacks a top-level section of `base`, since no fragment
+/// can remove one.
+pub fn minimal_fragment(base: &Value, desired: &Value) -> Result<Value, Error> {
+    match (base, desired) {
+        (Value::Object(_), Value::Object(_)) => {}
+        _ => return Err(Error::Message("configurations must be objects".to_owned())),
+    }
+    match delta(base, desired) {
+        Delta::Unchanged => Ok(Value::Object(Map::new())),
+        Delta::Merge(fragment) => Ok(fragment),
+        Delta::Replace => {
+            let removed: Vec<&str> = base
+                .as_object()
+                .unwrap()
+                .keys()
+                .filter(|k| desired.get(k).is_none())
+                .map(Key::as_str)
+                .collect();
+            Err(Error::Message(format!(
+                "sections cannot be removed by a fragment: {}",
+                removed.join(", ")
+            )))
+        }
+    }
+}
+
+fn delta(base: &Value, desired: &Value) -> Delta {
+    match (base, desired) {
+        (Value::Object(base), Value::Object(desired)) => {
+            if base.keys().any(|k| !desired.contains_key(k)) {
+                return Delta::Replace;
+            }
+            let mut fragment = Map::new();
+            for (key, value) in desired.iter() {
+                let delta = match base.get(key) {
+                    Some(old) => delta(old, value),
+                    None => Delta::Merge(value.clone()),
+                };
+                match delta {
+                    Delta::Unchanged => {}
+                    Delta::Merge(v) => {
+                        fragment.insert(key.clone(), v);
+                    }
+                    Delta::Replace => {
+                        fragment.insert(format!("{}{}", OVERRIDE_PREFIX, key), value.clone());
+                    }
+                }
+            }
+            if fragment.is_empty() {
+                Delta::Unchanged
+            } else {
+                Delta::Merge(Value::Object(fragment))
+            }
+        }
+        (Value::Array(base), Value::Array(desired)) => {
+            match desired.strip_prefix(base.as_slice()) {
+                Some([]) => Delta::Unchanged,
+                Some(appended) => Delta::Merge(Value::Array(appended.to_vec())),
+                None => Delta::Replace,
+            }
+        }
+        _ if base == desired => Delta::Unchanged,
+        _ => Delta::Merge(desired.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+
+    #[test]
+    fn test_merge() {
+        let mut base: Value = r#"
+            context.properties = { default.clock.rate = 44100, log.level = 2 }
+            context.modules = [ { name = a } ]
+            monitor.bluez.properties = { bluez5.codecs = [ sbc ] }
+            monitor.alsa.rules = [ { matches = [] actions = {} } ]
+        "#
+        .parse()
+        .unwrap();
+        let fragment: Value = r#"
+            context.properties = { default.clock.rate = 48000 }
+            context.modules = [ { name = b } ]
+            monitor.bluez.properties = { override.bluez5.codecs = [ aac ] }
+            override.monitor.alsa.rules = [ ]
+            stream.properties = { override.node.latency = 1024/48000 }
+        "#
+        .parse()
+        .unwrap();
+        merge(&mut base, fragment);
+
+        let expected: Value = r#"
+            context.properties = { default.clock.rate = 48000, log.level = 2 }
+            context.modules = [ { name = a } { name = b } ]
+            monitor.bluez.properties = { bluez5.codecs = [ aac ] }
+            monitor.alsa.rules = [ ]
+            stream.properties = { node.latency = 1024/48000 }
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(base, expected);
+    }
+
+    #[test]
+    fn test_minimal_fragment() {
+        let base: Value = r#"
+            context.properties = { default.clock.rate = 44100, log.level = 2 }
+            context.modules = [ { name = a } ]
+            monitor.bluez.properties = { bluez5.codecs = [ sbc aac ] }
+            stream.properties = { node.latency = 1024/48000 }
+        "#
+        .parse()
+        .unwrap();
+        let desired: Value = r#"
+            context.properties = { default.clock.rate = 48000, log.level = 2 }
+            context.modules = [ { name = a } { name = b } ]
+            monitor.bluez.properties = { bluez5.codecs = [ aac ] }
+            stream.properties = { }
+            pulse.properties = { server.address = [ "unix:native" ] }
+        "#
+        .parse()
+        .unwrap();
+
+        let fragment = minimal_fragment(&base, &desired).unwrap();
+        let options = SerializerOptions::new().braceless(true).inline_width(80);
+        assert_eq!(
+            to_string_with_options(&fragment, options).unwrap(),
+            "context.properties = { default.clock.rate = 48000 }\n\
+             context.modules = [ { name = b } ]\n\
+             monitor.bluez.properties = { override.bluez5.codecs = [ aac ] }\n\
+             override.stream.properties = { }\n\
+             pulse.properties = { server.address = [ \"unix:native\" ] }\n"
+        );
+
+        let mut merged = base.clone();
+        merge(&mut merged, fragment);
+        assert_eq!(merged.to_canonical_string(), desired.to_canonical_string());
+
+        assert_eq!(
+            minimal_fragment(&base, &base).unwrap(),
+            Value::Object(Map::new())
+        );
+        let error = minimal_fragment(&base, &Value::Object(Map::new())).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "sections cannot be removed by a fragment: context.properties, context.modules, \
+             monitor.bluez.properties, stream.properties"
+        );
+    }
+}
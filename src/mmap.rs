@@ -0,0 +1,100 @@
+//! Parsing files through a memory mapping instead of reading them into a
+//! buffer first, for scanning large `pw-dump` archives.
+//!
+//! Strings of types such as [`borrowed::Value`](crate::borrowed::Value)
+//! borrow from the mapping, so nothing is copied but what needs unescaping.
+//! As with any mapping, the file must not be modified while it is mapped.
+//!
+//! ```no_run
+//! use spa_json::borrowed;
+//! use spa_json::mmap::MappedFile;
+//!
+//! let file = MappedFile::open("dump.json")?;
+//! let objects: Vec<borrowed::Value> = file.parse()?;
+//! println!("{} objects", objects.len());
+//! # Ok::<(), spa_json::de::Error>(())
+//! ```
+
+use crate::de;
+use crate::error::Error;
+use memmap2::Mmap;
+use serde::de::{Deserialize, DeserializeOwned};
+use std::fs::File;
+use std::path::Path;
+
+/// A file mapped into memory whose contents are valid UTF-8.
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let in_file = |error: Error| Error::File {
+            path: path.to_owned(),
+            error: Box::new(error),
+        };
+        let file = File::open(path).map_err(|e| in_file(e.into()))?;
+        // SAFETY: the module documentation requires that the file is not
+        // modified while it is mapped.
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| in_file(e.into()))?;
+        std::str::from_utf8(&map).map_err(|e| in_file(Error::Message(e.to_string())))?;
+        Ok(MappedFile { map })
+    }
+
+    pub fn as_str(&self) -> &str {
+        // SAFETY: checked in `open`.
+        unsafe { std::str::from_utf8_unchecked(&self.map) }
+    }
+
+    /// Deserializes the contents, borrowing strings from the mapping where
+    /// `T` allows it.
+    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> Result<T, Error> {
+        de::from_str(self.as_str())
+    }
+}
+
+/// Deserializes the file at `path` through a memory mapping.
+pub fn from_file_mmap<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, Error> {
+    let path = path.as_ref();
+    MappedFile::open(path)?
+        .parse()
+        .map_err(|error| Error::File {
+            path: path.to_owned(),
+            error: Box::new(error),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::borrowed;
+    use crate::value::Value;
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_mmap() {
+        let dir = std::env::temp_dir().join(format!("spa-json-mmap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("dump.json");
+        std::fs::write(&path, r#"[ { id = 0, type = "PipeWire:Interface:Core" } ]"#).unwrap();
+
+        let file = MappedFile::open(&path).unwrap();
+        let objects: Vec<borrowed::Value> = file.parse().unwrap();
+        assert!(matches!(
+            objects[0].get("type"),
+            Some(borrowed::Value::String(Cow::Borrowed(
+                "PipeWire:Interface:Core"
+            )))
+        ));
+        let objects: Vec<Value> = from_file_mmap(&path).unwrap();
+        assert_eq!(objects[0].get("id"), Some(&Value::from(0)));
+
+        std::fs::write(&path, b"a = \xff").unwrap();
+        let error = MappedFile::open(&path).err().unwrap();
+        assert!(matches!(error, Error::File { .. }));
+        assert!(from_file_mmap::<Value>(dir.join("missing.json")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
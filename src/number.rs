@@ -0,0 +1,78 @@
+//! Reading numbers from bare scalars. The deserializer types bare scalars
+//! with these, and the serializer quotes strings they would read as a
+//! number.
+
+// The serializer only asks whether there is a number.
+#[cfg_attr(not(feature = "de"), allow(dead_code))]
+pub(crate) enum Number {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+pub(crate) fn parse_number(s: &str) -> Option<Number> {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        return parse_hex(s.starts_with('-'), hex);
+    }
+    let (digits, exponent) = match digits.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (digits, None),
+    };
+    let (int, frac) = match digits.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (digits, None),
+    };
+    let all_digits = |p: &str| p.bytes().all(|b| b.is_ascii_digit());
+    let valid_exponent = |e: &str| {
+        let e = e.strip_prefix(['-', '+']).unwrap_or(e);
+        !e.is_empty() && all_digits(e)
+    };
+    if int.is_empty() && frac.is_none_or(str::is_empty)
+        || !all_digits(int)
+        || !frac.is_none_or(all_digits)
+        || !exponent.is_none_or(valid_exponent)
+    {
+        return None;
+    }
+    if frac.is_none() && exponent.is_none() {
+        if let Ok(v) = s.parse() {
+            return Some(Number::Int(v));
+        }
+        if let Ok(v) = s.trim_start_matches('+').parse() {
+            return Some(Number::UInt(v));
+        }
+    }
+    s.parse().ok().map(Number::Float)
+}
+
+/// A bare `nan`, `inf` or `-inf`, in any case and also spelled `infinity`.
+pub(crate) fn parse_non_finite(s: &str) -> Option<f64> {
+    let (negative, word) = match s.strip_prefix('-') {
+        Some(word) => (true, word),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let v = if word.eq_ignore_ascii_case("nan") {
+        f64::NAN
+    } else if word.eq_ignore_ascii_case("inf") || word.eq_ignore_ascii_case("infinity") {
+        f64::INFINITY
+    } else {
+        return None;
+    };
+    Some(if negative { -v } else { v })
+}
+
+fn parse_hex(negative: bool, hex: &str) -> Option<Number> {
+    if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let v = u64::from_str_radix(hex, 16).ok()?;
+    if negative {
+        0i64.checked_sub_unsigned(v).map(Number::Int)
+    } else {
+        Some(i64::try_from(v).map_or(Number::UInt(v), Number::Int))
+    }
+}
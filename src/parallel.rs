@@ -0,0 +1,130 @@
+//! Parsing of large documents, such as multi-megabyte dumps, on several
+//! threads.
+//!
+//! A top-level object written without braces is split between its entries
+//! into about one piece per thread. The pieces are parsed in parallel and
+//! their entries merged in order, which gives the same result as parsing
+//! the whole document at once.
+
+use crate::error::Error;
+use crate::lexer::{Lexer, TokenKind};
+use crate::value::{Map, Value};
+use rayon::prelude::*;
+
+/// Documents are not split into pieces shorter than this.
+const MIN_PIECE: usize = 64 * 1024;
+
+/// Parses `input` like `input.parse::<Value>()`, splitting it between
+/// threads if it is a large enough object without braces.
+///
+/// On errors the whole document is parsed again on the calling thread, so
+/// that they are reported as [`str::parse`] would.
+pub fn from_str_parallel(input: &str) -> Result<Value, Error> {
+    let size = (input.len() / rayon::current_num_threads()).max(MIN_PIECE);
+    parse_pieces(input, size)
+}
+
+fn parse_pieces(input: &str, size: usize) -> Result<Value, Error> {
+    let pieces = split(input, size);
+    if pieces.len() < 2 {
+        return input.parse();
+    }
+    let parsed: Result<Vec<Value>, Error> = pieces.par_iter().map(|piece| piece.parse()).collect();
+    let Ok(parsed) = parsed else {
+        return input.parse();
+    };
+    let mut map = Map::new();
+    for mut value in parsed {
+        let Value::Object(entries) = &mut value else {
+            return input.parse();
+        };
+        for (key, value) in std::mem::take(entries) {
+            map.insert(key, value);
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+/// Splits a top-level object without braces before the keys that start
+/// after at least `size` bytes of the current piece. Other documents are
+/// returned whole.
+fn split(input: &str, size: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut expect_key = true;
+    let mut first_key = true;
+    for token in Lexer::new(input) {
+        match token.kind {
+            kind if kind.is_trivia() => {}
+            TokenKind::ObjectStart | TokenKind::ArrayStart => {
+                if depth == 0 && expect_key {
+                    return vec![input];
+                }
+                depth += 1;
+            }
+            TokenKind::ObjectEnd | TokenKind::ArrayEnd => {
+                if depth == 0 {
+                    return vec![input];
+                }
+                depth -= 1;
+                expect_key |= depth == 0;
+            }
+            TokenKind::Separator => {
+                if depth == 0 && expect_key {
+                    return vec![input];
+                }
+            }
+            _ if depth > 0 => {}
+            _ if expect_key => {
+                if !first_key && token.span.start - start >= size {
+                    pieces.push(&input[start..token.span.start]);
+                    start = token.span.start;
+                }
+                expect_key = false;
+                first_key = false;
+            }
+            _ => expect_key = true,
+        }
+    }
+    pieces.push(&input[start..]);
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pieces() {
+        let input = "# dump\n\
+                     a = { x = [ 1 2 ] } b = \"q } [\", # b\n\
+                     c: [ { y = 1 } ]\n\
+                     a = 3 d = null";
+        assert_eq!(
+            split(input, 1),
+            [
+                "# dump\na = { x = [ 1 2 ] } ",
+                "b = \"q } [\", # b\n",
+                "c: [ { y = 1 } ]\n",
+                "a = 3 ",
+                "d = null",
+            ]
+        );
+        assert_eq!(split(input, 30).len(), 2);
+        assert_eq!(parse_pieces(input, 1).unwrap(), input.parse().unwrap());
+        assert_eq!(
+            from_str_parallel(input).unwrap(),
+            input.parse::<Value>().unwrap()
+        );
+
+        for whole in ["{ a = 1 } ", "[ a b ]", "a = 1 } b = 2", "= 1"] {
+            assert_eq!(split(whole, 1), [whole]);
+        }
+        let broken = "a = 1\nb = [\nc = 2";
+        assert_eq!(
+            parse_pieces(broken, 1).unwrap_err().to_string(),
+            broken.parse::<Value>().unwrap_err().to_string()
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Paths to values inside a document, written as in
+//! `"context.modules"[2].args."node.name"`.
+//!
+//! Keys are separated by dots and array indices are written in brackets.
+//! Keys that are not plain words, such as those containing dots, are quoted
+//! and escaped as SPA-JSON strings are.
+//!
+//! ```
+//! use spa_json::path::{PathSegment, SpaPath};
+//!
+//! let path: SpaPath = r#""context.modules"[2].args."node.name""#.parse().unwrap();
+//! assert_eq!(path[1], PathSegment::Index(2));
+//! assert_eq!(path[3], PathSegment::Key("node.name".to_owned()));
+//! assert_eq!(path.to_string(), r#""context.modules"[2].args."node.name""#);
+//! ```
+
+#[cfg(feature = "de")]
+use crate::de::unescape_str;
+#[cfg(feature = "de")]
+use crate::error::Error;
+use crate::escape::escape_str;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "de")]
+use alloc::{borrow::ToOwned, format};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "de")]
+use core::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// A path from the root of a document, as a list of segments that can be
+/// pushed and popped like a `Vec`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpaPath(Vec<PathSegment>);
+
+impl SpaPath {
+    /// The path of the root value.
+    pub fn new() -> Self {
+        SpaPath::default()
+    }
+
+    /// Appends a key, for chained construction.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.0.push(PathSegment::Key(key.into()));
+        self
+    }
+
+    /// Appends an array index, for chained construction.
+    pub fn index(mut self, index: usize) -> Self {
+        self.0.push(PathSegment::Index(index));
+        self
+    }
+
+    pub fn into_segments(self) -> Vec<PathSegment> {
+        self.0
+    }
+}
+
+impl Deref for SpaPath {
+    type Target = Vec<PathSegment>;
+
+    fn deref(&self) -> &Vec<PathSegment> {
+        &self.0
+    }
+}
+
+impl DerefMut for SpaPath {
+    fn deref_mut(&mut self) -> &mut Vec<PathSegment> {
+        &mut self.0
+    }
+}
+
+impl From<Vec<PathSegment>> for SpaPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        SpaPath(segments)
+    }
+}
+
+impl From<&[PathSegment]> for SpaPath {
+    fn from(segments: &[PathSegment]) -> Self {
+        SpaPath(segments.to_vec())
+    }
+}
+
+impl From<SpaPath> for Vec<PathSegment> {
+    fn from(path: SpaPath) -> Self {
+        path.0
+    }
+}
+
+impl FromIterator<PathSegment> for SpaPath {
+    fn from_iter<I: IntoIterator<Item = PathSegment>>(iter: I) -> Self {
+        SpaPath(iter.into_iter().collect())
+    }
+}
+
+impl PartialEq<[PathSegment]> for SpaPath {
+    fn eq(&self, other: &[PathSegment]) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<Vec<PathSegment>> for SpaPath {
+    fn eq(&self, other: &Vec<PathSegment>) -> bool {
+        self.0 == *other
+    }
+}
+
+impl<const N: usize> PartialEq<[PathSegment; N]> for SpaPath {
+    fn eq(&self, other: &[PathSegment; N]) -> bool {
+        self.0 == other
+    }
+}
+
+/// Writes the path with nothing for the root, so that it parses back.
+impl fmt::Display for SpaPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_path(f, &self.0)
+    }
+}
+
+#[cfg(feature = "de")]
+impl FromStr for SpaPath {
+    type Err = Error;
+
+    /// Parses a path as it is displayed. The empty string is the root.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::Message(format!("invalid path `{}`", s));
+        let mut path = SpaPath::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            if let Some(index) = rest.strip_prefix('[') {
+                let (index, after) = index.split_once(']').ok_or_else(invalid)?;
+                path.push(PathSegment::Index(index.parse().map_err(|_| invalid())?));
+                rest = after;
+            } else if let Some(quoted) = rest.strip_prefix('"') {
+                let end = closing_quote(quoted).ok_or_else(invalid)?;
+                let key = unescape_str(&quoted[..end]).map_err(|_| invalid())?;
+                path.push(PathSegment::Key(key.into_owned()));
+                rest = &quoted[end + 1..];
+            } else {
+                let end = rest.find(['.', '[', '"']).unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+                path.push(PathSegment::Key(rest[..end].to_owned()));
+                rest = &rest[end..];
+            }
+            if let Some(after) = rest.strip_prefix('.') {
+                if after.is_empty() || after.starts_with('[') {
+                    return Err(invalid());
+                }
+                rest = after;
+            } else if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+        Ok(path)
+    }
+}
+
+/// The offset of the first `"` in `s` that is not escaped.
+#[cfg(feature = "de")]
+fn closing_quote(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' if !escaped => return Some(i),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn write_path(f: &mut fmt::Formatter, segments: &[PathSegment]) -> fmt::Result {
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            PathSegment::Index(index) => write!(f, "[{}]", index)?,
+            PathSegment::Key(key) => {
+                if i > 0 {
+                    f.write_str(".")?;
+                }
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    f.write_str(key)?;
+                } else {
+                    write!(f, "\"{}\"", escape_str(key))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Displays a path as `props."node.name"[2]`, quoting keys that are not plain words.
+pub struct DisplayPath<'a>(pub &'a [PathSegment]);
+
+impl fmt::Display for DisplayPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("(root)");
+        }
+        write_path(f, self.0)
+    }
+}
+
+#[cfg(all(test, feature = "de"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spa_path() {
+        let path = SpaPath::new()
+            .key("context.modules")
+            .index(0)
+            .key("args")
+            .key("say \"hi\"")
+            .index(3);
+        let text = r#""context.modules"[0].args."say \"hi\""[3]"#;
+        assert_eq!(path.to_string(), text);
+        assert_eq!(text.parse::<SpaPath>().unwrap(), path);
+        assert_eq!(DisplayPath(&path).to_string(), text);
+
+        assert_eq!("".parse::<SpaPath>().unwrap(), SpaPath::new());
+        assert_eq!(DisplayPath(&[]).to_string(), "(root)");
+        assert_eq!(
+            "[1].a".parse::<SpaPath>().unwrap(),
+            [PathSegment::Index(1), PathSegment::Key("a".to_owned())]
+        );
+        for invalid in [
+            "a.", ".a", "a..b", "a.[0]", "[x]", "[1", "\"a", "a\"b\"", "\"a\"b",
+        ] {
+            assert!(invalid.parse::<SpaPath>().is_err(), "{invalid}");
+        }
+    }
+}
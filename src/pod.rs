@@ -0,0 +1,245 @@
+//! Conversion between [`Value`]s and SPA POD, the binary format PipeWire
+//! uses for params on the wire.
+//!
+//! PODs are read and written in native byte order. The mapping is:
+//!
+//! | POD                      | Value                              |
+//! |--------------------------|------------------------------------|
+//! | None                     | `null`                             |
+//! | Bool                     | boolean                            |
+//! | Id, Int, Long            | integer                            |
+//! | Float, Double            | float                              |
+//! | String                   | string                             |
+//! | Bytes                    | array of byte values               |
+//! | Rectangle                | `{ width = … height = … }`         |
+//! | Fraction                 | `{ num = … denom = … }`            |
+//! | Array, Struct            | array                              |
+//!
+//! When encoding, integers become Int if they fit in 32 bits and Long
+//! otherwise, floats become Double and arrays become Structs. Objects,
+//! Choices, Sequences, Pointers and Fds are not supported.
+
+use crate::error::Error;
+use crate::value::{Map, Value};
+
+const NONE: u32 = 1;
+const BOOL: u32 = 2;
+const ID: u32 = 3;
+const INT: u32 = 4;
+const LONG: u32 = 5;
+const FLOAT: u32 = 6;
+const DOUBLE: u32 = 7;
+const STRING: u32 = 8;
+const BYTES: u32 = 9;
+const RECTANGLE: u32 = 10;
+const FRACTION: u32 = 11;
+const ARRAY: u32 = 13;
+const STRUCT: u32 = 14;
+
+/// Encodes `value` as a single POD.
+pub fn to_pod(value: &Value) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    write_pod(&mut out, value)?;
+    Ok(out)
+}
+
+/// Decodes the POD at the start of `bytes`.
+pub fn from_pod(bytes: &[u8]) -> Result<Value, Error> {
+    let (ty, body) = read_header(bytes)?;
+    read_body(ty, body)
+}
+
+fn error(message: impl Into<String>) -> Error {
+    Error::Message(message.into())
+}
+
+fn write_pod(out: &mut Vec<u8>, value: &Value) -> Result<(), Error> {
+    let start = out.len();
+    out.extend_from_slice(&[0; 8]);
+    let ty = match value {
+        Value::Null => NONE,
+        Value::Bool(b) => {
+            out.extend_from_slice(&i32::from(*b).to_ne_bytes());
+            BOOL
+        }
+        Value::Number(n) => match n.as_i64() {
+            Some(v) if !n.is_f64() => match i32::try_from(v) {
+                Ok(v) => {
+                    out.extend_from_slice(&v.to_ne_bytes());
+                    INT
+                }
+                Err(_) => {
+                    out.extend_from_slice(&v.to_ne_bytes());
+                    LONG
+                }
+            },
+            _ if !n.is_f64() => return Err(error(format!("integer {} does not fit a POD", n))),
+            _ => {
+                out.extend_from_slice(&n.as_f64().to_ne_bytes());
+                DOUBLE
+            }
+        },
+        Value::String(s) => {
+            if s.contains('\0') {
+                return Err(error("strings in a POD cannot contain NUL"));
+            }
+            out.extend_from_slice(s.as_bytes());
+            out.push(0);
+            STRING
+        }
+        Value::Array(a) => {
+            for v in a {
+                write_pod(out, v)?;
+            }
+            STRUCT
+        }
+        Value::Object(_) => return Err(error("objects cannot be converted to a POD")),
+    };
+    let size = u32::try_from(out.len() - start - 8).map_err(|_| error("POD too large"))?;
+    out[start..start + 4].copy_from_slice(&size.to_ne_bytes());
+    out[start + 4..start + 8].copy_from_slice(&ty.to_ne_bytes());
+    out.resize(out.len().next_multiple_of(8), 0);
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8]) -> Result<u32, Error> {
+    let bytes = bytes.get(..4).ok_or_else(|| error("truncated POD"))?;
+    Ok(u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+/// Splits off the header of the POD at the start of `bytes`, returning its
+/// type and body.
+fn read_header(bytes: &[u8]) -> Result<(u32, &[u8]), Error> {
+    let size = read_u32(bytes)? as usize;
+    let ty = read_u32(bytes.get(4..).unwrap_or_default())?;
+    let body = bytes
+        .get(8..)
+        .and_then(|b| b.get(..size))
+        .ok_or_else(|| error("truncated POD"))?;
+    Ok((ty, body))
+}
+
+fn read_body(ty: u32, body: &[u8]) -> Result<Value, Error> {
+    let fixed = |size: usize| {
+        body.get(..size)
+            .ok_or_else(|| error(format!("POD of type {} too small", ty)))
+    };
+    Ok(match ty {
+        NONE => Value::Null,
+        BOOL => Value::Bool(i32::from_ne_bytes(fixed(4)?.try_into().unwrap()) != 0),
+        ID => Value::from(u32::from_ne_bytes(fixed(4)?.try_into().unwrap())),
+        INT => Value::from(i32::from_ne_bytes(fixed(4)?.try_into().unwrap())),
+        LONG => Value::from(i64::from_ne_bytes(fixed(8)?.try_into().unwrap())),
+        FLOAT => Value::from(f32::from_ne_bytes(fixed(4)?.try_into().unwrap())),
+        DOUBLE => Value::from(f64::from_ne_bytes(fixed(8)?.try_into().unwrap())),
+        STRING => {
+            let s = body.split(|&b| b == 0).next().unwrap_or_default();
+            let s = std::str::from_utf8(s).map_err(|_| error("POD string is not UTF-8"))?;
+            Value::from(s)
+        }
+        BYTES => Value::Array(body.iter().map(|&b| Value::from(b)).collect()),
+        RECTANGLE => pair(fixed(8)?, "width", "height"),
+        FRACTION => pair(fixed(8)?, "num", "denom"),
+        ARRAY => {
+            let child_size = read_u32(body)? as usize;
+            let child_type = read_u32(body.get(4..).unwrap_or_default())?;
+            let items = &body[8.min(body.len())..];
+            if child_size == 0 {
+                Value::Array(Vec::new())
+            } else {
+                items
+                    .chunks_exact(child_size)
+                    .map(|item| read_body(child_type, item))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into()
+            }
+        }
+        STRUCT => {
+            let mut items = Vec::new();
+            let mut rest = body;
+            while !rest.is_empty() {
+                let (ty, body) = read_header(rest)?;
+                items.push(read_body(ty, body)?);
+                let len = (8 + body.len()).next_multiple_of(8);
+                rest = rest.get(len..).unwrap_or_default();
+            }
+            Value::Array(items)
+        }
+        _ => return Err(error(format!("unsupported POD type {}", ty))),
+    })
+}
+
+fn pair(body: &[u8], first: &str, second: &str) -> Value {
+    let a = u32::from_ne_bytes(body[..4].try_into().unwrap());
+    let b = u32::from_ne_bytes(body[4..8].try_into().unwrap());
+    let mut map = Map::with_capacity(2);
+    map.insert(first, Value::from(a));
+    map.insert(second, Value::from(b));
+    Value::Object(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pod(ty: u32, body: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&(body.len() as u32).to_ne_bytes());
+        out.extend_from_slice(&ty.to_ne_bytes());
+        out.extend_from_slice(body);
+        out.resize(out.len().next_multiple_of(8), 0);
+        out
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let value = Value::Array(vec![
+            Value::Null,
+            Value::from(true),
+            Value::from(48000),
+            Value::from(1i64 << 40),
+            Value::from(0.5),
+            Value::from("Horst Schlämmer"),
+            Value::Array(vec![Value::from(-1)]),
+        ]);
+        let bytes = to_pod(&value).unwrap();
+        assert_eq!(bytes.len() % 8, 0);
+        assert_eq!(&bytes[..8], &pod(STRUCT, &bytes[8..])[..8]);
+        assert_eq!(from_pod(&bytes).unwrap(), value);
+
+        assert_eq!(
+            to_pod(&Value::from("ab")).unwrap(),
+            pod(STRING, &[b'a', b'b', 0])
+        );
+        assert!(to_pod(&Value::Object(Map::new())).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let fraction = [1u32.to_ne_bytes(), 48000u32.to_ne_bytes()].concat();
+        assert_eq!(
+            from_pod(&pod(FRACTION, &fraction))
+                .unwrap()
+                .to_canonical_string(),
+            r#"{"denom":48000,"num":1}"#
+        );
+
+        let mut array = Vec::new();
+        array.extend_from_slice(&4u32.to_ne_bytes());
+        array.extend_from_slice(&ID.to_ne_bytes());
+        for id in [3u32, 7] {
+            array.extend_from_slice(&id.to_ne_bytes());
+        }
+        assert_eq!(
+            from_pod(&pod(ARRAY, &array)).unwrap(),
+            Value::Array(vec![Value::from(3), Value::from(7)])
+        );
+        assert_eq!(
+            from_pod(&pod(FLOAT, &0.25f32.to_ne_bytes())).unwrap(),
+            Value::from(0.25)
+        );
+
+        assert!(from_pod(&[8, 0, 0]).is_err());
+        assert!(from_pod(&pod(15, &[0; 8])).is_err());
+    }
+}
@@ -0,0 +1,46 @@
+//! The functions and types most programs need, to be imported at once:
+//!
+//! ```
+//! use spa_json::prelude::*;
+//!
+//! let rate: u32 = from_str("48000")?;
+//! assert_eq!(to_string(&rate)?, "48000");
+//! # Ok::<(), Error>(())
+//! ```
+
+#[cfg(feature = "ser")]
+pub use crate::comments::SpaJsonComments;
+#[cfg(feature = "de")]
+pub use crate::de::{from_str, from_str_with_options, ParseOptions};
+pub use crate::error::{Error, Result};
+#[cfg(feature = "de")]
+pub use crate::ext::FromSpaJson;
+#[cfg(feature = "ser")]
+pub use crate::ext::ToSpaJson;
+#[cfg(all(feature = "ser", feature = "std"))]
+pub use crate::ser::to_writer;
+#[cfg(feature = "ser")]
+pub use crate::ser::{to_string, to_string_with_options, SerializerOptions};
+#[cfg(feature = "value")]
+pub use crate::value::{Map, Value};
+
+#[cfg(all(test, feature = "value"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prelude() {
+        let value: Value = from_str("node.name = alsa_output").unwrap();
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "{\n  node.name = alsa_output\n}"
+        );
+        let result: Result<u32> = crate::from_str("rate");
+        assert!(matches!(result, Err(Error::Parse { .. })));
+        #[allow(deprecated)]
+        let rate: u32 = crate::spa_json_deserializer::from_str("48000").unwrap();
+        #[allow(deprecated)]
+        let text = crate::spa_json_serializer::to_string(&rate).unwrap();
+        assert_eq!(text, "48000");
+    }
+}
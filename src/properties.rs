@@ -0,0 +1,202 @@
+use crate::value::{Map, Value};
+use serde::de::{Deserialize, Deserializer};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use std::str::FromStr;
+
+/// Insertion-ordered string-to-string map, the way PipeWire stores object
+/// properties (`spa_dict`). Values are only given a type when they are read.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Properties {
+    entries: Vec<(String, String)>,
+}
+
+impl Properties {
+    pub fn new() -> Self {
+        Properties::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Reads a boolean like PipeWire does: `"true"` and `"1"` are true,
+    /// anything else is false.
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key).map(|v| v == "true" || v == "1")
+    }
+
+    pub fn get_u32(&self, key: &str) -> Option<u32> {
+        self.get_parsed(key)
+    }
+
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.get_parsed(key)
+    }
+
+    /// Parses the value of `key`, returning `None` if it is missing or does
+    /// not parse.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key).and_then(|v| v.trim().parse().ok())
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let key = key.into();
+        let value = value.into();
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, slot)) => Some(std::mem::replace(slot, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Sets every entry of `map` like `update-props` does: values are
+    /// converted to strings and `null` removes the property.
+    pub fn update(&mut self, map: &Map) {
+        for (key, value) in map.iter() {
+            if value.is_null() {
+                self.remove(key);
+            } else {
+                self.insert(key.as_str(), to_property(value));
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// Text of a property value: strings as they are, everything else in its
+/// canonical SPA-JSON form.
+fn to_property(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        v => v.to_canonical_string(),
+    }
+}
+
+/// Converts every entry to its string form; `null` entries are dropped.
+impl From<&Map> for Properties {
+    fn from(map: &Map) -> Self {
+        map.iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k.clone(), to_property(v)))
+            .collect()
+    }
+}
+
+impl From<Properties> for Map {
+    fn from(props: Properties) -> Self {
+        props
+            .entries
+            .into_iter()
+            .map(|(k, v)| (k, Value::from(v)))
+            .collect()
+    }
+}
+
+impl From<Properties> for Value {
+    fn from(props: Properties) -> Self {
+        Value::Object(props.into())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Properties {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut props = Properties::new();
+        for (k, v) in iter {
+            props.insert(k, v);
+        }
+        props
+    }
+}
+
+impl IntoIterator for Properties {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl Serialize for Properties {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Properties {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Properties, D::Error> {
+        Map::deserialize(deserializer).map(|map| Properties::from(&map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+
+    #[test]
+    fn test_properties() {
+        let props: Properties = from_str(
+            r#"
+            node.name = "alsa_output.pci"
+            node.pause-on-idle = false
+            priority.session = 1000
+            api.alsa.period-size = " 256 "
+            audio.position = [ FL FR ]
+            node.description = null
+            "#,
+        )
+        .unwrap();
+        assert_eq!(props.len(), 5);
+        assert_eq!(props.get("node.name"), Some("alsa_output.pci"));
+        assert_eq!(props.get_bool("node.pause-on-idle"), Some(false));
+        assert_eq!(props.get_u32("priority.session"), Some(1000));
+        assert_eq!(props.get_parsed::<u64>("api.alsa.period-size"), Some(256));
+        assert_eq!(props.get_i32("node.name"), None);
+        assert_eq!(props.get("audio.position"), Some(r#"["FL","FR"]"#));
+        assert!(!props.contains_key("node.description"));
+
+        let value = Value::from(props.clone());
+        assert_eq!(value.get("priority.session"), Some(&Value::from("1000")));
+        assert_eq!(Properties::from(value.as_object().unwrap()), props);
+
+        let mut updated = props.clone();
+        let update: Value = "node.name = renamed, priority.session = null, node.nick = [ 1 ]"
+            .parse()
+            .unwrap();
+        updated.update(update.as_object().unwrap());
+        assert_eq!(updated.get("node.name"), Some("renamed"));
+        assert!(!updated.contains_key("priority.session"));
+        assert_eq!(updated.get("node.nick"), Some("[1]"));
+        assert_eq!(updated.len(), 5);
+    }
+}
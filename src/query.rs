@@ -0,0 +1,663 @@
+//! JSONPath-style queries selecting parts of a document.
+//!
+//! A query starts at the root `$` and is followed by steps:
+//!
+//! - `.name` or `['name']` selects a key of an object, `[2]` an item of an
+//!   array, with `[-1]` counting from the end. Brackets may list several
+//!   keys or indices, as in `[0, 2]`.
+//! - `.*` or `[*]` selects all items or values.
+//! - `[1:3]` selects a slice of an array.
+//! - `..` before a step applies it to the value and all values below it.
+//! - `[?(expr)]` selects the items or values for which `expr` holds. In it,
+//!   `@` is the value being tested and `$` the root. Paths are compared
+//!   with `==`, `!=`, `<`, `<=`, `>` and `>=`, matched against regular
+//!   expressions with `=~`, and tested for existence on their own.
+//!   Conditions are combined with `&&`, `||`, `!` and parentheses.
+//!
+//! Since keys such as `context.modules` contain dots, names in dot notation
+//! also match keys made of several of them joined with dots.
+//!
+//! ```
+//! use spa_json::query::query;
+//! use spa_json::value::Value;
+//!
+//! let config: Value = r#"
+//!     context.modules = [
+//!         { name = libpipewire-module-rt, args = { nice.level = -11 } }
+//!         { name = libpipewire-module-loopback, args = { node.description = "Loopback" } }
+//!     ]
+//! "#
+//! .parse()
+//! .unwrap();
+//! let selected = query(
+//!     &config,
+//!     "$.context.modules[?(@.name == 'libpipewire-module-loopback')].args.node.description",
+//! )
+//! .unwrap();
+//! assert_eq!(selected, [&Value::from("Loopback")]);
+//! assert_eq!(query(&config, "$..nice.level").unwrap(), [&Value::from(-11)]);
+//! ```
+
+use crate::de::unescape_str;
+use crate::error::Error;
+use crate::path::{PathSegment, SpaPath};
+use crate::value::Value;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// A parsed query, which can be run against any number of documents.
+#[derive(Clone, Debug)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    /// Whether the selector applies to all values below as well, for `..`.
+    descendants: bool,
+    selector: Selector,
+}
+
+#[derive(Clone, Debug)]
+enum Selector {
+    /// Consecutive names in dot notation, which may match a single key
+    /// made of several of them.
+    Names(Vec<String>),
+    Wildcard,
+    /// Keys and indices in brackets, any of which may match.
+    Union(Vec<Member>),
+    Slice(Option<i64>, Option<i64>),
+    Filter(Box<Expr>),
+}
+
+#[derive(Clone, Debug)]
+enum Member {
+    Key(String),
+    Index(i64),
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(Operand),
+    Compare(Operand, Op, Operand),
+    Match(Operand, Regex),
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+    /// A path from the value being tested, `@`.
+    Current(Vec<Step>),
+    /// A path from the root, `$`.
+    Root(Vec<Step>),
+    Literal(Value),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Runs `query` against `value`, returning the selected values in
+/// document order.
+pub fn query<'v>(value: &'v Value, query: &str) -> Result<Vec<&'v Value>, Error> {
+    Ok(query.parse::<Query>()?.select(value))
+}
+
+impl Query {
+    pub fn select<'v>(&self, value: &'v Value) -> Vec<&'v Value> {
+        self.select_paths(value)
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    /// Like [`select`](Self::select), along with the path of each value.
+    pub fn select_paths<'v>(&self, value: &'v Value) -> Vec<(SpaPath, &'v Value)> {
+        run(&self.steps, value, value)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let mut parser = Parser { query: s, pos: 0 };
+        parser.skip_whitespace();
+        if !parser.eat("$") {
+            return Err(parser.error("expected `$`"));
+        }
+        let steps = parser.steps()?;
+        parser.skip_whitespace();
+        if parser.pos < s.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Query { steps })
+    }
+}
+
+fn run<'v>(steps: &[Step], start: &'v Value, root: &'v Value) -> Vec<(SpaPath, &'v Value)> {
+    let mut nodes = vec![(SpaPath::new(), start)];
+    for step in steps {
+        let mut next = Vec::new();
+        for (path, value) in nodes {
+            if step.descendants {
+                let mut all = Vec::new();
+                descendants(path, value, &mut all);
+                for (path, value) in all {
+                    apply(&step.selector, &path, value, root, &mut next);
+                }
+            } else {
+                apply(&step.selector, &path, value, root, &mut next);
+            }
+        }
+        nodes = next;
+    }
+    nodes
+}
+
+/// Collects `value` and everything below it, parents first.
+fn descendants<'v>(path: SpaPath, value: &'v Value, out: &mut Vec<(SpaPath, &'v Value)>) {
+    let children = children(value);
+    out.push((path.clone(), value));
+    for (segment, child) in children {
+        descendants(join(&path, segment), child, out);
+    }
+}
+
+fn children(value: &Value) -> Vec<(PathSegment, &Value)> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| (PathSegment::Key(key.to_string()), value))
+            .collect(),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (PathSegment::Index(i), item))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn join(path: &SpaPath, segment: PathSegment) -> SpaPath {
+    let mut path = path.clone();
+    path.push(segment);
+    path
+}
+
+fn apply<'v>(
+    selector: &Selector,
+    path: &SpaPath,
+    value: &'v Value,
+    root: &'v Value,
+    out: &mut Vec<(SpaPath, &'v Value)>,
+) {
+    match selector {
+        Selector::Names(names) => select_names(names, path.clone(), value, out),
+        Selector::Wildcard => {
+            for (segment, child) in children(value) {
+                out.push((join(path, segment), child));
+            }
+        }
+        Selector::Union(members) => {
+            for member in members {
+                match (member, value) {
+                    (Member::Key(key), Value::Object(map)) => {
+                        if let Some(child) = map.get(key) {
+                            out.push((join(path, PathSegment::Key(key.clone())), child));
+                        }
+                    }
+                    (Member::Index(i), Value::Array(items)) => {
+                        if let Some(i) = resolve(*i, items.len()).filter(|&i| i < items.len()) {
+                            out.push((join(path, PathSegment::Index(i)), &items[i]));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Selector::Slice(start, end) => {
+            if let Value::Array(items) = value {
+                let len = items.len();
+                let start = start.map_or(0, |i| resolve(i, len).unwrap_or(0).min(len));
+                let end = end.map_or(len, |i| resolve(i, len).unwrap_or(0).min(len));
+                for (i, item) in items.iter().enumerate().take(end).skip(start) {
+                    out.push((join(path, PathSegment::Index(i)), item));
+                }
+            }
+        }
+        Selector::Filter(expr) => {
+            for (segment, child) in children(value) {
+                if expr.test(child, root) {
+                    out.push((join(path, segment), child));
+                }
+            }
+        }
+    }
+}
+
+/// Selects the value at `names`, joining consecutive names into keys with
+/// dots wherever such keys exist.
+pub(crate) fn select_names<'v>(
+    names: &[String],
+    path: SpaPath,
+    value: &'v Value,
+    out: &mut Vec<(SpaPath, &'v Value)>,
+) {
+    if names.is_empty() {
+        out.push((path, value));
+        return;
+    }
+    let Value::Object(map) = value else {
+        return;
+    };
+    for n in 1..=names.len() {
+        let key = names[..n].join(".");
+        if let Some(child) = map.get(&key) {
+            select_names(&names[n..], join(&path, PathSegment::Key(key)), child, out);
+        }
+    }
+}
+
+/// Resolves an index that counts from the end if negative.
+fn resolve(index: i64, len: usize) -> Option<usize> {
+    if index < 0 {
+        len.checked_sub(usize::try_from(index.unsigned_abs()).ok()?)
+    } else {
+        usize::try_from(index).ok()
+    }
+}
+
+impl Expr {
+    fn test(&self, current: &Value, root: &Value) -> bool {
+        match self {
+            Expr::Or(a, b) => a.test(current, root) || b.test(current, root),
+            Expr::And(a, b) => a.test(current, root) && b.test(current, root),
+            Expr::Not(expr) => !expr.test(current, root),
+            Expr::Exists(operand) => operand.eval(current, root).is_some(),
+            Expr::Compare(a, op, b) => {
+                match (a.eval(current, root), b.eval(current, root)) {
+                    (Some(a), Some(b)) => compare(a, *op, b),
+                    // Only `!=` holds if either side selects nothing.
+                    _ => matches!(op, Op::Ne),
+                }
+            }
+            Expr::Match(operand, regex) => operand
+                .eval(current, root)
+                .and_then(Value::as_str)
+                .is_some_and(|s| regex.is_match(s)),
+        }
+    }
+}
+
+impl Operand {
+    fn eval<'v>(&'v self, current: &'v Value, root: &'v Value) -> Option<&'v Value> {
+        match self {
+            Operand::Current(steps) => run(steps, current, root).first().map(|(_, v)| *v),
+            Operand::Root(steps) => run(steps, root, root).first().map(|(_, v)| *v),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+fn compare(a: &Value, op: Op, b: &Value) -> bool {
+    let ordering = match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ if a == b => Some(Ordering::Equal),
+        _ => None,
+    };
+    match op {
+        Op::Eq => ordering == Some(Ordering::Equal),
+        Op::Ne => ordering != Some(Ordering::Equal),
+        Op::Lt => ordering == Some(Ordering::Less),
+        Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Op::Gt => ordering == Some(Ordering::Greater),
+        Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+struct Parser<'q> {
+    query: &'q str,
+    pos: usize,
+}
+
+/// Characters that end a name in dot notation.
+fn ends_name(c: char) -> bool {
+    c.is_whitespace() || ".[]()'\"=!<>&|,@$~".contains(c)
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Error {
+        Error::Message(format!(
+            "invalid query `{}`: {} at offset {}",
+            self.query, message, self.pos
+        ))
+    }
+
+    fn rest(&self) -> &str {
+        &self.query[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.query.len() - self.rest().trim_start().len();
+    }
+
+    /// Parses steps until something that cannot start one.
+    fn steps(&mut self) -> Result<Vec<Step>, Error> {
+        let mut steps = Vec::new();
+        loop {
+            let descendants = self.eat("..");
+            let selector = if self.peek() == Some('[') {
+                self.bracket()?
+            } else if descendants || self.eat(".") {
+                if self.eat("*") {
+                    Selector::Wildcard
+                } else {
+                    Selector::Names(self.names()?)
+                }
+            } else {
+                return Ok(steps);
+            };
+            steps.push(Step {
+                descendants,
+                selector,
+            });
+        }
+    }
+
+    /// Parses a name and the ones following it after single dots.
+    fn names(&mut self) -> Result<Vec<String>, Error> {
+        let mut names = vec![self.name()?];
+        while self.rest().starts_with('.')
+            && !self.rest().starts_with("..")
+            && !self.rest()[1..].starts_with(['*', '['])
+        {
+            self.pos += 1;
+            names.push(self.name()?);
+        }
+        Ok(names)
+    }
+
+    fn name(&mut self) -> Result<String, Error> {
+        let len = self.rest().find(ends_name).unwrap_or(self.rest().len());
+        if len == 0 {
+            return Err(self.error("expected a name"));
+        }
+        let name = self.rest()[..len].to_owned();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn bracket(&mut self) -> Result<Selector, Error> {
+        self.pos += 1;
+        self.skip_whitespace();
+        let selector = if self.eat("?") {
+            Selector::Filter(Box::new(self.or()?))
+        } else if self.eat("*") {
+            Selector::Wildcard
+        } else {
+            let mut members = Vec::new();
+            loop {
+                self.skip_whitespace();
+                if self.peek().is_some_and(|c| c == '\'' || c == '"') {
+                    members.push(Member::Key(self.string()?));
+                } else {
+                    let start = self.integer()?;
+                    self.skip_whitespace();
+                    if members.is_empty() && self.eat(":") {
+                        self.skip_whitespace();
+                        let end = self.integer()?;
+                        self.skip_whitespace();
+                        if !self.eat("]") {
+                            return Err(self.error("expected `]`"));
+                        }
+                        return Ok(Selector::Slice(start, end));
+                    }
+                    let index = start.ok_or_else(|| self.error("expected an index or key"))?;
+                    members.push(Member::Index(index));
+                }
+                self.skip_whitespace();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            Selector::Union(members)
+        };
+        self.skip_whitespace();
+        if !self.eat("]") {
+            return Err(self.error("expected `]`"));
+        }
+        Ok(selector)
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>, Error> {
+        let len = self
+            .rest()
+            .char_indices()
+            .find(|&(i, c)| !(c.is_ascii_digit() || i == 0 && c == '-'))
+            .map_or(self.rest().len(), |(i, _)| i);
+        if len == 0 {
+            return Ok(None);
+        }
+        let integer = self.rest()[..len]
+            .parse()
+            .map_err(|_| self.error("invalid index"))?;
+        self.pos += len;
+        Ok(Some(integer))
+    }
+
+    /// Parses a string in single or double quotes, with escapes as in
+    /// SPA-JSON strings.
+    fn string(&mut self) -> Result<String, Error> {
+        let quote = self.peek().unwrap();
+        let mut escaped = false;
+        let end = self.rest()[1..].char_indices().find_map(|(i, c)| {
+            if c == quote && !escaped {
+                return Some(i + 1);
+            }
+            escaped = c == '\\' && !escaped;
+            None
+        });
+        let end = end.ok_or_else(|| self.error("unfinished string"))?;
+        let string = unescape_str(&self.rest()[1..end])
+            .map_err(|_| self.error("invalid escape"))?
+            .into_owned();
+        self.pos += end + 1;
+        Ok(string)
+    }
+
+    fn or(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        self.skip_whitespace();
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.or()?;
+            self.skip_whitespace();
+            if !self.eat(")") {
+                return Err(self.error("expected `)`"));
+            }
+            return Ok(expr);
+        }
+        let left = self.operand()?;
+        self.skip_whitespace();
+        if self.eat("=~") {
+            self.skip_whitespace();
+            let pattern = match self.peek() {
+                Some('\'' | '"') => self.string()?,
+                _ => return Err(self.error("expected a pattern in quotes")),
+            };
+            let regex = Regex::new(&pattern).map_err(|e| self.error(&e.to_string()))?;
+            return Ok(Expr::Match(left, regex));
+        }
+        const OPS: [(&str, Op); 6] = [
+            ("==", Op::Eq),
+            ("!=", Op::Ne),
+            ("<=", Op::Le),
+            (">=", Op::Ge),
+            ("<", Op::Lt),
+            (">", Op::Gt),
+        ];
+        match OPS.iter().find(|(token, _)| self.rest().starts_with(token)) {
+            Some(&(token, op)) => {
+                self.pos += token.len();
+                self.skip_whitespace();
+                Ok(Expr::Compare(left, op, self.operand()?))
+            }
+            None if matches!(left, Operand::Literal(_)) => Err(self.error("expected a comparison")),
+            None => Ok(Expr::Exists(left)),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, Error> {
+        if self.eat("@") {
+            return Ok(Operand::Current(self.steps()?));
+        }
+        if self.eat("$") {
+            return Ok(Operand::Root(self.steps()?));
+        }
+        if self.peek().is_some_and(|c| c == '\'' || c == '"') {
+            return Ok(Operand::Literal(Value::from(self.string()?)));
+        }
+        let len = self.rest().find(ends_name).unwrap_or(self.rest().len());
+        match self.rest()[..len].parse::<Value>() {
+            Ok(value @ (Value::Number(_) | Value::Bool(_) | Value::Null)) if len > 0 => {
+                self.pos += len;
+                Ok(Operand::Literal(value))
+            }
+            _ => Err(self.error("expected a path or literal")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        context.properties = { default.clock.rate = 48000, log.level = 2 }
+        context.modules = [
+            { name = libpipewire-module-rt, args = { nice.level = -11, rt.prio = 88 } }
+            { name = libpipewire-module-protocol-native }
+            { name = libpipewire-module-loopback, args = { capture.props = { node.name = cap } } }
+        ]
+    "#;
+
+    fn names(query: &str) -> Vec<String> {
+        let config: Value = CONFIG.parse().unwrap();
+        query
+            .parse::<Query>()
+            .unwrap()
+            .select_paths(&config)
+            .into_iter()
+            .map(|(path, value)| format!("{} {}", path, value.to_canonical_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(
+            names("$.context.properties.*"),
+            [
+                "\"context.properties\".\"default.clock.rate\" 48000",
+                "\"context.properties\".\"log.level\" 2"
+            ]
+        );
+        assert_eq!(
+            names("$['context.modules'][-1].name"),
+            ["\"context.modules\"[2].name \"libpipewire-module-loopback\""]
+        );
+        assert_eq!(names("$.context.modules[0, 1].name").len(), 2);
+        assert_eq!(names("$.context.modules[1:].name").len(), 2);
+        assert_eq!(names("$.context.modules[:-2][*]").len(), 2);
+        assert_eq!(
+            names("$..node.name"),
+            ["\"context.modules\"[2].args.\"capture.props\".\"node.name\" \"cap\""]
+        );
+        assert_eq!(names("$..args..rt.prio").len(), 1);
+        assert_eq!(names("$.context.nothing"), Vec::<String>::new());
+        assert_eq!(names("$").len(), 1);
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(
+            names("$.context.modules[?(@.name == 'libpipewire-module-loopback')].name"),
+            ["\"context.modules\"[2].name \"libpipewire-module-loopback\""]
+        );
+        assert_eq!(names("$.context.modules[?(@.args)]").len(), 2);
+        assert_eq!(names("$.context.modules[?(!@.args)]").len(), 1);
+        assert_eq!(
+            names("$.context.modules[?(@.args.nice.level < -10 && @.args.rt.prio >= 88)]").len(),
+            1
+        );
+        assert_eq!(
+            names("$.context.modules[?(@.name =~ 'native$' || @.args.nice.level == -11)]").len(),
+            2
+        );
+        assert_eq!(
+            names("$.context.modules[?(@.name != \"libpipewire-module-rt\")]").len(),
+            2
+        );
+        assert_eq!(
+            names("$.context.properties[?(@ == $.context.properties.log.level)]"),
+            ["\"context.properties\".\"log.level\" 2"]
+        );
+
+        for invalid in [
+            "context",
+            "$.",
+            "$[",
+            "$[?(@.a ==)]",
+            "$[?(1)]",
+            "$.a b",
+            "$['a]",
+        ] {
+            assert!(invalid.parse::<Query>().is_err(), "{invalid}");
+        }
+    }
+}
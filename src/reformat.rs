@@ -0,0 +1,184 @@
+//! Formatting that only touches the lines that need it, so that reformatted
+//! hand-maintained configuration files give reviewable diffs.
+//!
+//! Each line is normalized on its own: it is indented by two spaces per
+//! level of nesting, tokens are separated by single spaces with `=` set off
+//! on both sides, and trailing whitespace is removed. Lines already in that
+//! form come out byte-identical, and comments, commas and blank lines are
+//! kept where they are.
+//!
+//! ```
+//! use spa_json::reformat::reformat;
+//!
+//! let input = "# rates\ncontext.properties = {\n  default.clock.rate = 48000\n      default.clock.quantum=1024  \n}\n";
+//! assert_eq!(
+//!     reformat(input).unwrap(),
+//!     "# rates\ncontext.properties = {\n  default.clock.rate = 48000\n  default.clock.quantum = 1024\n}\n"
+//! );
+//! ```
+
+use crate::error::Error;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::value::Value;
+
+/// Normalizes every line of `text` that is not formatted yet. The text
+/// must parse.
+pub fn reformat(text: &str) -> Result<String, Error> {
+    text.parse::<Value>()?;
+    let lines: Vec<&str> = text.split('\n').collect();
+    Ok(normalize(&lines, |_| true))
+}
+
+/// Normalizes only the lines of `new` that are not in `old`, the version
+/// before an edit, leaving the others as they are even if they are not
+/// formatted. `new` must parse.
+pub fn reformat_changes(old: &str, new: &str) -> Result<String, Error> {
+    new.parse::<Value>()?;
+    let old: Vec<&str> = old.split('\n').collect();
+    let lines: Vec<&str> = new.split('\n').collect();
+    let changed = changed_lines(&old, &lines);
+    Ok(normalize(&lines, |i| changed[i]))
+}
+
+/// Joins `lines` back together, normalizing those for which `select`
+/// returns true.
+fn normalize(lines: &[&str], select: impl Fn(usize) -> bool) -> String {
+    let mut out = String::new();
+    let mut depth = 0usize;
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let (line, cr) = match line.strip_suffix('\r') {
+            Some(line) => (line, "\r"),
+            None => (*line, ""),
+        };
+        let tokens: Vec<Token> = Lexer::new(line)
+            .filter(|token| token.kind != TokenKind::Whitespace)
+            .collect();
+        if select(i) {
+            let closing = tokens.first().is_some_and(|token| is_close(token.kind));
+            if !tokens.is_empty() {
+                let level = depth.saturating_sub(usize::from(closing));
+                out.push_str(&"  ".repeat(level));
+            }
+            write_tokens(&mut out, &tokens);
+        } else {
+            out.push_str(line);
+        }
+        out.push_str(cr);
+        for token in &tokens {
+            match token.kind {
+                TokenKind::ObjectStart | TokenKind::ArrayStart => depth += 1,
+                TokenKind::ObjectEnd | TokenKind::ArrayEnd => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+fn write_tokens(out: &mut String, tokens: &[Token]) {
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 {
+            let previous = tokens[i - 1].kind;
+            let space = match token.kind {
+                TokenKind::Comma => false,
+                TokenKind::Separator => token.text == "=",
+                kind if is_close(kind) => !is_open(previous),
+                _ => true,
+            };
+            if space {
+                out.push(' ');
+            }
+        }
+        out.push_str(token.text);
+    }
+}
+
+fn is_open(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::ObjectStart | TokenKind::ArrayStart)
+}
+
+fn is_close(kind: TokenKind) -> bool {
+    matches!(kind, TokenKind::ObjectEnd | TokenKind::ArrayEnd)
+}
+
+/// Marks the lines of `new` that are not part of a longest common
+/// subsequence with `old`.
+fn changed_lines(old: &[&str], new: &[&str]) -> Vec<bool> {
+    // Edits are usually small, so only the part between the common prefix
+    // and suffix is compared line by line.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // lengths[i][j] is the length of the longest common subsequence of
+    // a[i..] and b[j..].
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i * width + j] = if a[i] == b[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+    let mut changed = vec![false; new.len()];
+    let (mut i, mut j) = (0, 0);
+    while j < b.len() {
+        if i < a.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            changed[prefix + j] = true;
+            j += 1;
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reformat() {
+        let input = "context.modules = [\n\
+                     \t{ name = rt,args={ nice.level=-11 }}   # realtime\r\n\
+                     \n  \n\
+                     { name = \"protocol native\" , args = {} }\n\
+                       ]\n\
+                     \"a\": [ 1, 2 ]";
+        assert_eq!(
+            reformat(input).unwrap(),
+            "context.modules = [\n\
+             \x20 { name = rt, args = { nice.level = -11 } } # realtime\r\n\
+             \n\n\
+             \x20 { name = \"protocol native\", args = {} }\n\
+             ]\n\
+             \"a\": [ 1, 2 ]"
+        );
+        let formatted = reformat(input).unwrap();
+        assert_eq!(reformat(&formatted).unwrap(), formatted);
+        assert!(reformat("a = [").is_err());
+
+        let old = "a = {\n    b = 1\n}\nc  =  2\n";
+        let new = "a = {\n    b = 1\n   d=3\n}\nc  =  2\ne=4\n";
+        assert_eq!(
+            reformat_changes(old, new).unwrap(),
+            "a = {\n    b = 1\n  d = 3\n}\nc  =  2\ne = 4\n"
+        );
+        assert_eq!(reformat_changes(new, new).unwrap(), new);
+    }
+}
@@ -0,0 +1,215 @@
+//! Typed form of the WirePlumber rule lists:
+//!
+//! ```text
+//! monitor.alsa.rules = [
+//!   {
+//!     matches = [ { node.name = "~alsa_output.*" } ]
+//!     actions = { update-props = { node.nick = "Speakers" } }
+//!   }
+//! ]
+//! ```
+
+use crate::error::Error;
+use crate::matches::Match;
+use crate::properties::Properties;
+use crate::value::{Map, Value};
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// The rule applies if any of the objects matches; each object matches
+    /// if all of its properties do.
+    pub matches: Vec<Map>,
+    pub actions: Actions,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Actions {
+    /// Properties to set on matching objects, the `update-props` action.
+    pub update_props: Option<Map>,
+    /// Actions other than `update-props`, kept as written.
+    pub other: Map,
+}
+
+impl Rule {
+    pub fn new(matches: Vec<Map>, actions: Actions) -> Self {
+        Rule { matches, actions }
+    }
+
+    /// Applies the `update-props` action to `props` if the rule matches
+    /// them, returning whether it did.
+    pub fn apply(&self, props: &mut Properties) -> Result<bool, Error> {
+        if !Match::new(&self.matches)?.matches(props) {
+            return Ok(false);
+        }
+        if let Some(update) = &self.actions.update_props {
+            props.update(update);
+        }
+        Ok(true)
+    }
+}
+
+/// Runs a rule list on the properties of an object the way WirePlumber
+/// does: the rules are tried in order, each against the properties as
+/// updated by the ones before it, and every rule that matches applies its
+/// `update-props` action. Actions other than `update-props` are ignored.
+///
+/// Returns how many rules matched.
+pub fn apply_rules(rules: &Value, props: &mut Properties) -> Result<usize, Error> {
+    let invalid = |message: &str| Error::Message(message.to_owned());
+    let rules = rules
+        .as_array()
+        .ok_or_else(|| invalid("rules must be an array"))?;
+    let mut applied = 0;
+    for rule in rules {
+        let matches = rule
+            .get("matches")
+            .ok_or_else(|| invalid("rule without matches"))?;
+        if !Match::try_from(matches)?.matches(props) {
+            continue;
+        }
+        let update = rule.get("actions").and_then(|a| a.get("update-props"));
+        match update {
+            None => {}
+            Some(Value::Object(update)) => props.update(update),
+            Some(_) => return Err(invalid("update-props must be an object")),
+        }
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+impl Actions {
+    pub fn update_props(props: Map) -> Self {
+        Actions {
+            update_props: Some(props),
+            other: Map::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.update_props.is_none() && self.other.is_empty()
+    }
+}
+
+impl Serialize for Actions {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = self.other.len() + usize::from(self.update_props.is_some());
+        let mut map = serializer.serialize_map(Some(len))?;
+        if let Some(props) = &self.update_props {
+            map.serialize_entry("update-props", props)?;
+        }
+        for (k, v) in self.other.iter() {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Actions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Actions, D::Error> {
+        let mut other = Map::deserialize(deserializer)?;
+        let update_props = match &mut other.remove("update-props") {
+            None => None,
+            Some(Value::Object(props)) => Some(std::mem::take(props)),
+            Some(v) => {
+                return Err(de::Error::invalid_type(
+                    de::Unexpected::Other(v.type_name()),
+                    &"an object of properties",
+                ))
+            }
+        };
+        Ok(Actions {
+            update_props,
+            other,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_str;
+    use crate::ser::to_string;
+
+    #[test]
+    fn test_rules() {
+        let input = r#"[
+            {
+                matches = [
+                    { node.name = "~alsa_output.*" }
+                    { device.api = bluez5, media.class = Audio/Sink }
+                ]
+                actions = {
+                    update-props = { node.nick = Speakers, priority.session = 1000 }
+                    create-filter = { name = eq }
+                }
+            }
+        ]"#;
+        let rules: Vec<Rule> = from_str(input).unwrap();
+        assert_eq!(rules.len(), 1);
+        let rule = &rules[0];
+        assert_eq!(rule.matches.len(), 2);
+        assert_eq!(
+            rule.matches[1].get("media.class"),
+            Some(&Value::from("Audio/Sink"))
+        );
+        let props = rule.actions.update_props.as_ref().unwrap();
+        assert_eq!(props.get("priority.session"), Some(&Value::from(1000)));
+        assert!(rule.actions.other.contains_key("create-filter"));
+
+        let reparsed: Vec<Rule> = from_str(&to_string(&rules).unwrap()).unwrap();
+        assert_eq!(reparsed, rules);
+
+        assert!(from_str::<Rule>("matches = [] actions = { update-props = 1 }").is_err());
+    }
+
+    #[test]
+    fn test_apply_rules() {
+        let rules: Value = r#"[
+            {
+                matches = [ { node.name = "~alsa_output.*" } ]
+                actions = { update-props = { node.nick = Speakers, node.pause-on-idle = false } }
+            }
+            {
+                matches = [ { node.nick = Speakers } ]
+                actions = { update-props = { priority.session = 2000, node.description = null } }
+            }
+            {
+                matches = [ { device.api = bluez5 } ]
+                actions = { update-props = { node.nick = Headset } }
+            }
+        ]"#
+        .parse()
+        .unwrap();
+        let mut props: Properties = [
+            ("node.name", "alsa_output.pci-0000_00_1f.3.analog-stereo"),
+            ("node.description", "Built-in Audio"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(apply_rules(&rules, &mut props).unwrap(), 2);
+        assert_eq!(props.get("node.nick"), Some("Speakers"));
+        assert_eq!(props.get_bool("node.pause-on-idle"), Some(false));
+        assert_eq!(props.get_u32("priority.session"), Some(2000));
+        assert!(!props.contains_key("node.description"));
+
+        let typed: Vec<Rule> = from_str(&rules.to_canonical_string()).unwrap();
+        let mut headset: Properties = [("device.api", "bluez5")].into_iter().collect();
+        let applied: Vec<bool> = typed
+            .iter()
+            .map(|rule| rule.apply(&mut headset).unwrap())
+            .collect();
+        assert_eq!(applied, [false, false, true]);
+        assert_eq!(headset.get("node.nick"), Some("Headset"));
+
+        assert!(apply_rules(&Value::from(1), &mut props).is_err());
+        let bad: Value = "[ { matches = [ { a = b } ], actions = { update-props = 1 } } ]"
+            .parse()
+            .unwrap();
+        let mut matching: Properties = [("a", "b")].into_iter().collect();
+        assert!(apply_rules(&bad, &mut matching).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Finding the bytes that end strings, comments and bare scalars several
+//! bytes at a time, so that long values in large documents such as dumps
+//! are not looked at one byte after the other.
+//!
+//! Line breaks are found with `memchr`. Strings and bare scalars end at any
+//! of several bytes, which are tested for eight bytes at a time in a `u64`
+//! with the bit tricks of "Bit Twiddling Hacks". These can flag bytes after
+//! the first match but never before it, so the lowest flagged byte of a
+//! word read in little-endian order is the first match.
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Flags the bytes of `word` less than `n`, which is at most 128.
+fn less_than(word: u64, n: u8) -> u64 {
+    word.wrapping_sub(ONES * n as u64) & !word & HIGH_BITS
+}
+
+/// Flags the bytes of `word` equal to `byte`.
+fn equal(word: u64, byte: u8) -> u64 {
+    less_than(word ^ (ONES * byte as u64), 1)
+}
+
+/// The length of the run at the start of `bytes` up to the first byte
+/// flagged by `flags`, which `stops` tells for a single byte.
+fn run(bytes: &[u8], flags: impl Fn(u64) -> u64, stops: impl Fn(u8) -> bool) -> usize {
+    let mut chunks = bytes.chunks_exact(8);
+    let mut len = 0;
+    for chunk in &mut chunks {
+        let word = u64::from_le_bytes(chunk.try_into().unwrap());
+        let mask = flags(word);
+        if mask != 0 {
+            return len + mask.trailing_zeros() as usize / 8;
+        }
+        len += 8;
+    }
+    let rest = chunks.remainder();
+    len + rest.iter().position(|&c| stops(c)).unwrap_or(rest.len())
+}
+
+/// The length of the text before the next line break.
+pub(crate) fn line_len(bytes: &[u8]) -> usize {
+    memchr::memchr(b'\n', bytes).unwrap_or(bytes.len())
+}
+
+/// The length of the part of a string before its closing `quote`, a
+/// backslash or a control character. Without a quote, the string runs to
+/// the end of the input.
+pub(crate) fn string_len(bytes: &[u8], quote: Option<u8>) -> usize {
+    // Looking for a second backslash instead of no quote finds nothing more.
+    let quote = quote.unwrap_or(b'\\');
+    run(
+        bytes,
+        |word| equal(word, quote) | equal(word, b'\\') | less_than(word, 0x20),
+        |c| c == quote || c == b'\\' || c < 0x20,
+    )
+}
+
+/// The length of the printable ASCII at the start of a bare scalar, which
+/// ends at a delimiter or at a character that is not allowed in it.
+pub(crate) fn bare_len(bytes: &[u8]) -> usize {
+    run(
+        bytes,
+        |word| {
+            // Spaces, tabs, line breaks and other control characters.
+            less_than(word, 0x21)
+                | word & HIGH_BITS
+                | equal(word, 0x7f)
+                | equal(word, b'"')
+                | equal(word, b'#')
+                | equal(word, b',')
+                | equal(word, b':')
+                | equal(word, b'=')
+                | equal(word, b'\\')
+                | equal(word, b']')
+                | equal(word, b'}')
+        },
+        |c| !(0x21..0x7f).contains(&c) || b"\"#,:=\\]}".contains(&c),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let stops = b"\0\t\n\r \"#',:=\\]}\x7f\x80\xff".as_slice();
+        // Every stop at every offset of a word, after plain text and
+        // followed by bytes that stop too, checked against the byte by
+        // byte definition.
+        for &stop in stops {
+            for len in 0..20 {
+                let mut bytes = b"node.rate-48000/abcdefgh"[..len].to_vec();
+                bytes.push(stop);
+                bytes.extend_from_slice(stops);
+                let plain = |c: u8| (0x21..0x7f).contains(&c) && !b"\"#,:=\\]}".contains(&c);
+                let bare = bytes.iter().position(|&c| !plain(c)).unwrap();
+                assert_eq!(bare_len(&bytes), bare, "{bytes:?}");
+                for quote in [Some(b'"'), Some(b'\''), None] {
+                    let string = bytes
+                        .iter()
+                        .position(|&c| Some(c) == quote || c == b'\\' || c < 0x20)
+                        .unwrap();
+                    assert_eq!(string_len(&bytes, quote), string, "{bytes:?}");
+                }
+            }
+        }
+        assert_eq!(bare_len(b"unterminated.scalar.at.the.end"), 30);
+        assert_eq!(string_len("ünïcödé ünïcödé\"".as_bytes(), Some(b'"')), 23);
+        assert_eq!(line_len(b"# comment\nrate = 1"), 9);
+        assert_eq!(line_len(b"# comment"), 9);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::path::{DisplayPath, PathSegment};
+use crate::trace;
+use crate::value::Value;
+use std::fmt;
+
+#[cfg(feature = "schemars")]
+mod json_schema;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    Any,
+    Bool,
+    Integer(Range),
+    /// Any number; integers are accepted where a float is expected.
+    Float(Range),
+    String,
+    /// One of a fixed set of values.
+    Enum(Vec<Value>),
+    Array(Box<Schema>),
+    Object(ObjectSchema),
+    /// Valid if at least one alternative accepts the value.
+    OneOf(Vec<Schema>),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Range {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectSchema {
+    pub properties: Vec<Property>,
+    /// Schema for keys not listed in `properties`; `None` rejects them.
+    pub additional: Option<Box<Schema>>,
+    /// Whether strings are read as SPA-JSON where their property expects
+    /// something else, as PipeWire does with properties, so that `"48000"`
+    /// is an integer and `"true"` a boolean.
+    pub parse_strings: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub schema: Schema,
+    pub required: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub path: Vec<PathSegment>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", DisplayPath(&self.path), self.message)
+    }
+}
+
+impl Schema {
+    pub fn integer() -> Schema {
+        Schema::Integer(Range::default())
+    }
+
+    pub fn integer_range(min: i64, max: i64) -> Schema {
+        Schema::Integer(Range {
+            min: Some(min as f64),
+            max: Some(max as f64),
+        })
+    }
+
+    pub fn float() -> Schema {
+        Schema::Float(Range::default())
+    }
+
+    pub fn float_range(min: f64, max: f64) -> Schema {
+        Schema::Float(Range {
+            min: Some(min),
+            max: Some(max),
+        })
+    }
+
+    pub fn enumeration<I, V>(values: I) -> Schema
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        Schema::Enum(values.into_iter().map(Into::into).collect())
+    }
+
+    pub fn array(items: Schema) -> Schema {
+        Schema::Array(Box::new(items))
+    }
+
+    pub fn validate(&self, value: &Value) -> Vec<Diagnostic> {
+        let _phase = trace::phase!("validate");
+        let mut diagnostics = Vec::new();
+        self.validate_into(&mut Vec::new(), value, &mut diagnostics);
+        trace::event!(diagnostics = diagnostics.len(), "validated");
+        diagnostics
+    }
+
+    fn validate_into(
+        &self,
+        path: &mut Vec<PathSegment>,
+        value: &Value,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let mut report = |message: String| {
+            diagnostics.push(Diagnostic {
+                path: path.clone(),
+                message,
+            })
+        };
+        match (self, value) {
+            (Schema::Any, _)
+            | (Schema::Bool, Value::Bool(_))
+            | (Schema::String, Value::String(_)) => {}
+            (Schema::Integer(range), Value::Number(n)) if !n.is_f64() => {
+                range.check(n.as_f64(), &mut report)
+            }
+            (Schema::Float(range), Value::Number(n)) => range.check(n.as_f64(), &mut report),
+            (Schema::Enum(allowed), _) => {
+                if !allowed.contains(value) {
+                    let allowed: Vec<String> =
+                        allowed.iter().map(|v| v.to_canonical_string()).collect();
+                    report(format!(
+                        "invalid value {}, expected one of {}",
+                        value.to_canonical_string(),
+                        allowed.join(", ")
+                    ));
+                }
+            }
+            (Schema::Array(items), Value::Array(array)) => {
+                for (index, item) in array.iter().enumerate() {
+                    path.push(PathSegment::Index(index));
+                    items.validate_into(path, item, diagnostics);
+                    path.pop();
+                }
+            }
+            (Schema::Object(object), Value::Object(map)) => {
+                for property in object.properties.iter().filter(|p| p.required) {
+                    if !map.contains_key(&property.name) {
+                        report(format!("missing required key {:?}", property.name));
+                    }
+                }
+                for (key, item) in map.iter() {
+                    path.push(PathSegment::Key(key.to_string()));
+                    let schema = match object.get(key) {
+                        Some(property) => Some(&property.schema),
+                        None => object.additional.as_deref(),
+                    };
+                    match schema {
+                        Some(schema) if object.parse_strings => {
+                            schema.validate_into(path, &coerce(schema, item), diagnostics)
+                        }
+                        Some(schema) => schema.validate_into(path, item, diagnostics),
+                        None => diagnostics.push(Diagnostic {
+                            path: path.clone(),
+                            message: "unknown key".to_owned(),
+                        }),
+                    }
+                    path.pop();
+                }
+            }
+            (Schema::OneOf(alternatives), _) => {
+                if !alternatives.iter().any(|s| s.validate(value).is_empty()) {
+                    report(format!("expected {}, found {}", self, value.type_name()));
+                }
+            }
+            _ => report(format!("expected {}, found {}", self, value.type_name())),
+        }
+    }
+}
+
+/// Parses `value` if it is a string and `schema` expects something else.
+pub(crate) fn coerce(schema: &Schema, value: &Value) -> Value {
+    match (schema, value) {
+        (Schema::String | Schema::Any, _) => value.clone(),
+        (_, Value::String(s)) => s.parse().unwrap_or_else(|_| value.clone()),
+        _ => value.clone(),
+    }
+}
+
+impl Range {
+    fn check(&self, v: f64, report: &mut impl FnMut(String)) {
+        let below = self.min.is_some_and(|min| v < min);
+        let above = self.max.is_some_and(|max| v > max);
+        if below || above {
+            let bound = |b: Option<f64>| b.map_or("..".to_owned(), |b| b.to_string());
+            report(format!(
+                "value {} out of range [{}, {}]",
+                v,
+                bound(self.min),
+                bound(self.max)
+            ));
+        }
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Schema::Any => f.write_str("any value"),
+            Schema::Bool => f.write_str("boolean"),
+            Schema::Integer(_) => f.write_str("integer"),
+            Schema::Float(_) => f.write_str("number"),
+            Schema::String => f.write_str("string"),
+            Schema::Enum(_) => f.write_str("enumeration"),
+            Schema::Array(_) => f.write_str("array"),
+            Schema::Object(_) => f.write_str("object"),
+            Schema::OneOf(alternatives) => {
+                for (i, s) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" or ")?;
+                    }
+                    write!(f, "{}", s)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl ObjectSchema {
+    /// An object schema without properties that accepts any additional key.
+    pub fn new() -> Self {
+        ObjectSchema {
+            properties: Vec::new(),
+            additional: Some(Box::new(Schema::Any)),
+            parse_strings: false,
+        }
+    }
+
+    pub fn property(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        self.properties.push(Property {
+            name: name.into(),
+            schema,
+            required: false,
+        });
+        self
+    }
+
+    pub fn required(mut self, name: impl Into<String>, schema: Schema) -> Self {
+        self.properties.push(Property {
+            name: name.into(),
+            schema,
+            required: true,
+        });
+        self
+    }
+
+    pub fn additional(mut self, schema: Schema) -> Self {
+        self.additional = Some(Box::new(schema));
+        self
+    }
+
+    pub fn deny_unknown(mut self) -> Self {
+        self.additional = None;
+        self
+    }
+
+    /// Reads strings as the type their property expects, for objects of
+    /// PipeWire properties.
+    pub fn parse_strings(mut self) -> Self {
+        self.parse_strings = true;
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+}
+
+impl Default for ObjectSchema {
+    fn default() -> Self {
+        ObjectSchema::new()
+    }
+}
+
+impl From<ObjectSchema> for Schema {
+    fn from(object: ObjectSchema) -> Self {
+        Schema::Object(object)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Map;
+
+    #[test]
+    fn test_validate() {
+        let schema: Schema = ObjectSchema::new()
+            .required("default.clock.rate", Schema::integer_range(8000, 768000))
+            .property("log.level", Schema::enumeration(0..=5))
+            .property("modules", Schema::array(Schema::String))
+            .deny_unknown()
+            .into();
+
+        let ok = Value::from(Map::from_iter([
+            ("default.clock.rate", Value::from(48000)),
+            ("modules", Value::Array(vec![Value::from("a")])),
+        ]));
+        assert!(schema.validate(&ok).is_empty());
+
+        let bad = Value::from(Map::from_iter([
+            ("log.level", Value::from(9)),
+            ("modules", Value::Array(vec![Value::from(1)])),
+            ("mem.mlock", Value::from(true)),
+        ]));
+        let rendered: Vec<String> = schema
+            .validate(&bad)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "(root): missing required key \"default.clock.rate\"",
+                "\"log.level\": invalid value 9, expected one of 0, 1, 2, 3, 4, 5",
+                "modules[0]: expected string, found integer",
+                "\"mem.mlock\": unknown key",
+            ]
+        );
+
+        let rate = Value::from(Map::from_iter([(
+            "default.clock.rate",
+            Value::from("48000hz"),
+        )]));
+        assert_eq!(
+            schema.validate(&rate)[0].to_string(),
+            "\"default.clock.rate\": expected integer, found string"
+        );
+        let rate = Value::from(Map::from_iter([("default.clock.rate", Value::from(100))]));
+        assert_eq!(
+            schema.validate(&rate)[0].message,
+            "value 100 out of range [8000, 768000]"
+        );
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        let props = ObjectSchema::new()
+            .property("default.clock.rate", Schema::integer_range(8000, 768000))
+            .property("mem.warn-mlock", Schema::Bool)
+            .property("core.name", Schema::String);
+        let config: Value = r#"
+            default.clock.rate = "48000"
+            mem.warn-mlock = "true"
+            core.name = "1"
+        "#
+        .parse()
+        .unwrap();
+        let rendered: Vec<String> = Schema::from(props.clone())
+            .validate(&config)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "\"default.clock.rate\": expected integer, found string",
+                "\"mem.warn-mlock\": expected boolean, found string",
+            ]
+        );
+        let schema = Schema::from(props.parse_strings());
+        assert!(schema.validate(&config).is_empty());
+        let rate: Value = "default.clock.rate = \"100\"".parse().unwrap();
+        assert_eq!(
+            schema.validate(&rate)[0].message,
+            "value 100 out of range [8000, 768000]"
+        );
+    }
+
+    #[test]
+    fn test_one_of() {
+        let schema = Schema::OneOf(vec![Schema::integer(), ObjectSchema::new().into()]);
+        assert!(schema.validate(&Value::from(1)).is_empty());
+        assert!(schema.validate(&Value::from(Map::new())).is_empty());
+        assert_eq!(
+            schema.validate(&Value::from(1.5))[0].message,
+            "expected integer or object, found float"
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Conversion of the JSON Schemas generated by
+//! [`schemars`](https://docs.rs/schemars) into [`Schema`]s, so that the
+//! types a program reads its configuration into also drive validation and
+//! completion.
+
+use super::{ObjectSchema, Property, Range, Schema};
+use crate::value::Value;
+use serde::Deserialize;
+use serde_json::{Map as JsonMap, Value as Json};
+
+impl Schema {
+    /// The schema of the documents `T` deserializes from.
+    ///
+    /// ```
+    /// use schemars::JsonSchema;
+    /// use spa_json::schema::Schema;
+    ///
+    /// #[derive(JsonSchema)]
+    /// struct Props {
+    ///     #[serde(rename = "default.clock.rate")]
+    ///     rate: u32,
+    /// }
+    ///
+    /// let schema = Schema::for_type::<Props>();
+    /// let diagnostics = schema.validate(&"default.clock.rate = -1".parse().unwrap());
+    /// assert_eq!(diagnostics[0].to_string(), "\"default.clock.rate\": value -1 out of range [0, ..]");
+    /// ```
+    pub fn for_type<T: schemars::JsonSchema>() -> Schema {
+        Schema::from_json_schema(&schemars::schema_for!(T))
+    }
+
+    /// Converts a JSON Schema. References are resolved within `schema`,
+    /// and recursive ones accept any value. Keywords without an equivalent,
+    /// such as `pattern` or `exclusiveMinimum`, are ignored.
+    pub fn from_json_schema(schema: &schemars::Schema) -> Schema {
+        let root = schema.as_value();
+        Converter {
+            root,
+            expanding: Vec::new(),
+        }
+        .convert(root)
+    }
+}
+
+struct Converter<'a> {
+    root: &'a Json,
+    /// The references being converted, to stop at recursive ones.
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> Converter<'a> {
+    fn convert(&mut self, schema: &'a Json) -> Schema {
+        let object = match schema {
+            Json::Object(object) => object,
+            Json::Bool(false) => return Schema::OneOf(Vec::new()),
+            _ => return Schema::Any,
+        };
+        if let Some(reference) = object.get("$ref").and_then(Json::as_str) {
+            return self.reference(reference);
+        }
+        if let Some(values) = object.get("enum").and_then(Json::as_array) {
+            return Schema::Enum(values.iter().filter_map(value).collect());
+        }
+        if let Some(constant) = object.get("const") {
+            return Schema::Enum(value(constant).into_iter().collect());
+        }
+        for keyword in ["oneOf", "anyOf"] {
+            if let Some(alternatives) = object.get(keyword).and_then(Json::as_array) {
+                return Schema::OneOf(alternatives.iter().map(|s| self.convert(s)).collect());
+            }
+        }
+        if let Some([only]) = object
+            .get("allOf")
+            .and_then(Json::as_array)
+            .map(Vec::as_slice)
+        {
+            return self.convert(only);
+        }
+        match object.get("type") {
+            Some(Json::String(name)) => self.typed(name, object),
+            Some(Json::Array(names)) => Schema::OneOf(
+                names
+                    .iter()
+                    .filter_map(Json::as_str)
+                    .map(|name| self.typed(name, object))
+                    .collect(),
+            ),
+            _ => Schema::Any,
+        }
+    }
+
+    /// Converts a schema with the type `name`.
+    fn typed(&mut self, name: &str, object: &'a JsonMap<String, Json>) -> Schema {
+        match name {
+            "null" => Schema::Enum(vec![Value::Null]),
+            "boolean" => Schema::Bool,
+            "integer" => Schema::Integer(range(object)),
+            "number" => Schema::Float(range(object)),
+            "string" => Schema::String,
+            "array" => Schema::array(match object.get("items") {
+                Some(items) => self.convert(items),
+                None => Schema::Any,
+            }),
+            "object" => Schema::Object(self.object(object)),
+            _ => Schema::Any,
+        }
+    }
+
+    fn object(&mut self, object: &'a JsonMap<String, Json>) -> ObjectSchema {
+        let required: Vec<&str> = object
+            .get("required")
+            .and_then(Json::as_array)
+            .map_or(Vec::new(), |names| {
+                names.iter().filter_map(Json::as_str).collect()
+            });
+        let properties = object
+            .get("properties")
+            .and_then(Json::as_object)
+            .into_iter()
+            .flatten()
+            .map(|(name, schema)| Property {
+                name: name.clone(),
+                schema: self.convert(schema),
+                required: required.contains(&name.as_str()),
+            })
+            .collect();
+        let additional = match object.get("additionalProperties") {
+            None => Some(Box::new(Schema::Any)),
+            Some(Json::Bool(false)) => None,
+            Some(schema) => Some(Box::new(self.convert(schema))),
+        };
+        ObjectSchema {
+            properties,
+            additional,
+            parse_strings: false,
+        }
+    }
+
+    /// Converts the schema at `reference`, a JSON pointer into the root
+    /// such as `#/$defs/Module`.
+    fn reference(&mut self, reference: &'a str) -> Schema {
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| self.root.pointer(pointer));
+        match target {
+            Some(target) if !self.expanding.contains(&reference) => {
+                self.expanding.push(reference);
+                let schema = self.convert(target);
+                self.expanding.pop();
+                schema
+            }
+            _ => Schema::Any,
+        }
+    }
+}
+
+fn range(object: &JsonMap<String, Json>) -> Range {
+    Range {
+        min: object.get("minimum").and_then(Json::as_f64),
+        max: object.get("maximum").and_then(Json::as_f64),
+    }
+}
+
+fn value(json: &Json) -> Option<Value> {
+    Value::deserialize(json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schemars::JsonSchema;
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    #[serde(deny_unknown_fields)]
+    struct Props {
+        #[serde(rename = "default.clock.rate")]
+        rate: u32,
+        #[serde(rename = "log.level")]
+        level: Option<u8>,
+        modules: Vec<Module>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Module {
+        name: String,
+        flags: Option<Vec<Flag>>,
+        /// Recursive, so accepts anything.
+        children: Vec<Module>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    #[serde(rename_all = "lowercase")]
+    enum Flag {
+        IfExists,
+        NoFail,
+    }
+
+    #[test]
+    fn test_for_type() {
+        let schema = Schema::for_type::<Props>();
+        let Schema::Object(props) = &schema else {
+            panic!("{:?}", schema);
+        };
+        assert_eq!(props.additional, None);
+        assert_eq!(
+            props.get("log.level").unwrap(),
+            &Property {
+                name: "log.level".to_owned(),
+                schema: Schema::OneOf(vec![
+                    Schema::integer_range(0, 255),
+                    Schema::Enum(vec![Value::Null])
+                ]),
+                required: false,
+            }
+        );
+
+        let input = "default.clock.rate = 48000, log.level = null, \
+                     modules = [ { name = a, flags = [ ifexists ], children = [ { x = 1 } ] } ]";
+        assert_eq!(schema.validate(&input.parse().unwrap()), []);
+        let input =
+            "log.level = 256, modules = [ { name = 1, flags = [ x ], children = [ ] } ], a = 1";
+        let rendered: Vec<String> = schema
+            .validate(&input.parse().unwrap())
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "(root): missing required key \"default.clock.rate\"",
+                "\"log.level\": expected integer or enumeration, found integer",
+                "modules[0].name: expected string, found integer",
+                "modules[0].flags: expected array or enumeration, found array",
+                "a: unknown key",
+            ]
+        );
+    }
+}
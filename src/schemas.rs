@@ -0,0 +1,395 @@
+//! Schemas for the configuration files shipped with PipeWire and WirePlumber.
+//!
+//! Only the well-known keys are described; unknown sections and properties
+//! are accepted so that custom modules and fragments still validate.
+
+use crate::schema::{ObjectSchema, Schema};
+
+pub const NAMES: &[&str] = &["pipewire", "client", "pipewire-pulse", "wireplumber"];
+
+/// Looks up a bundled schema by name, with or without the `.conf` suffix.
+pub fn by_name(name: &str) -> Option<Schema> {
+    match name.strip_suffix(".conf").unwrap_or(name) {
+        "pipewire" => Some(pipewire()),
+        "client" => Some(client()),
+        "pipewire-pulse" => Some(pipewire_pulse()),
+        "wireplumber" => Some(wireplumber()),
+        _ => None,
+    }
+}
+
+/// `pipewire.conf`, the daemon configuration.
+pub fn pipewire() -> Schema {
+    ObjectSchema::new()
+        .property("context.properties", context_properties())
+        .property("context.spa-libs", spa_libs())
+        .property("context.modules", Schema::array(module()))
+        .property("context.objects", Schema::array(object()))
+        .property("context.exec", Schema::array(exec()))
+        .into()
+}
+
+/// `client.conf`, used by native PipeWire clients.
+pub fn client() -> Schema {
+    ObjectSchema::new()
+        .property("context.properties", context_properties())
+        .property("context.spa-libs", spa_libs())
+        .property("context.modules", Schema::array(module()))
+        .property("filter.properties", ObjectSchema::new().into())
+        .property("stream.properties", stream_properties())
+        .property("stream.rules", rules())
+        .into()
+}
+
+/// `pipewire-pulse.conf`, the PulseAudio compatibility server.
+pub fn pipewire_pulse() -> Schema {
+    let pulse_properties = ObjectSchema::new()
+        .property(
+            "server.address",
+            Schema::array(Schema::OneOf(vec![
+                Schema::String,
+                ObjectSchema::new().into(),
+            ])),
+        )
+        .property("server.dbus-name", Schema::String)
+        .property("pulse.min.req", Schema::String)
+        .property("pulse.default.req", Schema::String)
+        .property("pulse.min.frag", Schema::String)
+        .property("pulse.default.frag", Schema::String)
+        .property("pulse.default.tlength", Schema::String)
+        .property("pulse.min.quantum", Schema::String)
+        .property("pulse.default.format", Schema::String)
+        .property("pulse.default.position", Schema::String)
+        .property("pulse.idle.timeout", Schema::integer_range(0, i64::MAX))
+        .property("vm.overrides", ObjectSchema::new().into())
+        .parse_strings();
+    let cmd = ObjectSchema::new()
+        .required("cmd", Schema::String)
+        .property("args", Schema::String)
+        .property("flags", Schema::array(Schema::enumeration(["nofail"])))
+        .property("condition", conditions());
+
+    ObjectSchema::new()
+        .property("context.properties", context_properties())
+        .property("context.spa-libs", spa_libs())
+        .property("context.modules", Schema::array(module()))
+        .property("context.exec", Schema::array(exec()))
+        .property("pulse.cmd", Schema::array(cmd.into()))
+        .property("stream.properties", stream_properties())
+        .property("pulse.properties", pulse_properties.into())
+        .property("pulse.rules", rules())
+        .into()
+}
+
+/// `wireplumber.conf`, the session manager configuration.
+pub fn wireplumber() -> Schema {
+    let names = || Schema::array(Schema::String);
+    let component = ObjectSchema::new()
+        .required("type", Schema::String)
+        .property("name", Schema::String)
+        .property("arguments", ObjectSchema::new().into())
+        .property("provides", Schema::String)
+        .property("requires", names())
+        .property("wants", names())
+        .property("after", names())
+        .property("before", names());
+    let feature = Schema::enumeration(["required", "optional", "disabled"]);
+    let profile = ObjectSchema::new().additional(feature);
+
+    let mut schema = ObjectSchema::new()
+        .property("context.properties", context_properties())
+        .property("context.spa-libs", spa_libs())
+        .property("context.modules", Schema::array(module()))
+        .property("wireplumber.components", Schema::array(component.into()))
+        .property("wireplumber.components.rules", rules())
+        .property(
+            "wireplumber.profiles",
+            ObjectSchema::new().additional(profile.into()).into(),
+        )
+        .property("wireplumber.settings", ObjectSchema::new().into())
+        .property(
+            "wireplumber.settings.schema",
+            ObjectSchema::new()
+                .additional(ObjectSchema::new().required("type", Schema::String).into())
+                .into(),
+        );
+    for monitor in [
+        "alsa",
+        "alsa-midi",
+        "bluez",
+        "bluez-midi",
+        "v4l2",
+        "libcamera",
+    ] {
+        schema = schema
+            .property(
+                format!("monitor.{}.properties", monitor),
+                ObjectSchema::new().into(),
+            )
+            .property(format!("monitor.{}.rules", monitor), rules());
+    }
+    schema.into()
+}
+
+/// Well-known properties of PipeWire objects, as set in rule actions,
+/// module arguments and `context.objects`. Properties holding free-form
+/// text accept any value, since PipeWire stores every property as a string.
+pub fn properties() -> ObjectSchema {
+    let names = [
+        "node.name",
+        "node.nick",
+        "node.description",
+        "node.group",
+        "node.link-group",
+        "node.latency",
+        "node.max-latency",
+        "node.lock-quantum",
+        "node.force-quantum",
+        "node.rate",
+        "node.lock-rate",
+        "node.force-rate",
+        "media.class",
+        "media.role",
+        "media.type",
+        "media.category",
+        "media.name",
+        "device.name",
+        "device.nick",
+        "device.description",
+        "device.api",
+        "device.profile",
+        "device.profile-set",
+        "device.form-factor",
+        "device.icon-name",
+        "device.bus",
+        "api.alsa.path",
+        "api.alsa.pcm.card",
+        "api.alsa.pcm.stream",
+        "audio.format",
+        "audio.position",
+        "bluez5.roles",
+        "bluez5.codecs",
+        "application.name",
+        "application.process.binary",
+        "target.object",
+        "object.path",
+    ];
+    let mut properties = ObjectSchema::new().parse_strings();
+    for name in names {
+        properties = properties.property(name, Schema::Any);
+    }
+    let count = || Schema::integer_range(0, i64::MAX);
+    properties
+        .property("node.autoconnect", Schema::Bool)
+        .property("node.dont-reconnect", Schema::Bool)
+        .property("node.passive", Schema::Bool)
+        .property("node.pause-on-idle", Schema::Bool)
+        .property("node.suspend-on-idle", Schema::Bool)
+        .property("node.always-process", Schema::Bool)
+        .property("node.virtual", Schema::Bool)
+        .property("priority.driver", Schema::integer())
+        .property("priority.session", Schema::integer())
+        .property("session.suspend-timeout-seconds", count())
+        .property("audio.rate", Schema::integer_range(1, 768000))
+        .property("audio.channels", Schema::integer_range(1, 64))
+        .property("audio.allowed-rates", Schema::array(Schema::integer()))
+        .property("api.alsa.period-size", count())
+        .property("api.alsa.period-num", count())
+        .property("api.alsa.headroom", count())
+        .property("api.alsa.start-delay", count())
+        .property("api.alsa.disable-mmap", Schema::Bool)
+        .property("api.alsa.disable-batch", Schema::Bool)
+        .property("api.alsa.use-acp", Schema::Bool)
+        .property("api.alsa.use-ucm", Schema::Bool)
+        .property("api.alsa.soft-mixer", Schema::Bool)
+        .property("api.alsa.ignore-dB", Schema::Bool)
+        .property("api.acp.auto-profile", Schema::Bool)
+        .property("api.acp.auto-port", Schema::Bool)
+        .property("bluez5.enable-msbc", Schema::Bool)
+        .property("bluez5.enable-sbc-xq", Schema::Bool)
+        .property("bluez5.enable-hw-volume", Schema::Bool)
+        .property("bluez5.auto-connect", Schema::Any)
+        .property("resample.quality", Schema::integer_range(0, 14))
+        .property("channelmix.normalize", Schema::Bool)
+        .property("channelmix.mix-lfe", Schema::Bool)
+        .property("channelmix.upmix", Schema::Bool)
+        .property("monitor.channel-volumes", Schema::Bool)
+        .property("stream.dont-remix", Schema::Bool)
+}
+
+/// Properties that were renamed, with their replacement.
+pub const DEPRECATED: &[(&str, &str)] = &[
+    ("bluez5.msbc-support", "bluez5.enable-msbc"),
+    ("bluez5.sbc-xq-support", "bluez5.enable-sbc-xq"),
+    ("bluez5.headset-roles", "bluez5.roles"),
+    ("node.target", "target.object"),
+];
+
+fn context_properties() -> Schema {
+    let rate = || Schema::integer_range(1, 768000);
+    let quantum = || Schema::integer_range(1, 8192);
+    ObjectSchema::new()
+        .property("core.daemon", Schema::Bool)
+        .property("core.name", Schema::String)
+        .property("default.clock.rate", rate())
+        .property("default.clock.allowed-rates", Schema::array(rate()))
+        .property("default.clock.quantum", quantum())
+        .property("default.clock.min-quantum", quantum())
+        .property("default.clock.max-quantum", quantum())
+        .property("default.clock.quantum-limit", quantum())
+        .property("default.clock.quantum-floor", quantum())
+        .property("default.video.width", Schema::integer_range(1, i64::MAX))
+        .property("default.video.height", Schema::integer_range(1, i64::MAX))
+        .property("default.video.rate.num", Schema::integer_range(0, i64::MAX))
+        .property(
+            "default.video.rate.denom",
+            Schema::integer_range(1, i64::MAX),
+        )
+        .property("clock.power-of-two-quantum", Schema::Bool)
+        .property("link.max-buffers", Schema::integer_range(1, 64))
+        .property("log.level", Schema::integer_range(0, 5))
+        .property("mem.warn-mlock", Schema::Bool)
+        .property("mem.allow-mlock", Schema::Bool)
+        .property("mem.mlock-all", Schema::Bool)
+        .property("cpu.max-align", Schema::integer_range(1, i64::MAX))
+        .property("settings.check-quantum", Schema::Bool)
+        .property("settings.check-rate", Schema::Bool)
+        .property("support.dbus", Schema::Bool)
+        .property("module.access", Schema::Bool)
+        .property("module.x11.bell", Schema::Bool)
+        .property("module.jackdbus-detect", Schema::Bool)
+        .parse_strings()
+        .into()
+}
+
+fn stream_properties() -> Schema {
+    ObjectSchema::new()
+        .property("node.latency", Schema::String)
+        .property("node.autoconnect", Schema::Bool)
+        .property("resample.quality", Schema::integer_range(0, 14))
+        .property("resample.disable", Schema::Bool)
+        .property("channelmix.disable", Schema::Bool)
+        .property("channelmix.normalize", Schema::Bool)
+        .property("channelmix.mix-lfe", Schema::Bool)
+        .property("channelmix.upmix", Schema::Bool)
+        .property(
+            "channelmix.upmix-method",
+            Schema::enumeration(["none", "simple", "psd"]),
+        )
+        .property("channelmix.lfe-cutoff", Schema::float())
+        .property("channelmix.fc-cutoff", Schema::float())
+        .property("channelmix.rear-delay", Schema::float())
+        .property("channelmix.stereo-widen", Schema::float())
+        .property(
+            "channelmix.hilbert-taps",
+            Schema::integer_range(0, i64::MAX),
+        )
+        .property("dither.noise", Schema::integer_range(0, i64::MAX))
+        .property(
+            "dither.method",
+            Schema::enumeration([
+                "none",
+                "rectangular",
+                "triangular",
+                "triangular-hf",
+                "wannamaker3",
+                "shaped5",
+            ]),
+        )
+        .parse_strings()
+        .into()
+}
+
+fn spa_libs() -> Schema {
+    ObjectSchema::new().additional(Schema::String).into()
+}
+
+fn conditions() -> Schema {
+    Schema::array(ObjectSchema::new().into())
+}
+
+fn module() -> Schema {
+    ObjectSchema::new()
+        .required("name", Schema::String)
+        .property(
+            "args",
+            Schema::OneOf(vec![ObjectSchema::new().into(), Schema::String]),
+        )
+        .property(
+            "flags",
+            Schema::array(Schema::enumeration(["ifexists", "nofail"])),
+        )
+        .property("condition", conditions())
+        .into()
+}
+
+fn object() -> Schema {
+    ObjectSchema::new()
+        .required("factory", Schema::String)
+        .property("args", ObjectSchema::new().into())
+        .property("flags", Schema::array(Schema::enumeration(["nofail"])))
+        .property("condition", conditions())
+        .into()
+}
+
+fn exec() -> Schema {
+    ObjectSchema::new()
+        .required("path", Schema::String)
+        .property(
+            "args",
+            Schema::OneOf(vec![Schema::String, Schema::array(Schema::String)]),
+        )
+        .property("condition", conditions())
+        .into()
+}
+
+/// The `[ { matches = [ … ] actions = { … } } ]` list used by rule sections.
+fn rules() -> Schema {
+    Schema::array(
+        ObjectSchema::new()
+            .required("matches", Schema::array(ObjectSchema::new().into()))
+            .required("actions", ObjectSchema::new().into())
+            .into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Value;
+
+    #[test]
+    fn test_pipewire() {
+        let config: Value = r#"
+            context.properties = {
+                default.clock.rate = "48000hz"
+                default.clock.quantum = "1024"
+                mem.warn-mlock = "true"
+                log.level = 2
+            }
+            context.modules = [
+                { name = libpipewire-module-rt, flags = [ ifexists nofail ] }
+                { args = {} flags = [ sometimes ] }
+            ]
+        "#
+        .parse()
+        .unwrap();
+        let rendered: Vec<String> = by_name("pipewire.conf")
+            .unwrap()
+            .validate(&config)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "\"context.properties\".\"default.clock.rate\": expected integer, found string",
+                "\"context.modules\"[1]: missing required key \"name\"",
+                "\"context.modules\"[1].flags[0]: invalid value \"sometimes\", expected one of \"ifexists\", \"nofail\"",
+            ]
+        );
+        for name in NAMES {
+            assert!(by_name(name).unwrap().validate(&Value::from(1)).len() == 1);
+        }
+    }
+}
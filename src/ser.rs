@@ -0,0 +1,2295 @@
+use crate::path::PathSegment;
+#[cfg(feature = "value")]
+use crate::value::{to_value, Value};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "value")]
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use serde::{ser, Serialize};
+#[cfg(feature = "std")]
+use std::io;
+
+pub use crate::error::Error;
+use crate::escape::escape_into;
+pub use crate::escape::escape_str;
+use crate::number::{parse_non_finite, parse_number};
+
+/// Serializer writing SPA-JSON into a [`fmt::Write`] sink.
+///
+/// Output is staged line by line in an internal buffer, which only holds
+/// more than the current line while an open array or object may still be
+/// reformatted by [`SerializerOptions::inline_width`] or
+/// [`SerializerOptions::align_equals`].
+pub struct Serializer<W> {
+    writer: W,
+    output: String,
+    /// Nesting level of the line being written.
+    indent: usize,
+    /// The currently open arrays and objects, innermost last.
+    open: Vec<Frame>,
+    options: SerializerOptions,
+    /// Set while a map key is being written.
+    in_key: bool,
+    /// Set while a string that is written bare whatever it reads back as,
+    /// the text of a [`Hex`](crate::hex::Hex), is being written.
+    raw: bool,
+    /// Variant to be tagged in the next object, for an internally tagged
+    /// newtype variant.
+    pending_tag: Option<&'static str>,
+    /// Whether the output flushed so far ends a line.
+    ends_line: bool,
+    /// Path of the value being written, kept only when
+    /// [`SerializerOptions::comments`] or [`SerializerOptions::defaults`]
+    /// is set.
+    path: Vec<PathSegment>,
+}
+
+struct Frame {
+    /// Output offset of the opening bracket.
+    start: usize,
+    /// Set for a top-level object written without braces.
+    braceless: bool,
+    /// Output offset just after each key of an object, with the key's width.
+    keys: Vec<(usize, usize)>,
+    /// Number of items written to an array.
+    items: usize,
+    /// Output offset of the line of the object entry being written.
+    entry: usize,
+}
+
+/// Formatting choices for the serializer.
+#[derive(Clone, Debug, Default)]
+pub struct SerializerOptions {
+    inline_width: Option<usize>,
+    align_equals: bool,
+    trailing_newline: bool,
+    line_ending: LineEnding,
+    indent_style: IndentStyle,
+    ascii_only: bool,
+    single_quotes: bool,
+    plain_decimal: bool,
+    braceless: bool,
+    enum_repr: EnumRepr,
+    comments: Option<CommentFn>,
+    banner: Option<String>,
+    skip_empty_collections: bool,
+    #[cfg(feature = "value")]
+    defaults: Option<Value>,
+    max_decimals: Option<usize>,
+    max_width: Option<usize>,
+    wrap_strings: bool,
+    unit_struct_names: bool,
+    comment_out_values: bool,
+}
+
+/// Returns the comment to write above the key at a path, if any.
+pub type CommentFn = fn(&[PathSegment]) -> Option<String>;
+
+/// How enum variants are written. Unit variants are written as their name
+/// except when internally tagged.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum EnumRepr {
+    /// `{ Variant = … }`, the form read back by the deserializer.
+    #[default]
+    External,
+    /// The variant's fields with an extra `tag = Variant` entry, as in
+    /// `{ type = Variant, … }`. Newtype variants must contain an object;
+    /// tuple variants are not supported.
+    Internal(String),
+    /// Only the variant's payload.
+    Untagged,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndentStyle {
+    /// The given number of spaces per level.
+    Spaces(usize),
+    /// One tab per level.
+    Tabs,
+}
+
+impl Default for IndentStyle {
+    fn default() -> Self {
+        IndentStyle::Spaces(2)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+impl SerializerOptions {
+    pub fn new() -> Self {
+        SerializerOptions::default()
+    }
+
+    /// Writes arrays and objects on a single line, as `[ 1 2 ]` and
+    /// `{ a = 1 }`, when the line they start on stays within `width` columns.
+    pub fn inline_width(mut self, width: usize) -> Self {
+        self.inline_width = Some(width);
+        self
+    }
+
+    /// Keeps lines within `width` columns where possible. A value that
+    /// would run past it after its key is moved onto a line of its own,
+    /// indented one level deeper, and arrays and objects are only written
+    /// inline, by [`inline_width`](Self::inline_width), when they fit.
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Breaks quoted strings that are still longer than the
+    /// [`max_width`](Self::max_width) over several lines, ending each but
+    /// the last with a `\` line continuation. Only parsers reading with
+    /// [`ParseOptions::line_continuations`] read these back.
+    ///
+    /// [`ParseOptions::line_continuations`]: crate::de::ParseOptions::line_continuations
+    pub fn wrap_strings(mut self, wrap: bool) -> Self {
+        self.wrap_strings = wrap;
+        self
+    }
+
+    /// Pads the keys of each multi-line object so their `=` signs line up.
+    pub fn align_equals(mut self, align: bool) -> Self {
+        self.align_equals = align;
+        self
+    }
+
+    /// Ends the output with a newline, as configuration files conventionally do.
+    pub fn trailing_newline(mut self, newline: bool) -> Self {
+        self.trailing_newline = newline;
+        self
+    }
+
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn indent_style(mut self, indent_style: IndentStyle) -> Self {
+        self.indent_style = indent_style;
+        self
+    }
+
+    /// Escapes every non-ASCII character in strings as `\uXXXX`.
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Quotes strings with `'` instead of `"`.
+    pub fn single_quotes(mut self, single_quotes: bool) -> Self {
+        self.single_quotes = single_quotes;
+        self
+    }
+
+    /// Never writes floats in exponent notation. By default very large and
+    /// very small magnitudes are written as e.g. `1e21` and `1.5e-7`.
+    pub fn plain_decimal(mut self, plain_decimal: bool) -> Self {
+        self.plain_decimal = plain_decimal;
+        self
+    }
+
+    /// Rounds floats to at most `decimals` digits after the point, dropping
+    /// trailing zeros, so that e.g. `55.33333` is written as `55.333` with
+    /// 3 decimals.
+    pub fn max_decimals(mut self, decimals: usize) -> Self {
+        self.max_decimals = Some(decimals);
+        self
+    }
+
+    /// Writes a top-level object without braces, one entry per line, the
+    /// way configuration files and fragments are written.
+    pub fn braceless(mut self, braceless: bool) -> Self {
+        self.braceless = braceless;
+        self
+    }
+
+    /// Writes unit structs as their name, as given by `#[serde(rename)]`,
+    /// instead of `null`, for marker values. The deserializer reads unit
+    /// structs from either.
+    pub fn unit_struct_names(mut self, names: bool) -> Self {
+        self.unit_struct_names = names;
+        self
+    }
+
+    pub fn enum_repr(mut self, enum_repr: EnumRepr) -> Self {
+        self.enum_repr = enum_repr;
+        self
+    }
+
+    /// Calls `comments` with the path of every object key, and writes the
+    /// text it returns as `#` comment lines above the key, like
+    /// [`banner`](Self::banner). Objects and
+    /// arrays holding comments are never written inline.
+    pub fn comments(mut self, comments: CommentFn) -> Self {
+        self.comments = Some(comments);
+        self
+    }
+
+    /// Leaves out object entries whose value is an empty array or object,
+    /// including objects left empty by this. Items of arrays are always
+    /// written.
+    pub fn skip_empty_collections(mut self, skip: bool) -> Self {
+        self.skip_empty_collections = skip;
+        self
+    }
+
+    /// Leaves out object entries whose value equals the one at the same
+    /// path in `defaults`, so that only overrides are written. Objects left
+    /// without entries this way are left out as well.
+    ///
+    /// Values are compared as they read back, so an integral float matches
+    /// the same integer.
+    #[cfg(feature = "value")]
+    pub fn defaults(mut self, defaults: Value) -> Self {
+        self.defaults = Some(defaults);
+        self
+    }
+
+    /// Starts the output with `banner` as comment lines, followed by an
+    /// empty line. Lines that do not start with `#` get `# ` in front.
+    pub fn banner(mut self, banner: impl Into<String>) -> Self {
+        self.banner = Some(banner.into());
+        self
+    }
+
+    /// Writes object entries commented out, as `#key = value`, the way
+    /// shipped configuration files list options at their defaults. Entries
+    /// holding an object written over several lines stay, with their own
+    /// entries commented out instead, and arrays are commented out with
+    /// everything in them.
+    pub fn comment_out_values(mut self, comment_out: bool) -> Self {
+        self.comment_out_values = comment_out;
+        self
+    }
+
+    /// Whether entries equal to their defaults are left out.
+    #[cfg(feature = "value")]
+    fn has_defaults(&self) -> bool {
+        self.defaults.is_some()
+    }
+
+    #[cfg(not(feature = "value"))]
+    fn has_defaults(&self) -> bool {
+        false
+    }
+}
+
+pub fn to_string<T>(value: &T) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    to_string_with_options(value, SerializerOptions::default())
+}
+
+pub fn to_string_with_options<T>(value: &T, options: SerializerOptions) -> Result<String, Error>
+where
+    T: ?Sized + Serialize,
+{
+    let mut serializer = Serializer::with_options(String::new(), options);
+    serializer.serialize(value)?;
+    Ok(serializer.into_inner())
+}
+
+/// Writes a [`Value`] with [`Serializer::serialize_value`], which handles
+/// any depth of nesting.
+#[cfg(feature = "value")]
+pub fn to_string_value(value: &Value, options: SerializerOptions) -> Result<String, Error> {
+    let mut serializer = Serializer::with_options(String::new(), options);
+    serializer.serialize_value(value)?;
+    Ok(serializer.into_inner())
+}
+
+/// Writes only the entries of `value` that differ from [`T::default`],
+/// using [`SerializerOptions::defaults`].
+///
+/// [`T::default`]: Default::default
+#[cfg(feature = "value")]
+pub fn to_string_without_defaults<T>(value: &T, options: SerializerOptions) -> Result<String, Error>
+where
+    T: Serialize + Default,
+{
+    let defaults = to_value(&T::default(), &options.enum_repr, options.unit_struct_names)?;
+    to_string_with_options(value, options.defaults(defaults))
+}
+
+#[cfg(feature = "std")]
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<(), Error>
+where
+    W: io::Write,
+    T: Serialize,
+{
+    to_writer_with_options(writer, value, SerializerOptions::default())
+}
+
+#[cfg(feature = "std")]
+pub fn to_writer_with_options<W, T>(
+    writer: W,
+    value: &T,
+    options: SerializerOptions,
+) -> Result<(), Error>
+where
+    W: io::Write,
+    T: Serialize,
+{
+    let writer = IoWriter {
+        inner: writer,
+        error: None,
+    };
+    let mut serializer = Serializer::with_options(writer, options);
+    serializer
+        .serialize(value)
+        .map_err(|e| serializer.writer.error.take().map_or(e, Error::Io))
+}
+
+impl Serializer<String> {
+    /// A serializer into a string, with room for `capacity` bytes of output.
+    ///
+    /// Reusing it with [`clear`](Serializer::clear) keeps its allocations,
+    /// which avoids reallocating for every document:
+    ///
+    /// ```
+    /// # use spa_json::ser::Serializer;
+    /// let mut serializer = Serializer::with_capacity(4096);
+    /// for rate in [44100, 48000] {
+    ///     serializer.clear();
+    ///     serializer.serialize(&rate)?;
+    ///     assert_eq!(serializer.as_str(), rate.to_string());
+    /// }
+    /// # Ok::<(), spa_json::ser::Error>(())
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
+        Serializer::with_options(
+            String::with_capacity(capacity),
+            SerializerOptions::default(),
+        )
+    }
+
+    /// Discards the output written so far, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.writer.clear();
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.writer
+    }
+}
+
+/// Adapts an [`io::Write`] to [`fmt::Write`], keeping the I/O error.
+#[cfg(feature = "std")]
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl<W: fmt::Write> Serializer<W> {
+    pub fn new(writer: W) -> Self {
+        Serializer::with_options(writer, SerializerOptions::default())
+    }
+
+    pub fn with_options(writer: W, options: SerializerOptions) -> Self {
+        Serializer {
+            writer,
+            output: String::new(),
+            indent: 0,
+            open: Vec::new(),
+            options,
+            in_key: false,
+            raw: false,
+            pending_tag: None,
+            ends_line: false,
+            path: Vec::new(),
+        }
+    }
+
+    /// Writes `value` as a complete document. The serializer can be used
+    /// for further documents afterwards, even if this one failed.
+    pub fn serialize<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.begin()?;
+        value.serialize(&mut *self)?;
+        self.finish()
+    }
+
+    /// Writes `value` like [`serialize`](Self::serialize), but walks it
+    /// with a stack of its own instead of recursing, so that the depth of
+    /// the value is bounded only by memory.
+    #[cfg(feature = "value")]
+    pub fn serialize_value(&mut self, value: &Value) -> Result<(), Error> {
+        enum Step<'v> {
+            Value(&'v Value),
+            Item(&'v Value),
+            Entry(&'v str, &'v Value),
+            EndItem(usize),
+            EndEntry(usize, bool),
+            Close(&'static str),
+        }
+
+        self.begin()?;
+        let mut steps = vec![Step::Value(value)];
+        while let Some(step) = steps.pop() {
+            match step {
+                Step::Value(Value::Array(items)) => {
+                    self.open("[")?;
+                    steps.push(Step::Close("]"));
+                    steps.extend(items.iter().rev().map(Step::Item));
+                }
+                Step::Value(Value::Object(map)) => {
+                    self.open_object()?;
+                    steps.push(Step::Close("}"));
+                    let entries: Vec<Step> = map
+                        .iter()
+                        .map(|(k, v)| Step::Entry(k.as_str(), v))
+                        .collect();
+                    steps.extend(entries.into_iter().rev());
+                }
+                Step::Value(scalar) => scalar.serialize(&mut *self)?,
+                Step::Item(value) => {
+                    steps.push(Step::EndItem(self.begin_item()));
+                    steps.push(Step::Value(value));
+                }
+                Step::Entry(key, value) => {
+                    ser::SerializeMap::serialize_key(&mut &mut *self, key)?;
+                    let default = self.is_default(value);
+                    steps.push(Step::EndEntry(self.begin_value(), default));
+                    steps.push(Step::Value(value));
+                }
+                Step::EndItem(start) => self.end_item(start)?,
+                Step::EndEntry(start, default) => self.end_value(start, default)?,
+                Step::Close(bracket) => self.close(bracket),
+            }
+        }
+        self.finish()
+    }
+
+    /// Resets the state left by the previous document and writes the
+    /// banner.
+    fn begin(&mut self) -> Result<(), Error> {
+        self.output.clear();
+        self.open.clear();
+        self.indent = 0;
+        self.in_key = false;
+        self.pending_tag = None;
+        self.ends_line = false;
+        self.path.clear();
+        if let Some(banner) = self.options.banner.clone() {
+            self.write_comment_lines(&banner);
+            self.output += self.options.line_ending.as_str();
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Ends the document and flushes it.
+    fn finish(&mut self) -> Result<(), Error> {
+        let newline = self.options.line_ending.as_str();
+        let ends_line = match self.output.is_empty() {
+            true => self.ends_line,
+            false => self.output.ends_with(newline),
+        };
+        if self.options.trailing_newline && !ends_line {
+            self.output += newline;
+        }
+        self.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Passes the staged output on to the writer.
+    fn flush(&mut self) -> Result<(), Error> {
+        self.writer
+            .write_str(&self.output)
+            .map_err(|_| Error::Message("failed to write output".to_owned()))?;
+        if !self.output.is_empty() {
+            self.ends_line = self.output.ends_with('\n');
+        }
+        self.output.clear();
+        Ok(())
+    }
+
+    /// Starts an array or object, continuing on a new indented line.
+    fn open(&mut self, bracket: &str) -> Result<(), Error> {
+        if self.in_key {
+            return Err(Error::Message(
+                "map key must be a string or number".to_owned(),
+            ));
+        }
+        if self.pending_tag.is_some() {
+            return Err(untaggable());
+        }
+        self.open.push(Frame {
+            start: self.output.len(),
+            braceless: false,
+            keys: Vec::new(),
+            items: 0,
+            entry: 0,
+        });
+        self.output += bracket;
+        self.newline()?;
+        self.indent();
+        Ok(())
+    }
+
+    /// Starts an object, leaving out the braces if it is the top-level
+    /// value of a braceless document.
+    fn open_object(&mut self) -> Result<(), Error> {
+        let tag = self.pending_tag.take();
+        if self.options.braceless && self.open.is_empty() && self.output.is_empty() && !self.in_key
+        {
+            self.open.push(Frame {
+                start: 0,
+                braceless: true,
+                keys: Vec::new(),
+                items: 0,
+                entry: 0,
+            });
+        } else {
+            self.open("{")?;
+        }
+        match tag {
+            Some(variant) => self.write_tag(variant),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the `tag = variant` entry of an internally tagged variant.
+    fn write_tag(&mut self, variant: &str) -> Result<(), Error> {
+        let EnumRepr::Internal(tag) = &self.options.enum_repr else {
+            return Ok(());
+        };
+        let tag = tag.clone();
+        ser::SerializeMap::serialize_entry(&mut &mut *self, &tag, variant)
+    }
+
+    /// Ends the innermost open array or object on its own line.
+    fn close(&mut self, bracket: &str) {
+        if self.open.last().is_some_and(|frame| frame.braceless) {
+            let frame = self.open.pop().unwrap();
+            if self.options.align_equals {
+                self.align(&frame.keys);
+            }
+            return;
+        }
+        self.dedent();
+        self.write_indent();
+        self.output += bracket;
+        let Some(frame) = self.open.pop() else {
+            return;
+        };
+        if !self.try_inline(frame.start) && self.options.align_equals {
+            self.align(&frame.keys);
+        }
+    }
+
+    /// Marks the line starting at `line_start` as the start of an entry
+    /// of the innermost object.
+    fn start_entry(&mut self, line_start: usize) {
+        if let Some(frame) = self.open.last_mut() {
+            frame.entry = line_start;
+        }
+    }
+
+    /// Removes the current entry of the innermost object if its value,
+    /// written from `value_start`, is an empty array or object that should
+    /// be skipped, or `default` says it equals its default. Returns whether
+    /// it was removed.
+    fn skip_entry(&mut self, value_start: usize, default: bool) -> bool {
+        // Without these options, the value may have been flushed already.
+        if !self.options.skip_empty_collections && !self.options.has_defaults() {
+            return false;
+        }
+        // A value moved onto a line of its own starts with the line break.
+        let value = self.output[value_start..].trim_start();
+        let empty = value.len() >= 2
+            && value.starts_with(['[', '{'])
+            && value.ends_with([']', '}'])
+            && value[1..value.len() - 1].trim().is_empty();
+        // An empty object matches any default object, since its entries
+        // were all left out or there were none.
+        let skip = (self.options.skip_empty_collections && empty)
+            || default
+            || (empty && value.starts_with('{') && self.default_is_object());
+        if !skip {
+            return false;
+        }
+        let Some(frame) = self.open.last_mut() else {
+            return false;
+        };
+        frame.keys.pop();
+        self.output.truncate(frame.entry);
+        true
+    }
+
+    /// The default for the value at the current path.
+    #[cfg(feature = "value")]
+    fn default(&self) -> Option<&Value> {
+        self.options.defaults.as_ref()?.get_path(&self.path)
+    }
+
+    /// Whether `value`, the value at the current path, equals the default
+    /// there.
+    #[cfg(feature = "value")]
+    fn is_default<T: ?Sized + Serialize>(&self, value: &T) -> bool {
+        let Some(default) = self.default() else {
+            return false;
+        };
+        let options = &self.options;
+        let Ok(mut value) = to_value(value, &options.enum_repr, options.unit_struct_names) else {
+            return false;
+        };
+        let mut default = default.clone();
+        value.canonicalize();
+        default.canonicalize();
+        value == default
+    }
+
+    #[cfg(not(feature = "value"))]
+    fn is_default<T: ?Sized + Serialize>(&self, _value: &T) -> bool {
+        false
+    }
+
+    #[cfg(feature = "value")]
+    fn default_is_object(&self) -> bool {
+        self.default()
+            .is_some_and(|default| default.as_object().is_some())
+    }
+
+    #[cfg(not(feature = "value"))]
+    fn default_is_object(&self) -> bool {
+        false
+    }
+
+    /// Records that the key written since `start` has ended.
+    fn end_key(&mut self, start: usize) {
+        let width = self.output[start..].chars().count();
+        if let Some(frame) = self.open.last_mut() {
+            frame.keys.push((self.output.len(), width));
+        }
+    }
+
+    /// Pads every key to the width of the longest one.
+    fn align(&mut self, keys: &[(usize, usize)]) {
+        let max = keys.iter().map(|&(_, width)| width).max().unwrap_or(0);
+        for &(end, width) in keys.iter().rev() {
+            self.output.insert_str(end, &" ".repeat(max - width));
+        }
+    }
+
+    /// Joins the lines of the container starting at `start` if the result
+    /// fits within the inline width, moving it onto a line of its own if
+    /// only that keeps it within the maximum width.
+    fn try_inline(&mut self, start: usize) -> bool {
+        let Some(width) = self.options.inline_width else {
+            return false;
+        };
+        let inline: Vec<&str> = self.output[start..].lines().map(str::trim_start).collect();
+        if inline
+            .iter()
+            .any(|line| line.starts_with('#') || line.ends_with('\\'))
+        {
+            return false;
+        }
+        let inline = inline.join(" ");
+        let len = inline.chars().count();
+        if self.column(start) + len > width {
+            match self.options.max_width {
+                Some(max)
+                    if self.after_key(start)
+                        && self.indent_width(self.indent + 1) + len <= max.min(width) =>
+                {
+                    self.output.truncate(start);
+                    self.break_before_value();
+                }
+                _ => return false,
+            }
+        } else if self
+            .options
+            .max_width
+            .is_some_and(|max| self.column(start) + len > max)
+        {
+            return false;
+        } else {
+            self.output.truncate(start);
+        }
+        self.output += &inline;
+        true
+    }
+
+    /// The column at output offset `at`.
+    fn column(&self, at: usize) -> usize {
+        let line_start = self.output[..at].rfind('\n').map_or(0, |i| i + 1);
+        self.output[line_start..at].chars().count()
+    }
+
+    /// Whether the value at output offset `at` follows its key.
+    fn after_key(&self, at: usize) -> bool {
+        self.output[..at].ends_with(" = ")
+    }
+
+    /// Ends the line after the `=` of the current entry, and indents the
+    /// line for its value one level deeper.
+    fn break_before_value(&mut self) {
+        self.output.pop();
+        self.output += self.options.line_ending.as_str();
+        self.indent();
+        self.write_indent();
+        self.dedent();
+    }
+
+    /// Keeps the value written since `start`, if it is on one line, within
+    /// the maximum width: moves it onto a line of its own if it follows a
+    /// key, and then breaks it if it is a string and strings are wrapped.
+    fn fit_value(&mut self, start: usize) {
+        let Some(width) = self.options.max_width else {
+            return;
+        };
+        let value = &self.output[start..];
+        if value.contains('\n') || self.column(start) + value.chars().count() <= width {
+            return;
+        }
+        let value = self.output.split_off(start);
+        if self.after_key(start) {
+            self.break_before_value();
+        }
+        if self.options.wrap_strings && value.starts_with(['"', '\'']) {
+            self.wrap_string(&value, width);
+        } else {
+            self.output += &value;
+        }
+    }
+
+    /// Writes the quoted string `quoted` broken over lines ending in `\`
+    /// so that they stay within `width` columns where possible. Lines are
+    /// broken only before a character that is neither a space nor inside
+    /// an escape, since the indentation of the next line is left out when
+    /// reading it.
+    fn wrap_string(&mut self, quoted: &str, width: usize) {
+        let mut pieces = Vec::new();
+        let mut chars = quoted.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c == '\\' {
+                let len = match chars.next() {
+                    // A high surrogate and the low one after it are one
+                    // character, and cannot be read apart.
+                    Some((u, 'u')) if is_high_surrogate(&quoted[u + 1..]) => 10,
+                    Some((_, 'u')) => 4,
+                    _ => 0,
+                };
+                for _ in 0..len {
+                    chars.next();
+                }
+            }
+            let end = chars.peek().map_or(quoted.len(), |&(end, _)| end);
+            pieces.push(&quoted[i..end]);
+        }
+        let indent = self.indent_width(self.indent + 1);
+        let mut column = self.column(self.output.len());
+        let mut rest = pieces.as_slice();
+        while !rest.is_empty() {
+            let total: usize = rest.iter().map(|p| p.chars().count()).sum();
+            if column + total <= width {
+                break;
+            }
+            // The longest run of pieces leaving room for the `\`, preferably
+            // ending after a space, or the shortest one that can be broken
+            // after if none fits.
+            let mut len = 0;
+            let mut fit = None;
+            let mut word = None;
+            for (n, piece) in rest.iter().enumerate().take(rest.len() - 1).skip(1) {
+                len += rest[n - 1].chars().count();
+                if *piece == " " {
+                    continue;
+                }
+                if column + len < width || fit.is_none() {
+                    fit = Some(n);
+                    if rest[n - 1] == " " && column + len < width {
+                        word = Some(n);
+                    }
+                } else {
+                    break;
+                }
+            }
+            let Some(n) = word.or(fit) else {
+                break;
+            };
+            for piece in &rest[..n] {
+                self.output += piece;
+            }
+            self.output.push('\\');
+            self.output += self.options.line_ending.as_str();
+            self.indent();
+            self.write_indent();
+            self.dedent();
+            column = indent;
+            rest = &rest[n..];
+        }
+        for piece in rest {
+            self.output += piece;
+        }
+    }
+
+    /// Ends the current line, flushing it unless an open container may
+    /// still be reformatted.
+    fn newline(&mut self) -> Result<(), Error> {
+        self.output += self.options.line_ending.as_str();
+        // Lines of open containers may still change, and entries may be
+        // dropped.
+        let reformat = self.options.inline_width.is_some()
+            || self.options.max_width.is_some()
+            || self.options.align_equals
+            || self.options.skip_empty_collections
+            || self.options.has_defaults()
+            || self.options.comment_out_values;
+        if self.open.is_empty() || !reformat {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes a float of the given magnitude, formatted by its own type so
+    /// that it gets the shortest digits reading back as the same value.
+    fn write_float<F: fmt::Display + fmt::LowerExp>(&mut self, v: F, magnitude: f64) {
+        if let Some(decimals) = self.options.max_decimals.filter(|_| magnitude < 1e16) {
+            let rounded = format!("{:.*}", decimals, v);
+            let rounded = if decimals > 0 {
+                rounded.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                &rounded
+            };
+            // Negative values rounded to zero are written as `0`.
+            if rounded.trim_start_matches(['-', '0']).is_empty() {
+                self.output.push('0');
+            } else {
+                self.output += rounded;
+            }
+        } else if self.options.plain_decimal
+            || magnitude == 0.0
+            || (1e-5..1e16).contains(&magnitude)
+        {
+            write!(self.output, "{}", v).unwrap();
+        } else {
+            write!(self.output, "{:e}", v).unwrap();
+        }
+    }
+
+    /// Starts the next item of the innermost array on its own line, and
+    /// returns where its value starts.
+    fn begin_item(&mut self) -> usize {
+        self.push_index();
+        self.write_indent();
+        self.output.len()
+    }
+
+    /// Ends the array item whose value started at `start`.
+    fn end_item(&mut self, start: usize) -> Result<(), Error> {
+        self.fit_value(start);
+        self.pop_path();
+        self.newline()
+    }
+
+    /// Writes the `=` after the key of an object entry, and returns where
+    /// its value starts.
+    fn begin_value(&mut self) -> usize {
+        self.output += " = ";
+        self.output.len()
+    }
+
+    /// Ends the object entry whose value started at `start`, leaving it
+    /// out if `default` says it equals its default.
+    fn end_value(&mut self, start: usize, default: bool) -> Result<(), Error> {
+        self.fit_value(start);
+        let skipped = self.skip_entry(start, default);
+        if !skipped && self.options.comment_out_values {
+            self.comment_out_entry(start);
+        }
+        self.pop_path();
+        if !skipped {
+            self.newline()?;
+        }
+        Ok(())
+    }
+
+    /// Comments out the lines of the current entry of the innermost object,
+    /// unless its value, written from `value_start`, is an object with
+    /// entries on lines of their own, or the entry is in an array that gets
+    /// commented out as a whole.
+    fn comment_out_entry(&mut self, value_start: usize) {
+        let value = self.output[value_start..].trim_start();
+        if value.starts_with('{') && value.lines().count() > 2 {
+            return;
+        }
+        let in_array = self
+            .open
+            .iter()
+            .any(|frame| !frame.braceless && self.output[frame.start..].starts_with('['));
+        let Some(frame) = self.open.last_mut().filter(|_| !in_array) else {
+            return;
+        };
+        // Comment lines above the key already start with `#`; the line of
+        // the key gets one in front, moving the end of the key along.
+        if let Some(key) = frame.keys.last_mut() {
+            key.0 += 1;
+        }
+        let mut at = frame.entry;
+        while at < self.output.len() {
+            let line = &self.output[at..];
+            let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+            if !line[indent..].starts_with('#') {
+                self.output.insert(at + indent, '#');
+            }
+            at = match self.output[at..].find('\n') {
+                Some(i) => at + i + 1,
+                None => self.output.len(),
+            };
+        }
+    }
+
+    /// Writes `s` quoted and escaped.
+    fn write_quoted(&mut self, s: &str) {
+        let quote = if self.options.single_quotes {
+            '\''
+        } else {
+            '"'
+        };
+        self.output.push(quote);
+        escape_into(&mut self.output, s, quote, self.options.ascii_only);
+        self.output.push(quote);
+    }
+
+    fn indent(&mut self) {
+        self.indent += 1;
+    }
+
+    fn dedent(&mut self) {
+        self.indent = self.indent.saturating_sub(1);
+    }
+
+    fn write_indent(&mut self) {
+        let c = match self.options.indent_style {
+            IndentStyle::Spaces(_) => ' ',
+            IndentStyle::Tabs => '\t',
+        };
+        let n = self.indent_width(self.indent);
+        self.output.extend(core::iter::repeat_n(c, n));
+    }
+
+    /// The number of characters indenting a line at nesting `level`.
+    fn indent_width(&self, level: usize) -> usize {
+        match self.options.indent_style {
+            IndentStyle::Spaces(n) => n * level,
+            IndentStyle::Tabs => level,
+        }
+    }
+
+    fn tracks_path(&self) -> bool {
+        self.options.comments.is_some() || self.options.has_defaults()
+    }
+
+    /// Enters the next item of the innermost array.
+    fn push_index(&mut self) {
+        if !self.tracks_path() {
+            return;
+        }
+        if let Some(frame) = self.open.last_mut() {
+            self.path.push(PathSegment::Index(frame.items));
+            frame.items += 1;
+        }
+    }
+
+    /// Enters the entry of the key written since `start`, and writes its
+    /// comment above the line starting at `line_start`. Returns the length
+    /// of the comment.
+    fn push_key(&mut self, line_start: usize, start: usize) -> usize {
+        if !self.tracks_path() {
+            return 0;
+        }
+        let written = &self.output[start..];
+        let key = match written.starts_with(['"', '\'']) {
+            #[cfg(feature = "de")]
+            true => crate::de::from_str(written).unwrap_or_default(),
+            // Without the parser, the few keys that needed escapes are
+            // matched as written.
+            #[cfg(not(feature = "de"))]
+            true => written[1..written.len() - 1].to_owned(),
+            false => written.to_owned(),
+        };
+        self.path.push(PathSegment::Key(key));
+        self.write_comment(line_start)
+    }
+
+    /// Enters the payload of an externally tagged variant.
+    fn push_variant(&mut self, variant: &str) {
+        if self.tracks_path() {
+            self.path.push(PathSegment::Key(variant.to_owned()));
+        }
+    }
+
+    fn pop_path(&mut self) {
+        self.path.pop();
+    }
+
+    /// Inserts the comment for the current path at `at`, one `#` line per
+    /// line of text, and returns its length.
+    fn write_comment(&mut self, at: usize) -> usize {
+        let Some(text) = self
+            .options
+            .comments
+            .and_then(|comments| comments(&self.path))
+        else {
+            return 0;
+        };
+        let line_start = self.output.len();
+        self.write_comment_lines(&text);
+        let comment = self.output.split_off(line_start);
+        self.output.insert_str(at, &comment);
+        comment.len()
+    }
+
+    /// Writes `text` as comment lines at the current indentation, adding
+    /// `# ` to lines that do not start with `#` already.
+    fn write_comment_lines(&mut self, text: &str) {
+        for line in text.lines() {
+            self.write_indent();
+            if !line.starts_with('#') {
+                self.output.push('#');
+                if !line.is_empty() {
+                    self.output.push(' ');
+                }
+            }
+            self.output += line;
+            self.output += self.options.line_ending.as_str();
+        }
+    }
+}
+
+impl<W: fmt::Write> ser::Serializer for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.output += if v { "true" } else { "false" };
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        write!(self.output, "{}", v).unwrap();
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        write!(self.output, "{}", v).unwrap();
+        Ok(())
+    }
+
+    /// Written with the shortest digits that read back as the same `f32`,
+    /// so that `0.1f32` stays `0.1`.
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.write_float(v, f64::from(v.abs()));
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        self.write_float(v, v.abs());
+        Ok(())
+    }
+
+    /// Chars are written bare only if they are ASCII letters. Any other
+    /// char is quoted, so that punctuation such as `=` or `{` is never
+    /// read as structure and digits are never read back as numbers.
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        if v.is_ascii_alphabetic() {
+            self.output.push(v);
+            return Ok(());
+        }
+        self.write_quoted(v.encode_utf8(&mut [0; 4]));
+        Ok(())
+    }
+
+    /// Strings are written bare where the parser reads them back as the same
+    /// string, and quoted otherwise.
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        if self.raw || is_bare(v) {
+            self.output += v;
+        } else {
+            self.write_quoted(v);
+        }
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        use serde::ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        self.output += "null";
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok, Self::Error> {
+        if self.options.unit_struct_names {
+            return self.serialize_str(name);
+        }
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        if let EnumRepr::Internal(_) = self.options.enum_repr {
+            self.open_object()?;
+            self.write_tag(variant)?;
+            self.close("}");
+            return Ok(());
+        }
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        if name == crate::hex::TOKEN {
+            self.raw = true;
+            let result = value.serialize(&mut *self);
+            self.raw = false;
+            return result;
+        }
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        match self.options.enum_repr {
+            EnumRepr::External => {}
+            EnumRepr::Internal(_) => {
+                self.pending_tag = Some(variant);
+                value.serialize(&mut *self)?;
+                return match self.pending_tag.take() {
+                    Some(_) => Err(untaggable()),
+                    None => Ok(()),
+                };
+            }
+            EnumRepr::Untagged => return value.serialize(self),
+        }
+        self.output += "{ ";
+        variant.serialize(&mut *self)?;
+        self.output += " = ";
+        self.push_variant(variant);
+        value.serialize(&mut *self)?;
+        self.pop_path();
+        self.output += " }";
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.open("[")?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        match self.options.enum_repr {
+            EnumRepr::External => {}
+            EnumRepr::Internal(_) => return Err(untaggable()),
+            EnumRepr::Untagged => {
+                self.open("[")?;
+                return Ok(self);
+            }
+        }
+        self.open("{")?;
+        self.write_indent();
+        variant.serialize(&mut *self)?;
+        self.output += " = ";
+        self.push_variant(variant);
+        self.open("[")?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.open_object()?;
+        Ok(self)
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        match self.options.enum_repr {
+            EnumRepr::External => {}
+            EnumRepr::Internal(_) => {
+                self.open_object()?;
+                self.write_tag(variant)?;
+                return Ok(self);
+            }
+            EnumRepr::Untagged => {
+                self.open_object()?;
+                return Ok(self);
+            }
+        }
+        self.open("{")?;
+        self.write_indent();
+        variant.serialize(&mut *self)?;
+        self.output += " = ";
+        self.push_variant(variant);
+        self.open("{")?;
+        Ok(self)
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeSeq for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let start = self.begin_item();
+        value.serialize(&mut **self)?;
+        self.end_item(start)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("]");
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTuple for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let start = self.begin_item();
+        value.serialize(&mut **self)?;
+        self.end_item(start)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("]");
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTupleStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let start = self.begin_item();
+        value.serialize(&mut **self)?;
+        self.end_item(start)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("]");
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeTupleVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let start = self.begin_item();
+        value.serialize(&mut **self)?;
+        self.end_item(start)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("]");
+        if self.options.enum_repr == EnumRepr::External {
+            self.pop_path();
+            self.newline()?;
+            self.close("}");
+        }
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeMap for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Self::Error> {
+        let line_start = self.output.len();
+        self.start_entry(line_start);
+        self.write_indent();
+        let start = self.output.len();
+        self.in_key = true;
+        let result = key.serialize(&mut **self);
+        self.in_key = false;
+        result?;
+        let start = start + self.push_key(line_start, start);
+        self.end_key(start);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Self::Error> {
+        let default = self.is_default(value);
+        let start = self.begin_value();
+        value.serialize(&mut **self)?;
+        self.end_value(start, default)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("}");
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeStruct for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let line_start = self.output.len();
+        self.start_entry(line_start);
+        self.write_indent();
+        let start = self.output.len();
+        key.serialize(&mut **self)?;
+        let start = start + self.push_key(line_start, start);
+        self.end_key(start);
+        let default = self.is_default(value);
+        let start = self.begin_value();
+        value.serialize(&mut **self)?;
+        self.end_value(start, default)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("}");
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> ser::SerializeStructVariant for &mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error> {
+        let line_start = self.output.len();
+        self.start_entry(line_start);
+        self.write_indent();
+        let start = self.output.len();
+        key.serialize(&mut **self)?;
+        let start = start + self.push_key(line_start, start);
+        self.end_key(start);
+        let default = self.is_default(value);
+        let start = self.begin_value();
+        value.serialize(&mut **self)?;
+        self.end_value(start, default)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        self.close("}");
+        if self.options.enum_repr == EnumRepr::External {
+            self.pop_path();
+            self.newline()?;
+            self.close("}");
+        }
+        Ok(())
+    }
+}
+
+fn untaggable() -> Error {
+    Error::Message("only objects can be internally tagged".to_owned())
+}
+
+/// Whether `s` can be written without quotes: it has only characters
+/// allowed in bare scalars, and is not read back as a boolean, `null` or a
+/// number, counting `nan` and `inf`, which are read as floats on request.
+fn is_bare(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('\'')
+        && s.bytes().all(|c| {
+            c.is_ascii_graphic()
+                && !matches!(
+                    c,
+                    b'"' | b'#' | b':' | b',' | b'=' | b'\\' | b'[' | b']' | b'{' | b'}'
+                )
+        })
+        && !matches!(s, "true" | "false" | "null")
+        && parse_number(s).is_none()
+        && parse_non_finite(s).is_none()
+}
+
+/// Whether `s` starts with the hex digits of a high surrogate followed by
+/// the `\u` escape of its low surrogate.
+fn is_high_surrogate(s: &str) -> bool {
+    s.get(..4)
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+        .is_some_and(|unit| (0xd800..0xdc00).contains(&unit))
+        && s[4..].starts_with("\\u")
+}
+
+#[cfg(all(test, feature = "value"))]
+mod tests {
+    use super::*;
+    use crate::de::{from_str, from_str_with_options, ParseOptions};
+    use crate::value::Value;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_struct() {
+        #[derive(Serialize)]
+        struct Test {
+            int: u32,
+            seq: Vec<&'static str>,
+            str: String,
+        }
+
+        let test = Test {
+            int: 1,
+            seq: vec!["a", "b"],
+            str: "string".to_owned(),
+        };
+        let expected = "{\n  int = 1\n  seq = [\n    a\n    b\n  ]\n  str = string\n}";
+        assert_eq!(to_string(&test).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Serialize)]
+        enum E {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, u32),
+            Struct { a: u32 },
+        }
+
+        let u = E::Unit;
+        let expected = "Unit";
+        assert_eq!(to_string(&u).unwrap(), expected);
+
+        let n = E::Newtype(1);
+        let expected = "{ Newtype = 1 }";
+        assert_eq!(to_string(&n).unwrap(), expected);
+
+        let t = E::Tuple(1, 2);
+        let expected = "{\n  Tuple = [\n    1\n    2\n  ]\n}";
+        assert_eq!(to_string(&t).unwrap(), expected);
+
+        let s = E::Struct { a: 1 };
+        let expected = "{\n  Struct = {\n    a = 1\n  }\n}";
+        assert_eq!(to_string(&s).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_enum_repr() {
+        #[derive(Serialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Node {
+            Builtin {
+                name: &'static str,
+                label: &'static str,
+            },
+            Sofa,
+            Ladspa(Plugin),
+            Gain(f64),
+            Pair(u32, u32),
+        }
+
+        #[derive(Serialize)]
+        struct Plugin {
+            plugin: &'static str,
+        }
+
+        let nodes = [
+            Node::Builtin {
+                name: "copy",
+                label: "copy",
+            },
+            Node::Sofa,
+            Node::Ladspa(Plugin { plugin: "eq" }),
+        ];
+        let options = SerializerOptions::new().inline_width(80);
+
+        let internal = options
+            .clone()
+            .enum_repr(EnumRepr::Internal("type".to_owned()));
+        let expected = "[\n  { type = builtin name = copy label = copy }\n  { type = sofa }\n  { type = ladspa plugin = eq }\n]";
+        assert_eq!(
+            to_string_with_options(&nodes, internal.clone()).unwrap(),
+            expected
+        );
+        assert_eq!(
+            to_string_with_options(&nodes[0], internal.clone().braceless(true)).unwrap(),
+            "type = builtin\nname = copy\nlabel = copy\n"
+        );
+        for node in [Node::Gain(0.5), Node::Pair(1, 2)] {
+            assert_eq!(
+                to_string_with_options(&node, internal.clone())
+                    .unwrap_err()
+                    .to_string(),
+                "only objects can be internally tagged"
+            );
+        }
+
+        let untagged = options.enum_repr(EnumRepr::Untagged);
+        assert_eq!(
+            to_string_with_options(&nodes, untagged.clone()).unwrap(),
+            "[ { name = copy label = copy } sofa { plugin = eq } ]"
+        );
+        assert_eq!(
+            to_string_with_options(&[Node::Gain(0.5), Node::Pair(1, 2)], untagged).unwrap(),
+            "[ 0.5 [ 1 2 ] ]"
+        );
+    }
+
+    #[test]
+    fn test_to_writer() {
+        let value = vec![vec!["a"; 3]; 2];
+        let mut out = Vec::new();
+        to_writer(&mut out, &value).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), to_string(&value).unwrap());
+
+        let options = SerializerOptions::new().inline_width(20);
+        let mut out = Vec::new();
+        to_writer_with_options(&mut out, &value, options.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            to_string_with_options(&value, options).unwrap()
+        );
+
+        let mut full = [0u8; 8];
+        let error = to_writer(&mut full[..], &value).unwrap_err();
+        assert!(matches!(error, Error::Io(e) if e.kind() == io::ErrorKind::WriteZero));
+
+        let mut serializer = Serializer::new(String::new());
+        serializer.serialize(&[1]).unwrap();
+        assert_eq!(serializer.into_inner(), "[\n  1\n]");
+    }
+
+    #[test]
+    fn test_reuse() {
+        #[derive(Serialize)]
+        struct Unsupported(#[serde(serialize_with = "fail")] u32);
+
+        fn fail<S: ser::Serializer>(_: &u32, _: S) -> Result<S::Ok, S::Error> {
+            Err(ser::Error::custom("unsupported"))
+        }
+
+        let mut serializer = Serializer::with_capacity(64);
+        serializer.serialize(&[1]).unwrap();
+        assert_eq!(serializer.as_str(), "[\n  1\n]");
+
+        serializer.clear();
+        assert!(serializer.serialize(&[Unsupported(1)]).is_err());
+
+        serializer.clear();
+        serializer.serialize(&[2]).unwrap();
+        assert_eq!(serializer.as_str(), "[\n  2\n]");
+        assert!(serializer.into_inner().capacity() >= 64);
+    }
+
+    #[test]
+    fn test_serialize_value() {
+        let value: Value = "a = [ 1 { b = x, \"c d\" = [] } ], e = { f = 2.5 }"
+            .parse()
+            .unwrap();
+        for options in [
+            SerializerOptions::new(),
+            SerializerOptions::new().braceless(true).inline_width(20),
+            SerializerOptions::new().skip_empty_collections(true),
+        ] {
+            assert_eq!(
+                to_string_value(&value, options.clone()).unwrap(),
+                to_string_with_options(&value, options).unwrap()
+            );
+        }
+
+        let depth = 100_000;
+        let mut deep = Value::from("x");
+        for i in 0..depth {
+            deep = match i % 2 {
+                0 => Value::Array(vec![deep]),
+                _ => Value::Object([("a", deep)].into_iter().collect()),
+            };
+        }
+        let options = SerializerOptions::new().indent_style(IndentStyle::Spaces(0));
+        let output = to_string_value(&deep, options).unwrap();
+        assert!(output.starts_with("{\na = [\n{\na = [\n"));
+        assert_eq!(output.matches('x').count(), 1);
+    }
+
+    #[test]
+    fn test_flatten() {
+        #[derive(Serialize)]
+        struct Node {
+            name: &'static str,
+            #[serde(flatten)]
+            audio: Audio,
+            #[serde(flatten)]
+            extra: std::collections::BTreeMap<&'static str, u32>,
+            #[serde(flatten)]
+            missing: Option<Audio>,
+        }
+
+        #[derive(Serialize)]
+        struct Audio {
+            #[serde(rename = "audio.rate")]
+            rate: u32,
+            #[serde(flatten)]
+            format: Format,
+        }
+
+        #[derive(Serialize)]
+        struct Format {
+            #[serde(rename = "audio.format")]
+            format: &'static str,
+        }
+
+        let node = Node {
+            name: "sink",
+            audio: Audio {
+                rate: 48000,
+                format: Format { format: "S16LE" },
+            },
+            extra: [("priority.session", 1000)].into_iter().collect(),
+            missing: None,
+        };
+        let expected = "{\n  name = sink\n  audio.rate = 48000\n  audio.format = S16LE\n  priority.session = 1000\n}";
+        assert_eq!(to_string(&node).unwrap(), expected);
+
+        let options = SerializerOptions::new().align_equals(true).braceless(true);
+        let expected = "name             = sink\naudio.rate       = 48000\naudio.format     = S16LE\npriority.session = 1000\n";
+        assert_eq!(to_string_with_options(&node, options).unwrap(), expected);
+
+        let map: std::collections::BTreeMap<_, _> = [((1, 2), 3)].into_iter().collect();
+        assert_eq!(
+            to_string(&map).unwrap_err().to_string(),
+            "map key must be a string or number"
+        );
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let options = SerializerOptions::new().trailing_newline(true);
+        assert_eq!(
+            to_string_with_options(&[1], options.clone()).unwrap(),
+            "[\n  1\n]\n"
+        );
+        assert_eq!(to_string(&[1]).unwrap(), "[\n  1\n]");
+
+        let options = options.braceless(true);
+        let value: Value = "a = 1 b = [ 2 ]".parse().unwrap();
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "a = 1\nb = [\n  2\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_indent_style() {
+        let value = [[1]];
+        let options = SerializerOptions::new().indent_style(IndentStyle::Tabs);
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "[\n\t[\n\t\t1\n\t]\n]"
+        );
+        let options = SerializerOptions::new().indent_style(IndentStyle::Spaces(4));
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "[\n    [\n        1\n    ]\n]"
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        let strings = ["Horst Schlämmer", "a=b", "", "🎧", "alsa_output.pci-0000"];
+        assert_eq!(
+            to_string(&strings).unwrap(),
+            "[\n  \"Horst Schlämmer\"\n  \"a=b\"\n  \"\"\n  \"🎧\"\n  alsa_output.pci-0000\n]"
+        );
+        let options = SerializerOptions::new().ascii_only(true).inline_width(80);
+        let output = to_string_with_options(&strings, options).unwrap();
+        assert_eq!(
+            output,
+            r#"[ "Horst Schl\u00e4mmer" "a=b" "" "\ud83c\udfa7" alsa_output.pci-0000 ]"#
+        );
+        assert!(output.is_ascii());
+    }
+
+    #[test]
+    fn test_typed_strings() {
+        // Strings that read back as another type when bare are quoted.
+        let strings = [
+            "true", "false", "null", "5", "-48000", "0x10", "1e6", ".5", "nan", "-inf", "Infinity",
+            "trueish", "5.1.2", "0xzz", "infinite",
+        ];
+        let output = to_string_with_options(&strings, SerializerOptions::new().inline_width(200));
+        assert_eq!(
+            output.unwrap(),
+            r#"[ "true" "false" "null" "5" "-48000" "0x10" "1e6" ".5" "nan" "-inf" "Infinity" trueish 5.1.2 0xzz infinite ]"#
+        );
+        for s in strings {
+            let value = Value::from(s);
+            let options = ParseOptions::new().non_finite(crate::de::NonFinite::Float);
+            let text = to_string(&value).unwrap();
+            assert_eq!(from_str::<Value>(&text).unwrap(), value);
+            assert_eq!(
+                from_str_with_options::<Value>(&text, options).unwrap(),
+                value
+            );
+            assert_eq!(from_str::<String>(&text).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_chars() {
+        for c in (' '..='~').filter(|c| !c.is_ascii_alphanumeric()) {
+            let output = to_string(&c).unwrap();
+            assert!(
+                output.starts_with('"') && output.ends_with('"'),
+                "{}",
+                output
+            );
+            assert_eq!(from_str::<char>(&output).unwrap(), c);
+            assert_eq!(
+                from_str::<Value>(&output).unwrap(),
+                Value::from(c.to_string())
+            );
+
+            let entry = to_string(&BTreeMap::from([(c, c)])).unwrap();
+            assert_eq!(from_str::<BTreeMap<char, char>>(&entry).unwrap()[&c], c);
+        }
+        assert_eq!(
+            to_string(&['a', '7', 'é']).unwrap(),
+            "[\n  a\n  \"7\"\n  \"é\"\n]"
+        );
+        let options = SerializerOptions::new()
+            .single_quotes(true)
+            .ascii_only(true);
+        assert_eq!(
+            to_string_with_options(&'\'', options.clone()).unwrap(),
+            r"'\''"
+        );
+        assert_eq!(to_string_with_options(&'é', options).unwrap(), r"'\u00e9'");
+    }
+
+    #[test]
+    fn test_unit_struct_names() {
+        #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+        struct Required;
+        #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+        #[serde(rename = "any-of")]
+        struct AnyOf;
+        #[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+        struct Schema {
+            name: Required,
+            kind: AnyOf,
+        }
+
+        let options = SerializerOptions::new().unit_struct_names(true);
+        let output = to_string_with_options(&Schema::default(), options).unwrap();
+        assert_eq!(output, "{\n  name = Required\n  kind = any-of\n}");
+        assert_eq!(from_str::<Schema>(&output).unwrap(), Schema::default());
+        let output = to_string(&Schema::default()).unwrap();
+        assert_eq!(output, "{\n  name = null\n  kind = null\n}");
+        assert_eq!(from_str::<Schema>(&output).unwrap(), Schema::default());
+        assert_eq!(
+            from_str::<Schema>("name = Optional, kind = any-of")
+                .unwrap_err()
+                .to_string(),
+            "expected null or `Required` at line 1 column 8"
+        );
+    }
+
+    #[test]
+    fn test_floats() {
+        let floats = [0.5, 1e21, -1.5e-7, 0.0, 1e15];
+        let options = SerializerOptions::new().inline_width(80);
+        let output = to_string_with_options(&floats, options.clone()).unwrap();
+        assert_eq!(output, "[ 0.5 1e21 -1.5e-7 0 1000000000000000 ]");
+        let parsed: Vec<f64> = crate::de::from_str(&output).unwrap();
+        assert_eq!(parsed, floats);
+
+        let options = options.plain_decimal(true);
+        assert_eq!(
+            to_string_with_options(&floats, options.clone()).unwrap(),
+            "[ 0.5 1000000000000000000000 -0.00000015 0 1000000000000000 ]"
+        );
+
+        let floats = [55.333333, 0.25, -1.5e-7, 2.9999, 1e21];
+        assert_eq!(
+            to_string_with_options(&floats, options.max_decimals(3)).unwrap(),
+            "[ 55.333 0.25 0 3 1000000000000000000000 ]"
+        );
+
+        let floats = [0.1f32, 0.7, -3.4e38, 1.5e-7, 16777216.0];
+        let output =
+            to_string_with_options(&floats, SerializerOptions::new().inline_width(80)).unwrap();
+        assert_eq!(output, "[ 0.1 0.7 -3.4e38 1.5e-7 16777216 ]");
+        let parsed: Vec<f32> = crate::de::from_str(&output).unwrap();
+        assert_eq!(parsed, floats);
+    }
+
+    #[test]
+    fn test_single_quotes() {
+        let strings = ["it's", "'a'", "say \"hi\""];
+        let options = SerializerOptions::new()
+            .single_quotes(true)
+            .inline_width(80);
+        let output = to_string_with_options(&strings, options).unwrap();
+        assert_eq!(output, r#"[ it's '\'a\'' 'say "hi"' ]"#);
+        let parsed: Vec<String> = crate::de::from_str(&output).unwrap();
+        assert_eq!(parsed, strings);
+        assert_eq!(to_string(&"'a'").unwrap(), r#""'a'""#);
+    }
+
+    #[test]
+    fn test_escape_control_characters() {
+        let short = [
+            (8, "\\b"),
+            (9, "\\t"),
+            (10, "\\n"),
+            (12, "\\f"),
+            (13, "\\r"),
+        ];
+        for code in 0u8..0x20 {
+            let c = char::from(code);
+            let expected = match short.iter().find(|(k, _)| *k == code) {
+                Some((_, escape)) => escape.to_string(),
+                None => format!("\\u{:04x}", code),
+            };
+            assert_eq!(escape_str(&c.to_string()), expected);
+
+            let output = to_string(&format!("a{}b", c)).unwrap();
+            assert_eq!(output, format!("\"a{}b\"", expected));
+            let parsed: String = crate::de::from_str(&output).unwrap();
+            assert_eq!(parsed, format!("a{}b", c));
+        }
+        assert_eq!(escape_str("\u{7f} é"), "\u{7f} é");
+    }
+
+    #[test]
+    fn test_line_ending() {
+        let options = SerializerOptions::new()
+            .line_ending(LineEnding::CrLf)
+            .trailing_newline(true);
+        let expected = "{\r\n  a = [\r\n    1\r\n  ]\r\n}\r\n";
+        let value = [("a", [1])]
+            .into_iter()
+            .collect::<std::collections::BTreeMap<_, _>>();
+        assert_eq!(
+            to_string_with_options(&value, options.clone()).unwrap(),
+            expected
+        );
+
+        let options = options.inline_width(12);
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "{\r\n  a = [ 1 ]\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn test_align_equals() {
+        #[derive(Serialize)]
+        struct Props {
+            #[serde(rename = "node.name")]
+            name: &'static str,
+            #[serde(rename = "priority.session")]
+            priority: u32,
+            #[serde(rename = "audio.position")]
+            position: Vec<&'static str>,
+        }
+
+        let props = Props {
+            name: "speakers",
+            priority: 1000,
+            position: vec!["FL"],
+        };
+        let options = SerializerOptions::new().align_equals(true);
+        let expected = "{\n  node.name        = speakers\n  priority.session = 1000\n  audio.position   = [\n    FL\n  ]\n}";
+        assert_eq!(to_string_with_options(&props, options).unwrap(), expected);
+
+        let options = SerializerOptions::new().align_equals(true).inline_width(30);
+        let expected = "{\n  node.name        = speakers\n  priority.session = 1000\n  audio.position   = [ FL ]\n}";
+        assert_eq!(to_string_with_options(&props, options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_inline_width() {
+        #[derive(Serialize)]
+        struct Test {
+            position: Vec<&'static str>,
+            props: Vec<(u32, u32)>,
+            empty: Vec<u32>,
+        }
+
+        let test = Test {
+            position: vec!["FL", "FR"],
+            props: vec![(1, 2), (3, 4)],
+            empty: vec![],
+        };
+        let options = SerializerOptions::new().inline_width(24);
+        let expected = "{\n  position = [ FL FR ]\n  props = [\n    [ 1 2 ]\n    [ 3 4 ]\n  ]\n  empty = [ ]\n}";
+        assert_eq!(to_string_with_options(&test, options).unwrap(), expected);
+
+        let options = SerializerOptions::new().inline_width(80);
+        let expected = "{ position = [ FL FR ] props = [ [ 1 2 ] [ 3 4 ] ] empty = [ ] }";
+        assert_eq!(to_string_with_options(&test, options).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_max_width() {
+        let value: Value = r#"
+            node.description = "Built-in Audio Analog Stereo"
+            audio.position = [ FL FR ]
+            node.nick = short
+            api.alsa.path = [ front surround40 surround51 ]
+        "#
+        .parse()
+        .unwrap();
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .inline_width(40)
+            .max_width(30);
+        let expected = "node.description =\n  \"Built-in Audio Analog Stereo\"\n\
+                        audio.position = [ FL FR ]\n\
+                        node.nick = short\n\
+                        api.alsa.path = [\n  front\n  surround40\n  surround51\n]\n";
+        assert_eq!(to_string_with_options(&value, options).unwrap(), expected);
+
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .inline_width(40)
+            .max_width(40);
+        let written = to_string_with_options(&value, options).unwrap();
+        assert!(written.contains("api.alsa.path =\n  [ front surround40 surround51 ]\n"));
+        assert_eq!(written.parse::<Value>().unwrap(), value);
+
+        let options = SerializerOptions::new().max_width(16).wrap_strings(true);
+        let written = to_string_with_options(&["a long   string with spaces"], options).unwrap();
+        assert_eq!(
+            written,
+            "[\n  \"a long   \\\n    string \\\n    with spaces\"\n]"
+        );
+        assert!(written.lines().all(|line| line.chars().count() <= 16));
+        let continuations = ParseOptions::new().line_continuations(true);
+        let read: Vec<String> = from_str_with_options(&written, continuations).unwrap();
+        assert_eq!(read, ["a long   string with spaces"]);
+        assert!(from_str::<Vec<String>>(&written).is_err());
+
+        let options = SerializerOptions::new()
+            .ascii_only(true)
+            .max_width(16)
+            .wrap_strings(true);
+        let strings = ["𐌭𐌭", "aaaa𐌭", "aaaaaaaaa𐌭"];
+        let written = to_string_with_options(&strings, options).unwrap();
+        assert!(!written.contains("\\ud800\\\n"));
+        let continuations = ParseOptions::new().line_continuations(true);
+        let read: Vec<String> = from_str_with_options(&written, continuations).unwrap();
+        assert_eq!(read, strings);
+
+        #[derive(Serialize)]
+        struct Node {
+            name: &'static str,
+            ports: Vec<u32>,
+        }
+
+        let options = || SerializerOptions::new().max_width(80);
+        for text in ["a = {}", "{ a = [] }", "a = { b = [] c = {} } d = 1"] {
+            let value: Value = text.parse().unwrap();
+            let written = to_string_with_options(&value, options()).unwrap();
+            assert_eq!(written.parse::<Value>().unwrap(), value);
+        }
+        let node = Node {
+            name: "alsa_output",
+            ports: Vec::new(),
+        };
+        assert_eq!(
+            to_string_with_options(&node, options()).unwrap(),
+            "{\n  name = alsa_output\n  ports = [\n  ]\n}"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        fn comments(path: &[PathSegment]) -> Option<String> {
+            match path {
+                [PathSegment::Key(key)] if key == "context.properties" => {
+                    Some("Properties of the daemon.\n\nSee pipewire(1).".to_owned())
+                }
+                [.., PathSegment::Index(0), PathSegment::Key(key)] if key == "name" => {
+                    Some("The first module".to_owned())
+                }
+                [.., PathSegment::Key(key)] if key == "a b" => Some("quoted".to_owned()),
+                _ => None,
+            }
+        }
+
+        let value: Value = "context.properties = { \"a b\" = 1, c = 2 }\n\
+                            context.modules = [ { name = rt } { name = x } ]"
+            .parse()
+            .unwrap();
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .align_equals(true)
+            .inline_width(40)
+            .comments(comments);
+        assert_eq!(
+            to_string_with_options(&value, options).unwrap(),
+            "# Properties of the daemon.\n\
+             #\n\
+             # See pipewire(1).\n\
+             context.properties = {\n  \
+               # quoted\n  \
+               \"a b\" = 1\n  \
+               c     = 2\n\
+             }\n\
+             context.modules    = [\n  \
+               {\n    \
+                 # The first module\n    \
+                 name = rt\n  \
+               }\n  \
+               { name = x }\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn test_banner() {
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .line_ending(LineEnding::CrLf)
+            .banner("# Generated by my-tool — do not edit\n\nrates");
+        let value: Value = "a = 1".parse().unwrap();
+        let mut serializer = Serializer::with_options(String::new(), options);
+        serializer.serialize(&value).unwrap();
+        serializer.serialize(&[1]).unwrap();
+        assert_eq!(
+            serializer.into_inner(),
+            "# Generated by my-tool — do not edit\r\n#\r\n# rates\r\n\r\na = 1\r\n\
+             # Generated by my-tool — do not edit\r\n#\r\n# rates\r\n\r\n[\r\n  1\r\n]"
+        );
+    }
+
+    #[test]
+    fn test_comment_out_values() {
+        fn comments(path: &[PathSegment]) -> Option<String> {
+            match path.last() {
+                Some(PathSegment::Key(key)) if key == "rate" => Some("Hz".to_owned()),
+                _ => None,
+            }
+        }
+        let value: Value = "a = { rate = 48000, b = { } }, c = [ { rate = 1 } ], d = [ 1 2 ]"
+            .parse()
+            .unwrap();
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .comment_out_values(true)
+            .comments(comments);
+        assert_eq!(
+            to_string_value(&value, options.clone()).unwrap(),
+            "a = {\n  # Hz\n  #rate = 48000\n  #b = {\n  #}\n}\n\
+             #c = [\n  #{\n    # Hz\n    #rate = 1\n  #}\n#]\n\
+             #d = [\n  #1\n  #2\n#]\n"
+        );
+        let options = options.inline_width(40).align_equals(true);
+        assert_eq!(
+            to_string_value(&value, options).unwrap(),
+            "a = {\n  # Hz\n  #rate = 48000\n  #b    = { }\n}\n\
+             #c = [\n  #{\n    # Hz\n    #rate = 1\n  #}\n#]\n\
+             #d = [ 1 2 ]\n"
+        );
+    }
+
+    #[test]
+    fn test_skip_empty_collections() {
+        #[derive(Serialize)]
+        struct Person {
+            name: &'static str,
+            friends: Vec<&'static str>,
+            tags: std::collections::BTreeMap<&'static str, u32>,
+            nested: Vec<Vec<u32>>,
+        }
+
+        let person = Person {
+            name: "a",
+            friends: Vec::new(),
+            tags: [("b", 1)].into_iter().collect(),
+            nested: vec![Vec::new()],
+        };
+        let options = SerializerOptions::new()
+            .braceless(true)
+            .align_equals(true)
+            .skip_empty_collections(true);
+        assert_eq!(
+            to_string_with_options(&person, options.clone()).unwrap(),
+            "name   = a\ntags   = {\n  b = 1\n}\nnested = [\n  [\n  ]\n]\n"
+        );
+
+        let value: Value = "a = { b = {} }, c = [], d = { e = 1 }".parse().unwrap();
+        assert_eq!(
+            to_string_with_options(&value, options.align_equals(false)).unwrap(),
+            "d = {\n  e = 1\n}\n"
+        );
+        assert_eq!(to_string(&Vec::<u32>::new()).unwrap(), "[\n]");
+    }
+
+    #[test]
+    fn test_defaults() {
+        #[derive(Serialize)]
+        struct Stream {
+            rate: f64,
+            channels: Vec<&'static str>,
+            props: BTreeMap<&'static str, u32>,
+        }
+
+        impl Default for Stream {
+            fn default() -> Self {
+                Stream {
+                    rate: 48000.0,
+                    channels: vec!["FL", "FR"],
+                    props: [("latency", 1024), ("quantum", 256)].into_iter().collect(),
+                }
+            }
+        }
+
+        let options = SerializerOptions::new().braceless(true);
+        let stream = Stream {
+            rate: 44100.0,
+            ..Stream::default()
+        };
+        assert_eq!(
+            to_string_without_defaults(&stream, options.clone()).unwrap(),
+            "rate = 44100\n"
+        );
+        let mut stream = Stream::default();
+        stream.props.insert("quantum", 512);
+        assert_eq!(
+            to_string_without_defaults(&stream, options.clone()).unwrap(),
+            "props = {\n  quantum = 512\n}\n"
+        );
+        assert_eq!(
+            to_string_without_defaults(&Stream::default(), options.clone()).unwrap(),
+            ""
+        );
+
+        let defaults: Value = "a = [ { b = 1, c = 2 } ]".parse().unwrap();
+        let value: Value = "a = [ { b = 1, c = 3 } { b = 1 } ], d = 4".parse().unwrap();
+        assert_eq!(
+            to_string_with_options(&value, options.defaults(defaults).inline_width(40)).unwrap(),
+            "a = [ { c = 3 } { b = 1 } ]\nd = 4\n"
+        );
+    }
+}
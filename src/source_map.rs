@@ -0,0 +1,278 @@
+//! Byte ranges of the values of a document, looked up by their path, so
+//! that problems found in a parsed [`Value`] can be shown where they are
+//! in the text.
+//!
+//! ```
+//! use spa_json::path::PathSegment;
+//! use spa_json::source_map::{line_column, parse_with_source_map};
+//!
+//! let input = "node.name = alsa_output\nnode.rate = fast";
+//! let (value, map) = parse_with_source_map(input).unwrap();
+//! let span = map.get(&[PathSegment::Key("node.rate".to_owned())]).unwrap();
+//! assert_eq!(&input[span.value.clone()], "fast");
+//! assert_eq!(line_column(input, span.value.start), (2, 13));
+//! ```
+
+use crate::de;
+pub use crate::de::line_column;
+use crate::error::Error;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::path::PathSegment;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::ops::Range;
+
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    spans: HashMap<Vec<PathSegment>, Span>,
+}
+
+/// Where a value is in the text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    /// The key of an object member.
+    pub key: Option<Range<usize>>,
+    /// The value, brackets and quotes included. For a document without
+    /// braces, this is the whole text.
+    pub value: Range<usize>,
+}
+
+/// Parses a document along with the positions of all values in it.
+pub fn parse_with_source_map(input: &str) -> Result<(Value, SourceMap), Error> {
+    let value = input.parse()?;
+    let mut map = SourceMap::default();
+    map.add(&Node::build(input, 0), &mut Vec::new());
+    Ok((value, map))
+}
+
+impl SourceMap {
+    /// The span of the value at `path`. When a key is repeated in an
+    /// object, this is the last one, whose value is kept.
+    pub fn get(&self, path: &[PathSegment]) -> Option<&Span> {
+        self.spans.get(path)
+    }
+
+    /// The span of the value at the longest prefix of `path` that exists,
+    /// for pointing at the closest place to something that is missing.
+    pub fn nearest(&self, path: &[PathSegment]) -> Option<&Span> {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.get(&path[..len]))
+    }
+
+    /// The paths and spans of all values, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[PathSegment], &Span)> {
+        self.spans.iter().map(|(path, span)| (&path[..], span))
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    pub(crate) fn add(&mut self, node: &Node, path: &mut Vec<PathSegment>) {
+        self.spans.insert(
+            path.clone(),
+            Span {
+                key: node.key.as_ref().map(|(_, span)| span.clone()),
+                value: node.span.clone(),
+            },
+        );
+        for (i, child) in node.children.iter().enumerate() {
+            path.push(match &child.key {
+                Some((key, _)) => PathSegment::Key(key.clone()),
+                None => PathSegment::Index(i),
+            });
+            self.add(child, path);
+            path.pop();
+        }
+    }
+}
+
+/// A value in the text, with the values it contains.
+#[derive(Clone, Debug)]
+pub(crate) struct Node {
+    /// Byte range of the value, brackets included.
+    pub(crate) span: Range<usize>,
+    /// The key of an object member and its byte range.
+    pub(crate) key: Option<(String, Range<usize>)>,
+    /// The items or members of a container, in the order they are written.
+    pub(crate) children: Vec<Node>,
+    /// Whether this is an array or object with brackets.
+    pub(crate) container: bool,
+}
+
+impl Node {
+    /// Builds the tree of `text`, which must parse, with spans starting at
+    /// `offset`.
+    pub(crate) fn build(text: &str, offset: usize) -> Node {
+        let tokens: Vec<Token> = Lexer::new(text)
+            .filter(|token| !token.kind.is_trivia())
+            .collect();
+        let mut builder = Builder { tokens, pos: 0 };
+        // Like the parser, take anything but a container or a single
+        // scalar as an object without braces.
+        let braceless = match builder.tokens.first() {
+            None => true,
+            Some(token) => {
+                !matches!(token.kind, TokenKind::ObjectStart | TokenKind::ArrayStart)
+                    && builder.tokens.len() > 1
+            }
+        };
+        let mut root = if braceless {
+            Node {
+                span: 0..text.len(),
+                key: None,
+                children: builder.members(),
+                container: false,
+            }
+        } else {
+            builder.value()
+        };
+        root.shift(offset as isize);
+        root
+    }
+
+    pub(crate) fn shift(&mut self, delta: isize) {
+        self.span.start = self.span.start.wrapping_add_signed(delta);
+        self.span.end = self.span.end.wrapping_add_signed(delta);
+        if let Some((_, span)) = &mut self.key {
+            span.start = span.start.wrapping_add_signed(delta);
+            span.end = span.end.wrapping_add_signed(delta);
+        }
+        for child in &mut self.children {
+            child.shift(delta);
+        }
+    }
+}
+
+/// Walks the tokens of text that is known to parse.
+struct Builder<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl Builder<'_> {
+    fn next(&mut self) -> &Token<'_> {
+        self.pos += 1;
+        &self.tokens[self.pos - 1]
+    }
+
+    fn peek(&self) -> Option<TokenKind> {
+        self.tokens.get(self.pos).map(|token| token.kind)
+    }
+
+    fn value(&mut self) -> Node {
+        let token = self.next();
+        let (kind, span) = (token.kind, token.span.clone());
+        let children = match kind {
+            TokenKind::ObjectStart => self.members(),
+            TokenKind::ArrayStart => {
+                let mut children = Vec::new();
+                while self.peek() != Some(TokenKind::ArrayEnd) {
+                    children.push(self.value());
+                }
+                children
+            }
+            _ => {
+                return Node {
+                    span,
+                    key: None,
+                    children: Vec::new(),
+                    container: false,
+                }
+            }
+        };
+        Node {
+            span: span.start..self.next().span.end,
+            key: None,
+            children,
+            container: true,
+        }
+    }
+
+    /// Reads members up to the closing brace or the end of the text.
+    fn members(&mut self) -> Vec<Node> {
+        let mut children = Vec::new();
+        while !matches!(self.peek(), None | Some(TokenKind::ObjectEnd)) {
+            let token = self.next();
+            let key = match token.kind {
+                TokenKind::Quoted => de::from_str(token.text).unwrap_or_default(),
+                _ => token.text.to_owned(),
+            };
+            let key_span = token.span.clone();
+            while self.peek() == Some(TokenKind::Separator) {
+                self.pos += 1;
+            }
+            let mut child = self.value();
+            child.key = Some((key, key_span));
+            children.push(child);
+        }
+        children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_map() {
+        let input =
+            "context.modules = [\n  { name = rt, args = { nice.level = -11 } }\n  \"x\" = 1 \n]";
+        assert!(parse_with_source_map(input).is_err());
+
+        let input = "# modules\ncontext.modules = [\n  { name = rt, args = { nice.level = -11 } }\n]\n\"a b\" = 1, \"a b\" = 2";
+        let (value, map) = parse_with_source_map(input).unwrap();
+        assert_eq!(value.get("a b"), Some(&Value::from(2)));
+        assert_eq!(map.len(), 7);
+        let text = |path: &[PathSegment]| {
+            let span = map.get(path).unwrap();
+            (
+                span.key.clone().map(|key| &input[key]),
+                &input[span.value.clone()],
+            )
+        };
+        let modules = PathSegment::Key("context.modules".to_owned());
+        let level = [
+            modules.clone(),
+            PathSegment::Index(0),
+            PathSegment::Key("args".to_owned()),
+            PathSegment::Key("nice.level".to_owned()),
+        ];
+        assert_eq!(text(&[]), (None, input));
+        assert_eq!(text(&level), (Some("nice.level"), "-11"));
+        assert_eq!(text(&level[..3]), (Some("args"), "{ nice.level = -11 }"));
+        let offset = input.find("level").unwrap();
+        let (path, _) = map
+            .iter()
+            .find(|(_, span)| span.key.as_ref().is_some_and(|key| key.contains(&offset)))
+            .unwrap();
+        assert_eq!(path, level);
+        assert_eq!(
+            text(&[modules, PathSegment::Index(0)]),
+            (None, "{ name = rt, args = { nice.level = -11 } }")
+        );
+        assert_eq!(
+            text(&[PathSegment::Key("a b".to_owned())]),
+            (Some("\"a b\""), "2")
+        );
+
+        let missing = [
+            level[0].clone(),
+            PathSegment::Index(0),
+            PathSegment::Key("flags".to_owned()),
+        ];
+        assert_eq!(map.get(&missing), None);
+        assert_eq!(map.nearest(&missing), map.get(&missing[..2]));
+
+        assert_eq!(
+            line_column(input, map.get(&level).unwrap().value.start),
+            (3, 38)
+        );
+        assert_eq!(line_column("é\nü", 5), (2, 2));
+    }
+}
@@ -1,510 +0,0 @@
-use serde::{ser, Serialize};
-use std::io;
-
-pub struct Serializer {
-    output: String,
-    indent: usize,
-}
-
-pub fn to_string<T>(value: &T) -> Result<String, Error>
-where
-    T: Serialize,
-{
-    let mut serializer = Serializer {
-        output: String::new(),
-        indent: 0,
-    };
-    value.serialize(&mut serializer)?;
-    Ok(serializer.output)
-}
-
-impl Serializer {
-    fn indent(&mut self) {
-        self.indent += 2;
-    }
-
-    fn dedent(&mut self) {
-        self.indent = self.indent.saturating_sub(2);
-    }
-
-    fn write_indent(&mut self) {
-        self.output += &" ".repeat(self.indent);
-    }
-}
-
-impl<'a> ser::Serializer for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-    type SerializeSeq = Self;
-    type SerializeTuple = Self;
-    type SerializeTupleStruct = Self;
-    type SerializeTupleVariant = Self;
-    type SerializeMap = Self;
-    type SerializeStruct = Self;
-    type SerializeStructVariant = Self;
-
-    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.output += if v { "true" } else { "false" };
-        Ok(())
-    }
-
-    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
-        self.serialize_i64(i64::from(v))
-    }
-
-    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
-        self.serialize_i64(i64::from(v))
-    }
-
-    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
-        self.serialize_i64(i64::from(v))
-    }
-
-    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
-        self.output += &v.to_string();
-        Ok(())
-    }
-
-    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
-        self.serialize_u64(u64::from(v))
-    }
-
-    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
-        self.serialize_u64(u64::from(v))
-    }
-
-    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
-        self.serialize_u64(u64::from(v))
-    }
-
-    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
-        self.output += &v.to_string();
-        Ok(())
-    }
-
-    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
-        self.serialize_f64(f64::from(v))
-    }
-
-    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
-        self.output += &v.to_string();
-        Ok(())
-    }
-
-    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(&v.to_string())
-    }
-
-    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
-        self.output += &escape_string(v);
-        Ok(())
-    }
-
-    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        use serde::ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
-        }
-        seq.end()
-    }
-
-    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.serialize_unit()
-    }
-
-    fn serialize_some<T: ?Sized>(self, value: &T) -> Result<Self::Ok, Self::Error>
-    where
-        T: Serialize,
-    {
-        value.serialize(self)
-    }
-
-    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
-        self.output += "null";
-        Ok(())
-    }
-
-    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
-        self.serialize_unit()
-    }
-
-    fn serialize_unit_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        variant: &'static str,
-    ) -> Result<Self::Ok, Self::Error> {
-        self.serialize_str(variant)
-    }
-
-    fn serialize_newtype_struct<T: ?Sized>(
-        self,
-        _name: &'static str,
-        value: &T,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        T: Serialize,
-    {
-        value.serialize(self)
-    }
-
-    fn serialize_newtype_variant<T: ?Sized>(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        variant: &'static str,
-        value: &T,
-    ) -> Result<Self::Ok, Self::Error>
-    where
-        T: Serialize,
-    {
-        self.output += "{ ";
-        variant.serialize(&mut *self)?;
-        self.output += " = ";
-        value.serialize(&mut *self)?;
-        self.output += " }";
-        Ok(())
-    }
-
-    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
-        self.output += "[\n";
-        self.indent();
-        Ok(self)
-    }
-
-    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
-        self.serialize_seq(Some(len))
-    }
-
-    fn serialize_tuple_struct(
-        self,
-        _name: &'static str,
-        len: usize,
-    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        self.serialize_seq(Some(len))
-    }
-
-    fn serialize_tuple_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
-        self.output += "{\n";
-        self.indent();
-        self.write_indent();
-        variant.serialize(&mut *self)?;
-        self.output += " = [\n";
-        self.indent();
-        Ok(self)
-    }
-
-    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
-        self.output += "{\n";
-        self.indent();
-        Ok(self)
-    }
-
-    fn serialize_struct(
-        self,
-        _name: &'static str,
-        len: usize,
-    ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.serialize_map(Some(len))
-    }
-
-    fn serialize_struct_variant(
-        self,
-        _name: &'static str,
-        _variant_index: u32,
-        variant: &'static str,
-        _len: usize,
-    ) -> Result<Self::SerializeStructVariant, Self::Error> {
-        self.output += "{\n";
-        self.indent();
-        self.write_indent();
-        variant.serialize(&mut *self)?;
-        self.output += " = {\n";
-        self.indent();
-        Ok(self)
-    }
-}
-
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "]";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_element<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "]";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "]";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_field<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "]\n";
-        self.dedent();
-        self.write_indent();
-        self.output += "}";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeMap for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_key<T: ?Sized>(&mut self, key: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        key.serialize(&mut **self)
-    }
-
-    fn serialize_value<T: ?Sized>(&mut self, value: &T) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.output += " = ";
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "}";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        key.serialize(&mut **self)?;
-        self.output += " = ";
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "}";
-        Ok(())
-    }
-}
-
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
-    type Ok = ();
-    type Error = Error;
-
-    fn serialize_field<T: ?Sized>(
-        &mut self,
-        key: &'static str,
-        value: &T,
-    ) -> Result<(), Self::Error>
-    where
-        T: Serialize,
-    {
-        self.write_indent();
-        key.serialize(&mut **self)?;
-        self.output += " = ";
-        value.serialize(&mut **self)?;
-        self.output += "\n";
-        Ok(())
-    }
-
-    fn end(self) -> Result<(), Self::Error> {
-        self.dedent();
-        self.write_indent();
-        self.output += "}\n";
-        self.dedent();
-        self.write_indent();
-        self.output += "}";
-        Ok(())
-    }
-}
-
-#[derive(Debug)]
-pub enum Error {
-    Message(String),
-    Io(io::Error),
-}
-
-impl ser::Error for Error {
-    fn custom<T: std::fmt::Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
-    }
-}
-
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Error::Message(msg) => write!(f, "{}", msg),
-            Error::Io(err) => write!(f, "IO error: {}", err),
-        }
-    }
-}
-
-impl std::error::Error for Error {}
-
-impl From<io::Error> for Error {
-    fn from(err: io::Error) -> Self {
-        Error::Io(err)
-    }
-}
-
-fn escape_string(s: &str) -> String {
-    let mut escaped = String::with_capacity(s.len());
-    for c in s.chars() {
-        match c {
-            '"' => escaped.push_str("\\\""),
-            '\\' => escaped.push_str("\\\\"),
-            '\n' => escaped.push_str("\\n"),
-            '\r' => escaped.push_str("\\r"),
-            '\t' => escaped.push_str("\\t"),
-            '\u{0008}' => escaped.push_str("\\b"),
-            '\u{000C}' => escaped.push_str("\\f"),
-            _ => escaped.push(c),
-        }
-    }
-    escaped
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde::Serialize;
-
-    #[test]
-    fn test_struct() {
-        #[derive(Serialize)]
-        struct Test {
-            int: u32,
-            seq: Vec<&'static str>,
-            str: String,
-        }
-
-        let test = Test {
-            int: 1,
-            seq: vec!["a", "b"],
-            str: "string".to_owned(),
-        };
-        let expected = "{\n  int = 1\n  seq = [\n    a\n    b\n  ]\n  str = string\n}";
-        assert_eq!(to_string(&test).unwrap(), expected);
-    }
-
-    #[test]
-    fn test_enum() {
-        #[derive(Serialize)]
-        enum E {
-            Unit,
-            Newtype(u32),
-            Tuple(u32, u32),
-            Struct { a: u32 },
-        }
-
-        let u = E::Unit;
-        let expected = "Unit";
-        assert_eq!(to_string(&u).unwrap(), expected);
-
-        let n = E::Newtype(1);
-        let expected = "{ Newtype = 1 }";
-        assert_eq!(to_string(&n).unwrap(), expected);
-
-        let t = E::Tuple(1, 2);
-        let expected = "{\n  Tuple = [\n    1\n    2\n  ]\n}";
-        assert_eq!(to_string(&t).unwrap(), expected);
-
-        let s = E::Struct { a: 1 };
-        let expected = "{\n  Struct = {\n    a = 1\n  }\n}";
-        assert_eq!(to_string(&s).unwrap(), expected);
-    }
-}
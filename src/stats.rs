@@ -0,0 +1,133 @@
+//! Counts of what a document is made of, for dashboards and for choosing
+//! how to parse it, such as [`ParseOptions::max_depth`] or the
+//! [`iterative`](crate::de::from_str_value) parser for
+//! deep documents.
+//!
+//! ```
+//! use spa_json::stats::analyze;
+//!
+//! let stats = analyze("context.modules = [ { name = a } { name = b, args = { } } ]");
+//! assert_eq!(stats.objects, 4);
+//! assert_eq!(stats.arrays, 1);
+//! assert_eq!(stats.max_depth, 4);
+//! assert_eq!(stats.keys, 4);
+//! assert_eq!(stats.distinct_keys, 3);
+//! ```
+//!
+//! [`ParseOptions::max_depth`]: crate::de::ParseOptions::max_depth
+
+use crate::de::unescape_str;
+use crate::highlight::{classify, TokenClass};
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Objects, counting a document without braces as one.
+    pub objects: usize,
+    pub arrays: usize,
+    /// String values, quoted or bare. Keys are counted separately.
+    pub strings: usize,
+    pub numbers: usize,
+    /// `true` and `false`.
+    pub booleans: usize,
+    pub nulls: usize,
+    /// Keys of all objects, counting duplicates.
+    pub keys: usize,
+    /// Keys that differ from each other once unescaped.
+    pub distinct_keys: usize,
+    /// The deepest nesting of objects and arrays, with the document itself
+    /// at depth 1 when it is a container or an object without braces.
+    pub max_depth: usize,
+    /// The length of the document in bytes.
+    pub bytes: usize,
+}
+
+/// Counts the values, keys and nesting of `input` from its tokens, without
+/// building values. Like [`classify`], it works on text that does not parse:
+/// unterminated strings and bare scalars that need quoting count as strings,
+/// and unmatched closing brackets are ignored.
+pub fn analyze(input: &str) -> Stats {
+    let mut stats = Stats {
+        bytes: input.len(),
+        ..Stats::default()
+    };
+    let mut keys = HashSet::new();
+    let mut depth = 0;
+    let tokens = classify(input);
+    let first = tokens
+        .iter()
+        .find(|(_, class)| *class != TokenClass::Comment);
+    if let Some((_, TokenClass::Key)) = first {
+        stats.objects += 1;
+        depth = 1;
+    }
+    let root = depth;
+    stats.max_depth = depth;
+    for (span, class) in tokens {
+        let text = &input[span];
+        match class {
+            TokenClass::Key => {
+                stats.keys += 1;
+                let key = match text.as_bytes()[0] {
+                    b'"' | b'\'' if text.len() > 1 => {
+                        let inner = &text[1..text.len() - 1];
+                        unescape_str(inner).unwrap_or(inner.into())
+                    }
+                    _ => text.into(),
+                };
+                if !keys.contains(&*key) {
+                    keys.insert(key.into_owned());
+                }
+            }
+            TokenClass::String | TokenClass::Invalid => stats.strings += 1,
+            TokenClass::Number => stats.numbers += 1,
+            TokenClass::Boolean => stats.booleans += 1,
+            TokenClass::Null => stats.nulls += 1,
+            TokenClass::Punctuation => match text {
+                "{" | "[" => {
+                    match text {
+                        "{" => stats.objects += 1,
+                        _ => stats.arrays += 1,
+                    }
+                    depth += 1;
+                    stats.max_depth = stats.max_depth.max(depth);
+                }
+                "}" | "]" if depth > root => depth -= 1,
+                _ => {}
+            },
+            TokenClass::Comment => {}
+        }
+    }
+    stats.distinct_keys = keys.len();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze() {
+        let input = "# rt\na = [ 1 2.5 \"x\" y true null ], \"a\" = { 'b' = false, b = [ [ ] ] }";
+        assert_eq!(
+            analyze(input),
+            Stats {
+                objects: 2,
+                arrays: 3,
+                strings: 2,
+                numbers: 2,
+                booleans: 2,
+                nulls: 1,
+                keys: 4,
+                distinct_keys: 2,
+                max_depth: 4,
+                bytes: input.len(),
+            }
+        );
+        assert_eq!(analyze("").max_depth, 0);
+        assert_eq!(analyze("42").numbers, 1);
+        let stats = analyze("[ [ ] ] ] ] { a = \"open");
+        assert_eq!((stats.arrays, stats.objects, stats.strings), (2, 1, 1));
+        assert_eq!(stats.max_depth, 2);
+    }
+}
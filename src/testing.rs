@@ -0,0 +1,93 @@
+//! Helpers for testing code that reads or writes configuration.
+//!
+//! [`snapshot`] writes a value the same way every time, for snapshot tests
+//! with a tool such as [insta]:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! let props = HashMap::from([("node.name", "alsa_output"), ("media.class", "Audio/Sink")]);
+//! assert_eq!(
+//!     spa_json::testing::snapshot(&props),
+//!     "{\n  media.class = Audio/Sink\n  node.name = alsa_output\n}\n"
+//! );
+//! ```
+//!
+//! With the `proptest` feature, [proptest] strategies generate SPA-JSON
+//! documents for property tests. Generated values survive a serialize and
+//! parse round trip: floats always have a fractional part, since `2.0` is
+//! written as `2` and read back as an integer.
+//!
+//! ```
+//! # #[cfg(feature = "proptest")] {
+//! use proptest::prelude::*;
+//! use spa_json::testing::arb_config;
+//! use spa_json::value::Value;
+//!
+//! proptest!(|(config in arb_config())| {
+//!     let output = spa_json::ser::to_string(&config).unwrap();
+//!     prop_assert_eq!(output.parse::<Value>().unwrap(), config);
+//! });
+//! # }
+//! ```
+//!
+//! [insta]: https://docs.rs/insta
+//! [proptest]: https://docs.rs/proptest
+
+use crate::de::from_str;
+use crate::ser::{to_string_value, SerializerOptions};
+use crate::value::Value;
+use serde::Serialize;
+
+#[cfg(feature = "proptest")]
+pub use strategies::{
+    arb_config, arb_config_with, arb_key, arb_scalar, arb_string, arb_value, arb_value_with, Limits,
+};
+
+#[cfg(feature = "proptest")]
+mod strategies;
+
+/// Writes `value` for a snapshot: one entry or item per line, with the
+/// keys of every object sorted and integral floats written as integers, so
+/// that maps come out in the same order whatever their hasher and the
+/// output changes only when the data does. Arrays keep their order, so
+/// sets should be sorted before they are written.
+///
+/// # Panics
+///
+/// If `value` cannot be written as SPA-JSON, such as a map with keys that
+/// are not strings.
+pub fn snapshot<T: ?Sized + Serialize>(value: &T) -> String {
+    let mut value: Value = crate::ser::to_string(value)
+        .and_then(|text| from_str(&text))
+        .unwrap_or_else(|error| panic!("cannot write the snapshot: {error}"));
+    value.canonicalize();
+    let options = SerializerOptions::new().trailing_newline(true);
+    to_string_value(&value, options).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[test]
+    fn test_snapshot() {
+        let mut props = HashMap::new();
+        for i in 0..32 {
+            props.insert(format!("prop.{i:02}"), vec![f64::from(i), 0.5]);
+        }
+        let sorted: BTreeMap<_, _> = props.iter().collect();
+        let text = snapshot(&props);
+        assert_eq!(text, snapshot(&sorted));
+        assert!(text.starts_with("{\n  prop.00 = [\n    0\n    0.5\n  ]\n  prop.01 = [\n"));
+        assert_eq!(snapshot("x"), "x\n");
+        let nested: Value = "b = { d = 1 c = 2 } a = [ { z = 1 y = 2 } ]"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            snapshot(&nested),
+            "{\n  a = [\n    {\n      y = 2\n      z = 1\n    }\n  ]\n  b = {\n    c = 2\n    d = 1\n  }\n}\n"
+        );
+    }
+}
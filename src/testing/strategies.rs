@@ -0,0 +1,145 @@
+use crate::value::{Map, Value};
+use proptest::prelude::*;
+
+/// Bounds on the size of generated documents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    depth: u32,
+    size: u32,
+    items: u32,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Limits {
+            depth: 4,
+            size: 64,
+            items: 8,
+        }
+    }
+
+    /// Maximum nesting of arrays and objects.
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Number of values a document should have, roughly.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Maximum number of items of an array or entries of an object.
+    pub fn items(mut self, items: u32) -> Self {
+        self.items = items;
+        self
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::new()
+    }
+}
+
+/// Any value, within the default [`Limits`].
+pub fn arb_value() -> impl Strategy<Value = Value> {
+    arb_value_with(Limits::new())
+}
+
+/// Any value within `limits`.
+pub fn arb_value_with(limits: Limits) -> impl Strategy<Value = Value> {
+    let items = limits.items as usize;
+    arb_scalar().prop_recursive(limits.depth, limits.size, limits.items, move |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..=items).prop_map(Value::Array),
+            arb_map(inner, items).prop_map(Value::Object),
+        ]
+    })
+}
+
+/// An object with property-like keys, as found at the top of a
+/// configuration file, within the default [`Limits`].
+pub fn arb_config() -> impl Strategy<Value = Value> {
+    arb_config_with(Limits::new())
+}
+
+/// An object with property-like keys whose values are within `limits`.
+pub fn arb_config_with(limits: Limits) -> impl Strategy<Value = Value> {
+    arb_map(arb_value_with(limits), limits.items as usize).prop_map(Value::Object)
+}
+
+/// `null`, a boolean, a number or a string.
+pub fn arb_scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<u64>().prop_map(Value::from),
+        (any::<i32>(), 1..512u16)
+            .prop_map(|(i, f)| Value::from(f64::from(i) + f64::from(f) / 512.0)),
+        arb_string().prop_map(Value::from),
+    ]
+}
+
+/// A string: mostly words such as `alsa_output.pci-0000` or `Audio/Sink`,
+/// and sometimes text that looks like another type, such as `48000` or
+/// `true`, or arbitrary text.
+pub fn arb_string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[A-Za-z0-9_./:-]{1,24}",
+        1 => "true|false|null|[+-]?(nan|inf|infinity|0x[0-9a-f]{1,8}|[0-9]{0,6}(\\.[0-9]{1,3})?(e-?[0-9]{1,2})?)",
+        1 => any::<String>(),
+    ]
+}
+
+/// A property name such as `node.name` or `api.alsa.period-size`.
+pub fn arb_key() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9]{0,7}(\\.[a-z][a-z0-9-]{0,9}){0,3}"
+}
+
+fn arb_map(values: impl Strategy<Value = Value>, items: usize) -> impl Strategy<Value = Map> {
+    prop::collection::vec((arb_key(), values), 0..=items)
+        .prop_map(|entries| entries.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ser::{to_string, to_string_with_options, SerializerOptions};
+    use proptest::strategy::ValueTree;
+
+    proptest! {
+        #[test]
+        fn test_round_trip(value in arb_value()) {
+            let output = to_string(&value).unwrap();
+            prop_assert_eq!(output.parse::<Value>().unwrap(), value);
+        }
+
+        #[test]
+        fn test_config_round_trip(config in arb_config_with(Limits::new().depth(2))) {
+            let options = SerializerOptions::new().braceless(true);
+            let output = to_string_with_options(&config, options).unwrap();
+            prop_assert_eq!(output.parse::<Value>().unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        fn depth(value: &Value) -> u32 {
+            match value {
+                Value::Array(a) => 1 + a.iter().map(depth).max().unwrap_or(0),
+                Value::Object(m) => 1 + m.values().map(depth).max().unwrap_or(0),
+                _ => 0,
+            }
+        }
+
+        let mut runner = proptest::test_runner::TestRunner::deterministic();
+        let strategy = arb_value_with(Limits::new().depth(2).items(3));
+        for _ in 0..100 {
+            let value = strategy.new_tree(&mut runner).unwrap().current();
+            assert!(depth(&value) <= 2);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+//! Conversion between [`Value`]s and TOML, so configurations can be kept
+//! in TOML and rendered as SPA-JSON. Documents are read and written by
+//! the [toml] crate.
+//!
+//! TOML has no `null`, so values containing one cannot be written, and
+//! integers must fit in an `i64`. Dates and times are read as strings.
+//! SPA-JSON keys such as `default.clock.rate` are written quoted; dotted
+//! keys in TOML input create nested tables, as TOML specifies.
+//!
+//! [toml]: https://docs.rs/toml
+
+use crate::de::line_column;
+use crate::error::Error;
+use crate::path::SpaPath;
+use crate::value::{Map, Value};
+use ::toml::Value as Toml;
+
+/// Writes `value`, which must be an object, as a TOML document.
+pub fn to_toml(value: &Value) -> Result<String, Error> {
+    if value.as_object().is_none() {
+        return Err(Error::Message(
+            "only objects can be converted to TOML".to_owned(),
+        ));
+    }
+    if has_null(value) {
+        return Err(Error::Message(
+            "null cannot be converted to TOML".to_owned(),
+        ));
+    }
+    ::toml::to_string(value).map_err(|e| Error::Message(e.to_string()))
+}
+
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.iter().any(has_null),
+        Value::Object(map) => map.values().any(has_null),
+        _ => false,
+    }
+}
+
+/// Parses a TOML document.
+pub fn from_toml(input: &str) -> Result<Value, Error> {
+    let table: ::toml::Table = ::toml::from_str(input).map_err(|e| {
+        let (line, column) = e
+            .span()
+            .map_or((0, 0), |span| line_column(input, span.start));
+        Error::Parse {
+            message: e.message().to_owned(),
+            line,
+            column,
+            path: SpaPath::new(),
+        }
+    })?;
+    Ok(convert(Toml::Table(table)))
+}
+
+fn convert(toml: Toml) -> Value {
+    match toml {
+        Toml::String(s) => Value::from(s),
+        Toml::Integer(i) => Value::from(i),
+        Toml::Float(f) => Value::from(f),
+        Toml::Boolean(b) => Value::from(b),
+        Toml::Datetime(d) => Value::from(d.to_string()),
+        Toml::Array(items) => Value::Array(items.into_iter().map(convert).collect()),
+        Toml::Table(entries) => entries
+            .into_iter()
+            .map(|(k, v)| (k, convert(v)))
+            .collect::<Map>()
+            .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value: Value = r#"
+            context.properties = { default.clock.rate = 48000, log.level = 2 }
+            context.spa-libs = { audio.convert.* = audioconvert/libspa-audioconvert }
+            context.modules = [
+                { name = libpipewire-module-rt, args = { nice.level = -11 }, flags = [ ifexists nofail ] }
+                { name = libpipewire-module-protocol-native }
+            ]
+            stream.properties = { channelmix.upmix = true, resample.quality = 4.5 }
+            empty = {}
+        "#
+        .parse()
+        .unwrap();
+        let toml = to_toml(&value).unwrap();
+        let expected = r#"["context.properties"]
+"default.clock.rate" = 48000
+"log.level" = 2
+
+["context.spa-libs"]
+"audio.convert.*" = "audioconvert/libspa-audioconvert"
+
+[["context.modules"]]
+name = "libpipewire-module-rt"
+flags = ["ifexists", "nofail"]
+
+["context.modules".args]
+"nice.level" = -11
+
+[["context.modules"]]
+name = "libpipewire-module-protocol-native"
+
+["stream.properties"]
+"channelmix.upmix" = true
+"resample.quality" = 4.5
+
+[empty]
+"#;
+        assert_eq!(toml, expected);
+        assert_eq!(
+            from_toml(&toml).unwrap().to_canonical_string(),
+            value.to_canonical_string()
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = from_toml(
+            r#"
+# comment
+title = 'C:\path' # trailing comment
+"quoted key" = "tab\there \u00e4"
+numbers = [ 1_000, 0x1f, 0o17, 0b101, -2.5e3, +inf,
+            nan, ]
+point = { x = 1, y.z = 2 }
+when = 1979-05-27 07:32:00Z
+text = """
+first \
+  second"""
+raw = '''
+it's'''
+
+[server.alpha]
+ip = "10.0.0.1"
+
+[[fruits]]
+name = "apple"
+[[fruits.varieties]]
+name = "red"
+[[fruits]]
+name = "banana"
+"#,
+        )
+        .unwrap();
+        assert_eq!(value.get("title"), Some(&Value::from("C:\\path")));
+        assert_eq!(value.get("quoted key"), Some(&Value::from("tab\there ä")));
+        let numbers = value.get("numbers").unwrap().as_array().unwrap();
+        assert_eq!(numbers[..4], [1000, 31, 15, 5].map(Value::from));
+        assert_eq!(numbers[4], Value::from(-2500.0));
+        assert_eq!(numbers[5], Value::from(f64::INFINITY));
+        assert!(numbers[6].as_f64().unwrap().is_nan());
+        assert_eq!(
+            value.get("point").unwrap().to_canonical_string(),
+            r#"{"x":1,"y":{"z":2}}"#
+        );
+        assert_eq!(
+            value.get("when"),
+            Some(&Value::from("1979-05-27T07:32:00Z"))
+        );
+        assert_eq!(value.get("text"), Some(&Value::from("first second")));
+        assert_eq!(value.get("raw"), Some(&Value::from("it's")));
+        assert_eq!(
+            value.get("server").unwrap().to_canonical_string(),
+            r#"{"alpha":{"ip":"10.0.0.1"}}"#
+        );
+        assert_eq!(
+            value.get("fruits").unwrap().to_canonical_string(),
+            r#"[{"name":"apple","varieties":[{"name":"red"}]},{"name":"banana"}]"#
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| from_toml(s).unwrap_err().to_string();
+        assert_eq!(error("a = 1\na = 2"), "duplicate key at line 2 column 1");
+        assert_eq!(
+            error("a = 1\n[a.b]"),
+            "cannot extend value of type integer with a dotted key at line 2 column 2"
+        );
+        assert_eq!(
+            error("a = \"x"),
+            "invalid basic string, expected `\"` at line 1 column 7"
+        );
+        assert_eq!(
+            error("a = yes"),
+            "string values must be quoted, expected literal string at line 1 column 5"
+        );
+
+        assert!(to_toml(&Value::from(1)).is_err());
+        assert_eq!(
+            to_toml(&"a = [ 1 null ]".parse().unwrap())
+                .unwrap_err()
+                .to_string(),
+            "null cannot be converted to TOML"
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Spans for the phases of processing documents, emitted through
+//! [`tracing`](https://docs.rs/tracing) with the `tracing` feature. Without
+//! it, no spans or events are created and their fields are not evaluated.
+//!
+//! Every phase is a `DEBUG` span with the size of what it works on, which
+//! ends with a `done` event holding the time it took in microseconds.
+
+use crate::error::Error;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
+
+/// A phase that lasts until it is dropped.
+pub(crate) struct Phase {
+    #[cfg(feature = "tracing")]
+    span: tracing::span::EnteredSpan,
+    #[cfg(feature = "tracing")]
+    start: Instant,
+}
+
+impl Phase {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn enter(span: tracing::Span) -> Self {
+        Phase {
+            span: span.entered(),
+            start: Instant::now(),
+        }
+    }
+
+    /// Passes on the result of the phase, emitting an event with the error
+    /// if it failed.
+    pub(crate) fn result<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        #[cfg(feature = "tracing")]
+        if let Err(error) = &result {
+            tracing::debug!(%error, "failed");
+        }
+        result
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let elapsed_us = self.start.elapsed().as_micros() as u64;
+        self.span.in_scope(|| tracing::debug!(elapsed_us, "done"));
+    }
+}
+
+/// Enters a phase named by a literal, with the fields of the span as in
+/// `tracing::debug_span!`.
+macro_rules! phase {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        let phase = $crate::trace::Phase::enter(tracing::debug_span!($($arg)*));
+        #[cfg(not(feature = "tracing"))]
+        let phase = $crate::trace::Phase {};
+        phase
+    }};
+}
+
+/// Emits a `DEBUG` event, with the arguments of `tracing::debug!`.
+#[cfg(feature = "value")]
+macro_rules! event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+#[cfg(feature = "value")]
+pub(crate) use event;
+pub(crate) use phase;
+
+#[cfg(all(test, feature = "tracing", feature = "value"))]
+mod tests {
+    use crate::value::Value;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Records the names of spans as they are created and the messages of
+    /// events, with the fields of both.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        lines: Arc<Mutex<Vec<String>>>,
+        next_id: Arc<AtomicU64>,
+    }
+
+    struct Fields(Vec<String>);
+
+    impl tracing::field::Visit for Fields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            match field.name() {
+                "elapsed_us" => self.0.push("elapsed_us".to_owned()),
+                name => self.0.push(format!("{}={:?}", name, value)),
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes) -> Id {
+            let mut fields = Fields(vec![span.metadata().name().to_owned()]);
+            span.record(&mut fields);
+            self.lines.lock().unwrap().push(fields.0.join(" "));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event) {
+            let mut fields = Fields(Vec::new());
+            event.record(&mut fields);
+            self.lines.lock().unwrap().push(fields.0.join(" "));
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_phases() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut base: Value = "a = { b = 1 }".parse().unwrap();
+            crate::merge(&mut base, "a = { c = 2 }, d = 3".parse().unwrap());
+            assert!("a = [".parse::<Value>().is_err());
+            #[cfg(feature = "schemas")]
+            crate::schemas::pipewire().validate(&base);
+        });
+        let mut expected = vec![
+            "parse bytes=13",
+            "message=done elapsed_us",
+            "parse bytes=20",
+            "message=done elapsed_us",
+            "merge entries=2",
+            "message=done elapsed_us",
+            "parse bytes=5",
+            "message=failed error=expected array close bracket at line 1 column 6",
+            "message=done elapsed_us",
+        ];
+        if cfg!(feature = "schemas") {
+            expected.extend([
+                "validate",
+                "message=validated diagnostics=0",
+                "message=done elapsed_us",
+            ]);
+        }
+        assert_eq!(*recorder.lines.lock().unwrap(), expected);
+    }
+}
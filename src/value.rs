@@ -0,0 +1,1231 @@
+use indexmap::IndexMap;
+use serde::de::{self, Deserialize, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::de::Error;
+use crate::path::{DisplayPath, PathSegment};
+use crate::ser::escape_str;
+
+mod builder;
+mod deserializer;
+mod serializer;
+
+pub use builder::{ArrayBuilder, ObjectBuilder, ValueBuilder};
+pub(crate) use deserializer::{from_value, PathError};
+pub(crate) use serializer::to_value;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Str),
+    Array(Vec<Value>),
+    Object(Map),
+}
+
+/// Takes nested arrays and objects apart one level at a time, so that
+/// dropping a value never recurses, however deeply it is nested.
+impl Drop for Value {
+    fn drop(&mut self) {
+        let mut nested = Vec::new();
+        take_nested(self, &mut nested);
+        while let Some(mut value) = nested.pop() {
+            take_nested(&mut value, &mut nested);
+        }
+    }
+}
+
+/// Moves the items or entry values of `value` into `nested`.
+fn take_nested(value: &mut Value, nested: &mut Vec<Value>) {
+    match value {
+        Value::Array(a) => nested.append(a),
+        Value::Object(m) => nested.extend(m.entries.drain(..).map(|(_, v)| v)),
+        _ => {}
+    }
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().map(Number::as_f64)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Value>> {
+        match self {
+            Value::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            Value::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            Value::Object(m) => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_object().and_then(|m| m.get(key))
+    }
+
+    /// Looks up the value at `path`, such as a parsed
+    /// [`SpaPath`](crate::path::SpaPath).
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&Value> {
+        path.iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Value::Object(map), PathSegment::Key(key)) => map.get(key),
+                (Value::Array(items), PathSegment::Index(i)) => items.get(*i),
+                _ => None,
+            })
+    }
+
+    pub fn get_path_mut(&mut self, path: &[PathSegment]) -> Option<&mut Value> {
+        path.iter()
+            .try_fold(self, |value, segment| match (value, segment) {
+                (Value::Object(map), PathSegment::Key(key)) => map.get_mut(key),
+                (Value::Array(items), PathSegment::Index(i)) => items.get_mut(*i),
+                _ => None,
+            })
+    }
+
+    /// Sets the value at `path`. Missing keys are added, nulls on the way
+    /// are replaced by an object or array, and an index one past the end of
+    /// an array appends to it.
+    pub fn set_path(&mut self, path: &[PathSegment], new: Value) -> Result<(), Error> {
+        let mut value = self;
+        for (depth, segment) in path.iter().enumerate() {
+            if value.is_null() {
+                *value = match segment {
+                    PathSegment::Key(_) => Value::Object(Map::new()),
+                    PathSegment::Index(_) => Value::Array(Vec::new()),
+                };
+            }
+            let parent = DisplayPath(&path[..depth]);
+            value = match (value, segment) {
+                (Value::Object(map), PathSegment::Key(key)) => {
+                    if !map.contains_key(key) {
+                        map.insert(key, Value::Null);
+                    }
+                    map.get_mut(key).unwrap()
+                }
+                (Value::Array(items), PathSegment::Index(i)) => {
+                    if *i == items.len() {
+                        items.push(Value::Null);
+                    }
+                    let len = items.len();
+                    items.get_mut(*i).ok_or_else(|| {
+                        Error::Message(format!(
+                            "{}: index {} out of range for array of length {}",
+                            parent, i, len
+                        ))
+                    })?
+                }
+                (value, _) => {
+                    return Err(Error::Message(format!(
+                        "{}: cannot look up {} in {}",
+                        parent,
+                        DisplayPath(&path[depth..=depth]),
+                        value.type_name()
+                    )))
+                }
+            };
+        }
+        *value = new;
+        Ok(())
+    }
+
+    /// Looks up the value at `path` as it is typed on a command line, where
+    /// `context.properties.default.clock.rate` does not tell which dots are
+    /// part of a key. Consecutive keys are joined with dots when that names
+    /// a key, preferring the longest match that leads to a value.
+    pub fn lookup(&self, path: &[PathSegment]) -> Option<&Value> {
+        let Some(first) = path.first() else {
+            return Some(self);
+        };
+        match (self, first) {
+            (Value::Array(items), PathSegment::Index(i)) => items.get(*i)?.lookup(&path[1..]),
+            (Value::Object(map), PathSegment::Key(_)) => (1..=leading_keys(path))
+                .rev()
+                .filter_map(|n| Some((map.get(&join_keys(&path[..n]))?, n)))
+                .find_map(|(value, n)| value.lookup(&path[n..])),
+            _ => None,
+        }
+    }
+
+    /// Sets the value at `path`, matching keys as [`lookup`](Value::lookup)
+    /// does. A key that does not exist yet is made of all the keys up to
+    /// the next index, so that `context.properties.default.clock.rate`
+    /// adds `default.clock.rate` to an existing `context.properties`.
+    /// Otherwise this works as [`set_path`](Value::set_path) does.
+    pub fn assign(&mut self, path: &[PathSegment], new: Value) -> Result<(), Error> {
+        let mut value = self;
+        let mut depth = 0;
+        while let Some(segment) = path.get(depth) {
+            if value.is_null() {
+                *value = match segment {
+                    PathSegment::Key(_) => Value::Object(Map::new()),
+                    PathSegment::Index(_) => Value::Array(Vec::new()),
+                };
+            }
+            let parent = DisplayPath(&path[..depth]);
+            value = match (value, segment) {
+                (Value::Object(map), PathSegment::Key(_)) => {
+                    let keys = &path[depth..];
+                    let max = leading_keys(keys);
+                    let n = (1..=max)
+                        .rev()
+                        .find(|&n| map.contains_key(&join_keys(&keys[..n])))
+                        .unwrap_or(max);
+                    let key = join_keys(&keys[..n]);
+                    depth += n;
+                    if !map.contains_key(&key) {
+                        map.insert(key.as_str(), Value::Null);
+                    }
+                    map.get_mut(&key).unwrap()
+                }
+                (Value::Array(items), PathSegment::Index(i)) => {
+                    if *i == items.len() {
+                        items.push(Value::Null);
+                    }
+                    let len = items.len();
+                    depth += 1;
+                    items.get_mut(*i).ok_or_else(|| {
+                        Error::Message(format!(
+                            "{}: index {} out of range for array of length {}",
+                            parent, i, len
+                        ))
+                    })?
+                }
+                (value, _) => {
+                    return Err(Error::Message(format!(
+                        "{}: cannot look up {} in {}",
+                        parent,
+                        DisplayPath(&path[depth..=depth]),
+                        value.type_name()
+                    )))
+                }
+            };
+        }
+        *value = new;
+        Ok(())
+    }
+
+    /// Name of the value's type as used in error messages and diagnostics.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_f64() => "float",
+            Value::Number(_) => "integer",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        }
+    }
+
+    /// Normalizes the value in place: object keys are sorted recursively and
+    /// floats with an integral value are turned into integers.
+    pub fn canonicalize(&mut self) {
+        match self {
+            Value::Number(n) => *n = n.canonical(),
+            Value::Array(a) => a.iter_mut().for_each(Value::canonicalize),
+            Value::Object(m) => {
+                m.sort_keys();
+                m.iter_mut().for_each(|(_, v)| v.canonicalize());
+            }
+            _ => {}
+        }
+    }
+
+    /// Compact rendering of the canonicalized value with every key and string
+    /// quoted, so equal documents produce byte-identical output.
+    pub fn to_canonical_string(&self) -> String {
+        let mut value = self.clone();
+        value.canonicalize();
+        let mut out = String::new();
+        value.write_canonical(&mut out);
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => write!(out, "{}", n).unwrap(),
+            Value::String(s) => write_quoted(out, s),
+            Value::Array(a) => {
+                out.push('[');
+                for (i, v) in a.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    v.write_canonical(out);
+                }
+                out.push(']');
+            }
+            Value::Object(m) => {
+                out.push('{');
+                for (i, (k, v)) in m.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_quoted(out, k);
+                    out.push(':');
+                    v.write_canonical(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_quoted(out: &mut String, s: &str) {
+    out.push('"');
+    out.push_str(&escape_str(s));
+    out.push('"');
+}
+
+/// The number of keys at the start of `path`, before any index.
+fn leading_keys(path: &[PathSegment]) -> usize {
+    path.iter()
+        .take_while(|s| matches!(s, PathSegment::Key(_)))
+        .count()
+}
+
+/// The keys of `path` joined with dots, as one key.
+fn join_keys(path: &[PathSegment]) -> String {
+    let keys: Vec<&str> = path
+        .iter()
+        .map(|s| match s {
+            PathSegment::Key(key) => key.as_str(),
+            PathSegment::Index(_) => unreachable!(),
+        })
+        .collect();
+    keys.join(".")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Number(N);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum N {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+impl Number {
+    pub fn from_f64(v: f64) -> Number {
+        Number(N::Float(v))
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self.0, N::Float(_))
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.0 {
+            N::Int(v) => Some(v),
+            N::UInt(v) => i64::try_from(v).ok(),
+            N::Float(_) => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.0 {
+            N::Int(v) => u64::try_from(v).ok(),
+            N::UInt(v) => Some(v),
+            N::Float(_) => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self.0 {
+            N::Int(v) => v as f64,
+            N::UInt(v) => v as f64,
+            N::Float(v) => v,
+        }
+    }
+
+    fn canonical(self) -> Number {
+        match self.0 {
+            N::Float(v) if v.fract() == 0.0 && v >= i64::MIN as f64 && v < i64::MAX as f64 => {
+                Number(N::Int(v as i64))
+            }
+            _ => self,
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(v: i64) -> Self {
+        Number(N::Int(v))
+    }
+}
+
+impl From<u64> for Number {
+    fn from(v: u64) -> Self {
+        match i64::try_from(v) {
+            Ok(v) => Number(N::Int(v)),
+            Err(_) => Number(N::UInt(v)),
+        }
+    }
+}
+
+impl From<f64> for Number {
+    fn from(v: f64) -> Self {
+        Number::from_f64(v)
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            N::Int(v) => write!(f, "{}", v),
+            N::UInt(v) => write!(f, "{}", v),
+            N::Float(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+macro_rules! shared_str {
+    ($($(#[$attr:meta])* $name:ident;)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+            pub struct $name(Arc<str>);
+
+            impl $name {
+                pub fn as_str(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl Deref for $name {
+                type Target = str;
+
+                fn deref(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl AsRef<str> for $name {
+                fn as_ref(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl Borrow<str> for $name {
+                fn borrow(&self) -> &str {
+                    &self.0
+                }
+            }
+
+            impl fmt::Debug for $name {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    fmt::Debug::fmt(&*self.0, f)
+                }
+            }
+
+            impl fmt::Display for $name {
+                fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str(&self.0)
+                }
+            }
+
+            impl From<&str> for $name {
+                fn from(s: &str) -> Self {
+                    $name(Arc::from(s))
+                }
+            }
+
+            impl From<String> for $name {
+                fn from(s: String) -> Self {
+                    $name(Arc::from(s))
+                }
+            }
+
+            impl From<&String> for $name {
+                fn from(s: &String) -> Self {
+                    $name::from(s.as_str())
+                }
+            }
+
+            impl From<&$name> for $name {
+                fn from(s: &$name) -> Self {
+                    s.clone()
+                }
+            }
+
+            impl From<$name> for String {
+                fn from(s: $name) -> Self {
+                    s.0.to_string()
+                }
+            }
+
+            impl PartialEq<str> for $name {
+                fn eq(&self, other: &str) -> bool {
+                    &*self.0 == other
+                }
+            }
+
+            impl PartialEq<&str> for $name {
+                fn eq(&self, other: &&str) -> bool {
+                    &*self.0 == *other
+                }
+            }
+
+            impl PartialEq<String> for $name {
+                fn eq(&self, other: &String) -> bool {
+                    *self.0 == **other
+                }
+            }
+
+            impl PartialEq<$name> for str {
+                fn eq(&self, other: &$name) -> bool {
+                    self == &*other.0
+                }
+            }
+
+            impl PartialEq<$name> for &str {
+                fn eq(&self, other: &$name) -> bool {
+                    *self == &*other.0
+                }
+            }
+
+            impl PartialEq<$name> for String {
+                fn eq(&self, other: &$name) -> bool {
+                    **self == *other.0
+                }
+            }
+        )*
+    };
+}
+
+shared_str! {
+    /// An object key. Keys are reference counted, so that parsed documents
+    /// share one allocation between all objects with the same key, like the
+    /// `node.name` of every node in a `pw-dump`.
+    Key;
+    /// A string value. Strings are reference counted, so that cloning a
+    /// document, as merging and diffing do, does not copy its text.
+    Str;
+}
+
+impl From<Str> for Key {
+    fn from(s: Str) -> Self {
+        Key(s.0)
+    }
+}
+
+impl From<&Str> for Key {
+    fn from(s: &Str) -> Self {
+        Key(s.0.clone())
+    }
+}
+
+impl From<Key> for Str {
+    fn from(key: Key) -> Self {
+        Str(key.0)
+    }
+}
+
+impl From<&Key> for Str {
+    fn from(key: &Key) -> Self {
+        Str(key.0.clone())
+    }
+}
+
+/// The keys seen while parsing one document.
+#[derive(Default)]
+pub(crate) struct Interner(HashSet<Key>);
+
+impl Interner {
+    pub(crate) fn intern(&mut self, key: &str) -> Key {
+        match self.0.get(key) {
+            Some(key) => key.clone(),
+            None => {
+                let key = Key::from(key);
+                self.0.insert(key.clone());
+                key
+            }
+        }
+    }
+}
+
+/// Insertion-ordered object map; inserting an existing key replaces its value in place.
+/// Keys are hashed, so lookups and inserts take constant time however many
+/// entries an object has.
+#[derive(Clone, Debug, Default)]
+pub struct Map {
+    entries: IndexMap<Key, Value>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map {
+            entries: IndexMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.entries.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries.get_mut(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: impl Into<Key>, value: Value) -> Option<Value> {
+        self.entries.insert(key.into(), value)
+    }
+
+    /// Removes `key`, keeping the order of the other entries.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.entries.shift_remove(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Key, &Value)> {
+        self.entries.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Key, &mut Value)> {
+        self.entries.iter_mut()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.entries.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.values()
+    }
+
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_keys();
+    }
+}
+
+/// Maps are equal when they have the same entries in the same order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries.iter().eq(other.entries.iter())
+    }
+}
+
+impl<K: Into<Key>> FromIterator<(K, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<Key>> Extend<(K, Value)> for Map {
+    fn extend<I: IntoIterator<Item = (K, Value)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (Key, Value);
+    type IntoIter = indexmap::map::IntoIter<Key, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+macro_rules! from_integer {
+    ($($ty:ty => $via:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(v: $ty) -> Self {
+                    Value::Number(Number::from(<$via>::from(v)))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8 => i64, i16 => i64, i32 => i64, i64 => i64, u8 => u64, u16 => u64, u32 => u64, u64 => u64);
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::Number(Number::from_f64(f64::from(v)))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(v: f64) -> Self {
+        Value::Number(Number::from_f64(v))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::String(Str::from(v))
+    }
+}
+
+impl From<String> for Value {
+    fn from(v: String) -> Self {
+        Value::String(Str::from(v))
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(v: Vec<Value>) -> Self {
+        Value::Array(v)
+    }
+}
+
+impl From<Map> for Value {
+    fn from(v: Map) -> Self {
+        Value::Object(v)
+    }
+}
+
+impl Serialize for Number {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            N::Int(v) => serializer.serialize_i64(v),
+            N::UInt(v) => serializer.serialize_u64(v),
+            N::Float(v) => serializer.serialize_f64(v),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Number(n) => n.serialize(serializer),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Array(a) => {
+                let mut seq = serializer.serialize_seq(Some(a.len()))?;
+                for v in a {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Object(m) => m.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Map {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self.iter() {
+            map.serialize_entry(k.as_str(), v)?;
+        }
+        map.end()
+    }
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    /// Parses with [`from_str_value`](crate::de::from_str_value),
+    /// so that deeply nested input cannot overflow the stack.
+    fn from_str(s: &str) -> Result<Value, Error> {
+        crate::de::from_str_value(s, Default::default())
+    }
+}
+
+/// Deserializes a value, interning the keys of all objects in it.
+struct ValueSeed<'a> {
+    keys: &'a mut Interner,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor { keys: self.keys })
+    }
+}
+
+struct KeySeed<'a> {
+    keys: &'a mut Interner,
+}
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_> {
+    type Value = Key;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Key, D::Error> {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_> {
+    type Value = Key;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an object key")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Key, E> {
+        Ok(self.keys.intern(v))
+    }
+}
+
+struct ValueVisitor<'a> {
+    keys: &'a mut Interner,
+}
+
+impl<'de> Visitor<'de> for ValueVisitor<'_> {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any SPA-JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        ValueSeed { keys: self.keys }.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut array = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(v) = seq.next_element_seed(ValueSeed {
+            keys: &mut *self.keys,
+        })? {
+            array.push(v);
+        }
+        Ok(Value::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some(k) = access.next_key_seed(KeySeed {
+            keys: &mut *self.keys,
+        })? {
+            let v = access.next_value_seed(ValueSeed {
+                keys: &mut *self.keys,
+            })?;
+            map.insert(k, v);
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        ValueSeed {
+            keys: &mut Interner::default(),
+        }
+        .deserialize(deserializer)
+    }
+}
+
+impl Serialize for Key {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Key, D::Error> {
+        String::deserialize(deserializer).map(Key::from)
+    }
+}
+
+impl<'de> Deserialize<'de> for Map {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Map, D::Error> {
+        match &mut Value::deserialize(deserializer)? {
+            Value::Object(m) => Ok(std::mem::take(m)),
+            other => Err(de::Error::invalid_type(
+                de::Unexpected::Other(other.type_name()),
+                &"an object",
+            )),
+        }
+    }
+}
+
+/// Generates documents that survive a serialize and parse round trip:
+/// floats always have a fractional part, since `2.0` is written as `2`
+/// and read back as an integer, and are never NaN or infinite.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Value {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_value(u, 0)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+const ARBITRARY_DEPTH: usize = 8;
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_value(u: &mut arbitrary::Unstructured, depth: usize) -> arbitrary::Result<Value> {
+    // Containers get rarer with depth, and are never generated past the
+    // limit.
+    let kinds = if depth < ARBITRARY_DEPTH { 7 } else { 5 };
+    Ok(match u.choose_index(kinds)? {
+        0 => Value::Null,
+        1 => Value::Bool(u.arbitrary()?),
+        2 => match u.choose_index(3)? {
+            0 => Value::from(u.arbitrary::<i64>()?),
+            1 => Value::from(u.arbitrary::<u64>()?),
+            _ => {
+                let fraction = (f64::from(u.arbitrary::<u8>()?) + 1.0) / 512.0;
+                Value::from(f64::from(u.arbitrary::<i32>()?) + fraction)
+            }
+        },
+        3 | 4 => Value::from(u.arbitrary::<String>()?),
+        5 => {
+            let len = u.arbitrary_len::<Value>()?.min(8);
+            let items = (0..len)
+                .map(|_| arbitrary_value(u, depth + 1))
+                .collect::<arbitrary::Result<_>>()?;
+            Value::Array(items)
+        }
+        _ => {
+            let len = u.arbitrary_len::<(String, Value)>()?.min(8);
+            let mut map = Map::with_capacity(len);
+            for _ in 0..len {
+                let key: String = u.arbitrary()?;
+                map.insert(key, arbitrary_value(u, depth + 1)?);
+            }
+            Value::Object(map)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_string() {
+        let a: Map = vec![
+            ("b".to_owned(), Value::from(2.0)),
+            ("a".to_owned(), Value::from("x \"y\"")),
+            (
+                "c".to_owned(),
+                Value::Array(vec![Value::Null, Value::from(true), Value::from(0.5)]),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let mut b = a.clone();
+        b.sort_keys();
+        b.insert("b", Value::from(2));
+
+        let expected = r#"{"a":"x \"y\"","b":2,"c":[null,true,0.5]}"#;
+        assert_eq!(Value::Object(a.clone()).to_canonical_string(), expected);
+        assert_eq!(Value::Object(b).to_canonical_string(), expected);
+
+        let mut canonical = Value::Object(a);
+        canonical.canonicalize();
+        assert_eq!(canonical.get("b"), Some(&Value::from(2)));
+        assert_eq!(canonical.as_object().unwrap().keys().next().unwrap(), "a");
+    }
+
+    #[test]
+    fn test_paths() {
+        use crate::path::SpaPath;
+
+        let mut value: Value = "context.modules = [ { name = rt } ]".parse().unwrap();
+        let path: SpaPath = "\"context.modules\"[0].name".parse().unwrap();
+        assert_eq!(value.get_path(&path), Some(&Value::from("rt")));
+        assert_eq!(
+            value
+                .get_path(&path[..1])
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(value.get_path(&"x[0]".parse::<SpaPath>().unwrap()), None);
+
+        let args: SpaPath = "\"context.modules\"[0].args.\"nice.level\""
+            .parse()
+            .unwrap();
+        value.set_path(&args, Value::from(-11)).unwrap();
+        let flags: SpaPath = "\"context.modules\"[0].flags[0]".parse().unwrap();
+        value.set_path(&flags, Value::from("ifexists")).unwrap();
+        *value.get_path_mut(&path).unwrap() = Value::from("rtkit");
+        let expected: Value = r#"context.modules = [
+            { name = rtkit, args = { nice.level = -11 }, flags = [ ifexists ] }
+        ]"#
+        .parse()
+        .unwrap();
+        assert_eq!(value, expected);
+
+        let error = |path: &str| {
+            let path: SpaPath = path.parse().unwrap();
+            value
+                .clone()
+                .set_path(&path, Value::Null)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("\"context.modules\"[2]"),
+            "\"context.modules\": index 2 out of range for array of length 1"
+        );
+        assert_eq!(
+            error("\"context.modules\".name"),
+            "\"context.modules\": cannot look up name in array"
+        );
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        use crate::path::SpaPath;
+
+        let path = |text: &str| text.parse::<SpaPath>().unwrap();
+        let mut value: Value = r#"
+            context.properties = { default.clock.rate = 48000, default = { clock = 1 } }
+            context.modules = [ { name = rt } ]
+        "#
+        .parse()
+        .unwrap();
+        let rate = path("context.properties.default.clock.rate");
+        assert_eq!(value.get_path(&rate), None);
+        assert_eq!(value.lookup(&rate), Some(&Value::from(48000)));
+        assert_eq!(
+            value.lookup(&path("context.properties.default.clock")),
+            Some(&Value::from(1))
+        );
+        assert_eq!(
+            value.lookup(&path("context.modules[0].name")),
+            Some(&Value::from("rt"))
+        );
+        assert_eq!(value.lookup(&path("context.modules.name")), None);
+
+        value.assign(&rate, Value::from(96000)).unwrap();
+        value
+            .assign(
+                &path("context.properties.mem.warn-mlock"),
+                Value::from(true),
+            )
+            .unwrap();
+        value
+            .assign(&path("context.modules[1].name"), Value::from("x"))
+            .unwrap();
+        let expected: Value = r#"
+            context.properties = {
+                default.clock.rate = 96000
+                default = { clock = 1 }
+                mem.warn-mlock = true
+            }
+            context.modules = [ { name = rt } { name = x } ]
+        "#
+        .parse()
+        .unwrap();
+        assert_eq!(value, expected);
+        assert_eq!(
+            value
+                .assign(&path("context.modules.name"), Value::Null)
+                .unwrap_err()
+                .to_string(),
+            "context.modules: cannot look up name in array"
+        );
+    }
+
+    #[test]
+    fn test_interned_keys() {
+        let value: Value = "nodes = [ { node.name = a } { node.name = b, \"node.name\" = c } ]"
+            .parse()
+            .unwrap();
+        let keys: Vec<&Key> = value
+            .get("nodes")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|node| node.as_object().unwrap().keys())
+            .collect();
+        assert_eq!(keys.len(), 2);
+        assert!(Arc::ptr_eq(&keys[0].0, &keys[1].0));
+        assert_eq!(
+            value.get("nodes").unwrap().as_array().unwrap()[1].get("node.name"),
+            Some(&Value::from("c"))
+        );
+
+        let key = Key::from("node.name");
+        assert_eq!(key, "node.name");
+        assert_eq!(format!("{} {:?}", key, key), "node.name \"node.name\"");
+        assert_eq!(String::from(key), "node.name");
+    }
+
+    #[test]
+    fn test_shared_strings() {
+        let value: Value = "node.description = \"Built-in Audio\"".parse().unwrap();
+        let copy = value.clone();
+        let (Some(Value::String(a)), Some(Value::String(b))) =
+            (value.get("node.description"), copy.get("node.description"))
+        else {
+            panic!("expected strings");
+        };
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(a, "Built-in Audio");
+        assert_eq!(Key::from(a.clone()), "Built-in Audio");
+    }
+
+    #[test]
+    fn test_map() {
+        let mut map: Map = (0..100_000)
+            .map(|i| (format!("prop.{i}"), Value::from(i)))
+            .collect();
+        assert_eq!(map.len(), 100_000);
+        assert_eq!(map.get("prop.99999"), Some(&Value::from(99_999)));
+        assert_eq!(map.insert("prop.0", Value::Null), Some(Value::from(0)));
+        assert_eq!(map.remove("prop.1"), Some(Value::from(1)));
+        let keys: Vec<&str> = map.keys().take(3).map(Key::as_str).collect();
+        assert_eq!(keys, ["prop.0", "prop.2", "prop.3"]);
+
+        let a: Map = [("a", Value::from(1)), ("b", Value::from(2))]
+            .into_iter()
+            .collect();
+        let mut b: Map = [("b", Value::from(2)), ("a", Value::from(1))]
+            .into_iter()
+            .collect();
+        assert_ne!(a, b);
+        b.sort_keys();
+        assert_eq!(a, b);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_round_trip() {
+        use crate::ser::to_string;
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // A cheap deterministic byte stream to draw documents from.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let bytes: Vec<u8> = (0..1 << 16)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let mut u = Unstructured::new(&bytes);
+        let mut containers = 0;
+        while !u.is_empty() {
+            let value = Value::arbitrary(&mut u).unwrap();
+            containers += usize::from(matches!(value, Value::Array(_) | Value::Object(_)));
+            let output = to_string(&value).unwrap();
+            assert_eq!(output.parse::<Value>().unwrap(), value, "{}", output);
+        }
+        assert!(containers > 0);
+    }
+}
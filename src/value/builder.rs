@@ -0,0 +1,136 @@
+use super::{Key, Map, Value};
+
+/// Entry point for building a [`Value`] with chained calls.
+///
+/// Builders convert into [`Value`], so they can be nested as the values of
+/// other builders.
+///
+/// ```
+/// use spa_json::value::{ArrayBuilder, ObjectBuilder, Value, ValueBuilder};
+///
+/// let config = ValueBuilder::object()
+///     .entry("context.properties", ObjectBuilder::new().entry("default.clock.rate", 48000))
+///     .entry(
+///         "context.modules",
+///         ArrayBuilder::new().item(
+///             ObjectBuilder::new()
+///                 .entry("name", "libpipewire-module-rt")
+///                 .entry("args", ObjectBuilder::new().entry("nice.level", -11)),
+///         ),
+///     )
+///     .build();
+/// let expected: Value = r#"
+///     context.properties = { default.clock.rate = 48000 }
+///     context.modules = [ { name = libpipewire-module-rt, args = { nice.level = -11 } } ]
+/// "#
+/// .parse()
+/// .unwrap();
+/// assert_eq!(config, expected);
+/// ```
+pub struct ValueBuilder;
+
+impl ValueBuilder {
+    pub fn object() -> ObjectBuilder {
+        ObjectBuilder::new()
+    }
+
+    pub fn array() -> ArrayBuilder {
+        ArrayBuilder::new()
+    }
+}
+
+/// Builds an object entry by entry, keeping their order.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectBuilder {
+    map: Map,
+}
+
+impl ObjectBuilder {
+    pub fn new() -> Self {
+        ObjectBuilder::default()
+    }
+
+    /// Adds an entry, replacing the value of an earlier one with the same
+    /// key.
+    pub fn entry(mut self, key: impl Into<Key>, value: impl Into<Value>) -> Self {
+        self.map.insert(key, value.into());
+        self
+    }
+
+    /// Adds the entry only if `value` is `Some`.
+    pub fn entry_opt(self, key: impl Into<Key>, value: Option<impl Into<Value>>) -> Self {
+        match value {
+            Some(value) => self.entry(key, value),
+            None => self,
+        }
+    }
+
+    pub fn build(self) -> Value {
+        Value::Object(self.map)
+    }
+}
+
+/// Builds an array item by item.
+#[derive(Clone, Debug, Default)]
+pub struct ArrayBuilder {
+    items: Vec<Value>,
+}
+
+impl ArrayBuilder {
+    pub fn new() -> Self {
+        ArrayBuilder::default()
+    }
+
+    pub fn item(mut self, value: impl Into<Value>) -> Self {
+        self.items.push(value.into());
+        self
+    }
+
+    pub fn items<V: Into<Value>>(mut self, values: impl IntoIterator<Item = V>) -> Self {
+        self.items.extend(values.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn build(self) -> Value {
+        Value::Array(self.items)
+    }
+}
+
+impl From<ObjectBuilder> for Value {
+    fn from(builder: ObjectBuilder) -> Self {
+        builder.build()
+    }
+}
+
+impl From<ArrayBuilder> for Value {
+    fn from(builder: ArrayBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builders() {
+        let value = ValueBuilder::object()
+            .entry("a", 1)
+            .entry_opt("b", None::<bool>)
+            .entry_opt("c", Some("x"))
+            .entry(
+                "d",
+                ValueBuilder::array().items([1.5, 2.5]).item(Value::Null),
+            )
+            .entry("a", ObjectBuilder::new())
+            .build();
+        assert_eq!(
+            value.to_canonical_string(),
+            r#"{"a":{},"c":"x","d":[1.5,2.5,null]}"#
+        );
+        assert_eq!(
+            value.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["a", "c", "d"]
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use super::{Key, Map, Number, Value, N};
+use crate::number::{parse_number, Number as NumberToken};
+use crate::path::{PathSegment, SpaPath};
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+use serde::Deserialize;
+use std::fmt;
+
+/// An error deserializing from a [`Value`], with the path of the value it
+/// is about.
+#[derive(Debug)]
+pub(crate) struct PathError {
+    pub message: String,
+    pub path: SpaPath,
+}
+
+impl de::Error for PathError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        PathError {
+            message: msg.to_string(),
+            path: SpaPath::new(),
+        }
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl PathError {
+    fn within(mut self, segment: PathSegment) -> Self {
+        self.path.insert(0, segment);
+        self
+    }
+}
+
+/// Deserializes `T` from `value` the way it would be read from the
+/// document `value` is written as.
+pub(crate) fn from_value<'a, T: Deserialize<'a>>(value: &'a Value) -> Result<T, PathError> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+struct ValueDeserializer<'a>(&'a Value);
+
+impl<'a> ValueDeserializer<'a> {
+    /// The text of a scalar, as it is written.
+    fn scalar_text(&self) -> Option<String> {
+        match self.0 {
+            Value::Null => Some("null".to_owned()),
+            Value::Bool(b) => Some(b.to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            Value::String(_) | Value::Array(_) | Value::Object(_) => None,
+        }
+    }
+
+    fn error(&self, message: &str) -> PathError {
+        de::Error::custom(message)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = PathError;
+
+    /// Values are typed as they are in the document, as the text
+    /// deserializer types bare scalars.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Number(Number(N::Int(v))) => visitor.visit_i64(*v),
+            Value::Number(Number(N::UInt(v))) => visitor.visit_u64(*v),
+            Value::Number(Number(N::Float(v))) => visitor.visit_f64(*v),
+            Value::String(s) => visitor.visit_borrowed_str(s),
+            Value::Array(items) => visitor.visit_seq(Seq {
+                items: items.iter(),
+                index: 0,
+            }),
+            Value::Object(map) => visitor.visit_map(Entries {
+                entries: map.iter(),
+                value: None,
+            }),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 bytes byte_buf ignored_any
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        self.deserialize_str(visitor)
+    }
+
+    /// Scalars are untyped in SPA-JSON, so any of them is read as the
+    /// string it is written as.
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.scalar_text() {
+            Some(text) => visitor.visit_string(text),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            _ => Err(self.error("expected null")),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Null | Value::Array(_) | Value::Object(_) => self.deserialize_unit(visitor),
+            Value::String(s) if &**s == name => visitor.visit_unit(),
+            _ if self.scalar_text().as_deref() == Some(name) => visitor.visit_unit(),
+            _ => Err(self.error(&format!("expected null or `{}`", name))),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Array(_) => self.deserialize_any(visitor),
+            _ => Err(self.error("expected array")),
+        }
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Object(_) => self.deserialize_any(visitor),
+            _ => Err(self.error("expected object")),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        self.deserialize_map(visitor)
+    }
+
+    /// Enums are a variant name, or an object with a single entry from
+    /// the variant name to its content.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        match self.0 {
+            Value::Object(map) => match single_entry(map) {
+                Some((variant, value)) => visitor
+                    .visit_enum(Enum { variant, value })
+                    .map_err(|e| e.within(PathSegment::Key(variant.to_owned()))),
+                None => Err(self.error("expected an object with a single key")),
+            },
+            Value::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            Value::Array(_) => Err(self.error("expected enum variant")),
+            _ => {
+                let text = self.scalar_text().unwrap_or_default();
+                visitor.visit_enum(text.into_deserializer())
+            }
+        }
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        self.deserialize_str(visitor)
+    }
+}
+
+fn single_entry(map: &Map) -> Option<(&str, &Value)> {
+    let mut entries = map.iter();
+    match (entries.next(), entries.next()) {
+        (Some((key, value)), None) => Some((key, value)),
+        _ => None,
+    }
+}
+
+struct Seq<'a> {
+    items: std::slice::Iter<'a, Value>,
+    index: usize,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'de> {
+    type Error = PathError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, PathError> {
+        let Some(item) = self.items.next() else {
+            return Ok(None);
+        };
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(ValueDeserializer(item))
+            .map(Some)
+            .map_err(|e| e.within(PathSegment::Index(index)))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+struct Entries<'a, I> {
+    entries: I,
+    /// The key and value of the entry whose key was just read.
+    value: Option<(&'a str, &'a Value)>,
+}
+
+impl<'de, I> MapAccess<'de> for Entries<'de, I>
+where
+    I: Iterator<Item = (&'de Key, &'de Value)>,
+{
+    type Error = PathError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, PathError> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some((key.as_str(), value));
+        seed.deserialize(KeyDeserializer(key))
+            .map(Some)
+            .map_err(|e: PathError| e.within(PathSegment::Key(key.to_string())))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, PathError> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(ValueDeserializer(value))
+            .map_err(|e| e.within(PathSegment::Key(key.to_owned())))
+    }
+}
+
+/// Reads an object key, as a string unless a number or boolean is asked
+/// for.
+struct KeyDeserializer<'a>(&'a str);
+
+impl<'de> KeyDeserializer<'de> {
+    fn deserialize_typed<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        match self.0 {
+            "true" => visitor.visit_bool(true),
+            "false" => visitor.visit_bool(false),
+            key => match parse_number(key) {
+                Some(NumberToken::Int(v)) => visitor.visit_i64(v),
+                Some(NumberToken::UInt(v)) => visitor.visit_u64(v),
+                Some(NumberToken::Float(v)) => visitor.visit_f64(v),
+                None => visitor.visit_borrowed_str(key),
+            },
+        }
+    }
+}
+
+macro_rules! deserialize_typed {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+                self.deserialize_typed(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = PathError;
+
+    /// Keys are always strings, however they look.
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, PathError> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    deserialize_typed! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        char str string bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct Enum<'a> {
+    variant: &'a str,
+    value: &'a Value,
+}
+
+impl<'de> EnumAccess<'de> for Enum<'de> {
+    type Error = PathError;
+    type Variant = ValueDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), PathError> {
+        let variant = seed.deserialize(de::value::BorrowedStrDeserializer::new(self.variant))?;
+        Ok((variant, ValueDeserializer(self.value)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer<'de> {
+    type Error = PathError;
+
+    fn unit_variant(self) -> Result<(), PathError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, PathError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, PathError> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_from_value() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        enum Mode {
+            Off,
+            Rate(u32),
+            Range { min: u32, max: u32 },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Config {
+            name: String,
+            rate: String,
+            quoted: String,
+            level: Option<i8>,
+            unset: Option<i8>,
+            modes: Vec<Mode>,
+            pair: (f64, char),
+            props: BTreeMap<String, bool>,
+        }
+
+        let text = r#"
+            name = alsa_output
+            rate = 48000
+            quoted = "true"
+            level = -2
+            unset = null
+            modes = [ Off { Rate = 44100 } { Range = { min = 1, max = 2 } } ]
+            pair = [ 0.5 x ]
+            props = { 1 = true, node.pause-on-idle = false }
+        "#;
+        let value: Value = text.parse().unwrap();
+        let config: Config = from_value(&value).unwrap();
+        assert_eq!(config, crate::de::from_str::<Config>(text).unwrap());
+        assert_eq!(config.rate, "48000");
+        assert_eq!(config.modes[2], Mode::Range { min: 1, max: 2 });
+
+        let value: Value = "{ 44100 = cd, 48000 = dvd }".parse().unwrap();
+        let rates: BTreeMap<u32, String> = from_value(&value).unwrap();
+        assert_eq!(rates[&48000], "dvd");
+        let value: Value = "{ true = 1 }".parse().unwrap();
+        assert_eq!(from_value::<BTreeMap<bool, u8>>(&value).unwrap()[&true], 1);
+
+        let value: Value = "a = [ 1, { b = x } ]".parse().unwrap();
+        let error =
+            from_value::<BTreeMap<String, (u32, BTreeMap<String, u32>)>>(&value).unwrap_err();
+        assert_eq!(error.path.to_string(), "a[1].b");
+        assert_eq!(error.message, "invalid type: string \"x\", expected u32");
+    }
+}
@@ -0,0 +1,477 @@
+use super::{Key, Map, Value};
+use crate::error::Error;
+use crate::number::{parse_number, Number};
+use crate::ser::EnumRepr;
+use serde::ser::{self, Serialize};
+
+/// Converts `value` to the [`Value`] its SPA-JSON output reads back as,
+/// with enums written as `enum_repr` says and unit structs as their name
+/// if `unit_struct_names` is set.
+pub(crate) fn to_value<T: ?Sized + Serialize>(
+    value: &T,
+    enum_repr: &EnumRepr,
+    unit_struct_names: bool,
+) -> Result<Value, Error> {
+    value.serialize(ValueSerializer {
+        enum_repr,
+        unit_struct_names,
+    })
+}
+
+#[derive(Clone, Copy)]
+struct ValueSerializer<'a> {
+    enum_repr: &'a EnumRepr,
+    unit_struct_names: bool,
+}
+
+impl<'a> ValueSerializer<'a> {
+    /// `{ tag = variant }` for internally tagged enums.
+    fn tagged(&self, variant: &str) -> Option<Map> {
+        let EnumRepr::Internal(tag) = self.enum_repr else {
+            return None;
+        };
+        Some(Map::from_iter([(tag.as_str(), Value::from(variant))]))
+    }
+
+    /// `{ variant = value }` for externally tagged enums.
+    fn external(variant: &str, value: Value) -> Value {
+        Value::Object(Map::from_iter([(variant, value)]))
+    }
+
+    fn seq(self, variant: Option<&'static str>) -> Seq<'a> {
+        Seq {
+            serializer: self,
+            variant,
+            items: Vec::new(),
+        }
+    }
+
+    fn object(self, variant: Option<&'static str>, map: Map) -> Object<'a> {
+        Object {
+            serializer: self,
+            variant,
+            map,
+            key: None,
+        }
+    }
+}
+
+fn untaggable() -> Error {
+    Error::Message("only objects can be internally tagged".to_owned())
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = Seq<'a>;
+    type SerializeTuple = Seq<'a>;
+    type SerializeTupleStruct = Seq<'a>;
+    type SerializeTupleVariant = Seq<'a>;
+    type SerializeMap = Object<'a>;
+    type SerializeStruct = Object<'a>;
+    type SerializeStructVariant = Object<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value, Error> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    /// `f32`s are written with their own shortest digits, so `0.1f32`
+    /// reads back as the `f64` `0.1`.
+    fn serialize_f32(self, v: f32) -> Result<Value, Error> {
+        Ok(Value::from(
+            v.to_string().parse::<f64>().unwrap_or(f64::from(v)),
+        ))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value, Error> {
+        Ok(Value::from(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value, Error> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value, Error> {
+        Ok(Value::Array(v.iter().map(|&b| Value::from(b)).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<Value, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value, Error> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Value, Error> {
+        if self.unit_struct_names {
+            return Ok(Value::from(name));
+        }
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value, Error> {
+        Ok(match self.tagged(variant) {
+            Some(map) => Value::Object(map),
+            None => Value::from(variant),
+        })
+    }
+
+    /// Hex numbers are written bare, and read back as numbers.
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let value = value.serialize(self)?;
+        if name != crate::hex::TOKEN {
+            return Ok(value);
+        }
+        Ok(match value.as_str().and_then(parse_number) {
+            Some(Number::Int(v)) => Value::from(v),
+            Some(Number::UInt(v)) => Value::from(v),
+            Some(Number::Float(v)) => Value::from(v),
+            None => value,
+        })
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value, Error> {
+        let value = value.serialize(self)?;
+        match self.enum_repr {
+            EnumRepr::External => Ok(ValueSerializer::external(variant, value)),
+            EnumRepr::Internal(_) => {
+                let Value::Object(fields) = &value else {
+                    return Err(untaggable());
+                };
+                let mut map = self.tagged(variant).unwrap();
+                map.extend(fields.iter().map(|(k, v)| (k.clone(), v.clone())));
+                Ok(Value::Object(map))
+            }
+            EnumRepr::Untagged => Ok(value),
+        }
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Seq<'a>, Error> {
+        Ok(self.seq(None))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Seq<'a>, Error> {
+        Ok(self.seq(None))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Seq<'a>, Error> {
+        Ok(self.seq(None))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Seq<'a>, Error> {
+        match self.enum_repr {
+            EnumRepr::External => Ok(self.seq(Some(variant))),
+            EnumRepr::Internal(_) => Err(untaggable()),
+            EnumRepr::Untagged => Ok(self.seq(None)),
+        }
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Object<'a>, Error> {
+        Ok(self.object(None, Map::new()))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Object<'a>, Error> {
+        Ok(self.object(None, Map::new()))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Object<'a>, Error> {
+        Ok(match self.enum_repr {
+            EnumRepr::External => self.object(Some(variant), Map::new()),
+            EnumRepr::Internal(_) => self.object(None, self.tagged(variant).unwrap()),
+            EnumRepr::Untagged => self.object(None, Map::new()),
+        })
+    }
+}
+
+/// An array, wrapped as `{ variant = [ … ] }` for tuple variants.
+struct Seq<'a> {
+    serializer: ValueSerializer<'a>,
+    variant: Option<&'static str>,
+    items: Vec<Value>,
+}
+
+impl Seq<'_> {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.items.push(value.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        let items = Value::Array(self.items);
+        match self.variant {
+            Some(variant) => ValueSerializer::external(variant, items),
+            None => items,
+        }
+    }
+}
+
+impl ser::SerializeSeq for Seq<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTuple for Seq<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleStruct for Seq<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeTupleVariant for Seq<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+/// An object, wrapped as `{ variant = { … } }` for struct variants.
+struct Object<'a> {
+    serializer: ValueSerializer<'a>,
+    variant: Option<&'static str>,
+    map: Map,
+    /// The key of the entry whose value comes next.
+    key: Option<Key>,
+}
+
+impl Object<'_> {
+    fn insert<T: ?Sized + Serialize>(&mut self, key: Key, value: &T) -> Result<(), Error> {
+        self.map.insert(key, value.serialize(self.serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        let map = Value::Object(self.map);
+        match self.variant {
+            Some(variant) => ValueSerializer::external(variant, map),
+            None => map,
+        }
+    }
+}
+
+impl ser::SerializeMap for Object<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    /// Keys are read back as strings, whatever they were written from.
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+        let key = match &key.serialize(self.serializer)? {
+            Value::String(s) => Key::from(s.clone()),
+            Value::Array(_) | Value::Object(_) => {
+                return Err(Error::Message("object keys must be scalars".to_owned()))
+            }
+            scalar => Key::from(scalar.to_canonical_string()),
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::Message("value serialized before its key".to_owned()))?;
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStruct for Object<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(Key::from(key), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+impl ser::SerializeStructVariant for Object<'_> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.insert(Key::from(key), value)
+    }
+
+    fn end(self) -> Result<Value, Error> {
+        Ok(self.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hex::Hex;
+    use crate::ser::{to_string_with_options, SerializerOptions};
+    use serde::Serialize;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_to_value() {
+        #[derive(Serialize)]
+        struct Unit;
+
+        #[derive(Serialize)]
+        enum Mode {
+            Off,
+            Range { min: u32, max: u32 },
+            Props(BTreeMap<u32, bool>),
+        }
+
+        #[derive(Serialize)]
+        struct Config {
+            rate: u32,
+            volume: f32,
+            level: Option<i8>,
+            name: &'static str,
+            quoted: &'static str,
+            flag: char,
+            mask: Hex<u32>,
+            unit: Unit,
+            modes: Vec<Mode>,
+        }
+
+        let config = Config {
+            rate: 48000,
+            volume: 0.1,
+            level: None,
+            name: "alsa_output",
+            quoted: "48000",
+            flag: '=',
+            mask: Hex(0x3f),
+            unit: Unit,
+            modes: vec![
+                Mode::Off,
+                Mode::Range { min: 1, max: 2 },
+                Mode::Props(BTreeMap::from([(1, true)])),
+            ],
+        };
+        for (enum_repr, unit_struct_names) in [
+            (EnumRepr::External, false),
+            (EnumRepr::Internal("type".to_owned()), true),
+            (EnumRepr::Untagged, false),
+        ] {
+            let options = SerializerOptions::new()
+                .enum_repr(enum_repr.clone())
+                .unit_struct_names(unit_struct_names);
+            let text = to_string_with_options(&config, options).unwrap();
+            let value = to_value(&config, &enum_repr, unit_struct_names).unwrap();
+            assert_eq!(value, text.parse::<Value>().unwrap(), "{text}");
+        }
+    }
+}
@@ -0,0 +1,8 @@
+//! Helpers for `#[serde(with = "...")]` that give common field types the
+//! notation used in configuration files.
+
+#[cfg(feature = "de")]
+pub mod bool_from_anything;
+pub mod comma_separated;
+pub mod duration;
+pub mod string_or_number;
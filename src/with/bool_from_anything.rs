@@ -0,0 +1,107 @@
+//! `bool` fields that also accept the other spellings found in properties,
+//! such as `"true"`, `yes`, `off` or `1`.
+//!
+//! Deserializing accepts booleans, the strings `true`/`false`, `yes`/`no`,
+//! `on`/`off` and `1`/`0` in any case, quoted or not, and the integers 1
+//! and 0. Serializing writes `true` or `false`. Unlike
+//! [`ParseOptions::lenient_bools`], this only affects the fields it is
+//! used on.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Props {
+//!     #[serde(with = "spa_json::with::bool_from_anything")]
+//!     pause_on_idle: bool,
+//! }
+//!
+//! let props: Props = spa_json::de::from_str("pause_on_idle = \"On\"").unwrap();
+//! assert!(props.pause_on_idle);
+//! let output = spa_json::ser::to_string(&props).unwrap();
+//! assert_eq!(output, "{\n  pause_on_idle = true\n}");
+//! ```
+//!
+//! [`ParseOptions::lenient_bools`]: crate::de::ParseOptions::lenient_bools
+
+use crate::de::lenient_bool;
+use core::fmt;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+pub fn serialize<S: Serializer>(v: &bool, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_bool(*v)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    deserializer.deserialize_any(BoolVisitor)
+}
+
+struct BoolVisitor;
+
+impl Visitor<'_> for BoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a boolean such as true, \"yes\", off or 1")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+        Ok(v)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<bool, E> {
+        match v {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<bool, E> {
+        match u64::try_from(v) {
+            Ok(v) => self.visit_u64(v),
+            Err(_) => Err(E::invalid_value(de::Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+        lenient_bool(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::de::from_str;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Props {
+        #[serde(with = "super")]
+        flag: bool,
+    }
+
+    #[test]
+    fn test_bool_from_anything() {
+        for (input, expected) in [
+            ("true", true),
+            ("\"false\"", false),
+            ("YES", true),
+            ("'no'", false),
+            ("on", true),
+            ("Off", false),
+            ("1", true),
+            ("\"0\"", false),
+        ] {
+            let props: Props = from_str(&format!("flag = {}", input)).unwrap();
+            assert_eq!(props.flag, expected, "{}", input);
+        }
+        for invalid in ["2", "-1", "maybe", "null", "[ ]"] {
+            assert!(
+                from_str::<Props>(&format!("flag = {}", invalid)).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! `Vec` fields written as a string of comma-separated items, such as
+//! `audio.position = "FL,FR"`.
+//!
+//! Each item is parsed with [`FromStr`] and written with [`Display`].
+//! Deserializing ignores whitespace around items and reads an empty
+//! string as no items. It also accepts an array of strings, the other way
+//! PipeWire lets such lists be written. Serializing always writes a
+//! string.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Props {
+//!     #[serde(rename = "audio.position", with = "spa_json::with::comma_separated")]
+//!     position: Vec<String>,
+//! }
+//!
+//! let props: Props = spa_json::de::from_str("audio.position = \"FL, FR\"").unwrap();
+//! assert_eq!(props.position, ["FL", "FR"]);
+//! let output = spa_json::ser::to_string(&props).unwrap();
+//! assert_eq!(output, "{\n  audio.position = \"FL,FR\"\n}");
+//! ```
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+use core::str::FromStr;
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+
+pub fn serialize<T: Display, S: Serializer>(items: &[T], serializer: S) -> Result<S::Ok, S::Error> {
+    let joined: Vec<String> = items.iter().map(ToString::to_string).collect();
+    serializer.serialize_str(&joined.join(","))
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(ListVisitor(PhantomData))
+}
+
+fn parse_item<T, E>(item: &str) -> Result<T, E>
+where
+    T: FromStr,
+    T::Err: Display,
+    E: de::Error,
+{
+    let item = item.trim();
+    item.parse()
+        .map_err(|e| E::custom(format!("invalid item `{}`: {}", item, e)))
+}
+
+struct ListVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for ListVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string of comma-separated items or an array of strings")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<T>, E> {
+        if v.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        v.split(',').map(parse_item).collect()
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element::<String>()? {
+            items.push(parse_item(&item)?);
+        }
+        Ok(items)
+    }
+}
+
+#[cfg(all(test, feature = "de"))]
+mod tests {
+    use crate::de::from_str;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Props {
+        #[serde(with = "super")]
+        rates: Vec<u32>,
+    }
+
+    #[test]
+    fn test_comma_separated() {
+        for (input, expected) in [
+            ("\"44100,48000\"", vec![44100, 48000]),
+            ("\" 44100 , 48000 \"", vec![44100, 48000]),
+            ("\"\"", vec![]),
+            ("[ \"44100\" 48000 ]", vec![44100, 48000]),
+            ("[ ]", vec![]),
+        ] {
+            let props: Props = from_str(&format!("rates = {}", input)).unwrap();
+            assert_eq!(props.rates, expected, "{}", input);
+        }
+        for invalid in ["\"44100,\"", "\"a\"", "[ \"1,2\" ]"] {
+            assert!(
+                from_str::<Props>(&format!("rates = {}", invalid)).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! [`Duration`] fields written as strings with a unit suffix, such as
+//! `"5ms"` or `"2s"`.
+//!
+//! Serializing uses the largest of `s`, `ms`, `us` and `ns` that represents
+//! the duration exactly. Deserializing accepts any of them, with a
+//! fractional part as in `"1.5s"`, and `µs` for microseconds.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use std::time::Duration;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Props {
+//!     #[serde(with = "spa_json::with::duration")]
+//!     timeout: Duration,
+//! }
+//!
+//! let props: Props = spa_json::de::from_str("timeout = 1.5s").unwrap();
+//! assert_eq!(props.timeout, Duration::from_millis(1500));
+//! let output = spa_json::ser::to_string(&props).unwrap();
+//! assert_eq!(output, "{\n  timeout = 1500ms\n}");
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::time::Duration;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+/// Units from the largest, with their length in nanoseconds.
+const UNITS: [(&str, u128); 4] = [
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format(duration))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    deserializer.deserialize_str(DurationVisitor)
+}
+
+fn format(duration: &Duration) -> String {
+    let nanos = duration.as_nanos();
+    let (unit, length) = UNITS
+        .into_iter()
+        .find(|(_, length)| nanos.is_multiple_of(*length))
+        .unwrap();
+    format!("{}{}", nanos / length, unit)
+}
+
+/// Parses a duration with a unit suffix, or `None` if it is malformed or
+/// out of range.
+fn parse(text: &str) -> Option<Duration> {
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let length = match unit {
+        "µs" => 1_000,
+        unit => UNITS.iter().find(|(name, _)| *name == unit)?.1,
+    };
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() || fraction.contains('.') {
+        return None;
+    }
+    let whole: u128 = if whole.is_empty() {
+        0
+    } else {
+        whole.parse().ok()?
+    };
+    let mut nanos = whole.checked_mul(length)?;
+    // Add the fraction digit by digit, dropping what is below a nanosecond.
+    let mut scale = length;
+    for digit in fraction.bytes() {
+        scale /= 10;
+        nanos += u128::from(digit - b'0') * scale;
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000).ok()?;
+    Some(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+struct DurationVisitor;
+
+impl Visitor<'_> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a duration such as \"5ms\" or \"2s\"")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Duration, E> {
+        parse(v).ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(format(&Duration::from_secs(2)), "2s");
+        assert_eq!(format(&Duration::from_millis(2500)), "2500ms");
+        assert_eq!(format(&Duration::from_nanos(1)), "1ns");
+        assert_eq!(format(&Duration::ZERO), "0s");
+
+        assert_eq!(parse("5ms"), Some(Duration::from_millis(5)));
+        assert_eq!(parse("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse(".25ms"), Some(Duration::from_micros(250)));
+        assert_eq!(parse("20µs"), Some(Duration::from_micros(20)));
+        assert_eq!(parse("7ns"), Some(Duration::from_nanos(7)));
+        for invalid in ["5", "ms", "1.2.3s", "5 ms", "-1s", "1min"] {
+            assert_eq!(parse(invalid), None, "{invalid}");
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Numeric fields that may also be written as strings, as PipeWire writes
+//! many properties, such as `"node.rate" = "48000"`.
+//!
+//! Deserializing accepts a number, or a string that the field type parses
+//! with [`FromStr`], ignoring surrounding whitespace. Serializing writes
+//! the field as it would without this helper, normally as a number.
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Props {
+//!     #[serde(rename = "node.rate", with = "spa_json::with::string_or_number")]
+//!     rate: u32,
+//! }
+//!
+//! let props: Props = spa_json::de::from_str("node.rate = \"48000\"").unwrap();
+//! assert_eq!(props.rate, 48000);
+//! let output = spa_json::ser::to_string(&props).unwrap();
+//! assert_eq!(output, "{\n  node.rate = 48000\n}");
+//! ```
+
+use alloc::format;
+use alloc::string::ToString;
+use core::fmt::{self, Display};
+use core::marker::PhantomData;
+use core::str::FromStr;
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+pub fn serialize<T: Serialize, S: Serializer>(v: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    v.serialize(serializer)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(NumberVisitor(PhantomData))
+}
+
+struct NumberVisitor<T>(PhantomData<T>);
+
+impl<T> NumberVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn parse<E: de::Error>(text: &str) -> Result<T, E> {
+        text.trim()
+            .parse()
+            .map_err(|e| E::custom(format!("invalid number `{}`: {}", text, e)))
+    }
+}
+
+impl<T> Visitor<'_> for NumberVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or a string holding one")
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+        Self::parse(&v.to_string())
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        Self::parse(v)
+    }
+}
+
+#[cfg(all(test, feature = "de"))]
+mod tests {
+    use crate::de::from_str;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Props {
+        #[serde(with = "super")]
+        rate: u32,
+        #[serde(with = "super")]
+        volume: f64,
+    }
+
+    #[test]
+    fn test_string_or_number() {
+        let props: Props = from_str("rate = \" 44100 \", volume = 0.5").unwrap();
+        assert_eq!(
+            props,
+            Props {
+                rate: 44100,
+                volume: 0.5
+            }
+        );
+        let props: Props = from_str("rate = 48000, volume = \"1\"").unwrap();
+        assert_eq!(
+            props,
+            Props {
+                rate: 48000,
+                volume: 1.0
+            }
+        );
+        for invalid in [
+            "rate = -1, volume = 1",
+            "rate = 1.5, volume = 1",
+            "rate = x, volume = 1",
+        ] {
+            assert!(from_str::<Props>(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
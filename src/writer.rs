@@ -0,0 +1,241 @@
+//! Incremental writing of SPA-JSON, one key or item at a time, for output
+//! generated from data that does not implement `Serialize`.
+//!
+//! Objects and arrays are written one entry per line. Values given as a
+//! whole, including arrays and objects, are written on a single line.
+//!
+//! ```
+//! use spa_json::writer::ObjectWriter;
+//!
+//! let mut out = Vec::new();
+//! let mut w = ObjectWriter::new(&mut out);
+//! w.comment("Generated")?;
+//! let mut props = w.key("context.properties")?.object()?;
+//! props.key("default.clock.rate")?.value(48000)?;
+//! props.end()?;
+//! let mut modules = w.key("context.modules")?.array()?;
+//! modules.item()?.value("libpipewire-module-rt")?;
+//! modules.end()?;
+//! w.end()?;
+//! assert_eq!(
+//!     String::from_utf8(out).unwrap(),
+//!     "# Generated\n\
+//!      context.properties = {\n  \
+//!        default.clock.rate = 48000\n\
+//!      }\n\
+//!      context.modules = [\n  \
+//!        libpipewire-module-rt\n\
+//!      ]\n"
+//! );
+//! # Ok::<(), spa_json::ser::Error>(())
+//! ```
+
+use crate::error::Error;
+use crate::ser::{to_string, to_string_with_options, SerializerOptions};
+use crate::value::Value;
+use std::io::Write;
+
+/// Writes the entries of an object. At the top level the object is written
+/// without braces, the way configuration files are.
+pub struct ObjectWriter<'a, W: Write> {
+    out: &'a mut W,
+    depth: usize,
+    /// Whether nothing was written since the opening brace, which is then
+    /// still on its line.
+    empty: bool,
+}
+
+/// Writes the items of an array.
+pub struct ArrayWriter<'a, W: Write> {
+    out: &'a mut W,
+    depth: usize,
+    empty: bool,
+}
+
+/// Writes the value of an object entry or array item.
+#[must_use]
+pub struct ValueWriter<'a, W: Write> {
+    out: &'a mut W,
+    /// The depth of the entry or item.
+    depth: usize,
+}
+
+impl<'a, W: Write> ObjectWriter<'a, W> {
+    /// Starts a top-level object, written without braces.
+    pub fn new(out: &'a mut W) -> Self {
+        ObjectWriter {
+            out,
+            depth: 0,
+            empty: false,
+        }
+    }
+
+    pub fn key(&mut self, key: &str) -> Result<ValueWriter<'_, W>, Error> {
+        start_line(self.out, self.depth, &mut self.empty)?;
+        write!(self.out, "{} = ", to_string(&key)?)?;
+        Ok(ValueWriter {
+            out: self.out,
+            depth: self.depth,
+        })
+    }
+
+    /// Writes `text` as comment lines before the next entry.
+    pub fn comment(&mut self, text: &str) -> Result<(), Error> {
+        write_comment(self.out, self.depth, &mut self.empty, text)
+    }
+
+    /// Closes the object. For the top-level object this does nothing.
+    pub fn end(self) -> Result<(), Error> {
+        if self.depth == 0 {
+            return Ok(());
+        }
+        end(self.out, self.depth, self.empty, '}')
+    }
+}
+
+impl<'a, W: Write> ArrayWriter<'a, W> {
+    /// Starts a top-level array.
+    pub fn new(out: &'a mut W) -> Result<Self, Error> {
+        out.write_all(b"[")?;
+        Ok(ArrayWriter {
+            out,
+            depth: 1,
+            empty: true,
+        })
+    }
+
+    pub fn item(&mut self) -> Result<ValueWriter<'_, W>, Error> {
+        start_line(self.out, self.depth, &mut self.empty)?;
+        Ok(ValueWriter {
+            out: self.out,
+            depth: self.depth,
+        })
+    }
+
+    /// Writes `text` as comment lines before the next item.
+    pub fn comment(&mut self, text: &str) -> Result<(), Error> {
+        write_comment(self.out, self.depth, &mut self.empty, text)
+    }
+
+    pub fn end(self) -> Result<(), Error> {
+        end(self.out, self.depth, self.empty, ']')
+    }
+}
+
+impl<'a, W: Write> ValueWriter<'a, W> {
+    /// Writes a complete value on the current line.
+    pub fn value(self, value: impl Into<Value>) -> Result<(), Error> {
+        let options = SerializerOptions::new().inline_width(usize::MAX);
+        writeln!(
+            self.out,
+            "{}",
+            to_string_with_options(&value.into(), options)?
+        )?;
+        Ok(())
+    }
+
+    /// Starts an object value, whose entries are written with the returned
+    /// writer.
+    pub fn object(self) -> Result<ObjectWriter<'a, W>, Error> {
+        self.out.write_all(b"{")?;
+        Ok(ObjectWriter {
+            out: self.out,
+            depth: self.depth + 1,
+            empty: true,
+        })
+    }
+
+    /// Starts an array value, whose items are written with the returned
+    /// writer.
+    pub fn array(self) -> Result<ArrayWriter<'a, W>, Error> {
+        self.out.write_all(b"[")?;
+        Ok(ArrayWriter {
+            out: self.out,
+            depth: self.depth + 1,
+            empty: true,
+        })
+    }
+}
+
+/// Starts a line at `depth` for an entry, item or comment, ending the line
+/// of the opening bracket first if this is the first.
+fn start_line(out: &mut impl Write, depth: usize, empty: &mut bool) -> Result<(), Error> {
+    if std::mem::take(empty) {
+        out.write_all(b"\n")?;
+    }
+    write!(out, "{:1$}", "", depth * 2)?;
+    Ok(())
+}
+
+fn write_comment(
+    out: &mut impl Write,
+    depth: usize,
+    empty: &mut bool,
+    text: &str,
+) -> Result<(), Error> {
+    for line in text.lines() {
+        start_line(out, depth, empty)?;
+        if line.is_empty() {
+            writeln!(out, "#")?;
+        } else {
+            writeln!(out, "# {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the closing bracket of a container whose contents are at
+/// `depth`, directly after the opening one if it is empty.
+fn end(out: &mut impl Write, depth: usize, empty: bool, bracket: char) -> Result<(), Error> {
+    if !empty {
+        write!(out, "{:1$}", "", (depth - 1) * 2)?;
+    }
+    writeln!(out, "{}", bracket)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Map;
+
+    #[test]
+    fn test_writer() {
+        let mut out = Vec::new();
+        let mut w = ArrayWriter::new(&mut out).unwrap();
+        let mut node = w.item().unwrap().object().unwrap();
+        node.comment("first\n\nnode").unwrap();
+        node.key("node name").unwrap().value("a \"b\"").unwrap();
+        node.key("rate").unwrap().value(44100.5).unwrap();
+        let props: Map = [("x", Value::from(vec![Value::from(1)]))]
+            .into_iter()
+            .collect();
+        node.key("props").unwrap().value(props).unwrap();
+        node.key("empty").unwrap().array().unwrap().end().unwrap();
+        node.end().unwrap();
+        w.item().unwrap().value(Value::Null).unwrap();
+        w.end().unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert_eq!(
+            output,
+            "[\n  \
+               {\n    \
+                 # first\n    \
+                 #\n    \
+                 # node\n    \
+                 \"node name\" = \"a \\\"b\\\"\"\n    \
+                 rate = 44100.5\n    \
+                 props = { x = [ 1 ] }\n    \
+                 empty = []\n  \
+               }\n  \
+               null\n\
+             ]\n"
+        );
+        assert!(output.parse::<Value>().is_ok());
+
+        let mut out = Vec::new();
+        ArrayWriter::new(&mut out).unwrap().end().unwrap();
+        assert_eq!(out, b"[]\n");
+    }
+}
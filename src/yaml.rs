@@ -0,0 +1,230 @@
+//! Conversion between [`Value`]s and YAML, so configurations can be kept
+//! in YAML and rendered as SPA-JSON. Documents are read and written by
+//! [serde_yaml].
+//!
+//! Keys that are not strings, such as `1` or `true`, are read as the text
+//! they are written as, and tags are ignored.
+//!
+//! [serde_yaml]: https://docs.rs/serde_yaml
+
+use crate::error::Error;
+use crate::value::{Map, Value};
+use serde_yaml::Value as Yaml;
+
+/// Writes `value` as a YAML document.
+pub fn to_yaml(value: &Value) -> Result<String, Error> {
+    serde_yaml::to_string(value).map_err(|e| Error::Message(e.to_string()))
+}
+
+/// Parses a YAML document.
+pub fn from_yaml(input: &str) -> Result<Value, Error> {
+    let yaml: Yaml = serde_yaml::from_str(input).map_err(|e| Error::Message(e.to_string()))?;
+    Ok(convert(yaml))
+}
+
+fn convert(yaml: Yaml) -> Value {
+    match yaml {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::from(b),
+        Yaml::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(v), _) => Value::from(v),
+            (None, Some(v)) => Value::from(v),
+            _ => Value::from(n.as_f64().unwrap_or(f64::NAN)),
+        },
+        Yaml::String(s) => Value::from(s),
+        Yaml::Sequence(items) => Value::Array(items.into_iter().map(convert).collect()),
+        Yaml::Mapping(entries) => entries
+            .into_iter()
+            .map(|(k, v)| (key(k), convert(v)))
+            .collect::<Map>()
+            .into(),
+        Yaml::Tagged(tagged) => convert(tagged.value),
+    }
+}
+
+/// The text of a key, however it is typed.
+fn key(yaml: Yaml) -> String {
+    match yaml {
+        Yaml::String(s) => s,
+        Yaml::Tagged(tagged) => key(tagged.value),
+        other => serde_yaml::to_string(&other)
+            .map(|s| s.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let value: Value = r#"
+            context.properties = { default.clock.rate = 48000, log.level = 2 }
+            context.modules = [
+                { name = libpipewire-module-rt, args = { nice.level = -11 }, flags = [ ifexists nofail ] }
+                { name = libpipewire-module-protocol-native, args = {} }
+            ]
+            stream.properties = { channelmix.upmix = true, resample.quality = 4.0, node.latency = "1024/48000" }
+            strings = [ "" "true" "12" "- x" "a: b" " pad" "line\nbreak" "it's" null ]
+        "#
+        .parse()
+        .unwrap();
+        let yaml = to_yaml(&value).unwrap();
+        let expected = r#"context.properties:
+  default.clock.rate: 48000
+  log.level: 2
+context.modules:
+- name: libpipewire-module-rt
+  args:
+    nice.level: -11
+  flags:
+  - ifexists
+  - nofail
+- name: libpipewire-module-protocol-native
+  args: {}
+stream.properties:
+  channelmix.upmix: true
+  resample.quality: 4.0
+  node.latency: 1024/48000
+strings:
+- ''
+- 'true'
+- '12'
+- '- x'
+- 'a: b'
+- ' pad'
+- |-
+  line
+  break
+- it's
+- null
+"#;
+        assert_eq!(yaml, expected);
+        assert_eq!(from_yaml(&yaml).unwrap(), value);
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = from_yaml(
+            r#"
+---
+# comment
+plain: hello world # trailing comment
+quoted: "tab\there ä \U0001F3A7"
+single: 'it''s # not a comment'
+types: [ ~, true, 0x1f, 0o17, -1.5e3, .inf, .5, 1_000, 1.2.3 ]
+flow: { a: 1, b: [ x, "y" ], c }
+keys: { 1: a, true: b, null: c }
+anchors: { a: &x 1, b: *x, c: !custom 2 }
+multi: [ one,
+  two ]
+folded plain: first
+  second
+"quoted key": 1
+empty:
+seq:
+- a
+-   - b
+    - c
+- key: value
+  other: 2
+-
+  nested: true
+literal: |
+  line one
+   indented
+
+  line three
+folded: >-
+  one
+  two
+
+  three
+keep: |+
+  text
+
+...
+"#,
+        )
+        .unwrap();
+        assert_eq!(value.get("plain"), Some(&Value::from("hello world")));
+        assert_eq!(value.get("quoted"), Some(&Value::from("tab\there ä 🎧")));
+        assert_eq!(
+            value.get("single"),
+            Some(&Value::from("it's # not a comment"))
+        );
+        let types = value.get("types").unwrap().as_array().unwrap();
+        assert_eq!(
+            types[..4],
+            [
+                Value::Null,
+                Value::from(true),
+                Value::from(31),
+                Value::from(15)
+            ]
+        );
+        assert_eq!(
+            types[4..7],
+            [
+                Value::from(-1500.0),
+                Value::from(f64::INFINITY),
+                Value::from(0.5)
+            ]
+        );
+        assert_eq!(types[7..], [Value::from("1_000"), Value::from("1.2.3")]);
+        assert_eq!(
+            value.get("flow").unwrap().to_canonical_string(),
+            r#"{"a":1,"b":["x","y"],"c":null}"#
+        );
+        assert_eq!(
+            value.get("keys").unwrap().to_canonical_string(),
+            r#"{"1":"a","null":"c","true":"b"}"#
+        );
+        assert_eq!(
+            value.get("anchors").unwrap().to_canonical_string(),
+            r#"{"a":1,"b":1,"c":2}"#
+        );
+        assert_eq!(
+            value.get("multi").unwrap().to_canonical_string(),
+            r#"["one","two"]"#
+        );
+        assert_eq!(
+            value.get("folded plain"),
+            Some(&Value::from("first second"))
+        );
+        assert_eq!(value.get("quoted key"), Some(&Value::from(1)));
+        assert_eq!(value.get("empty"), Some(&Value::Null));
+        assert_eq!(
+            value.get("seq").unwrap().to_canonical_string(),
+            r#"["a",["b","c"],{"key":"value","other":2},{"nested":true}]"#
+        );
+        assert_eq!(
+            value.get("literal"),
+            Some(&Value::from("line one\n indented\n\nline three\n"))
+        );
+        assert_eq!(value.get("folded"), Some(&Value::from("one two\nthree")));
+        assert_eq!(value.get("keep"), Some(&Value::from("text\n\n")));
+        assert_eq!(from_yaml("").unwrap(), Value::Null);
+        assert_eq!(from_yaml("42").unwrap(), Value::from(42));
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |s: &str| from_yaml(s).unwrap_err().to_string();
+        assert_eq!(error("a: 1\na: 2"), "duplicate entry with key \"a\"");
+        assert_eq!(
+            error("a: 1\n  b: 2"),
+            "mapping values are not allowed in this context at line 2 column 4"
+        );
+        assert_eq!(
+            error("a: \"x"),
+            "found unexpected end of stream at line 1 column 6, \
+             while scanning a quoted scalar at line 1 column 4"
+        );
+        assert_eq!(
+            error("a: 1\n---\nb: 2"),
+            "deserializing from YAML containing more than one document is not supported"
+        );
+    }
+}